@@ -0,0 +1,543 @@
+use std::convert::TryInto;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Read, Seek, SeekFrom};
+
+use super::entry::{Entry, EntryId, FileDates, MacFileInfo};
+use crate::finder::FinderInfo;
+use crate::util::{u16_at, u32_at, BoundedReader};
+
+/// The magic number at the start of every AppleSingle file.
+pub(super) const APPLESINGLE_MAGIC: u32 = 0x0005_1600;
+
+/// The magic number at the start of every AppleDouble header file.
+pub(super) const APPLEDOUBLE_MAGIC: u32 = 0x0005_1607;
+
+/// The length, in bytes, of the fixed portion of an AppleSingle header (magic number, version,
+/// home file system/filler, and number of entries).
+pub(super) const HEADER_LENGTH: usize = 26;
+
+/// The length, in bytes, of each entry descriptor that follows the fixed portion of the header.
+pub(super) const ENTRY_DESCRIPTOR_LENGTH: usize = 12;
+
+/// Indicates whether a file is an AppleSingle file or an AppleDouble header file.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AppleSingleKind {
+    /// An AppleSingle file, which contains a file's data fork along with its resource fork and
+    /// metadata.
+    AppleSingle,
+
+    /// An AppleDouble header file, which contains a file's resource fork and metadata; the data
+    /// fork is stored separately as a plain file.
+    AppleDouble,
+}
+
+impl AppleSingleKind {
+    pub(super) fn magic(self) -> u32 {
+        match self {
+            AppleSingleKind::AppleSingle => APPLESINGLE_MAGIC,
+            AppleSingleKind::AppleDouble => APPLEDOUBLE_MAGIC,
+        }
+    }
+}
+
+/// The version of the AppleSingle/AppleDouble format in which a file was written.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AppleSingleVersion {
+    /// Version 1, which stores dates and attributes in a single "file info" entry whose layout
+    /// depends on the home file system.
+    V1,
+
+    /// Version 2, which stores dates and attributes in separate, file system-independent entries.
+    V2,
+}
+
+impl AppleSingleVersion {
+    pub(super) fn number(self) -> u32 {
+        match self {
+            AppleSingleVersion::V1 => 0x0001_0000,
+            AppleSingleVersion::V2 => 0x0002_0000,
+        }
+    }
+}
+
+/// An AppleSingle file or AppleDouble header file.
+///
+/// AppleSingle files combine a file's data fork, resource fork, and metadata into a series of
+/// "entries." AppleDouble header files use the same structure, but omit the data fork. Files
+/// written in either version 1 or version 2 of the format can be read.
+///
+/// Because entries may appear anywhere in the file, archives are read from a seekable source, and
+/// any entry may be read at any time and in any order.
+pub struct AppleSingleArchive<R: Read + Seek> {
+    source: R,
+    base_offset: u64,
+    kind: AppleSingleKind,
+    version: AppleSingleVersion,
+    entries: Vec<Entry>,
+}
+
+impl<R: Read + Seek> AppleSingleArchive<R> {
+    /// Creates a new AppleSingle archive that will read entries from the given source.
+    ///
+    /// The archive's header must begin at the source's current position; entry offsets are
+    /// interpreted relative to that position.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the source doesn't begin with a valid AppleSingle or
+    /// AppleDouble header, if the header was written in an unsupported version of the format, or if
+    /// any entry extends beyond the end of the source.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use clarus::applesingle::{AppleSingleArchive, AppleSingleError};
+    ///
+    /// fn main() -> Result<(), AppleSingleError> {
+    ///     let archive = AppleSingleArchive::new(File::open("example.as")?)?;
+    ///
+    ///     for entry in archive.entries() {
+    ///         println!("{:?}: {} bytes", entry.id(), entry.len());
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn new(mut source: R) -> Result<Self, AppleSingleError> {
+        let base_offset = source.stream_position()?;
+
+        let mut header = [0; HEADER_LENGTH];
+        source.read_exact(&mut header)?;
+
+        let kind = match u32_at(&header, 0) {
+            APPLESINGLE_MAGIC => AppleSingleKind::AppleSingle,
+            APPLEDOUBLE_MAGIC => AppleSingleKind::AppleDouble,
+            _ => return Err(AppleSingleError::InvalidHeader),
+        };
+
+        let version = match u32_at(&header, 4) {
+            0x0001_0000 => AppleSingleVersion::V1,
+            0x0002_0000 => AppleSingleVersion::V2,
+            version => return Err(AppleSingleError::UnsupportedVersion(version)),
+        };
+
+        let entry_count = u16_at(&header, 24) as usize;
+
+        let mut descriptors = vec![0; entry_count * ENTRY_DESCRIPTOR_LENGTH];
+        source.read_exact(&mut descriptors)?;
+
+        let source_len = source.seek(SeekFrom::End(0))? - base_offset;
+
+        let entries = descriptors
+            .chunks_exact(ENTRY_DESCRIPTOR_LENGTH)
+            .map(|descriptor| {
+                let entry = Entry::new(
+                    EntryId::from(u32_at(descriptor, 0)),
+                    u32_at(descriptor, 4),
+                    u32_at(descriptor, 8),
+                );
+
+                if entry.offset() as u64 + entry.len() as u64 > source_len {
+                    Err(AppleSingleError::InvalidHeader)
+                } else {
+                    Ok(entry)
+                }
+            })
+            .collect::<Result<Vec<Entry>, AppleSingleError>>()?;
+
+        Ok(AppleSingleArchive {
+            source,
+            base_offset,
+            kind,
+            version,
+            entries,
+        })
+    }
+
+    /// Indicates whether this archive is an AppleSingle file or an AppleDouble header file.
+    pub fn kind(&self) -> AppleSingleKind {
+        self.kind
+    }
+
+    /// Returns the version of the format in which this archive was written.
+    pub fn version(&self) -> AppleSingleVersion {
+        self.version
+    }
+
+    /// Returns the entries in this archive in the order in which they appear in the header.
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Returns the first entry in this archive with the given ID, if any.
+    pub fn entry(&self, id: EntryId) -> Option<&Entry> {
+        self.entries.iter().find(|entry| entry.id() == id)
+    }
+
+    /// Returns a reader for the content of the first entry with the given ID.
+    ///
+    /// The returned reader reads directly from this archive's source and is seekable within the
+    /// bounds of the entry, and so it can be passed to anything that expects a standalone stream.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if this archive doesn't contain an entry with the given
+    /// ID.
+    ///
+    /// # Example
+    ///
+    /// The resource fork entry of an AppleSingle or AppleDouble file can be read as a resource fork
+    /// without first copying it into memory:
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use clarus::applesingle::{AppleSingleArchive, AppleSingleError, EntryId};
+    /// use clarus::rsrc::ResourceFork;
+    ///
+    /// fn main() -> Result<(), AppleSingleError> {
+    ///     let mut archive = AppleSingleArchive::new(File::open("._example")?)?;
+    ///
+    ///     if let Ok(resource_fork) = ResourceFork::new(archive.entry_reader(EntryId::ResourceFork)?) {
+    ///         println!("{} resources", resource_fork.resources().count());
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn entry_reader(&mut self, id: EntryId) -> Result<EntryReader<'_, R>, AppleSingleError> {
+        let entry = *self.entry(id).ok_or(AppleSingleError::EntryNotFound(id))?;
+
+        Ok(EntryReader {
+            reader: BoundedReader::new(
+                &mut self.source,
+                self.base_offset + entry.offset() as u64,
+                entry.len() as u64,
+            ),
+        })
+    }
+
+    /// Returns a reader for this archive's data fork.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if this archive doesn't contain a data fork (as is always
+    /// the case for AppleDouble header files).
+    pub fn data_fork(&mut self) -> Result<EntryReader<'_, R>, AppleSingleError> {
+        self.entry_reader(EntryId::DataFork)
+    }
+
+    /// Returns a reader for this archive's resource fork.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if this archive doesn't contain a resource fork.
+    pub fn resource_fork(&mut self) -> Result<EntryReader<'_, R>, AppleSingleError> {
+        self.entry_reader(EntryId::ResourceFork)
+    }
+
+    /// Reads the entire content of the first entry with the given ID.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if this archive doesn't contain an entry with the given ID
+    /// or if an IO error occurs while reading the entry.
+    pub fn read_entry(&mut self, id: EntryId) -> Result<Vec<u8>, AppleSingleError> {
+        let mut reader = self.entry_reader(id)?;
+        let mut content = Vec::with_capacity(reader.reader.len() as usize);
+
+        reader.read_to_end(&mut content)?;
+
+        if content.len() as u64 != reader.reader.len() {
+            return Err(AppleSingleError::IoError(io::ErrorKind::UnexpectedEof));
+        }
+
+        Ok(content)
+    }
+
+    /// Returns the file's name as it was known on its home file system, if this archive includes a
+    /// real name entry.
+    ///
+    /// Names are decoded using the Macintosh character encoding.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if an IO error occurs while reading the entry.
+    pub fn real_name(&mut self) -> Result<Option<String>, AppleSingleError> {
+        Ok(self
+            .read_optional_entry(EntryId::RealName)?
+            .map(|name_bytes| {
+                let (name, _, _) = encoding_rs::MACINTOSH.decode(&name_bytes);
+                name.into_owned()
+            }))
+    }
+
+    /// Returns the file's Get Info comment, if this archive includes a comment entry.
+    ///
+    /// Comments are returned as raw bytes in the Macintosh character encoding.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if an IO error occurs while reading the entry.
+    pub fn comment(&mut self) -> Result<Option<Vec<u8>>, AppleSingleError> {
+        self.read_optional_entry(EntryId::Comment)
+    }
+
+    /// Returns the file's Finder information, if this archive includes a Finder info entry.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if the Finder info entry is too short or if an IO error
+    /// occurs while reading the entry.
+    pub fn finder_info(&mut self) -> Result<Option<FinderInfo>, AppleSingleError> {
+        self.read_optional_entry(EntryId::FinderInfo)?
+            .map(|bytes| {
+                let finder_info: [u8; 16] = bytes
+                    .get(..16)
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .ok_or(AppleSingleError::InvalidHeader)?;
+
+                Ok(FinderInfo::from(finder_info))
+            })
+            .transpose()
+    }
+
+    /// Returns the file's extended Finder information (known as `FXInfo` in "Inside Macintosh"),
+    /// which follows the basic Finder information in the Finder info entry.
+    ///
+    /// Extended Finder information is returned as raw bytes, and is `None` if this archive doesn't
+    /// include a Finder info entry or if the entry only includes basic Finder information.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if an IO error occurs while reading the entry.
+    pub fn extended_finder_info(&mut self) -> Result<Option<[u8; 16]>, AppleSingleError> {
+        Ok(self
+            .read_optional_entry(EntryId::FinderInfo)?
+            .and_then(|bytes| bytes.get(16..32).and_then(|bytes| bytes.try_into().ok())))
+    }
+
+    /// Returns the file's creation, modification, backup, and access dates, if this archive
+    /// includes them.
+    ///
+    /// Version 2 archives store dates in a file dates entry. Version 1 archives store creation,
+    /// modification, and backup dates in a file info entry; access dates are always unknown for
+    /// version 1 archives.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if the entry containing the dates is too short or if an IO
+    /// error occurs while reading the entry.
+    pub fn file_dates(&mut self) -> Result<Option<FileDates>, AppleSingleError> {
+        if let Some(bytes) = self.read_optional_entry(EntryId::FileDates)? {
+            let dates: [u8; 16] = bytes
+                .get(..16)
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or(AppleSingleError::InvalidHeader)?;
+
+            return Ok(Some(FileDates::from_v2_bytes(&dates)));
+        }
+
+        Ok(self.read_v1_file_info()?.map(|file_info| {
+            let mac_date_at = |offset: usize| u32_at(&file_info, offset);
+
+            FileDates {
+                created: FileDates::from_mac_date(mac_date_at(0)),
+                modified: FileDates::from_mac_date(mac_date_at(4)),
+                backed_up: FileDates::from_mac_date(mac_date_at(8)),
+                accessed: FileDates::UNKNOWN,
+            }
+        }))
+    }
+
+    /// Returns the file's Macintosh attributes, if this archive includes them.
+    ///
+    /// Version 2 archives store attributes in a Macintosh file info entry, while version 1 archives
+    /// store them in a file info entry.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if the entry containing the attributes is too short or if
+    /// an IO error occurs while reading the entry.
+    pub fn mac_file_info(&mut self) -> Result<Option<MacFileInfo>, AppleSingleError> {
+        if let Some(bytes) = self.read_optional_entry(EntryId::MacFileInfo)? {
+            let attributes: [u8; 4] = bytes
+                .get(..4)
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or(AppleSingleError::InvalidHeader)?;
+
+            return Ok(Some(MacFileInfo::from(u32::from_be_bytes(attributes))));
+        }
+
+        Ok(self
+            .read_v1_file_info()?
+            .map(|file_info| MacFileInfo::from(u32_at(&file_info, 12))))
+    }
+
+    fn read_optional_entry(&mut self, id: EntryId) -> Result<Option<Vec<u8>>, AppleSingleError> {
+        match self.entry(id) {
+            Some(_) => Ok(Some(self.read_entry(id)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Reads the version 1 "file info" entry, which contains dates and attributes for files whose
+    /// home file system is a Macintosh file system.
+    fn read_v1_file_info(&mut self) -> Result<Option<[u8; 16]>, AppleSingleError> {
+        if self.version != AppleSingleVersion::V1 {
+            return Ok(None);
+        }
+
+        self.read_optional_entry(EntryId::FileInfo)?
+            .map(|bytes| {
+                bytes
+                    .get(..16)
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .ok_or(AppleSingleError::InvalidHeader)
+            })
+            .transpose()
+    }
+}
+
+/// A reader for the content of a single entry in an AppleSingle or AppleDouble file.
+///
+/// Entry readers read directly from the underlying archive's source. Seeking is relative to the
+/// start of the entry, and reads never extend beyond the end of the entry.
+pub struct EntryReader<'a, R: Read + Seek> {
+    reader: BoundedReader<'a, R>,
+}
+
+impl<'a, R: Read + Seek> Read for EntryReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+impl<'a, R: Read + Seek> Seek for EntryReader<'a, R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.reader.seek(pos)
+    }
+}
+
+/// The error type for operations on AppleSingle and AppleDouble files.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AppleSingleError {
+    /// An [`std::io::Error`] occurred while reading or writing some part of the file.
+    ///
+    /// The kind of IO error is included.
+    IoError(io::ErrorKind),
+
+    /// The file's header was malformed, or one of its entries could not be interpreted.
+    InvalidHeader,
+
+    /// The file was written in a version of the format other than version 1 or 2.
+    ///
+    /// The version number from the file's header is included.
+    UnsupportedVersion(u32),
+
+    /// The given filename could not be represented in a real name entry.
+    ///
+    /// Real names must not be empty and must be representable in the Macintosh character encoding.
+    InvalidFilename,
+
+    /// The file doesn't contain an entry with the given ID.
+    EntryNotFound(EntryId),
+
+    /// An entry with the given ID can't be added as raw content; fork entries, for example, can
+    /// only be written from the forks passed to [`super::AppleSingleWriter::encode`].
+    InvalidEntry(EntryId),
+}
+
+impl Display for AppleSingleError {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AppleSingleError::IoError(kind) => write!(fmt, "IO error: {:?}", kind),
+            AppleSingleError::InvalidHeader => write!(fmt, "Invalid AppleSingle header"),
+            AppleSingleError::UnsupportedVersion(version) => {
+                write!(fmt, "Unsupported version: {:08x}", version)
+            }
+            AppleSingleError::InvalidFilename => write!(fmt, "Invalid filename"),
+            AppleSingleError::EntryNotFound(id) => write!(fmt, "Entry not found: {:?}", id),
+            AppleSingleError::InvalidEntry(id) => write!(fmt, "Invalid entry: {:?}", id),
+        }
+    }
+}
+
+impl From<io::Error> for AppleSingleError {
+    fn from(error: io::Error) -> Self {
+        AppleSingleError::IoError(error.kind())
+    }
+}
+
+impl error::Error for AppleSingleError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn entry_reader() -> Result<(), AppleSingleError> {
+        let mut file = vec![0x00, 0x05, 0x16, 0x00, 0x00, 0x02, 0x00, 0x00];
+        file.extend_from_slice(&[0; 16]);
+        file.extend_from_slice(&[0x00, 0x01]);
+        file.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 38, 0, 0, 0, 5]);
+        file.extend_from_slice(b"Hello, world!");
+
+        let mut archive = AppleSingleArchive::new(Cursor::new(file))?;
+
+        assert_eq!(AppleSingleKind::AppleSingle, archive.kind());
+        assert_eq!(AppleSingleVersion::V2, archive.version());
+        assert_eq!(1, archive.entries().len());
+
+        let mut reader = archive.data_fork()?;
+        let mut content = String::new();
+        reader.read_to_string(&mut content)?;
+
+        assert_eq!("Hello", content);
+
+        reader.seek(SeekFrom::End(-2))?;
+        content.clear();
+        reader.read_to_string(&mut content)?;
+
+        assert_eq!("lo", content);
+        assert!(reader.seek(SeekFrom::Current(-6)).is_err());
+
+        assert_eq!(
+            AppleSingleError::EntryNotFound(EntryId::ResourceFork),
+            archive.resource_fork().err().unwrap()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_header() {
+        let mut file = vec![0x00, 0x05, 0x16, 0x00, 0x00, 0x03, 0x00, 0x00];
+        file.extend_from_slice(&[0; 18]);
+
+        assert_eq!(
+            AppleSingleError::UnsupportedVersion(0x0003_0000),
+            AppleSingleArchive::new(Cursor::new(&file)).err().unwrap()
+        );
+
+        file[3] = 0x01;
+
+        assert_eq!(
+            AppleSingleError::InvalidHeader,
+            AppleSingleArchive::new(Cursor::new(&file)).err().unwrap()
+        );
+
+        // An entry that extends beyond the end of the file
+        let mut file = vec![0x00, 0x05, 0x16, 0x07, 0x00, 0x02, 0x00, 0x00];
+        file.extend_from_slice(&[0; 16]);
+        file.extend_from_slice(&[0x00, 0x01]);
+        file.extend_from_slice(&[0, 0, 0, 2, 0, 0, 0, 38, 0, 0, 0, 5]);
+
+        assert_eq!(
+            AppleSingleError::InvalidHeader,
+            AppleSingleArchive::new(Cursor::new(&file)).err().unwrap()
+        );
+    }
+}
@@ -0,0 +1,281 @@
+use crate::util::i32_at;
+
+/// The number of seconds between the classic Mac OS epoch (midnight, January 1, 1904) and the
+/// AppleSingle version 2 epoch (midnight GMT, January 1, 2000).
+const MAC_EPOCH_OFFSET: i64 = 3_029_529_600;
+
+/// The attribute bit that indicates that a file is locked.
+const LOCKED: u32 = 0x0000_0001;
+
+/// The attribute bit that indicates that a file is protected.
+const PROTECTED: u32 = 0x0000_0002;
+
+/// The ID of an entry in an AppleSingle or AppleDouble file.
+///
+/// IDs 1 through 15 are defined by Apple; applications may use other IDs for their own entries.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum EntryId {
+    /// The file's data fork.
+    DataFork,
+
+    /// The file's resource fork.
+    ResourceFork,
+
+    /// The file's name as created on the home file system.
+    RealName,
+
+    /// The file's Get Info comment.
+    Comment,
+
+    /// The file's black-and-white icon.
+    IconBlackAndWhite,
+
+    /// The file's color icon.
+    IconColor,
+
+    /// The file's dates and attributes (version 1 only).
+    FileInfo,
+
+    /// The file's creation, modification, backup, and access dates (version 2 only).
+    FileDates,
+
+    /// The file's Finder information.
+    FinderInfo,
+
+    /// The file's Macintosh attributes (version 2 only).
+    MacFileInfo,
+
+    /// The file's ProDOS file information (version 2 only).
+    ProDosFileInfo,
+
+    /// The file's MS-DOS file information (version 2 only).
+    MsDosFileInfo,
+
+    /// The file's AFP short name (version 2 only).
+    ShortName,
+
+    /// The file's AFP file information (version 2 only).
+    AfpFileInfo,
+
+    /// The file's AFP directory ID (version 2 only).
+    DirectoryId,
+
+    /// An entry with an ID not defined by Apple.
+    Other(u32),
+}
+
+impl From<u32> for EntryId {
+    fn from(id: u32) -> Self {
+        match id {
+            1 => EntryId::DataFork,
+            2 => EntryId::ResourceFork,
+            3 => EntryId::RealName,
+            4 => EntryId::Comment,
+            5 => EntryId::IconBlackAndWhite,
+            6 => EntryId::IconColor,
+            7 => EntryId::FileInfo,
+            8 => EntryId::FileDates,
+            9 => EntryId::FinderInfo,
+            10 => EntryId::MacFileInfo,
+            11 => EntryId::ProDosFileInfo,
+            12 => EntryId::MsDosFileInfo,
+            13 => EntryId::ShortName,
+            14 => EntryId::AfpFileInfo,
+            15 => EntryId::DirectoryId,
+            _ => EntryId::Other(id),
+        }
+    }
+}
+
+impl From<EntryId> for u32 {
+    fn from(id: EntryId) -> Self {
+        match id {
+            EntryId::DataFork => 1,
+            EntryId::ResourceFork => 2,
+            EntryId::RealName => 3,
+            EntryId::Comment => 4,
+            EntryId::IconBlackAndWhite => 5,
+            EntryId::IconColor => 6,
+            EntryId::FileInfo => 7,
+            EntryId::FileDates => 8,
+            EntryId::FinderInfo => 9,
+            EntryId::MacFileInfo => 10,
+            EntryId::ProDosFileInfo => 11,
+            EntryId::MsDosFileInfo => 12,
+            EntryId::ShortName => 13,
+            EntryId::AfpFileInfo => 14,
+            EntryId::DirectoryId => 15,
+            EntryId::Other(id) => id,
+        }
+    }
+}
+
+/// The location of an entry's data within an AppleSingle or AppleDouble file.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Entry {
+    id: EntryId,
+    offset: u32,
+    length: u32,
+}
+
+impl Entry {
+    pub(super) fn new(id: EntryId, offset: u32, length: u32) -> Self {
+        Entry { id, offset, length }
+    }
+
+    /// Returns this entry's ID.
+    pub fn id(&self) -> EntryId {
+        self.id
+    }
+
+    /// Returns the offset, in bytes from the start of the file, of this entry's data.
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    /// Returns the length, in bytes, of this entry's data.
+    pub fn len(&self) -> u32 {
+        self.length
+    }
+
+    /// Returns `true` if this entry contains no data.
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+}
+
+/// A file's dates, in seconds relative to midnight GMT, January 1, 2000.
+///
+/// This is the representation used by version 2 of the AppleSingle format. Dates from version 1
+/// files (which are in seconds since midnight, January 1, 1904, in local time) are converted to
+/// this representation without any adjustment for time zones. Unknown dates are represented by
+/// [`FileDates::UNKNOWN`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct FileDates {
+    /// The date the file was created.
+    pub created: i32,
+
+    /// The date the file was last modified.
+    pub modified: i32,
+
+    /// The date the file was last backed up.
+    pub backed_up: i32,
+
+    /// The date the file was last accessed.
+    pub accessed: i32,
+}
+
+impl FileDates {
+    /// The value that represents an unknown date.
+    pub const UNKNOWN: i32 = i32::MIN;
+
+    pub(super) fn from_v2_bytes(bytes: &[u8; 16]) -> Self {
+        FileDates {
+            created: i32_at(bytes, 0),
+            modified: i32_at(bytes, 4),
+            backed_up: i32_at(bytes, 8),
+            accessed: i32_at(bytes, 12),
+        }
+    }
+
+    pub(super) fn to_v2_bytes(self) -> [u8; 16] {
+        let mut bytes = [0; 16];
+
+        bytes[0..4].copy_from_slice(&self.created.to_be_bytes());
+        bytes[4..8].copy_from_slice(&self.modified.to_be_bytes());
+        bytes[8..12].copy_from_slice(&self.backed_up.to_be_bytes());
+        bytes[12..16].copy_from_slice(&self.accessed.to_be_bytes());
+
+        bytes
+    }
+
+    /// Converts a classic Mac OS date (in seconds since midnight, January 1, 1904) to a date
+    /// relative to January 1, 2000.
+    pub(crate) fn from_mac_date(date: u32) -> i32 {
+        match date {
+            0 => FileDates::UNKNOWN,
+            _ => (date as i64 - MAC_EPOCH_OFFSET) as i32,
+        }
+    }
+
+    /// Converts a date relative to January 1, 2000 to a classic Mac OS date, which may not be able
+    /// to represent dates after 2040.
+    pub(crate) fn to_mac_date(date: i32) -> u32 {
+        match date {
+            FileDates::UNKNOWN => 0,
+            _ => (date as i64 + MAC_EPOCH_OFFSET).clamp(0, u32::MAX as i64) as u32,
+        }
+    }
+}
+
+impl Default for FileDates {
+    fn default() -> Self {
+        FileDates {
+            created: FileDates::UNKNOWN,
+            modified: FileDates::UNKNOWN,
+            backed_up: FileDates::UNKNOWN,
+            accessed: FileDates::UNKNOWN,
+        }
+    }
+}
+
+/// A file's Macintosh attributes.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct MacFileInfo {
+    /// `true` if the file is locked.
+    pub locked: bool,
+
+    /// `true` if the file is protected.
+    pub protected: bool,
+}
+
+impl From<u32> for MacFileInfo {
+    fn from(attributes: u32) -> Self {
+        MacFileInfo {
+            locked: attributes & LOCKED != 0,
+            protected: attributes & PROTECTED != 0,
+        }
+    }
+}
+
+impl From<MacFileInfo> for u32 {
+    fn from(info: MacFileInfo) -> Self {
+        let mut attributes = 0;
+
+        if info.locked {
+            attributes |= LOCKED;
+        }
+
+        if info.protected {
+            attributes |= PROTECTED;
+        }
+
+        attributes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn entry_ids() {
+        for id in 0..20 {
+            assert_eq!(id, u32::from(EntryId::from(id)));
+        }
+
+        assert_eq!(EntryId::ResourceFork, EntryId::from(2));
+        assert_eq!(EntryId::Other(0x8000_0001), EntryId::from(0x8000_0001));
+    }
+
+    #[test]
+    fn mac_dates() {
+        // Midnight, January 1, 2000
+        assert_eq!(0, FileDates::from_mac_date(0xb492_f400));
+        assert_eq!(0xb492_f400, FileDates::to_mac_date(0));
+
+        assert_eq!(FileDates::UNKNOWN, FileDates::from_mac_date(0));
+        assert_eq!(0, FileDates::to_mac_date(FileDates::UNKNOWN));
+        assert_eq!(u32::MAX, FileDates::to_mac_date(i32::MAX));
+    }
+}
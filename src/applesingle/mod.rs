@@ -0,0 +1,21 @@
+//! Tools for reading and writing AppleSingle and AppleDouble files.
+//!
+//! AppleSingle files combine the data fork, resource fork, and metadata associated with a "classic"
+//! Mac file into a single binary file made up of a header and a series of "entries." AppleDouble
+//! files use the same structure, but store everything except the data fork in a separate header
+//! file; macOS, for example, writes AppleDouble header files named `._<filename>` when copying files
+//! to file systems that don't support resource forks.
+//!
+//! Both version 1 and version 2 of the format can be read and written. For details about version 2
+//! of the format, please see [RFC 1740: MIME Encapsulation of Macintosh files -
+//! MacMIME](https://tools.ietf.org/html/rfc1740).
+
+mod archive;
+mod entry;
+mod write;
+
+pub use archive::{
+    AppleSingleArchive, AppleSingleError, AppleSingleKind, AppleSingleVersion, EntryReader,
+};
+pub use entry::{Entry, EntryId, FileDates, MacFileInfo};
+pub use write::AppleSingleWriter;
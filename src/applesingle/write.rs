@@ -0,0 +1,512 @@
+use std::io::{Read, Write};
+
+use super::archive::{
+    AppleSingleError, AppleSingleKind, AppleSingleVersion, ENTRY_DESCRIPTOR_LENGTH, HEADER_LENGTH,
+};
+use super::entry::{EntryId, FileDates, MacFileInfo};
+use crate::finder::FinderInfo;
+use crate::util::copy_exact;
+
+/// The "home file system" field written in version 1 headers.
+const MACINTOSH_HOME_FILE_SYSTEM: &[u8; 16] = b"Macintosh       ";
+
+/// Writes AppleSingle files and AppleDouble header files.
+///
+/// An `AppleSingleWriter` collects a file's metadata, then writes it along with the file's forks
+/// when [`AppleSingleWriter::encode`] is called. Metadata entries are written before the forks, and
+/// the data fork (if any) is always written last.
+pub struct AppleSingleWriter<W: Write> {
+    dest: W,
+    kind: AppleSingleKind,
+    version: AppleSingleVersion,
+    real_name: Option<Vec<u8>>,
+    comment: Option<Vec<u8>>,
+    finder_info: Option<FinderInfo>,
+    extended_finder_info: Option<[u8; 16]>,
+    file_dates: Option<FileDates>,
+    mac_file_info: Option<MacFileInfo>,
+    other_entries: Vec<(EntryId, Vec<u8>)>,
+}
+
+impl<W: Write> AppleSingleWriter<W> {
+    /// Creates a new writer that will write a file of the given kind and version, with no metadata,
+    /// to the given destination.
+    pub fn new(dest: W, kind: AppleSingleKind, version: AppleSingleVersion) -> Self {
+        AppleSingleWriter {
+            dest,
+            kind,
+            version,
+            real_name: None,
+            comment: None,
+            finder_info: None,
+            extended_finder_info: None,
+            file_dates: None,
+            mac_file_info: None,
+            other_entries: vec![],
+        }
+    }
+
+    /// Sets the file's real name.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if the given name is empty or contains characters that
+    /// can't be represented in the Macintosh character encoding.
+    pub fn set_real_name(&mut self, name: &str) -> Result<(), AppleSingleError> {
+        let (name_bytes, _, had_errors) = encoding_rs::MACINTOSH.encode(name);
+
+        if name_bytes.is_empty() || had_errors {
+            return Err(AppleSingleError::InvalidFilename);
+        }
+
+        self.real_name = Some(name_bytes.into_owned());
+
+        Ok(())
+    }
+
+    /// Sets the file's Get Info comment, which should be encoded in the Macintosh character
+    /// encoding.
+    pub fn set_comment(&mut self, comment: &[u8]) {
+        self.comment = Some(comment.to_vec());
+    }
+
+    /// Sets the file's Finder information.
+    pub fn set_finder_info(&mut self, finder_info: FinderInfo) {
+        self.finder_info = Some(finder_info);
+    }
+
+    /// Sets the file's extended Finder information (known as `FXInfo` in "Inside Macintosh").
+    ///
+    /// Extended Finder information is written in the same entry as the basic Finder information; if
+    /// no basic Finder information has been set, the basic portion of the entry is zeroed.
+    pub fn set_extended_finder_info(&mut self, extended_finder_info: [u8; 16]) {
+        self.extended_finder_info = Some(extended_finder_info);
+    }
+
+    /// Sets the file's dates.
+    ///
+    /// Version 1 files can't represent access dates or dates outside of the range of classic Mac OS
+    /// dates (which end in 2040); such dates are clamped or discarded when writing version 1 files.
+    pub fn set_file_dates(&mut self, file_dates: FileDates) {
+        self.file_dates = Some(file_dates);
+    }
+
+    /// Sets the file's Macintosh attributes.
+    pub fn set_mac_file_info(&mut self, mac_file_info: MacFileInfo) {
+        self.mac_file_info = Some(mac_file_info);
+    }
+
+    /// Adds an entry with the given ID and raw content, replacing any entry previously added with
+    /// this method with the same ID.
+    ///
+    /// Entries added with this method are written in addition to (and after) any entries generated
+    /// from typed metadata, and so callers should generally avoid adding entries that duplicate
+    /// typed metadata.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if the given ID identifies a data or resource fork entry;
+    /// forks are provided to [`AppleSingleWriter::encode`] instead.
+    pub fn set_entry(&mut self, id: EntryId, content: Vec<u8>) -> Result<(), AppleSingleError> {
+        if id == EntryId::DataFork || id == EntryId::ResourceFork {
+            return Err(AppleSingleError::InvalidEntry(id));
+        }
+
+        match self
+            .other_entries
+            .iter_mut()
+            .find(|(entry_id, _)| *entry_id == id)
+        {
+            Some(entry) => entry.1 = content,
+            None => self.other_entries.push((id, content)),
+        }
+
+        Ok(())
+    }
+
+    /// Writes a complete file containing this writer's metadata and the given forks to this
+    /// writer's destination, returning the destination when the file has been written.
+    ///
+    /// Exactly `data_fork_len` bytes are read from `data_fork`, and exactly `resource_fork_len`
+    /// bytes are read from `resource_fork`. Empty forks are omitted from the written file.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error immediately if it encounters an IO error while reading from
+    /// either fork or writing to the destination, or if either fork ends before the given number of
+    /// bytes could be read. It also returns an error if a non-empty data fork is provided when
+    /// writing an AppleDouble header file or if the file would be too large to be represented in an
+    /// AppleSingle header.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use std::io;
+    /// use clarus::applesingle::{AppleSingleError, AppleSingleKind, AppleSingleVersion, AppleSingleWriter};
+    /// use clarus::finder::{FinderFlags, FinderInfo};
+    ///
+    /// fn main() -> Result<(), AppleSingleError> {
+    ///     let mut resource_file = File::open("example.rsrc")?;
+    ///     let resource_fork_len = resource_file.metadata()?.len() as usize;
+    ///
+    ///     let mut writer = AppleSingleWriter::new(
+    ///         File::create("._example.txt")?,
+    ///         AppleSingleKind::AppleDouble,
+    ///         AppleSingleVersion::V2,
+    ///     );
+    ///
+    ///     writer.set_finder_info(FinderInfo::new(*b"TEXT", *b"ttxt", FinderFlags::default()));
+    ///     writer.encode(&mut io::empty(), 0, &mut resource_file, resource_fork_len)?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn encode(
+        mut self,
+        data_fork: &mut impl Read,
+        data_fork_len: usize,
+        resource_fork: &mut impl Read,
+        resource_fork_len: usize,
+    ) -> Result<W, AppleSingleError> {
+        if self.kind == AppleSingleKind::AppleDouble && data_fork_len > 0 {
+            return Err(AppleSingleError::InvalidHeader);
+        }
+
+        let mut entries = self.metadata_entries();
+
+        if entries.len() + 2 > u16::MAX as usize {
+            return Err(AppleSingleError::InvalidHeader);
+        }
+
+        let fork_entries = [
+            (EntryId::ResourceFork, resource_fork_len),
+            (EntryId::DataFork, data_fork_len),
+        ];
+
+        let entry_count = entries.len() + fork_entries.iter().filter(|(_, len)| *len > 0).count();
+
+        let mut header = Vec::with_capacity(HEADER_LENGTH + entry_count * ENTRY_DESCRIPTOR_LENGTH);
+        header.extend_from_slice(&self.kind.magic().to_be_bytes());
+        header.extend_from_slice(&self.version.number().to_be_bytes());
+        header.extend_from_slice(match self.version {
+            AppleSingleVersion::V1 => MACINTOSH_HOME_FILE_SYSTEM,
+            AppleSingleVersion::V2 => &[0; 16],
+        });
+        header.extend_from_slice(&(entry_count as u16).to_be_bytes());
+
+        let mut offset = (HEADER_LENGTH + entry_count * ENTRY_DESCRIPTOR_LENGTH) as u64;
+
+        let descriptors = entries
+            .iter()
+            .map(|(id, content)| (*id, content.len()))
+            .chain(fork_entries.iter().copied().filter(|(_, len)| *len > 0));
+
+        for (id, len) in descriptors {
+            if offset + len as u64 > u32::MAX as u64 {
+                return Err(AppleSingleError::InvalidHeader);
+            }
+
+            header.extend_from_slice(&u32::from(id).to_be_bytes());
+            header.extend_from_slice(&(offset as u32).to_be_bytes());
+            header.extend_from_slice(&(len as u32).to_be_bytes());
+
+            offset += len as u64;
+        }
+
+        self.dest.write_all(&header)?;
+
+        for (_, content) in entries.drain(..) {
+            self.dest.write_all(&content)?;
+        }
+
+        copy_exact(resource_fork, &mut self.dest, resource_fork_len as u64)?;
+        copy_exact(data_fork, &mut self.dest, data_fork_len as u64)?;
+
+        Ok(self.dest)
+    }
+
+    /// Serializes this writer's metadata as a list of entries in the order in which they should be
+    /// written.
+    fn metadata_entries(&self) -> Vec<(EntryId, Vec<u8>)> {
+        let mut entries = vec![];
+
+        if let Some(real_name) = &self.real_name {
+            entries.push((EntryId::RealName, real_name.clone()));
+        }
+
+        if let Some(comment) = &self.comment {
+            entries.push((EntryId::Comment, comment.clone()));
+        }
+
+        match self.version {
+            AppleSingleVersion::V1 => {
+                if self.file_dates.is_some() || self.mac_file_info.is_some() {
+                    let file_dates = self.file_dates.unwrap_or_default();
+                    let attributes = u32::from(self.mac_file_info.unwrap_or_default());
+
+                    let mut file_info = Vec::with_capacity(16);
+                    file_info.extend_from_slice(
+                        &FileDates::to_mac_date(file_dates.created).to_be_bytes(),
+                    );
+                    file_info.extend_from_slice(
+                        &FileDates::to_mac_date(file_dates.modified).to_be_bytes(),
+                    );
+                    file_info.extend_from_slice(
+                        &FileDates::to_mac_date(file_dates.backed_up).to_be_bytes(),
+                    );
+                    file_info.extend_from_slice(&attributes.to_be_bytes());
+
+                    entries.push((EntryId::FileInfo, file_info));
+                }
+            }
+            AppleSingleVersion::V2 => {
+                if let Some(file_dates) = self.file_dates {
+                    entries.push((EntryId::FileDates, file_dates.to_v2_bytes().to_vec()));
+                }
+            }
+        }
+
+        if self.finder_info.is_some() || self.extended_finder_info.is_some() {
+            let mut finder_info = Vec::with_capacity(32);
+            finder_info.extend_from_slice(&<[u8; 16]>::from(&self.finder_info.unwrap_or_default()));
+            finder_info.extend_from_slice(&self.extended_finder_info.unwrap_or_default());
+
+            entries.push((EntryId::FinderInfo, finder_info));
+        }
+
+        if let (AppleSingleVersion::V2, Some(mac_file_info)) = (self.version, self.mac_file_info) {
+            entries.push((
+                EntryId::MacFileInfo,
+                u32::from(mac_file_info).to_be_bytes().to_vec(),
+            ));
+        }
+
+        entries.extend(self.other_entries.iter().cloned());
+
+        entries
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::applesingle::AppleSingleArchive;
+    use crate::finder::FinderFlags;
+    use crate::rsrc::ResourceFork;
+    use std::io::Cursor;
+
+    const DATA_FORK: &[u8] = b"===== Hello from the data fork! =====";
+    const RESOURCE_FORK: &[u8] = include_bytes!("../rsrc/string-table.rsrc");
+
+    fn encode(
+        kind: AppleSingleKind,
+        version: AppleSingleVersion,
+        data_fork: &[u8],
+    ) -> Result<Vec<u8>, AppleSingleError> {
+        let mut finder_info = FinderInfo::new(*b"TEXT", *b"ttxt", FinderFlags::from(0x0100));
+        finder_info.set_location((16, 32));
+
+        let mut writer = AppleSingleWriter::new(vec![], kind, version);
+        writer.set_real_name("SimpleText™ Document")?;
+        writer.set_comment(b"A comment");
+        writer.set_finder_info(finder_info);
+        writer.set_file_dates(FileDates {
+            created: 1_000,
+            modified: 2_000,
+            backed_up: FileDates::UNKNOWN,
+            accessed: 3_000,
+        });
+        writer.set_mac_file_info(MacFileInfo {
+            locked: true,
+            protected: false,
+        });
+
+        writer.encode(
+            &mut Cursor::new(data_fork),
+            data_fork.len(),
+            &mut Cursor::new(RESOURCE_FORK),
+            RESOURCE_FORK.len(),
+        )
+    }
+
+    #[test]
+    fn round_trip_v2() -> Result<(), AppleSingleError> {
+        let encoded = encode(
+            AppleSingleKind::AppleSingle,
+            AppleSingleVersion::V2,
+            DATA_FORK,
+        )?;
+
+        let mut archive = AppleSingleArchive::new(Cursor::new(encoded))?;
+
+        assert_eq!(AppleSingleKind::AppleSingle, archive.kind());
+        assert_eq!(AppleSingleVersion::V2, archive.version());
+        assert_eq!(
+            Some(String::from("SimpleText™ Document")),
+            archive.real_name()?
+        );
+        assert_eq!(Some(b"A comment".to_vec()), archive.comment()?);
+
+        let finder_info = archive.finder_info()?.unwrap();
+        assert_eq!(*b"TEXT", finder_info.file_type());
+        assert_eq!(*b"ttxt", finder_info.creator());
+        assert_eq!(0x0100, finder_info.flags().bits());
+        assert_eq!((16, 32), finder_info.location());
+        assert_eq!(Some([0; 16]), archive.extended_finder_info()?);
+
+        assert_eq!(
+            Some(FileDates {
+                created: 1_000,
+                modified: 2_000,
+                backed_up: FileDates::UNKNOWN,
+                accessed: 3_000,
+            }),
+            archive.file_dates()?
+        );
+        assert_eq!(
+            Some(MacFileInfo {
+                locked: true,
+                protected: false
+            }),
+            archive.mac_file_info()?
+        );
+
+        assert_eq!(DATA_FORK, archive.read_entry(EntryId::DataFork)?.as_slice());
+        assert_eq!(
+            RESOURCE_FORK,
+            archive.read_entry(EntryId::ResourceFork)?.as_slice()
+        );
+
+        // The data fork should be the last entry in the file
+        let last_entry = archive
+            .entries()
+            .iter()
+            .max_by_key(|entry| entry.offset())
+            .unwrap();
+
+        assert_eq!(EntryId::DataFork, last_entry.id());
+
+        Ok(())
+    }
+
+    #[test]
+    fn round_trip_v1() -> Result<(), AppleSingleError> {
+        let encoded = encode(
+            AppleSingleKind::AppleSingle,
+            AppleSingleVersion::V1,
+            DATA_FORK,
+        )?;
+
+        assert_eq!(MACINTOSH_HOME_FILE_SYSTEM, &encoded[8..24]);
+
+        let mut archive = AppleSingleArchive::new(Cursor::new(encoded))?;
+
+        assert_eq!(AppleSingleVersion::V1, archive.version());
+        assert!(archive.entry(EntryId::FileInfo).is_some());
+        assert!(archive.entry(EntryId::FileDates).is_none());
+        assert!(archive.entry(EntryId::MacFileInfo).is_none());
+
+        // Version 1 files can't represent access dates
+        assert_eq!(
+            Some(FileDates {
+                created: 1_000,
+                modified: 2_000,
+                backed_up: FileDates::UNKNOWN,
+                accessed: FileDates::UNKNOWN,
+            }),
+            archive.file_dates()?
+        );
+        assert_eq!(
+            Some(MacFileInfo {
+                locked: true,
+                protected: false
+            }),
+            archive.mac_file_info()?
+        );
+
+        assert_eq!(DATA_FORK, archive.read_entry(EntryId::DataFork)?.as_slice());
+
+        Ok(())
+    }
+
+    #[test]
+    fn apple_double() -> Result<(), AppleSingleError> {
+        assert_eq!(
+            AppleSingleError::InvalidHeader,
+            encode(
+                AppleSingleKind::AppleDouble,
+                AppleSingleVersion::V2,
+                DATA_FORK
+            )
+            .err()
+            .unwrap()
+        );
+
+        let encoded = encode(AppleSingleKind::AppleDouble, AppleSingleVersion::V2, &[])?;
+        let mut archive = AppleSingleArchive::new(Cursor::new(encoded))?;
+
+        assert_eq!(AppleSingleKind::AppleDouble, archive.kind());
+        assert!(archive.entry(EntryId::DataFork).is_none());
+        assert_eq!(
+            AppleSingleError::EntryNotFound(EntryId::DataFork),
+            archive.data_fork().err().unwrap()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn resource_fork_entry() -> Result<(), AppleSingleError> {
+        let encoded = encode(
+            AppleSingleKind::AppleSingle,
+            AppleSingleVersion::V2,
+            DATA_FORK,
+        )?;
+
+        let mut archive = AppleSingleArchive::new(Cursor::new(encoded))?;
+        let resource_fork = ResourceFork::new(archive.resource_fork()?).unwrap();
+
+        assert!(resource_fork.resources().count() > 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn set_entry() -> Result<(), AppleSingleError> {
+        let mut writer =
+            AppleSingleWriter::new(vec![], AppleSingleKind::AppleSingle, AppleSingleVersion::V2);
+
+        writer.set_entry(EntryId::Other(0x8000_0000), b"first".to_vec())?;
+        writer.set_entry(EntryId::Other(0x8000_0000), b"second".to_vec())?;
+
+        assert_eq!(
+            Err(AppleSingleError::InvalidEntry(EntryId::DataFork)),
+            writer.set_entry(EntryId::DataFork, b"rejected".to_vec())
+        );
+
+        assert_eq!(
+            Err(AppleSingleError::InvalidEntry(EntryId::ResourceFork)),
+            writer.set_entry(EntryId::ResourceFork, b"rejected".to_vec())
+        );
+
+        let encoded = writer.encode(&mut std::io::empty(), 0, &mut std::io::empty(), 0)?;
+        let mut archive = AppleSingleArchive::new(Cursor::new(encoded))?;
+
+        assert_eq!(1, archive.entries().len());
+        assert_eq!(
+            b"second".to_vec(),
+            archive.read_entry(EntryId::Other(0x8000_0000))?
+        );
+
+        assert_eq!(
+            AppleSingleError::InvalidFilename,
+            AppleSingleWriter::new(vec![], AppleSingleKind::AppleSingle, AppleSingleVersion::V2)
+                .set_real_name("")
+                .err()
+                .unwrap()
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,309 @@
+use std::io::{Chain, Cursor, ErrorKind, Read, Write};
+
+use super::archive::{BinHexArchive, BinHexError};
+use super::header::BinHexHeader;
+use super::legacy::{banner_format, LegacyBinHexArchive};
+use super::parts::find;
+use super::read::BANNER;
+
+const READ_SIZE: usize = 8192;
+
+/// A BinHex text format.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum BinHexFormat {
+    /// The hexadecimal format written by BinHex 1.0 and 2.0, usually with a `.hex` extension.
+    Hex,
+
+    /// The compressed format written by BinHex 3.0, usually with a `.hcx` extension.
+    Compressed,
+
+    /// The BinHex 4.0 format, usually with a `.hqx` extension.
+    BinHex4,
+}
+
+impl BinHexFormat {
+    /// Returns the file extension conventionally used for archives in this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            BinHexFormat::Hex => "hex",
+            BinHexFormat::Compressed => "hcx",
+            BinHexFormat::BinHex4 => "hqx",
+        }
+    }
+}
+
+/// Returns the format named by the first BinHex banner found anywhere in the given text, if any.
+///
+/// Banners that name an unrecognized version are assumed to introduce BinHex 4.0 archives, just as
+/// they are by [`AnyBinHexArchive::new`].
+pub(crate) fn sniff_format(text: &[u8]) -> Option<BinHexFormat> {
+    let start = find(text, BANNER)?;
+
+    Some(banner_format(&text[start + BANNER.len()..]).unwrap_or(BinHexFormat::BinHex4))
+}
+
+/// A BinHex archive in any of the formats written by BinHex 1.0 through 4.0.
+///
+/// The format of the archive is detected from the version named in its banner (e.g. `(This file
+/// must be converted with BinHex 2.0)`). Sources with no banner, or with a banner that names an
+/// unrecognized version, are read as BinHex 4.0 archives.
+///
+/// To detect an archive's format, the source is buffered in memory up to the end of its banner;
+/// if the source has no banner, the entire source is buffered.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs::File;
+/// use clarus::binhex::{AnyBinHexArchive, BinHexError};
+///
+/// fn main() -> Result<(), BinHexError> {
+///     let archive = AnyBinHexArchive::new(File::open("unknown-vintage.txt")?)?;
+///
+///     println!("{} is a .{} archive", archive.header().filename(), archive.format().extension());
+///
+///     archive.extract(
+///         &mut File::create("data_fork.bin")?,
+///         &mut File::create("resource_fork.bin")?,
+///     )?;
+///
+///     Ok(())
+/// }
+/// ```
+pub enum AnyBinHexArchive<R: Read> {
+    /// A BinHex 1.0, 2.0, or 3.0 archive.
+    Legacy(LegacyBinHexArchive),
+
+    /// A BinHex 4.0 archive.
+    BinHex4(Box<BinHexArchive<Chain<Cursor<Vec<u8>>, R>>>),
+}
+
+impl<R: Read> AnyBinHexArchive<R> {
+    /// Detects the format of the archive in the given source and opens it.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the archive can't be opened in its detected format (see
+    /// [`BinHexArchive::new`] and [`LegacyBinHexArchive::new`]) or if an IO error occurs while
+    /// reading from the source.
+    pub fn new(mut source: R) -> Result<Self, BinHexError> {
+        let mut prefix = vec![];
+
+        // Each block read from the source is only scanned once; because a banner may straddle two
+        // blocks, the search for the next banner backs up by one byte less than a banner's length.
+        let mut banner_search_start = 0;
+        let mut version_start = None;
+        let mut version_end_search_start = 0;
+
+        let format = loop {
+            if version_start.is_none() {
+                match find(&prefix[banner_search_start..], BANNER) {
+                    Some(start) => {
+                        let start = banner_search_start + start + BANNER.len();
+
+                        version_start = Some(start);
+                        version_end_search_start = start;
+                    }
+                    None => {
+                        banner_search_start =
+                            banner_search_start.max(prefix.len().saturating_sub(BANNER.len() - 1));
+                    }
+                }
+            }
+
+            if let Some(version_start) = version_start {
+                if memchr::memchr(b')', &prefix[version_end_search_start..]).is_some() {
+                    break banner_format(&prefix[version_start..]);
+                }
+
+                version_end_search_start = prefix.len();
+            }
+
+            let len = prefix.len();
+            prefix.resize(len + READ_SIZE, 0);
+
+            let bytes_read = loop {
+                match source.read(&mut prefix[len..]) {
+                    Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                    result => break result?,
+                }
+            };
+
+            prefix.truncate(len + bytes_read);
+
+            if bytes_read == 0 {
+                break None;
+            }
+        };
+
+        match format {
+            Some(BinHexFormat::Hex) | Some(BinHexFormat::Compressed) => {
+                // Legacy archives are decoded all at once, so there's no need to copy the prefix
+                // into a second buffer
+                source.read_to_end(&mut prefix)?;
+
+                Ok(AnyBinHexArchive::Legacy(LegacyBinHexArchive::from_text(
+                    &prefix,
+                )?))
+            }
+            _ => Ok(AnyBinHexArchive::BinHex4(Box::new(BinHexArchive::new(
+                Cursor::new(prefix).chain(source),
+            )?))),
+        }
+    }
+
+    /// Returns the format of this archive.
+    pub fn format(&self) -> BinHexFormat {
+        match self {
+            AnyBinHexArchive::Legacy(archive) => archive.format(),
+            AnyBinHexArchive::BinHex4(_) => BinHexFormat::BinHex4,
+        }
+    }
+
+    /// Returns this archive's header.
+    pub fn header(&self) -> &BinHexHeader {
+        match self {
+            AnyBinHexArchive::Legacy(archive) => archive.header(),
+            AnyBinHexArchive::BinHex4(archive) => archive.header(),
+        }
+    }
+
+    /// Writes the archived file's data and resource forks to the given writers.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error if the archive's data is malformed, if a checksum fails, or if
+    /// an IO error occurs while reading or writing either fork.
+    pub fn extract(
+        self,
+        data_writer: &mut impl Write,
+        resource_writer: &mut impl Write,
+    ) -> Result<(), BinHexError> {
+        match self {
+            AnyBinHexArchive::Legacy(archive) => archive.extract(data_writer, resource_writer),
+            AnyBinHexArchive::BinHex4(archive) => archive.extract(data_writer, resource_writer),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn detect_hex() -> Result<(), BinHexError> {
+        let archive = AnyBinHexArchive::new(
+            &indoc! {b"
+                Some text before the archive.
+
+                (This file must be converted with BinHex 1.0)
+                #TEXTttxt
+                ***DATA
+                4869
+                ***END
+            "}[..],
+        )?;
+
+        assert_eq!(BinHexFormat::Hex, archive.format());
+        assert_eq!(2, archive.header().data_fork_len());
+
+        let mut data_fork = vec![];
+        let mut resource_fork = vec![];
+
+        archive.extract(&mut data_fork, &mut resource_fork)?;
+
+        assert_eq!(b"Hi", data_fork.as_slice());
+        assert!(resource_fork.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn detect_compressed() -> Result<(), BinHexError> {
+        let archive = AnyBinHexArchive::new(
+            &indoc! {b"
+                (This file must be converted with BinHex 3.0)
+                #TEXTttxt
+                ***DATA
+                2&D
+            "}[..],
+        )?;
+
+        assert_eq!(BinHexFormat::Compressed, archive.format());
+        assert_eq!(2, archive.header().data_fork_len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn detect_binhex_4() -> Result<(), BinHexError> {
+        let archive = AnyBinHexArchive::new(
+            &indoc! {b"
+                (This file must be converted with BinHex 4.0)
+
+                :$f*TEQKPH#edCA0d,R4iG!#3$L8!N!-TR@dpN!8J5'9XE'mJCR*[E5\"dD'8JC'&
+                dB5\"QEh*V)5!pN!9Bm5f3\"5\")C@aXEb\"QFQpY)(4SC5\"bCA0[GA*MC5\"QEh*V)5!
+                YN!8SI!:
+            "}[..],
+        )?;
+
+        assert_eq!(BinHexFormat::BinHex4, archive.format());
+        assert_eq!(
+            &String::from("binhex-test.txt"),
+            archive.header().filename()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn detect_banner_across_reads() -> Result<(), BinHexError> {
+        /// A source that only ever returns a single byte from each read.
+        struct ByteReader<'a>(&'a [u8]);
+
+        impl Read for ByteReader<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let len = self.0.len().min(buf.len()).min(1);
+                buf[..len].copy_from_slice(&self.0[..len]);
+                self.0 = &self.0[len..];
+
+                Ok(len)
+            }
+        }
+
+        let archive = AnyBinHexArchive::new(ByteReader(indoc! {b"
+            (This file must be converted with BinHex 4.0)
+
+            :$f*TEQKPH#edCA0d,R4iG!#3$L8!N!-TR@dpN!8J5'9XE'mJCR*[E5\"dD'8JC'&
+            dB5\"QEh*V)5!pN!9Bm5f3\"5\")C@aXEb\"QFQpY)(4SC5\"bCA0[GA*MC5\"QEh*V)5!
+            YN!8SI!:
+        "}))?;
+
+        assert_eq!(BinHexFormat::BinHex4, archive.format());
+        assert_eq!(
+            &String::from("binhex-test.txt"),
+            archive.header().filename()
+        );
+
+        let archive = AnyBinHexArchive::new(ByteReader(indoc! {b"
+            Some text before the archive.
+            (This file must be converted with BinHex 2.0)
+            #TEXTttxt
+            ***DATA
+            4869
+        "}))?;
+
+        assert_eq!(BinHexFormat::Hex, archive.format());
+        assert_eq!(2, archive.header().data_fork_len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn extension() {
+        assert_eq!("hex", BinHexFormat::Hex.extension());
+        assert_eq!("hcx", BinHexFormat::Compressed.extension());
+        assert_eq!("hqx", BinHexFormat::BinHex4.extension());
+    }
+}
@@ -1,20 +1,26 @@
+use std::cmp;
+use std::collections::VecDeque;
 use std::convert::{TryFrom, TryInto};
 use std::error;
 use std::fmt::{self, Display, Formatter};
 use std::hash::Hasher;
 use std::io::{self, Read, Write};
-use std::ops::Deref;
 
-use super::expand::BinHexExpander;
-use super::read::EncodedBinHexReader;
+use super::compress::BinHexCompressor;
+use super::decode::{Radix64Decoder, BINHEX_ALPHABET};
+use super::expand::{BinHexExpander, RunLengthError};
+use super::header::BinHexHeader;
+use super::read::{Anomaly, EncodedBinHexReader, InvalidCharacterError};
+use super::write::EncodedBinHexWriter;
+
+use crate::finder::FinderFlags;
+use crate::util::copy_exact;
 
 use crc16::{State, XMODEM};
-use radix64::io::DecodeReader;
 use radix64::CustomConfig;
 
 lazy_static::lazy_static! {
-    static ref BINHEX_CONFIG: CustomConfig = CustomConfig::with_alphabet(
-        r##"!"#$%&'()*+,-012345689@ABCDEFGHIJKLMNPQRSTUVXYZ[`abcdefhijklmpqr"##)
+    static ref BINHEX_CONFIG: CustomConfig = CustomConfig::with_alphabet(BINHEX_ALPHABET)
     .no_padding()
     .build()
     .expect("Failed to build BinHex base64 config");
@@ -25,8 +31,9 @@ lazy_static::lazy_static! {
 /// BinHex archives encode the data fork, resource fork, and metadata associated with a "classic"
 /// Macintosh file.
 pub struct BinHexArchive<R: Read> {
-    source: BinHexExpander<DecodeReader<&'static CustomConfig, EncodedBinHexReader<R>>>,
+    source: Source<R>,
     header: BinHexHeader,
+    position: Position,
 }
 
 impl<R: Read> BinHexArchive<R> {
@@ -37,9 +44,40 @@ impl<R: Read> BinHexArchive<R> {
     /// This function will return an error if a valid BinHex header could not be read from the given
     /// source.
     pub fn new(source: R) -> Result<Self, BinHexError> {
-        let reader = EncodedBinHexReader::new(source);
-        let decoder = DecodeReader::new(BINHEX_CONFIG.deref(), reader);
-        let mut expander = BinHexExpander::new(decoder);
+        BinHexArchive::with_options(source, BinHexOptions::default())
+    }
+
+    /// Creates a new BinHex archive that will extract data from the given reader using the given
+    /// options.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a BinHex header could not be read from the given
+    /// source. In recovery mode, a header whose checksum doesn't match its content is recorded as
+    /// an anomaly rather than returned as an error.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use clarus::binhex::{BinHexArchive, BinHexError, BinHexOptions};
+    ///
+    /// fn main() -> Result<(), BinHexError> {
+    ///     let binhex_file = File::open("damaged.hqx")?;
+    ///     let archive = BinHexArchive::with_options(binhex_file, BinHexOptions::new().with_recovery(true))?;
+    ///
+    ///     let mut data_fork_content = vec![];
+    ///     let mut rsrc_fork_content = vec![];
+    ///
+    ///     for anomaly in archive.extract_with_report(&mut data_fork_content, &mut rsrc_fork_content)? {
+    ///         println!("Recovered from {}", anomaly);
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn with_options(source: R, options: BinHexOptions) -> Result<Self, BinHexError> {
+        let mut source = Source::new(source, options);
 
         let header = {
             // Headers have a minimum size of 22 bytes (assuming a zero-length name) and a maximum
@@ -48,25 +86,48 @@ impl<R: Read> BinHexArchive<R> {
             let mut header_bytes = Vec::with_capacity(277);
             header_bytes.resize(22, 0);
 
-            expander.read_exact(header_bytes.as_mut_slice())?;
+            source.read_exact(header_bytes.as_mut_slice(), ChecksumSection::Header, 0)?;
 
             let name_length = header_bytes[0] as usize;
 
             header_bytes.resize(header_bytes.len() + name_length, 0);
-            expander.read_exact(&mut header_bytes.as_mut_slice()[22..])?;
+            source.read_exact(
+                &mut header_bytes.as_mut_slice()[22..],
+                ChecksumSection::Header,
+                22,
+            )?;
+
+            if options.recovery {
+                // Record a bad checksum, then replace it so we can still parse the header
+                let (content, checksum_bytes) = header_bytes.split_at_mut(name_length + 20);
+
+                let provided_checksum = u16::from_be_bytes((&*checksum_bytes).try_into().unwrap());
+                let calculated_checksum = crc16::State::<crc16::XMODEM>::calculate(content);
+
+                if provided_checksum != calculated_checksum {
+                    source.anomalies.push(BinHexError::InvalidChecksum(
+                        ChecksumSection::Header,
+                        provided_checksum,
+                        calculated_checksum,
+                    ));
+
+                    checksum_bytes.copy_from_slice(&calculated_checksum.to_be_bytes());
+                }
+            }
 
             BinHexHeader::try_from(header_bytes)?
         };
 
         Ok(BinHexArchive {
-            source: expander,
+            source,
             header,
+            position: Position::Fork(ChecksumSection::DataFork, 0, State::<XMODEM>::new()),
         })
     }
 
     /// Returns the original filename of the file contained in this archive.
     pub fn filename(&mut self) -> &String {
-        &self.header.name
+        self.header.filename()
     }
 
     /// Returns the file type identifier for the file contained in this archive.
@@ -76,7 +137,7 @@ impl<R: Read> BinHexArchive<R> {
     /// Documents" section of "Inside Macintosh: Macintosh Toolbox
     /// Essentials"](https://developer.apple.com/library/archive/documentation/mac/pdf/MacintoshToolboxEssentials.pdf#page=806).
     pub fn file_type(&mut self) -> [u8; 4] {
-        self.header.file_type
+        self.header.file_type()
     }
 
     /// Returns the creator identifier for the file contained in this archive.
@@ -86,7 +147,7 @@ impl<R: Read> BinHexArchive<R> {
     /// Documents" section of "Inside Macintosh: Macintosh Toolbox
     /// Essentials"](https://developer.apple.com/library/archive/documentation/mac/pdf/MacintoshToolboxEssentials.pdf#page=806).
     pub fn creator(&mut self) -> [u8; 4] {
-        self.header.creator
+        self.header.creator()
     }
 
     /// Returns the Finder flags for the file contained in this archive.
@@ -94,18 +155,153 @@ impl<R: Read> BinHexArchive<R> {
     /// For a detailed description of the Finder flags, please see [the "File Information Record"
     /// section of "Inside Macintosh: Macintosh Toolbox
     /// Essentials"](https://developer.apple.com/library/archive/documentation/mac/pdf/MacintoshToolboxEssentials.pdf#page=845).
+    /// Typed access to individual flags is available via [`BinHexArchive::header`].
     pub fn flags(&mut self) -> u16 {
-        self.header.flag
+        self.header.flags().bits()
     }
 
     /// Returns the length, in bytes after decoding, of the data fork contained in this archive.
     pub fn data_fork_len(&mut self) -> usize {
-        self.header.data_fork_length
+        self.header.data_fork_len()
     }
 
     /// Returns the length, in bytes after decoding, of the resource fork contained in this archive.
     pub fn resource_fork_len(&mut self) -> usize {
-        self.header.resource_fork_length
+        self.header.resource_fork_len()
+    }
+
+    /// Returns this archive's header, which contains all of the metadata for the file contained in
+    /// this archive.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use clarus::binhex::{BinHexArchive, BinHexError};
+    ///
+    /// fn main() -> Result<(), BinHexError> {
+    ///     let archive = BinHexArchive::new(File::open("example.hqx")?)?;
+    ///     let header = archive.header();
+    ///
+    ///     if header.flags().is_invisible() {
+    ///         println!("{} is invisible", header.filename());
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn header(&self) -> &BinHexHeader {
+        &self.header
+    }
+
+    /// Returns a reader for this archive's data fork.
+    ///
+    /// The returned reader produces exactly [`BinHexArchive::data_fork_len`] bytes. When the reader
+    /// reaches the end of the fork, it reads the checksum that follows the fork's content and
+    /// compares it to the checksum calculated from the fork's content; if the checksums don't
+    /// match, the read returns an [`std::io::Error`] with a kind of
+    /// [`std::io::ErrorKind::InvalidData`] that wraps a [`BinHexError::InvalidChecksum`]. Note that
+    /// the checksum is only verified when a read reaches the end of the fork (i.e. returns zero
+    /// bytes); callers that use [`std::io::Read::read_exact`] should read until the end of the fork
+    /// to ensure that the fork's checksum is verified.
+    ///
+    /// BinHex archives are read sequentially, and the data fork precedes the resource fork. A reader
+    /// for the data fork may be created any number of times until a reader for the resource fork
+    /// has been created; each data fork reader resumes reading where the previous reader stopped.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if a reader for the resource fork has already been created.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use std::io;
+    /// use clarus::binhex::{BinHexArchive, BinHexError};
+    ///
+    /// fn main() -> Result<(), BinHexError> {
+    ///     let binhex_file = File::open("example.hqx")?;
+    ///     let mut archive = BinHexArchive::new(binhex_file)?;
+    ///
+    ///     let mut data_file = File::create("example.txt")?;
+    ///     io::copy(&mut archive.data_fork()?, &mut data_file)?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn data_fork(&mut self) -> Result<BinHexForkReader<'_, R>, BinHexError> {
+        match self.position {
+            Position::Fork(ChecksumSection::DataFork, _, _) => Ok(BinHexForkReader {
+                archive: self,
+                section: ChecksumSection::DataFork,
+            }),
+            _ => Err(BinHexError::ForkUnavailable(ChecksumSection::DataFork)),
+        }
+    }
+
+    /// Returns a reader for this archive's resource fork.
+    ///
+    /// The returned reader produces exactly [`BinHexArchive::resource_fork_len`] bytes and verifies
+    /// the fork's checksum when it reaches the end of the fork; please see
+    /// [`BinHexArchive::data_fork`] for details.
+    ///
+    /// Because the resource fork follows the data fork in a BinHex archive, any unread portion of
+    /// the data fork is read (and its checksum verified) and discarded before the reader is
+    /// returned. After a reader for the resource fork has been created, the data fork is no longer
+    /// available.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if an IO error occurs while skipping the remainder of the
+    /// data fork, if the data fork's checksum does not match its content, or if the resource fork
+    /// has already been read in its entirety.
+    ///
+    /// # Example
+    ///
+    /// Resource forks can be passed to a [`crate::rsrc::ResourceFork`] after being loaded into
+    /// memory, without loading the data fork:
+    ///
+    /// ```no_run
+    /// use std::error::Error;
+    /// use std::fs::File;
+    /// use std::io::{Cursor, Read};
+    /// use clarus::binhex::BinHexArchive;
+    /// use clarus::rsrc::ResourceFork;
+    ///
+    /// fn main() -> Result<(), Box<dyn Error>> {
+    ///     let binhex_file = File::open("example.hqx")?;
+    ///     let mut archive = BinHexArchive::new(binhex_file)?;
+    ///
+    ///     let mut resource_fork_content = Vec::with_capacity(archive.resource_fork_len());
+    ///     archive.resource_fork()?.read_to_end(&mut resource_fork_content)?;
+    ///
+    ///     let resource_fork = ResourceFork::new(Cursor::new(resource_fork_content))
+    ///         .map_err(|error| format!("{:?}", error))?;
+    ///
+    ///     println!("Resource fork contains {} resources", resource_fork.resources().count());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn resource_fork(&mut self) -> Result<BinHexForkReader<'_, R>, BinHexError> {
+        if let Position::Fork(ChecksumSection::DataFork, _, _) = self.position {
+            io::copy(
+                &mut BinHexForkReader {
+                    archive: self,
+                    section: ChecksumSection::DataFork,
+                },
+                &mut io::sink(),
+            )?;
+        }
+
+        match self.position {
+            Position::Fork(ChecksumSection::ResourceFork, _, _) => Ok(BinHexForkReader {
+                archive: self,
+                section: ChecksumSection::ResourceFork,
+            }),
+            _ => Err(BinHexError::ForkUnavailable(ChecksumSection::ResourceFork)),
+        }
     }
 
     /// Extracts this archive's content to the given writers, verifying checksums in the process.
@@ -198,60 +394,550 @@ impl<R: Read> BinHexArchive<R> {
         data_writer: &mut impl Write,
         resource_writer: &mut impl Write,
     ) -> Result<(), BinHexError> {
-        self.copy_fork(
-            ChecksumSection::DataFork,
-            data_writer,
-            self.header.data_fork_length,
-        )?;
+        io::copy(&mut self.data_fork()?, data_writer)?;
+        io::copy(&mut self.resource_fork()?, resource_writer)?;
 
-        self.copy_fork(
-            ChecksumSection::ResourceFork,
-            resource_writer,
-            self.header.resource_fork_length,
-        )
+        Ok(())
     }
 
-    /// Copies one of an archive's two forks to a destination writer and verifies the checksum at
-    /// the end of the fork's content.
+    /// Extracts this archive's content to the given writers in the same manner as
+    /// [`BinHexArchive::extract`], returning every anomaly found in the archive.
     ///
-    /// The length of the fork must be known, and the source `Read` must be positioned at the start
-    /// of the fork.
+    /// In recovery mode, this method writes as much of each fork as can be recovered and returns
+    /// the problems it worked around along the way. In strict mode, any problem with the archive's
+    /// content is returned as an error, and so the returned list of anomalies is always empty.
     ///
     /// # Errors
     ///
-    /// This function will return an error immediately if an IO operation (i.e. [`std::io::copy`])
-    /// returns an error. It will also return an error if the checksum at the end of the fork's
-    /// content does not match the checksum calculated from the fork's content.
-    fn copy_fork(
-        &mut self,
-        section: ChecksumSection,
-        dest: &mut impl Write,
-        len: usize,
-    ) -> Result<(), BinHexError> {
-        let (bytes_copied, calculated_checksum) = {
-            let mut fork_reader = ForkReader::new(&mut self.source, len);
-            (io::copy(&mut fork_reader, dest)?, fork_reader.checksum())
-        };
+    /// This method returns an error immediately if it encounters an IO error while extracting data
+    /// or, in strict mode, if it encounters malformed data in the archive or if a checksum fails.
+    pub fn extract_with_report(
+        mut self,
+        data_writer: &mut impl Write,
+        resource_writer: &mut impl Write,
+    ) -> Result<Vec<BinHexError>, BinHexError> {
+        io::copy(&mut self.data_fork()?, data_writer)?;
+        io::copy(&mut self.resource_fork()?, resource_writer)?;
 
-        debug_assert!(bytes_copied == len as u64);
+        Ok(self.source.anomalies)
+    }
 
-        let provided_checksum = {
-            let mut checksum_bytes = [0; 2];
-            self.source.read_exact(&mut checksum_bytes)?;
+    /// Returns the anomalies found in this archive so far.
+    ///
+    /// Anomalies are only recorded in recovery mode (see [`BinHexOptions`]); each anomaly is the
+    /// error that would have been returned in strict mode. Anomalies are found as the archive is
+    /// read, and so this list may grow as the archive's forks are read.
+    pub fn anomalies(&self) -> &[BinHexError] {
+        &self.source.anomalies
+    }
 
-            u16::from_be_bytes(checksum_bytes)
+    /// Reads bytes from the given fork into the given buffer, verifying the fork's checksum after
+    /// all of the fork's content has been read.
+    ///
+    /// If the source is not positioned within the given fork, this method returns `Ok(0)`.
+    fn read_fork(&mut self, section: ChecksumSection, buf: &mut [u8]) -> io::Result<usize> {
+        let (bytes_read, mut crc) = match self.position {
+            Position::Fork(current_section, bytes_read, crc) if current_section == section => {
+                (bytes_read, crc)
+            }
+            _ => return Ok(0),
         };
 
-        if provided_checksum == calculated_checksum {
-            Ok(())
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let len = match section {
+            ChecksumSection::DataFork => self.header.data_fork_len(),
+            _ => self.header.resource_fork_len(),
+        };
+
+        if bytes_read < len {
+            let capacity = cmp::min(buf.len(), len - bytes_read);
+            let bytes_copied = self
+                .source
+                .read(&mut buf[..capacity], section, bytes_read)?;
+
+            if bytes_copied == 0 {
+                let error = self.source.unexpected_end(section, bytes_read);
+
+                return if self.source.recovery {
+                    // Give up on this section and move on to the next; there's no checksum to read
+                    self.source.anomalies.push(error);
+                    self.position = next_position(section);
+
+                    Ok(0)
+                } else {
+                    Err(io::Error::new(io::ErrorKind::InvalidData, error))
+                };
+            }
+
+            crc.update(&buf[..bytes_copied]);
+            self.position = Position::Fork(section, bytes_read + bytes_copied, crc);
+
+            Ok(bytes_copied)
+        } else {
+            self.position = next_position(section);
+
+            let provided_checksum = {
+                let mut checksum_bytes = [0; 2];
+
+                match self.source.read_exact(&mut checksum_bytes, section, len) {
+                    Ok(()) => u16::from_be_bytes(checksum_bytes),
+                    Err(error)
+                        if self.source.recovery && error.kind() == io::ErrorKind::InvalidData =>
+                    {
+                        self.source.anomalies.push(BinHexError::from(error));
+                        return Ok(0);
+                    }
+                    Err(error) => return Err(error),
+                }
+            };
+
+            if section == ChecksumSection::ResourceFork {
+                // Attribute any characters skipped after the end of the archive's content
+                self.source.attribute_anomalies(section, len, 0, 0);
+            }
+
+            let calculated_checksum = crc.get();
+
+            if provided_checksum == calculated_checksum {
+                Ok(0)
+            } else {
+                let error =
+                    BinHexError::InvalidChecksum(section, provided_checksum, calculated_checksum);
+
+                if self.source.recovery {
+                    self.source.anomalies.push(error);
+                    Ok(0)
+                } else {
+                    Err(io::Error::new(io::ErrorKind::InvalidData, error))
+                }
+            }
+        }
+    }
+}
+
+/// Returns the position at the start of the section that follows the given section.
+fn next_position(section: ChecksumSection) -> Position {
+    match section {
+        ChecksumSection::DataFork => {
+            Position::Fork(ChecksumSection::ResourceFork, 0, State::<XMODEM>::new())
+        }
+        _ => Position::End,
+    }
+}
+
+/// Options that control how a [`BinHexArchive`] reads an archive.
+///
+/// New options may be added in the future, and so options should be created with
+/// [`BinHexOptions::new`] (or [`Default::default`]) and then adjusted.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct BinHexOptions {
+    /// Whether to recover as much content as possible from a damaged archive.
+    ///
+    /// By default (i.e. in strict mode), archives stop at the first problem with the archive's
+    /// content and return an error. In recovery mode, archives instead skip characters that aren't
+    /// part of the BinHex alphabet, treat the end of the source as the end of the archive if the
+    /// closing delimiter is missing, skip malformed run-length sequences, end a fork early if the
+    /// archive's data runs out, and continue past checksum mismatches. Each problem is recorded as
+    /// an anomaly; please see [`BinHexArchive::anomalies`] and
+    /// [`BinHexArchive::extract_with_report`].
+    pub recovery: bool,
+}
+
+impl BinHexOptions {
+    /// Returns the default options, which read archives in strict mode.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a copy of these options with recovery mode enabled or disabled; please see
+    /// [`BinHexOptions::recovery`].
+    pub fn with_recovery(self, recovery: bool) -> Self {
+        BinHexOptions { recovery }
+    }
+}
+
+/// The chain of readers that extracts decoded, expanded bytes from a BinHex source, along with the
+/// state needed to locate (or, in recovery mode, work around) malformed data.
+struct Source<R: Read> {
+    expander: BinHexExpander<Radix64Decoder<EncodedBinHexReader<R>>>,
+    recovery: bool,
+    anomalies: Vec<BinHexError>,
+
+    /// Invalid characters skipped by the encoded reader that haven't been attributed to a section
+    /// yet, along with the offset in the compressed stream at which they were found.
+    pending_characters: VecDeque<(usize, InvalidCharacterError)>,
+}
+
+impl<R: Read> Source<R> {
+    fn new(source: R, options: BinHexOptions) -> Self {
+        let reader = if options.recovery {
+            EncodedBinHexReader::with_recovery(source)
         } else {
-            Err(BinHexError::InvalidChecksum(
+            EncodedBinHexReader::new(source)
+        };
+
+        Source {
+            expander: BinHexExpander::new(Radix64Decoder::new(reader)),
+            recovery: options.recovery,
+            anomalies: vec![],
+            pending_characters: VecDeque::new(),
+        }
+    }
+
+    fn reader(&self) -> &EncodedBinHexReader<R> {
+        self.expander.get_ref().get_ref()
+    }
+
+    /// Reads decoded bytes into the given buffer; the start of the buffer is at the given offset
+    /// within the given section.
+    ///
+    /// Errors caused by malformed data are reported as [`BinHexError`]s that include the location
+    /// of the malformed data or, in recovery mode, are recorded as anomalies.
+    fn read(
+        &mut self,
+        buf: &mut [u8],
+        section: ChecksumSection,
+        offset: usize,
+    ) -> io::Result<usize> {
+        loop {
+            let consumed_before = self.expander.bytes_consumed();
+            let result = self.expander.read(buf);
+
+            self.attribute_anomalies(
                 section,
-                provided_checksum,
-                calculated_checksum,
-            ))
+                offset,
+                consumed_before,
+                *result.as_ref().unwrap_or(&0),
+            );
+
+            // Run-length errors point back to (at most) the escape byte that started a run, which
+            // may have been consumed by the previous read; we won't need the locations of any
+            // characters before that
+            let oldest_char_index = self.expander.bytes_consumed().saturating_sub(2) * 4 / 3;
+
+            self.expander
+                .get_mut()
+                .get_mut()
+                .discard_positions_before(oldest_char_index);
+
+            match result {
+                Ok(len) => return Ok(len),
+                Err(error) => {
+                    let error = self.locate_error(error, section, offset);
+
+                    let malformed = matches!(
+                        error
+                            .get_ref()
+                            .and_then(|inner| inner.downcast_ref::<BinHexError>()),
+                        Some(BinHexError::InvalidData(_))
+                    );
+
+                    if self.recovery && malformed {
+                        // The expander skips malformed run-length sequences, so we can keep going
+                        self.anomalies.push(BinHexError::from(error));
+                        continue;
+                    }
+
+                    return Err(error);
+                }
+            }
         }
     }
+
+    /// Fills the given buffer with decoded bytes; the start of the buffer is at the given offset
+    /// within the given section.
+    fn read_exact(
+        &mut self,
+        buf: &mut [u8],
+        section: ChecksumSection,
+        offset: usize,
+    ) -> io::Result<()> {
+        let mut bytes_read = 0;
+
+        while bytes_read < buf.len() {
+            match self.read(&mut buf[bytes_read..], section, offset + bytes_read) {
+                Ok(0) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        self.unexpected_end(section, offset + bytes_read),
+                    ))
+                }
+                Ok(len) => bytes_read += len,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Converts errors caused by malformed encoded or compressed data into [`BinHexError`]s that
+    /// include the location of the malformed data; other errors are returned unchanged.
+    fn locate_error(&self, error: io::Error, section: ChecksumSection, offset: usize) -> io::Error {
+        let inner = match error.get_ref() {
+            Some(inner) => inner,
+            None => return error,
+        };
+
+        let binhex_error =
+            if let Some(invalid_character) = inner.downcast_ref::<InvalidCharacterError>() {
+                BinHexError::InvalidCharacter(
+                    invalid_character.byte,
+                    ErrorLocation {
+                        line: invalid_character.line,
+                        column: invalid_character.column,
+                        section,
+                        offset,
+                    },
+                )
+            } else if let Some(run_length_error) = inner.downcast_ref::<RunLengthError>() {
+                // Every three compressed bytes are encoded as four characters
+                let char_index = run_length_error.offset * 4 / 3;
+                let (line, column) = self.reader().position_of(char_index);
+
+                BinHexError::InvalidData(ErrorLocation {
+                    line,
+                    column,
+                    section,
+                    offset,
+                })
+            } else {
+                return error;
+            };
+
+        io::Error::new(io::ErrorKind::InvalidData, binhex_error)
+    }
+
+    /// Returns an error indicating that the archive's encoded data ended at the given offset within
+    /// the given section, before the section was complete.
+    fn unexpected_end(&self, section: ChecksumSection, offset: usize) -> BinHexError {
+        let (line, column) = self.reader().end_position();
+
+        BinHexError::InvalidData(ErrorLocation {
+            line,
+            column,
+            section,
+            offset,
+        })
+    }
+
+    /// Records the problems worked around by a lenient encoded reader as anomalies; skipped
+    /// characters are recorded once the expander has consumed the compressed data in which they
+    /// were found.
+    ///
+    /// The read that triggered this call started at the given offset within the given section and
+    /// produced `len` bytes after consuming compressed data from `consumed_before` onward.
+    fn attribute_anomalies(
+        &mut self,
+        section: ChecksumSection,
+        offset: usize,
+        consumed_before: usize,
+        len: usize,
+    ) {
+        for anomaly in self.expander.get_mut().get_mut().take_anomalies() {
+            match anomaly {
+                Anomaly::SkippedCharacter(char_index, invalid_character) => {
+                    // Every four characters are decoded as three compressed bytes
+                    self.pending_characters
+                        .push_back((char_index * 3 / 4, invalid_character));
+                }
+                Anomaly::MissingDataEnd => self.anomalies.push(BinHexError::MissingDataEnd),
+            }
+        }
+
+        let consumed = self.expander.bytes_consumed();
+
+        while let Some(&(compressed_offset, invalid_character)) = self.pending_characters.front() {
+            // Anything left over when the data runs out belongs at the end of the current section
+            if compressed_offset >= consumed && len > 0 {
+                break;
+            }
+
+            self.pending_characters.pop_front();
+
+            // Compressed and decoded offsets only differ when runs are involved, so this is an
+            // estimate, but it will always fall within the bytes produced by the current read
+            let offset = offset + cmp::min(compressed_offset.saturating_sub(consumed_before), len);
+
+            self.anomalies.push(BinHexError::InvalidCharacter(
+                invalid_character.byte,
+                ErrorLocation {
+                    line: invalid_character.line,
+                    column: invalid_character.column,
+                    section,
+                    offset,
+                },
+            ));
+        }
+    }
+}
+
+/// A reader for the content of one of the forks in a BinHex archive.
+///
+/// Fork readers are created by [`BinHexArchive::data_fork`] and [`BinHexArchive::resource_fork`].
+pub struct BinHexForkReader<'a, R: Read> {
+    archive: &'a mut BinHexArchive<R>,
+    section: ChecksumSection,
+}
+
+impl<'a, R: Read> Read for BinHexForkReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.archive.read_fork(self.section, buf)
+    }
+}
+
+/// The position of an archive's source within the archive's content.
+#[derive(Copy, Clone, Debug)]
+enum Position {
+    /// Reading the content of the given fork; the number of bytes of the fork read so far and the
+    /// checksum of those bytes are included.
+    Fork(ChecksumSection, usize, State<XMODEM>),
+
+    /// Both forks (and their checksums) have been read.
+    End,
+}
+
+/// Writes BinHex-encoded archives.
+///
+/// A `BinHexWriter` combines the data fork, resource fork, and metadata associated with a "classic"
+/// Macintosh file into a single BinHex 4.0 archive that can be read with [`BinHexArchive`].
+pub struct BinHexWriter<W: Write> {
+    dest: W,
+    header: BinHexHeader,
+}
+
+impl<W: Write> BinHexWriter<W> {
+    /// Creates a new BinHex writer that will write an archive with the given metadata to the given
+    /// destination.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the given filename is empty, is longer than 63 bytes
+    /// when encoded, or contains characters that can't be represented in the Macintosh character
+    /// encoding.
+    pub fn new(
+        dest: W,
+        filename: &str,
+        file_type: [u8; 4],
+        creator: [u8; 4],
+        flags: u16,
+    ) -> Result<Self, BinHexError> {
+        let header = BinHexHeader::new(filename, file_type, creator, FinderFlags::from(flags))?;
+
+        Ok(BinHexWriter::with_header(dest, header))
+    }
+
+    /// Creates a new BinHex writer that will write an archive with the given header to the given
+    /// destination.
+    ///
+    /// All of the header's metadata, including its version byte and the raw bytes of its filename,
+    /// is written unchanged, except for the fork lengths, which are replaced with the lengths given
+    /// to [`BinHexWriter::encode`].
+    ///
+    /// # Example
+    ///
+    /// Headers read from one archive may be used to write another:
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use std::io::Cursor;
+    /// use clarus::binhex::{BinHexArchive, BinHexError, BinHexWriter};
+    ///
+    /// fn main() -> Result<(), BinHexError> {
+    ///     let archive = BinHexArchive::new(File::open("original.hqx")?)?;
+    ///     let header = archive.header().clone();
+    ///
+    ///     let mut data_fork = vec![];
+    ///     let mut resource_fork = vec![];
+    ///     archive.extract(&mut data_fork, &mut resource_fork)?;
+    ///
+    ///     // ...modify the forks...
+    ///
+    ///     BinHexWriter::with_header(File::create("modified.hqx")?, header).encode(
+    ///         &mut Cursor::new(&data_fork),
+    ///         data_fork.len(),
+    ///         &mut Cursor::new(&resource_fork),
+    ///         resource_fork.len(),
+    ///     )?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn with_header(dest: W, header: BinHexHeader) -> Self {
+        BinHexWriter { dest, header }
+    }
+
+    /// Encodes the given forks and writes a complete archive to this writer's destination,
+    /// returning the destination when the archive has been written.
+    ///
+    /// Exactly `data_fork_len` bytes are read from `data_fork`, and exactly `resource_fork_len`
+    /// bytes are read from `resource_fork`.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error immediately if it encounters an IO error while reading from
+    /// either fork or writing to the destination, if either fork ends before the given number of
+    /// bytes could be read, or if either fork length is too large to be represented in a BinHex
+    /// header.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use std::io;
+    /// use clarus::binhex::{BinHexError, BinHexWriter};
+    ///
+    /// fn main() -> Result<(), BinHexError> {
+    ///     let mut data_file = File::open("example.txt")?;
+    ///     let data_fork_len = data_file.metadata()?.len() as usize;
+    ///
+    ///     let binhex_file = File::create("example.hqx")?;
+    ///     let writer = BinHexWriter::new(binhex_file, "example.txt", *b"TEXT", *b"ttxt", 0)?;
+    ///
+    ///     writer.encode(&mut data_file, data_fork_len, &mut io::empty(), 0)?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn encode(
+        self,
+        data_fork: &mut impl Read,
+        data_fork_len: usize,
+        resource_fork: &mut impl Read,
+        resource_fork_len: usize,
+    ) -> Result<W, BinHexError> {
+        if data_fork_len > u32::MAX as usize || resource_fork_len > u32::MAX as usize {
+            return Err(BinHexError::InvalidHeader);
+        }
+
+        let header = self
+            .header
+            .with_fork_lengths(data_fork_len, resource_fork_len);
+
+        let mut compressor =
+            BinHexCompressor::new(Radix64Writer::new(EncodedBinHexWriter::new(self.dest)));
+
+        compressor.write_all(&Vec::from(&header))?;
+
+        copy_fork(data_fork, &mut compressor, data_fork_len)?;
+        copy_fork(resource_fork, &mut compressor, resource_fork_len)?;
+
+        Ok(compressor.finish()?.finish()?.finish()?)
+    }
+}
+
+/// Copies exactly `len` bytes of a fork from the given source to the given destination, then
+/// writes the checksum of the copied bytes.
+fn copy_fork(source: &mut impl Read, dest: &mut impl Write, len: usize) -> io::Result<()> {
+    let mut crc_writer = CrcWriter::new(dest);
+    copy_exact(source, &mut crc_writer, len as u64)?;
+
+    let checksum = crc_writer.checksum();
+    dest.write_all(&checksum.to_be_bytes())
 }
 
 /// The error type for operations on BinHex-encoded files.
@@ -265,11 +951,26 @@ pub enum BinHexError {
     /// The kind of IO error is included.
     IoError(io::ErrorKind),
 
-    /// The BinHex archive's header was malformed and could not be read.
+    /// The BinHex archive's header was malformed and could not be read, or the given metadata could
+    /// not be represented in a BinHex header.
     InvalidHeader,
 
-    /// Data in some part of the BinHex archive was malformed and could not be read.
-    InvalidData,
+    /// The given filename could not be represented in a BinHex header.
+    ///
+    /// Filenames in BinHex headers must be between 1 and 63 bytes long and must be representable in
+    /// the Macintosh character encoding.
+    InvalidFilename,
+
+    /// Data in some part of the BinHex archive was malformed and could not be read, or the archive's
+    /// encoded data ended before all of the archive's content could be read.
+    ///
+    /// The location of the malformed data (or the end of the encoded data) is included.
+    InvalidData(ErrorLocation),
+
+    /// The archive's encoded data contained a character that is not part of the BinHex alphabet.
+    ///
+    /// The invalid character and its location are included.
+    InvalidCharacter(u8, ErrorLocation),
 
     /// The checksum included in a section of a BinHex archive did not match the checksum calculated
     /// from its content.
@@ -277,6 +978,24 @@ pub enum BinHexError {
     /// The section in which the checksum did not match, the checksum provided in the BinHex file,
     /// and the checksum calculated from the section's content are included.
     InvalidChecksum(ChecksumSection, u16, u16),
+
+    /// A reader for the given fork could not be created because the archive has already been read
+    /// beyond the start of that fork.
+    ForkUnavailable(ChecksumSection),
+
+    /// A multi-part BinHex archive could not be reassembled because the part with the given part
+    /// number is missing.
+    MissingPart(usize),
+
+    /// A multi-part BinHex archive could not be reassembled because more than one part with the
+    /// given part number was provided.
+    DuplicatePart(usize),
+
+    /// The archive's encoded data ended without a closing delimiter.
+    ///
+    /// This is only reported as an anomaly in recovery mode; in strict mode, the end of the source
+    /// is reported as an IO error instead.
+    MissingDataEnd,
 }
 
 impl Display for BinHexError {
@@ -284,24 +1003,103 @@ impl Display for BinHexError {
         match self {
             BinHexError::IoError(kind) => write!(fmt, "IO error: {:?}", kind),
             BinHexError::InvalidHeader => write!(fmt, "Malformed BinHex header"),
-            BinHexError::InvalidData => write!(fmt, "Malformed BinHex data"),
+            BinHexError::InvalidFilename => write!(fmt, "Invalid BinHex filename"),
+            BinHexError::InvalidData(location) => {
+                write!(fmt, "Malformed BinHex data; {}", location)
+            }
+            BinHexError::InvalidCharacter(byte, location) => {
+                write!(fmt, "Invalid character 0x{:02x}; {}", byte, location)
+            }
             BinHexError::InvalidChecksum(section, provided, calculated) => write!(
                 fmt,
                 "Invalid checksum; section={:?}, expected={:04x}, calculated={:04x}",
                 section, provided, calculated
             ),
+            BinHexError::ForkUnavailable(section) => {
+                write!(fmt, "Fork no longer available; section={:?}", section)
+            }
+            BinHexError::MissingPart(part) => write!(fmt, "Missing part {}", part),
+            BinHexError::DuplicatePart(part) => write!(fmt, "Duplicate part {}", part),
+            BinHexError::MissingDataEnd => write!(fmt, "Missing end of BinHex data"),
         }
     }
 }
 
 impl From<io::Error> for BinHexError {
     fn from(error: io::Error) -> Self {
-        BinHexError::IoError(error.kind())
+        // Fork readers report BinHex-specific errors (like checksum mismatches) wrapped in IO errors
+        match error
+            .get_ref()
+            .and_then(|inner| inner.downcast_ref::<BinHexError>())
+        {
+            Some(binhex_error) => *binhex_error,
+            None => BinHexError::IoError(error.kind()),
+        }
     }
 }
 
 impl error::Error for BinHexError {}
 
+/// The location of malformed data within a BinHex archive.
+///
+/// Locations identify both the position of the malformed data in the archive's encoded text and
+/// the section of the archive (and offset within that section) being decoded when the malformed
+/// data was found.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ErrorLocation {
+    line: usize,
+    column: usize,
+    section: ChecksumSection,
+    offset: usize,
+}
+
+impl ErrorLocation {
+    pub(super) fn new(line: usize, column: usize, section: ChecksumSection, offset: usize) -> Self {
+        ErrorLocation {
+            line,
+            column,
+            section,
+            offset,
+        }
+    }
+
+    /// Returns the (one-based) line number in the archive's encoded text at which the malformed
+    /// data was found.
+    ///
+    /// Lines are counted from the start of the archive's source, and may be separated by carriage
+    /// returns, line feeds, or both.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Returns the (one-based) column in the archive's encoded text at which the malformed data was
+    /// found.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Returns the section of the archive that was being decoded when the malformed data was found.
+    pub fn section(&self) -> ChecksumSection {
+        self.section
+    }
+
+    /// Returns the offset, in bytes after decoding and from the start of the section, at which the
+    /// malformed data was found.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl Display for ErrorLocation {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            fmt,
+            "line={}, column={}, section={:?}, offset={}",
+            self.line, self.column, self.section, self.offset
+        )
+    }
+}
+
 /// A section of a BinHex archive.
 ///
 /// BinHex archives are divided into a header, a data fork, and a resource fork, each of which has
@@ -313,121 +1111,109 @@ pub enum ChecksumSection {
     ResourceFork,
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
-struct BinHexHeader {
-    name: String,
-    file_type: [u8; 4],
-    creator: [u8; 4],
-    flag: u16,
-    data_fork_length: usize,
-    resource_fork_length: usize,
+/// A `Write` adapter that calculates the checksum of all bytes written to an underlying writer.
+struct CrcWriter<'a, W: Write> {
+    dest: &'a mut W,
+    crc: State<XMODEM>,
 }
 
-impl TryFrom<Vec<u8>> for BinHexHeader {
-    type Error = BinHexError;
-
-    fn try_from(header_bytes: Vec<u8>) -> Result<Self, Self::Error> {
-        let (name_length_bytes, remaining_bytes) = header_bytes.split_at(1);
-        let name_length = name_length_bytes[0] as usize;
-
-        if header_bytes.len() != name_length + 22 {
-            return Err(BinHexError::InvalidHeader);
+impl<'a, W: Write> CrcWriter<'a, W> {
+    fn new(dest: &'a mut W) -> Self {
+        CrcWriter {
+            dest,
+            crc: State::<XMODEM>::new(),
         }
+    }
 
-        let (name_bytes, remaining_bytes) = remaining_bytes.split_at(name_length);
-        let (_version_byte, remaining_bytes) = remaining_bytes.split_at(1);
-        let (file_type_bytes, remaining_bytes) = remaining_bytes.split_at(4);
-        let (creator_bytes, remaining_bytes) = remaining_bytes.split_at(4);
-        let (flag_bytes, remaining_bytes) = remaining_bytes.split_at(2);
-        let (data_fork_length_bytes, remaining_bytes) = remaining_bytes.split_at(4);
-        let (resource_fork_length_bytes, remaining_bytes) = remaining_bytes.split_at(4);
-        let (checksum_bytes, remaining_bytes) = remaining_bytes.split_at(2);
-
-        debug_assert!(remaining_bytes.is_empty());
+    fn checksum(&self) -> u16 {
+        self.crc.get()
+    }
+}
 
-        let calculated_checksum =
-            crc16::State::<crc16::XMODEM>::calculate(&header_bytes[..header_bytes.len() - 2]);
-        let provided_checksum = u16::from_be_bytes(checksum_bytes.try_into().unwrap());
+impl<'a, W: Write> Write for CrcWriter<'a, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let bytes_written = self.dest.write(buf)?;
+        self.crc.write(&buf[..bytes_written]);
 
-        if provided_checksum != calculated_checksum {
-            return Err(BinHexError::InvalidChecksum(
-                ChecksumSection::Header,
-                provided_checksum,
-                calculated_checksum,
-            ));
-        }
+        Ok(bytes_written)
+    }
 
-        let (name_cow, _, _) = encoding_rs::MACINTOSH.decode(name_bytes);
-        let name = name_cow.to_string();
-        let file_type: [u8; 4] = TryInto::<[u8; 4]>::try_into(file_type_bytes).unwrap();
-        let creator: [u8; 4] = TryInto::<[u8; 4]>::try_into(creator_bytes).unwrap();
-        let flag: u16 = u16::from_be_bytes(flag_bytes.try_into().unwrap());
-        let data_fork_length: usize =
-            u32::from_be_bytes(data_fork_length_bytes.try_into().unwrap()) as usize;
-        let resource_fork_length: usize =
-            u32::from_be_bytes(resource_fork_length_bytes.try_into().unwrap()) as usize;
-
-        Ok(BinHexHeader {
-            name,
-            file_type,
-            creator,
-            flag,
-            data_fork_length,
-            resource_fork_length,
-        })
+    fn flush(&mut self) -> io::Result<()> {
+        self.dest.flush()
     }
 }
 
-struct ForkReader<'a, R: Read> {
-    source: &'a mut R,
-    len: usize,
-    bytes_read: usize,
-    crc: State<XMODEM>,
+/// A `Write` adapter that encodes bytes with BinHex's radix-64 alphabet.
+///
+/// Bytes are encoded in groups of three; any trailing partial group is encoded when
+/// [`Radix64Writer::finish`] is called.
+struct Radix64Writer<W: Write> {
+    dest: W,
+    pending: [u8; 3],
+    pending_len: usize,
 }
 
-impl<'a, R: Read> ForkReader<'a, R> {
-    fn new(source: &'a mut R, len: usize) -> Self {
-        ForkReader {
-            source,
-            len,
-            bytes_read: 0,
-            crc: State::<XMODEM>::new(),
+impl<W: Write> Radix64Writer<W> {
+    fn new(dest: W) -> Self {
+        Radix64Writer {
+            dest,
+            pending: [0; 3],
+            pending_len: 0,
         }
     }
 
-    fn checksum(&self) -> u16 {
-        debug_assert!(self.bytes_read == self.len);
-        self.crc.get()
+    fn finish(mut self) -> io::Result<W> {
+        if self.pending_len > 0 {
+            let mut encoded = [0; 4];
+            let encoded_len =
+                BINHEX_CONFIG.encode_slice(&self.pending[..self.pending_len], &mut encoded);
+
+            self.dest.write_all(&encoded[..encoded_len])?;
+        }
+
+        Ok(self.dest)
     }
 }
 
-impl<'a, R: Read> Read for ForkReader<'a, R> {
-    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        if buf.is_empty() || self.bytes_read == self.len {
-            Ok(0)
-        } else {
-            let remaining_bytes = self.len - self.bytes_read;
+impl<W: Write> Write for Radix64Writer<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.pending_len > 0 || buf.len() < 3 {
+            // Top up the partial group before encoding anything else
+            let len = std::cmp::min(buf.len(), 3 - self.pending_len);
 
-            let bytes_copied = if buf.len() <= remaining_bytes {
-                self.source.read(buf)?
-            } else {
-                self.source.read_exact(&mut buf[..remaining_bytes])?;
-                remaining_bytes
-            };
+            self.pending[self.pending_len..self.pending_len + len].copy_from_slice(&buf[..len]);
+            self.pending_len += len;
 
-            self.crc.write(&buf[..bytes_copied]);
-            self.bytes_read += bytes_copied;
+            if self.pending_len == 3 {
+                let mut encoded = [0; 4];
+                BINHEX_CONFIG.encode_slice(&self.pending, &mut encoded);
 
-            Ok(bytes_copied)
+                self.dest.write_all(&encoded)?;
+                self.pending_len = 0;
+            }
+
+            Ok(len)
+        } else {
+            let mut encoded = [0; 1024];
+            let len = std::cmp::min(buf.len() / 3, encoded.len() / 4) * 3;
+            let encoded_len = BINHEX_CONFIG.encode_slice(&buf[..len], &mut encoded);
+
+            self.dest.write_all(&encoded[..encoded_len])?;
+
+            Ok(len)
         }
     }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.dest.flush()
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
     use indoc::indoc;
-    use std::io::Cursor;
+    use std::io::{Cursor, Read};
 
     const BINHEX_DATA: &[u8] = indoc! {br#"
             (This file must be converted with BinHex 4.0)
@@ -517,4 +1303,431 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn fork_readers() -> Result<(), BinHexError> {
+        let mut archive = BinHexArchive::new(Cursor::new(BINHEX_DATA))?;
+
+        let mut data_fork = vec![];
+        let mut resource_fork = vec![];
+
+        archive.data_fork()?.read_to_end(&mut data_fork)?;
+        archive.resource_fork()?.read_to_end(&mut resource_fork)?;
+
+        assert_eq!(DATA_FORK, data_fork.as_slice());
+        assert_eq!(RESOURCE_FORK, resource_fork.as_slice());
+
+        Ok(())
+    }
+
+    #[test]
+    fn fork_readers_resume() -> Result<(), BinHexError> {
+        let mut archive = BinHexArchive::new(Cursor::new(BINHEX_DATA))?;
+
+        let mut data_fork = vec![0; 5];
+        archive.data_fork()?.read_exact(&mut data_fork)?;
+        archive.data_fork()?.read_to_end(&mut data_fork)?;
+
+        assert_eq!(DATA_FORK, data_fork.as_slice());
+
+        Ok(())
+    }
+
+    #[test]
+    fn resource_fork_only() -> Result<(), BinHexError> {
+        let mut archive = BinHexArchive::new(Cursor::new(BINHEX_DATA))?;
+
+        let mut resource_fork = vec![];
+        archive.resource_fork()?.read_to_end(&mut resource_fork)?;
+
+        assert_eq!(RESOURCE_FORK, resource_fork.as_slice());
+        assert!(matches!(
+            archive.data_fork(),
+            Err(BinHexError::ForkUnavailable(ChecksumSection::DataFork))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn fork_reader_invalid_checksum() -> Result<(), BinHexError> {
+        // Corrupt a single byte in the resource fork
+        let binhex_data = String::from_utf8(BINHEX_DATA.to_vec())
+            .unwrap()
+            .replace(r#"C5"bCA0["#, r#"C5"bCA1["#);
+
+        let mut archive = BinHexArchive::new(Cursor::new(binhex_data))?;
+
+        let mut data_fork = vec![];
+        archive.data_fork()?.read_to_end(&mut data_fork)?;
+
+        assert_eq!(DATA_FORK, data_fork.as_slice());
+
+        let mut resource_fork = vec![];
+        let error = archive
+            .resource_fork()?
+            .read_to_end(&mut resource_fork)
+            .unwrap_err();
+
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+        assert!(matches!(
+            BinHexError::from(error),
+            BinHexError::InvalidChecksum(ChecksumSection::ResourceFork, 0x287c, _)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn extract_trailing_character() -> Result<(), BinHexError> {
+        // This archive's encoded data ends with a single character that doesn't represent a whole
+        // byte
+        let mut archive = BinHexArchive::new(Cursor::new(SIMPLE_TEXT_DOCUMENT))?;
+        let archive_resource_fork_len = archive.resource_fork_len();
+
+        let mut data_fork = vec![];
+        let mut resource_fork = vec![];
+
+        archive.extract(&mut data_fork, &mut resource_fork)?;
+
+        assert_eq!(b"Hello from SimpleText!".to_vec(), data_fork);
+        assert_eq!(archive_resource_fork_len, resource_fork.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_character_location() -> Result<(), BinHexError> {
+        let binhex_data = String::from_utf8(BINHEX_DATA.to_vec())
+            .unwrap()
+            .replace("QFQ", "QF~");
+
+        let archive = BinHexArchive::new(Cursor::new(binhex_data))?;
+        let error = archive
+            .extract(&mut io::sink(), &mut io::sink())
+            .unwrap_err();
+
+        match error {
+            BinHexError::InvalidCharacter(b'~', location) => {
+                assert_eq!(3, location.line());
+                assert_eq!(35, location.column());
+                assert_eq!(ChecksumSection::ResourceFork, location.section());
+                assert_eq!(13, location.offset());
+            }
+            _ => panic!("Unexpected error: {:?}", error),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_character_in_header() {
+        let binhex_data = String::from_utf8(BINHEX_DATA.to_vec())
+            .unwrap()
+            .replace("$f*TEQ", "$f*T Q|");
+
+        let error = BinHexArchive::new(Cursor::new(binhex_data)).err().unwrap();
+
+        match error {
+            BinHexError::InvalidCharacter(b'|', location) => {
+                assert_eq!(2, location.line());
+                assert_eq!(8, location.column());
+                assert_eq!(ChecksumSection::Header, location.section());
+            }
+            _ => panic!("Unexpected error: {:?}", error),
+        }
+    }
+
+    #[test]
+    fn truncated_data_location() -> Result<(), BinHexError> {
+        let binhex_data = String::from_utf8(BINHEX_DATA.to_vec())
+            .unwrap()
+            .replace("YN!8SI!:", "YN!8:");
+
+        let archive = BinHexArchive::new(Cursor::new(binhex_data))?;
+        let error = archive
+            .extract(&mut io::sink(), &mut io::sink())
+            .unwrap_err();
+
+        match error {
+            BinHexError::InvalidData(location) => {
+                assert_eq!(4, location.line());
+                assert_eq!(5, location.column());
+                assert_eq!(ChecksumSection::ResourceFork, location.section());
+            }
+            _ => panic!("Unexpected error: {:?}", error),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_run_length_location() -> Result<(), BinHexError> {
+        // Start the compressed stream with a run length that has no preceding byte to repeat
+        let mut writer = Radix64Writer::new(EncodedBinHexWriter::new(vec![]));
+        writer.write_all(&[0x90, 0x04])?;
+        writer.write_all(&[0; 22])?;
+
+        let encoded = writer.finish()?.finish()?;
+
+        match BinHexArchive::new(Cursor::new(encoded)).err().unwrap() {
+            BinHexError::InvalidData(location) => {
+                assert_eq!(2, location.line());
+                assert_eq!(2, location.column());
+                assert_eq!(ChecksumSection::Header, location.section());
+                assert_eq!(0, location.offset());
+            }
+            error => panic!("Unexpected error: {:?}", error),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn recovery() -> Result<(), BinHexError> {
+        // Add a stray character, corrupt a byte in the resource fork, and remove the closing
+        // delimiter
+        let binhex_data = String::from_utf8(BINHEX_DATA.to_vec())
+            .unwrap()
+            .replace("\ndB5", "\n>dB5")
+            .replace(r#"C5"bCA0["#, r#"C5"bCA1["#)
+            .replace("SI!:", "SI!");
+
+        assert!(matches!(
+            BinHexArchive::new(Cursor::new(&binhex_data))?
+                .extract(&mut io::sink(), &mut io::sink()),
+            Err(BinHexError::InvalidCharacter(b'>', _))
+        ));
+
+        let archive = BinHexArchive::with_options(
+            Cursor::new(&binhex_data),
+            BinHexOptions::new().with_recovery(true),
+        )?;
+
+        let mut data_fork = vec![];
+        let mut resource_fork = vec![];
+
+        let anomalies = archive.extract_with_report(&mut data_fork, &mut resource_fork)?;
+
+        assert_eq!(DATA_FORK, data_fork.as_slice());
+        assert_eq!(RESOURCE_FORK.len(), resource_fork.len());
+        assert_ne!(RESOURCE_FORK, resource_fork.as_slice());
+
+        assert_eq!(3, anomalies.len());
+        assert!(anomalies.contains(&BinHexError::MissingDataEnd));
+        assert!(anomalies.iter().any(|anomaly| matches!(
+            anomaly,
+            BinHexError::InvalidChecksum(ChecksumSection::ResourceFork, 0x287c, _)
+        )));
+        assert!(anomalies.iter().any(|anomaly| match anomaly {
+            BinHexError::InvalidCharacter(b'>', location) => {
+                location.line() == 3
+                    && location.column() == 1
+                    && location.section() == ChecksumSection::DataFork
+            }
+            _ => false,
+        }));
+
+        Ok(())
+    }
+
+    #[test]
+    fn recovery_truncated() -> Result<(), BinHexError> {
+        let binhex_data = String::from_utf8(BINHEX_DATA.to_vec())
+            .unwrap()
+            .replace("C5\"QEh*V)5!\nYN!8SI!:", "C5\"QEh:");
+
+        let archive = BinHexArchive::with_options(
+            Cursor::new(binhex_data),
+            BinHexOptions::new().with_recovery(true),
+        )?;
+
+        let mut data_fork = vec![];
+        let mut resource_fork = vec![];
+
+        let anomalies = archive.extract_with_report(&mut data_fork, &mut resource_fork)?;
+
+        assert_eq!(DATA_FORK, data_fork.as_slice());
+        assert!(RESOURCE_FORK.starts_with(&resource_fork));
+        assert!(resource_fork.len() < RESOURCE_FORK.len());
+
+        assert!(matches!(
+            anomalies.as_slice(),
+            [BinHexError::InvalidData(location)] if location.section() == ChecksumSection::ResourceFork
+                && location.offset() == resource_fork.len()
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn recovery_header_checksum() -> Result<(), BinHexError> {
+        // Corrupt a byte in the filename
+        let binhex_data = String::from_utf8(BINHEX_DATA.to_vec())
+            .unwrap()
+            .replace("$f*TEQKPH", "$f*TEQKPI");
+
+        assert!(matches!(
+            BinHexArchive::new(Cursor::new(&binhex_data)),
+            Err(BinHexError::InvalidChecksum(ChecksumSection::Header, _, _))
+        ));
+
+        let mut archive = BinHexArchive::with_options(
+            Cursor::new(&binhex_data),
+            BinHexOptions::new().with_recovery(true),
+        )?;
+
+        assert_ne!(&String::from("binhex-test.txt"), archive.filename());
+        assert!(matches!(
+            archive.anomalies(),
+            [BinHexError::InvalidChecksum(ChecksumSection::Header, _, _)]
+        ));
+
+        let mut data_fork = vec![];
+        let mut resource_fork = vec![];
+
+        assert_eq!(
+            1,
+            archive
+                .extract_with_report(&mut data_fork, &mut resource_fork)?
+                .len()
+        );
+
+        assert_eq!(DATA_FORK, data_fork.as_slice());
+        assert_eq!(RESOURCE_FORK, resource_fork.as_slice());
+
+        Ok(())
+    }
+
+    #[test]
+    fn encode() -> Result<(), BinHexError> {
+        let writer = BinHexWriter::new(vec![], "SimpleText™ Document", *b"TEXT", *b"ttxt", 0x0100)?;
+        let encoded = writer.encode(
+            &mut Cursor::new(DATA_FORK),
+            DATA_FORK.len(),
+            &mut Cursor::new(RESOURCE_FORK),
+            RESOURCE_FORK.len(),
+        )?;
+
+        let mut archive = BinHexArchive::new(Cursor::new(encoded))?;
+
+        assert_eq!(&String::from("SimpleText™ Document"), archive.filename());
+        assert_eq!(b"TEXT", &archive.file_type());
+        assert_eq!(b"ttxt", &archive.creator());
+        assert_eq!(0x0100, archive.flags());
+
+        let mut data_fork = vec![];
+        let mut resource_fork = vec![];
+
+        archive.extract(&mut data_fork, &mut resource_fork)?;
+
+        assert_eq!(DATA_FORK, data_fork.as_slice());
+        assert_eq!(RESOURCE_FORK, resource_fork.as_slice());
+
+        Ok(())
+    }
+
+    #[test]
+    fn encode_with_header() -> Result<(), BinHexError> {
+        let mut header_bytes = vec![4, b'a', 0x8e, b'b', b'c', 0x01];
+        header_bytes.extend_from_slice(b"TEXTttxt");
+        header_bytes.extend_from_slice(&[0x01, 0x00, 0, 0, 0, 0, 0, 0, 0, 0]);
+        header_bytes.extend_from_slice(&State::<XMODEM>::calculate(&header_bytes).to_be_bytes());
+
+        let header = BinHexHeader::try_from(header_bytes)?;
+        let encoded = BinHexWriter::with_header(vec![], header.clone()).encode(
+            &mut Cursor::new(DATA_FORK),
+            DATA_FORK.len(),
+            &mut Cursor::new(RESOURCE_FORK),
+            RESOURCE_FORK.len(),
+        )?;
+
+        let archive = BinHexArchive::new(Cursor::new(encoded))?;
+
+        assert_eq!(&String::from("aébc"), archive.header().filename());
+        assert_eq!(header.filename_bytes(), archive.header().filename_bytes());
+        assert_eq!(1, archive.header().version());
+        assert!(archive.header().flags().has_been_inited());
+        assert_eq!(
+            header.with_fork_lengths(DATA_FORK.len(), RESOURCE_FORK.len()),
+            *archive.header()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn encode_canonical() -> Result<(), BinHexError> {
+        let writer = BinHexWriter::new(vec![], "binhex-test.txt", [0; 4], [0; 4], 0)?;
+        let encoded = writer.encode(
+            &mut Cursor::new(DATA_FORK),
+            DATA_FORK.len(),
+            &mut Cursor::new(RESOURCE_FORK),
+            RESOURCE_FORK.len(),
+        )?;
+
+        assert_eq!(BINHEX_DATA, encoded.as_slice());
+
+        Ok(())
+    }
+
+    #[test]
+    fn encode_escape_bytes() -> Result<(), BinHexError> {
+        let data_fork: Vec<u8> = (0..=255).chain(vec![0x90; 300]).collect();
+        let resource_fork = [0x90, 0x00, 0x90, 0x90, 0x04];
+
+        let writer = BinHexWriter::new(vec![], "Escapes", *b"BINA", *b"????", 0)?;
+        let encoded = writer.encode(
+            &mut Cursor::new(&data_fork),
+            data_fork.len(),
+            &mut Cursor::new(&resource_fork),
+            resource_fork.len(),
+        )?;
+
+        let mut extracted_data_fork = vec![];
+        let mut extracted_resource_fork = vec![];
+
+        BinHexArchive::new(Cursor::new(encoded))?
+            .extract(&mut extracted_data_fork, &mut extracted_resource_fork)?;
+
+        assert_eq!(data_fork, extracted_data_fork);
+        assert_eq!(&resource_fork[..], extracted_resource_fork.as_slice());
+
+        Ok(())
+    }
+
+    #[test]
+    fn encode_short_fork() -> Result<(), BinHexError> {
+        let writer = BinHexWriter::new(vec![], "Short", *b"TEXT", *b"ttxt", 0)?;
+
+        assert_eq!(
+            Err(BinHexError::IoError(io::ErrorKind::UnexpectedEof)),
+            writer.encode(
+                &mut Cursor::new(DATA_FORK),
+                DATA_FORK.len() + 1,
+                &mut io::empty(),
+                0
+            )
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn writer_invalid_filename() {
+        assert!(matches!(
+            BinHexWriter::new(vec![], "", *b"TEXT", *b"ttxt", 0),
+            Err(BinHexError::InvalidFilename)
+        ));
+
+        assert!(matches!(
+            BinHexWriter::new(vec![], &"x".repeat(64), *b"TEXT", *b"ttxt", 0),
+            Err(BinHexError::InvalidFilename)
+        ));
+
+        assert!(matches!(
+            BinHexWriter::new(vec![], "🤘", *b"TEXT", *b"ttxt", 0),
+            Err(BinHexError::InvalidFilename)
+        ));
+    }
 }
@@ -0,0 +1,195 @@
+use std::io::{Result, Write};
+
+use super::expand::{CANCEL_ESCAPE, RLE_ESCAPE};
+
+/// The longest run that can be represented by a single run-length marker.
+const MAX_RUN_LENGTH: usize = 255;
+
+/// The shortest run worth compressing; shorter runs are no larger when written literally.
+const MIN_RUN_LENGTH: usize = 3;
+
+/// Compresses data with BinHex's run-length encoding scheme.
+///
+/// BinHex compresses runs of three or more identical bytes by writing the byte once, followed by an
+/// escape byte (`0x90`) and the total length of the run. Literal `0x90` bytes are written as `0x90`
+/// followed by `0x00`. Runs longer than 255 bytes are split into multiple runs. This is the
+/// counterpart to [`super::BinHexExpander`], and produces the same compressed form as the original
+/// BinHex 4.0 application.
+///
+/// Because a run may continue across calls to `write`, the end of the final run is held until
+/// [`BinHexCompressor::finish`] is called; callers must call `finish` to write all compressed data
+/// to the underlying writer.
+///
+/// # Example
+///
+/// ```
+/// use std::io::Write;
+/// use clarus::binhex::BinHexCompressor;
+///
+/// fn main() -> std::io::Result<()> {
+///     let mut compressor = BinHexCompressor::new(vec![]);
+///     compressor.write_all(&[0x2b, 0xff, 0xff, 0xff, 0xff, 0x90])?;
+///
+///     assert_eq!(vec![0x2b, 0xff, 0x90, 0x04, 0x90, 0x00], compressor.finish()?);
+///
+///     Ok(())
+/// }
+/// ```
+pub struct BinHexCompressor<W: Write> {
+    dest: W,
+    run: Option<(u8, usize)>,
+}
+
+impl<W: Write> BinHexCompressor<W> {
+    /// Creates a new compressor that writes compressed data to the given destination.
+    pub fn new(dest: W) -> Self {
+        BinHexCompressor { dest, run: None }
+    }
+
+    /// Writes any pending run to the underlying writer and returns the underlying writer.
+    pub fn finish(mut self) -> Result<W> {
+        if let Some((byte, run_length)) = self.run.take() {
+            let mut compressed = Vec::with_capacity(4);
+            append_run(&mut compressed, byte, run_length);
+
+            self.dest.write_all(&compressed)?;
+        }
+
+        self.dest.flush()?;
+
+        Ok(self.dest)
+    }
+}
+
+impl<W: Write> Write for BinHexCompressor<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let mut compressed = Vec::with_capacity(buf.len() + 4);
+
+        for &byte in buf {
+            self.run = match self.run {
+                Some((run_byte, run_length)) if run_byte == byte && run_length < MAX_RUN_LENGTH => {
+                    Some((run_byte, run_length + 1))
+                }
+                Some((run_byte, run_length)) => {
+                    append_run(&mut compressed, run_byte, run_length);
+                    Some((byte, 1))
+                }
+                None => Some((byte, 1)),
+            };
+        }
+
+        self.dest.write_all(&compressed)?;
+
+        Ok(buf.len())
+    }
+
+    /// Flushes the underlying writer.
+    ///
+    /// Note that flushing does not write a pending run to the underlying writer, since the run may
+    /// continue with the next write; use [`BinHexCompressor::finish`] to write the final run.
+    fn flush(&mut self) -> Result<()> {
+        self.dest.flush()
+    }
+}
+
+/// Appends the compressed form of a run of `run_length` copies of `byte` to the given buffer.
+fn append_run(dest: &mut Vec<u8>, byte: u8, run_length: usize) {
+    debug_assert!(run_length > 0 && run_length <= MAX_RUN_LENGTH);
+
+    append_literal(dest, byte);
+
+    if run_length >= MIN_RUN_LENGTH {
+        dest.extend_from_slice(&[RLE_ESCAPE, run_length as u8]);
+    } else if run_length == 2 {
+        append_literal(dest, byte);
+    }
+}
+
+/// Appends a single byte to the given buffer, escaping it if needed.
+fn append_literal(dest: &mut Vec<u8>, byte: u8) {
+    if byte == RLE_ESCAPE {
+        dest.extend_from_slice(&[RLE_ESCAPE, CANCEL_ESCAPE]);
+    } else {
+        dest.push(byte);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::expand::BinHexExpander;
+    use super::*;
+    use std::io::Read;
+
+    fn compress(bytes: &[u8]) -> Vec<u8> {
+        let mut compressor = BinHexCompressor::new(vec![]);
+        compressor.write_all(bytes).unwrap();
+        compressor.finish().unwrap()
+    }
+
+    #[test]
+    fn compress_no_runs() {
+        assert_eq!(vec![0, 1, 2, 3], compress(&[0, 1, 2, 3]));
+    }
+
+    #[test]
+    fn compress_short_runs() {
+        assert_eq!(vec![0x2b, 0x2b, 0x14], compress(&[0x2b, 0x2b, 0x14]));
+        assert_eq!(
+            vec![0x2b, 0x90, 0x03, 0x14],
+            compress(&[0x2b, 0x2b, 0x2b, 0x14])
+        );
+    }
+
+    #[test]
+    fn compress_escape() {
+        assert_eq!(vec![0x2b, 0x90, 0x00, 0x14], compress(&[0x2b, 0x90, 0x14]));
+        assert_eq!(vec![0x90, 0x00, 0x90, 0x00], compress(&[0x90, 0x90]));
+        assert_eq!(
+            vec![0x2b, 0x90, 0x00, 0x90, 0x05],
+            compress(&[0x2b, 0x90, 0x90, 0x90, 0x90, 0x90])
+        );
+    }
+
+    #[test]
+    fn compress_long_run() {
+        assert_eq!(
+            vec![0xff, 0x90, 0xff, 0xff, 0x90, 0x0a],
+            compress(&[0xff; 265])
+        );
+
+        assert_eq!(vec![0xff, 0x90, 0xff, 0xff, 0xff], compress(&[0xff; 257]));
+    }
+
+    #[test]
+    fn compress_run_across_writes() {
+        let mut compressor = BinHexCompressor::new(vec![]);
+        compressor.write_all(&[0x2b, 0xff, 0xff]).unwrap();
+        compressor.write_all(&[0xff, 0xff]).unwrap();
+        compressor.write_all(&[0x14]).unwrap();
+
+        assert_eq!(
+            vec![0x2b, 0xff, 0x90, 0x04, 0x14],
+            compressor.finish().unwrap()
+        );
+    }
+
+    #[test]
+    fn compress_expand() {
+        let original: Vec<u8> = (0..=255u8)
+            .chain(vec![0x90; 600])
+            .chain(vec![0x00; 3])
+            .chain(vec![0x90, 0x00, 0x90, 0x90])
+            .chain(vec![0x2b; 1000])
+            .collect();
+
+        let compressed = compress(&original);
+        assert!(compressed.len() < original.len());
+
+        let mut expanded = vec![];
+        BinHexExpander::new(compressed.as_slice())
+            .read_to_end(&mut expanded)
+            .unwrap();
+
+        assert_eq!(original, expanded);
+    }
+}
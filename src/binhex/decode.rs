@@ -0,0 +1,213 @@
+use std::io::{ErrorKind, Read, Result};
+
+/// The 64 characters used to encode data in BinHex archives, in order.
+pub(super) const BINHEX_ALPHABET: &[u8] =
+    br##"!"#$%&'()*+,-012345689@ABCDEFGHIJKLMNPQRSTUVXYZ[`abcdefhijklmpqr"##;
+
+const INVALID: u8 = 0xff;
+const DECODE_TABLE: [u8; 256] = decode_table();
+
+/// Decodes BinHex's radix-64 encoding.
+///
+/// The source must produce only characters from the BinHex alphabet (i.e. the output of an
+/// [`super::read::EncodedBinHexReader`]). Groups of four characters are decoded into three bytes. A
+/// trailing group of two or three characters is decoded into one or two bytes, respectively, and
+/// any unused trailing bits are ignored. Some BinHex encoders emit a single extra character at the
+/// end of the encoded data; a trailing "group" of one character can't represent a whole byte, and
+/// is ignored.
+pub struct Radix64Decoder<R: Read> {
+    source: R,
+    chars: [u8; 1024],
+    chars_len: usize,
+    pending: [u8; 3],
+    pending_start: usize,
+    pending_len: usize,
+    eof: bool,
+}
+
+impl<R: Read> Radix64Decoder<R> {
+    pub fn new(source: R) -> Self {
+        Radix64Decoder {
+            source,
+            chars: [0; 1024],
+            chars_len: 0,
+            pending: [0; 3],
+            pending_start: 0,
+            pending_len: 0,
+            eof: false,
+        }
+    }
+
+    /// Returns a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.source
+    }
+
+    /// Returns a mutable reference to the underlying reader.
+    ///
+    /// Reading directly from the underlying reader will corrupt this decoder's output.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.source
+    }
+
+    /// Reads characters from the source until at least one complete group of four characters is
+    /// available or the source is exhausted.
+    fn fill(&mut self) -> Result<()> {
+        while self.chars_len < 4 && !self.eof {
+            match self.source.read(&mut self.chars[self.chars_len..]) {
+                Ok(0) => self.eof = true,
+                Ok(bytes_read) => self.chars_len += bytes_read,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for Radix64Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        if self.pending_len == 0 {
+            self.fill()?;
+
+            let full_groups = self.chars_len / 4;
+
+            if full_groups > 0 {
+                // Decode as many whole groups as we can directly into the destination buffer
+                let groups = std::cmp::min(full_groups, buf.len() / 3);
+
+                for group in 0..groups {
+                    decode_group(
+                        &self.chars[group * 4..group * 4 + 4],
+                        &mut buf[group * 3..group * 3 + 3],
+                    );
+                }
+
+                if groups > 0 {
+                    self.chars.copy_within(groups * 4..self.chars_len, 0);
+                    self.chars_len -= groups * 4;
+
+                    return Ok(groups * 3);
+                }
+
+                // The destination buffer is too small for a whole group
+                decode_group(&self.chars[..4], &mut self.pending);
+                self.pending_len = 3;
+
+                self.chars.copy_within(4..self.chars_len, 0);
+                self.chars_len -= 4;
+            } else {
+                // We've reached the end of the source with less than a full group remaining
+                debug_assert!(self.eof);
+
+                // Pad the partial group with zero-valued characters
+                let mut group = [BINHEX_ALPHABET[0]; 4];
+                group[..self.chars_len].copy_from_slice(&self.chars[..self.chars_len]);
+
+                decode_group(&group, &mut self.pending);
+
+                self.pending_len = match self.chars_len {
+                    2 => 1,
+                    3 => 2,
+                    _ => 0,
+                };
+
+                self.chars_len = 0;
+            }
+
+            self.pending_start = 0;
+        }
+
+        let len = std::cmp::min(buf.len(), self.pending_len);
+
+        buf[..len].copy_from_slice(&self.pending[self.pending_start..self.pending_start + len]);
+        self.pending_start += len;
+        self.pending_len -= len;
+
+        Ok(len)
+    }
+}
+
+/// Returns `true` if the given byte is part of the BinHex alphabet.
+pub(super) fn is_encoded_char(byte: u8) -> bool {
+    DECODE_TABLE[byte as usize] != INVALID
+}
+
+/// Decodes a group of four characters into three bytes.
+fn decode_group(chars: &[u8], dest: &mut [u8]) {
+    let bits = chars.iter().fold(0u32, |bits, &c| {
+        debug_assert!(is_encoded_char(c));
+        (bits << 6) | DECODE_TABLE[c as usize] as u32
+    });
+
+    dest[0] = (bits >> 16) as u8;
+    dest[1] = (bits >> 8) as u8;
+    dest[2] = bits as u8;
+}
+
+const fn decode_table() -> [u8; 256] {
+    let mut table = [INVALID; 256];
+    let mut i = 0;
+
+    while i < BINHEX_ALPHABET.len() {
+        table[BINHEX_ALPHABET[i] as usize] = i as u8;
+        i += 1;
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn decode(chars: &[u8]) -> Vec<u8> {
+        let mut decoded = vec![];
+
+        Radix64Decoder::new(chars)
+            .read_to_end(&mut decoded)
+            .unwrap();
+
+        decoded
+    }
+
+    #[test]
+    fn decode_groups() {
+        assert_eq!(b"Hello!".to_vec(), decode(b"5'9XE'mK"));
+    }
+
+    #[test]
+    fn decode_partial_groups() {
+        assert_eq!(b"Hello".to_vec(), decode(b"5'9XE'm"));
+        assert_eq!(b"Hell".to_vec(), decode(b"5'9XE!"));
+        assert_eq!(b"Hel".to_vec(), decode(b"5'9X"));
+        assert_eq!(b"Hel".to_vec(), decode(b"5'9X!"));
+    }
+
+    #[test]
+    fn decode_tiny_buffer() {
+        let mut decoder = Radix64Decoder::new(&b"5'9XE'm"[..]);
+        let mut buf = [0; 1];
+        let mut decoded = vec![];
+
+        while let Ok(1) = decoder.read(&mut buf) {
+            decoded.extend_from_slice(&buf);
+        }
+
+        assert_eq!(b"Hello".to_vec(), decoded);
+    }
+
+    #[test]
+    fn is_encoded_char() {
+        assert!(super::is_encoded_char(b'!'));
+        assert!(super::is_encoded_char(b'r'));
+        assert!(!super::is_encoded_char(b's'));
+        assert!(!super::is_encoded_char(b':'));
+        assert!(!super::is_encoded_char(b' '));
+    }
+}
@@ -1,30 +1,106 @@
 use std::cmp;
+use std::error;
+use std::fmt::{self, Display, Formatter};
 use std::io::{BufRead, BufReader, Error, ErrorKind, Read, Result};
 
-const RLE_ESCAPE: u8 = 0x90;
-const CANCEL_ESCAPE: u8 = 0x00;
+pub(super) const RLE_ESCAPE: u8 = 0x90;
+pub(super) const CANCEL_ESCAPE: u8 = 0x00;
 
 /// Expands run-length-encoded BinHex data.
+///
+/// BinHex compresses runs of identical bytes by writing the byte once, followed by an escape byte
+/// (`0x90`) and the total length of the run; a literal `0x90` byte is written as `0x90` followed by
+/// `0x00`. This is the counterpart to [`super::BinHexCompressor`].
+///
+/// If the compressed data is malformed (i.e. if it ends with an unresolved escape byte or contains
+/// a run length with no preceding byte to repeat), reads return an [`std::io::Error`] with a kind
+/// of [`std::io::ErrorKind::InvalidData`].
+///
+/// # Example
+///
+/// ```
+/// use std::io::Read;
+/// use clarus::binhex::BinHexExpander;
+///
+/// fn main() -> std::io::Result<()> {
+///     let mut expander = BinHexExpander::new(&[0x2b, 0xff, 0x90, 0x04, 0x90, 0x00][..]);
+///
+///     let mut expanded = vec![];
+///     expander.read_to_end(&mut expanded)?;
+///
+///     assert_eq!(vec![0x2b, 0xff, 0xff, 0xff, 0xff, 0x90], expanded);
+///
+///     Ok(())
+/// }
+/// ```
 pub struct BinHexExpander<R: Read> {
     source: BufReader<R>,
     state: State,
+    bytes_consumed: usize,
+    escape_offset: usize,
+    error: Option<Error>,
 }
 
 impl<R: Read> BinHexExpander<R> {
+    /// Creates a new expander that reads compressed data from the given source.
     pub fn new(source: R) -> Self {
         BinHexExpander {
             source: BufReader::new(source),
             state: State::Scan(None),
+            bytes_consumed: 0,
+            escape_offset: 0,
+            error: None,
         }
     }
+
+    /// Returns a reference to the underlying reader.
+    pub(super) fn get_ref(&self) -> &R {
+        self.source.get_ref()
+    }
+
+    /// Returns a mutable reference to the underlying reader.
+    ///
+    /// Reading directly from the underlying reader will corrupt this expander's output.
+    pub(super) fn get_mut(&mut self) -> &mut R {
+        self.source.get_mut()
+    }
+
+    /// Returns the number of compressed bytes this expander has consumed from its source.
+    pub(super) fn bytes_consumed(&self) -> usize {
+        self.bytes_consumed
+    }
+
+    /// Reports the given error, deferring it until the next read if some bytes have already been
+    /// copied to the destination buffer so callers receive all valid data before the error.
+    fn fail(&mut self, error: Error, bytes_copied: usize) -> Result<usize> {
+        if bytes_copied > 0 {
+            self.error = Some(error);
+            Ok(bytes_copied)
+        } else {
+            Err(error)
+        }
+    }
+
+    /// Marks the given number of bytes from the source's buffer as consumed.
+    fn consume(&mut self, amt: usize) {
+        self.source.consume(amt);
+        self.bytes_consumed += amt;
+    }
 }
 
 impl<R: Read> Read for BinHexExpander<R> {
     fn read(&mut self, dest: &mut [u8]) -> Result<usize> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+
         let mut bytes_copied = 0;
 
         loop {
-            let buf = self.source.fill_buf()?;
+            let buf = match self.source.fill_buf() {
+                Ok(buf) => buf,
+                Err(error) => return self.fail(error, bytes_copied),
+            };
 
             let event = match self.state {
                 State::Scan(_) => {
@@ -37,7 +113,8 @@ impl<R: Read> Read for BinHexExpander<R> {
 
                         match memchr::memchr(RLE_ESCAPE, &buf[..capacity]) {
                             Some(0) => {
-                                self.source.consume(1);
+                                self.escape_offset = self.bytes_consumed;
+                                self.consume(1);
 
                                 Event::FoundEscape
                             }
@@ -49,7 +126,7 @@ impl<R: Read> Read for BinHexExpander<R> {
                                 let last_byte = buf[pos - 1];
 
                                 bytes_copied += pos;
-                                self.source.consume(pos);
+                                self.consume(pos);
 
                                 Event::CopiedBytes(pos, last_byte)
                             }
@@ -60,7 +137,7 @@ impl<R: Read> Read for BinHexExpander<R> {
                                 let last_byte = buf[capacity - 1];
 
                                 bytes_copied += capacity;
-                                self.source.consume(capacity);
+                                self.consume(capacity);
 
                                 Event::CopiedBytes(capacity, last_byte)
                             }
@@ -76,13 +153,13 @@ impl<R: Read> Read for BinHexExpander<R> {
                                 dest[bytes_copied] = RLE_ESCAPE;
 
                                 bytes_copied += 1;
-                                self.source.consume(1);
+                                self.consume(1);
 
                                 Event::CopiedBytes(1, RLE_ESCAPE)
                             }
                             _ => {
                                 let run_length = buf[0] as usize;
-                                self.source.consume(1);
+                                self.consume(1);
 
                                 // We subtract one because we've already copied one instance of the
                                 // byte to be expanded
@@ -104,7 +181,30 @@ impl<R: Read> Read for BinHexExpander<R> {
                 }
             };
 
-            self.state = self.state.advance(event)?;
+            let source_empty = matches!(event, Event::SourceEmpty);
+
+            self.state = match self.state.advance(event) {
+                Ok(state) => state,
+                Err(message) => {
+                    // Skip past the malformed escape sequence so callers may continue reading
+                    // after the error if they choose
+                    self.state = if source_empty {
+                        State::Done
+                    } else {
+                        State::Scan(None)
+                    };
+
+                    let error = Error::new(
+                        ErrorKind::InvalidData,
+                        RunLengthError {
+                            offset: self.escape_offset,
+                            message,
+                        },
+                    );
+
+                    return self.fail(error, bytes_copied);
+                }
+            };
 
             if bytes_copied == dest.len() {
                 return Ok(bytes_copied);
@@ -132,7 +232,7 @@ enum State {
 }
 
 impl State {
-    fn advance(&self, event: Event) -> Result<Self> {
+    fn advance(&self, event: Event) -> std::result::Result<Self, &'static str> {
         match (self, &event) {
             (State::Scan(_), Event::CopiedBytes(_, last_byte)) => Ok(State::Scan(Some(*last_byte))),
             (State::Scan(expandable_byte), Event::FoundEscape) => {
@@ -145,10 +245,12 @@ impl State {
             (State::Escape(Some(expandable_byte)), Event::FoundRunLength(run_length)) => {
                 Ok(State::Expand(*expandable_byte, *run_length))
             }
-            (State::Escape(_), Event::SourceEmpty) => Err(Error::new(
-                ErrorKind::InvalidData,
-                "Stream ended with unresolved RLE escape",
-            )),
+            (State::Escape(None), Event::FoundRunLength(_)) => {
+                Err("RLE run length has no preceding byte to repeat")
+            }
+            (State::Escape(_), Event::SourceEmpty) => {
+                Err("Stream ended with unresolved RLE escape")
+            }
             (State::Expand(byte, run_length), Event::CopiedBytes(bytes_copied, _)) => {
                 if bytes_copied < run_length {
                     Ok(State::Expand(*byte, run_length - bytes_copied))
@@ -161,6 +263,23 @@ impl State {
     }
 }
 
+/// An error indicating that a BinHex stream contained a malformed RLE escape sequence.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(super) struct RunLengthError {
+    /// The offset, in bytes from the start of the compressed stream, of the escape byte that
+    /// started the malformed sequence.
+    pub offset: usize,
+    message: &'static str,
+}
+
+impl Display for RunLengthError {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        write!(fmt, "{} at offset {}", self.message, self.offset)
+    }
+}
+
+impl error::Error for RunLengthError {}
+
 /// An event produced when expanding BinHex data that may alter that expander's state.
 #[derive(Debug)]
 enum Event {
@@ -272,4 +391,42 @@ mod test {
         assert_eq!(6, expander.read(&mut buf).unwrap());
         assert_eq!(buf[0..6], [0x2b, 0x90, 0x90, 0x90, 0x90, 0x90]);
     }
+
+    #[test]
+    fn expand_unresolved_escape() {
+        let mut expander = BinHexExpander::new(&[0x2b, 0x14, 0x90][..]);
+        let mut expanded = vec![];
+
+        let error = expander.read_to_end(&mut expanded).unwrap_err();
+
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+        assert_eq!(
+            Some(2),
+            error
+                .get_ref()
+                .and_then(|inner| inner.downcast_ref::<RunLengthError>())
+                .map(|error| error.offset)
+        );
+    }
+
+    #[test]
+    fn expand_run_without_byte() {
+        let mut expander = BinHexExpander::new(&[0x90, 0x04, 0x2b][..]);
+        let mut expanded = vec![];
+
+        let error = expander.read_to_end(&mut expanded).unwrap_err();
+
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+        assert_eq!(
+            Some(0),
+            error
+                .get_ref()
+                .and_then(|inner| inner.downcast_ref::<RunLengthError>())
+                .map(|error| error.offset)
+        );
+
+        // Expansion can continue after the malformed escape sequence
+        assert_eq!(1, expander.read_to_end(&mut expanded).unwrap());
+        assert_eq!(vec![0x2b], expanded);
+    }
 }
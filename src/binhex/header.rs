@@ -0,0 +1,289 @@
+use std::convert::{TryFrom, TryInto};
+
+use super::archive::{BinHexError, ChecksumSection};
+use crate::finder::FinderFlags;
+use crate::util::{encode_filename, MAX_FILENAME_LENGTH};
+
+/// The header of a BinHex archive, which contains the metadata for the archived file.
+///
+/// Headers preserve all of the information stored in an archive's header, including the version
+/// byte and the raw bytes of the filename, so a header read from one archive (via
+/// [`super::BinHexArchive::header`]) can be written to another archive unchanged (via
+/// [`super::BinHexWriter::with_header`]).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BinHexHeader {
+    name: String,
+    name_bytes: Vec<u8>,
+    version: u8,
+    file_type: [u8; 4],
+    creator: [u8; 4],
+    flags: FinderFlags,
+    data_fork_length: usize,
+    resource_fork_length: usize,
+}
+
+impl BinHexHeader {
+    /// Creates a new header with the given metadata, a version byte of zero, and empty forks.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the given filename is empty, is longer than 63 bytes
+    /// when encoded, or contains characters that can't be represented in the Macintosh character
+    /// encoding.
+    pub fn new(
+        filename: &str,
+        file_type: [u8; 4],
+        creator: [u8; 4],
+        flags: FinderFlags,
+    ) -> Result<Self, BinHexError> {
+        let name_bytes = encode_filename(filename).ok_or(BinHexError::InvalidFilename)?;
+
+        Ok(BinHexHeader {
+            name: String::from(filename),
+            name_bytes: name_bytes.to_vec(),
+            version: 0,
+            file_type,
+            creator,
+            flags,
+            data_fork_length: 0,
+            resource_fork_length: 0,
+        })
+    }
+
+    /// Creates a new header from the metadata found in a pre-4.0 archive, which may have an empty
+    /// name.
+    pub(super) fn from_legacy(
+        name_bytes: &[u8],
+        file_type: [u8; 4],
+        creator: [u8; 4],
+        flags: FinderFlags,
+    ) -> Result<Self, BinHexError> {
+        if name_bytes.len() > MAX_FILENAME_LENGTH {
+            return Err(BinHexError::InvalidFilename);
+        }
+
+        let (name, _, _) = encoding_rs::MACINTOSH.decode(name_bytes);
+
+        Ok(BinHexHeader {
+            name: name.to_string(),
+            name_bytes: name_bytes.to_vec(),
+            version: 0,
+            file_type,
+            creator,
+            flags,
+            data_fork_length: 0,
+            resource_fork_length: 0,
+        })
+    }
+
+    /// Returns the original filename of the archived file, decoded from the Macintosh character
+    /// encoding.
+    pub fn filename(&self) -> &String {
+        &self.name
+    }
+
+    /// Returns the raw bytes of the archived file's name, before decoding.
+    pub fn filename_bytes(&self) -> &[u8] {
+        &self.name_bytes
+    }
+
+    /// Returns the header's version byte.
+    ///
+    /// The BinHex 4.0 specification requires the version byte to be zero, but some encoders wrote
+    /// other values.
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// Returns the file type identifier for the archived file.
+    ///
+    /// For a detailed description of file signatures (including file type identifiers), please see
+    /// the ["Giving a Signature to Your Application and a Creator and a File Type to Your
+    /// Documents" section of "Inside Macintosh: Macintosh Toolbox
+    /// Essentials"](https://developer.apple.com/library/archive/documentation/mac/pdf/MacintoshToolboxEssentials.pdf#page=806).
+    pub fn file_type(&self) -> [u8; 4] {
+        self.file_type
+    }
+
+    /// Returns the creator identifier for the archived file.
+    ///
+    /// For a detailed description of file signatures (including creator identifiers), please see
+    /// the ["Giving a Signature to Your Application and a Creator and a File Type to Your
+    /// Documents" section of "Inside Macintosh: Macintosh Toolbox
+    /// Essentials"](https://developer.apple.com/library/archive/documentation/mac/pdf/MacintoshToolboxEssentials.pdf#page=806).
+    pub fn creator(&self) -> [u8; 4] {
+        self.creator
+    }
+
+    /// Returns the Finder flags for the archived file.
+    pub fn flags(&self) -> FinderFlags {
+        self.flags
+    }
+
+    /// Returns the length, in bytes after decoding, of the archived file's data fork.
+    pub fn data_fork_len(&self) -> usize {
+        self.data_fork_length
+    }
+
+    /// Returns the length, in bytes after decoding, of the archived file's resource fork.
+    pub fn resource_fork_len(&self) -> usize {
+        self.resource_fork_length
+    }
+
+    /// Returns a copy of this header with the given fork lengths.
+    pub(super) fn with_fork_lengths(
+        self,
+        data_fork_length: usize,
+        resource_fork_length: usize,
+    ) -> Self {
+        BinHexHeader {
+            data_fork_length,
+            resource_fork_length,
+            ..self
+        }
+    }
+}
+
+impl TryFrom<Vec<u8>> for BinHexHeader {
+    type Error = BinHexError;
+
+    fn try_from(header_bytes: Vec<u8>) -> Result<Self, Self::Error> {
+        let (name_length_bytes, remaining_bytes) = header_bytes.split_at(1);
+        let name_length = name_length_bytes[0] as usize;
+
+        if header_bytes.len() != name_length + 22 {
+            return Err(BinHexError::InvalidHeader);
+        }
+
+        let (name_bytes, remaining_bytes) = remaining_bytes.split_at(name_length);
+        let (version_bytes, remaining_bytes) = remaining_bytes.split_at(1);
+        let (file_type_bytes, remaining_bytes) = remaining_bytes.split_at(4);
+        let (creator_bytes, remaining_bytes) = remaining_bytes.split_at(4);
+        let (flag_bytes, remaining_bytes) = remaining_bytes.split_at(2);
+        let (data_fork_length_bytes, remaining_bytes) = remaining_bytes.split_at(4);
+        let (resource_fork_length_bytes, remaining_bytes) = remaining_bytes.split_at(4);
+        let (checksum_bytes, remaining_bytes) = remaining_bytes.split_at(2);
+
+        debug_assert!(remaining_bytes.is_empty());
+
+        let calculated_checksum =
+            crc16::State::<crc16::XMODEM>::calculate(&header_bytes[..header_bytes.len() - 2]);
+        let provided_checksum = u16::from_be_bytes(checksum_bytes.try_into().unwrap());
+
+        if provided_checksum != calculated_checksum {
+            return Err(BinHexError::InvalidChecksum(
+                ChecksumSection::Header,
+                provided_checksum,
+                calculated_checksum,
+            ));
+        }
+
+        let (name_cow, _, _) = encoding_rs::MACINTOSH.decode(name_bytes);
+        let name = name_cow.to_string();
+        let version = version_bytes[0];
+        let file_type: [u8; 4] = TryInto::<[u8; 4]>::try_into(file_type_bytes).unwrap();
+        let creator: [u8; 4] = TryInto::<[u8; 4]>::try_into(creator_bytes).unwrap();
+        let flags = FinderFlags::from(u16::from_be_bytes(flag_bytes.try_into().unwrap()));
+        let data_fork_length: usize =
+            u32::from_be_bytes(data_fork_length_bytes.try_into().unwrap()) as usize;
+        let resource_fork_length: usize =
+            u32::from_be_bytes(resource_fork_length_bytes.try_into().unwrap()) as usize;
+
+        Ok(BinHexHeader {
+            name,
+            name_bytes: name_bytes.to_vec(),
+            version,
+            file_type,
+            creator,
+            flags,
+            data_fork_length,
+            resource_fork_length,
+        })
+    }
+}
+
+impl From<&BinHexHeader> for Vec<u8> {
+    fn from(header: &BinHexHeader) -> Self {
+        let mut header_bytes = Vec::with_capacity(header.name_bytes.len() + 22);
+
+        header_bytes.push(header.name_bytes.len() as u8);
+        header_bytes.extend_from_slice(&header.name_bytes);
+        header_bytes.push(header.version);
+        header_bytes.extend_from_slice(&header.file_type);
+        header_bytes.extend_from_slice(&header.creator);
+        header_bytes.extend_from_slice(&header.flags.bits().to_be_bytes());
+        header_bytes.extend_from_slice(&(header.data_fork_length as u32).to_be_bytes());
+        header_bytes.extend_from_slice(&(header.resource_fork_length as u32).to_be_bytes());
+
+        let checksum = crc16::State::<crc16::XMODEM>::calculate(&header_bytes);
+        header_bytes.extend_from_slice(&checksum.to_be_bytes());
+
+        header_bytes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn new() -> Result<(), BinHexError> {
+        let header = BinHexHeader::new(
+            "SimpleText™ Document",
+            *b"TEXT",
+            *b"ttxt",
+            FinderFlags::from(0x0100),
+        )?;
+
+        assert_eq!("SimpleText™ Document", header.filename());
+        assert_eq!(b"SimpleText\xaa Document", header.filename_bytes());
+        assert_eq!(0, header.version());
+        assert!(header.flags().has_been_inited());
+
+        Ok(())
+    }
+
+    #[test]
+    fn new_invalid_filename() {
+        assert_eq!(
+            Err(BinHexError::InvalidFilename),
+            BinHexHeader::new("", *b"TEXT", *b"ttxt", FinderFlags::default())
+        );
+
+        assert_eq!(
+            Err(BinHexError::InvalidFilename),
+            BinHexHeader::new(&"x".repeat(64), *b"TEXT", *b"ttxt", FinderFlags::default())
+        );
+
+        assert_eq!(
+            Err(BinHexError::InvalidFilename),
+            BinHexHeader::new("🤘", *b"TEXT", *b"ttxt", FinderFlags::default())
+        );
+    }
+
+    #[test]
+    fn round_trip() -> Result<(), BinHexError> {
+        let mut header_bytes = vec![3, b'a', b'b', b'c', 0x01];
+        header_bytes.extend_from_slice(b"TEXTttxt");
+        header_bytes.extend_from_slice(&[0x44, 0x00]);
+        header_bytes.extend_from_slice(&[0, 0, 0, 5, 0, 0, 1, 0]);
+
+        let checksum = crc16::State::<crc16::XMODEM>::calculate(&header_bytes);
+        header_bytes.extend_from_slice(&checksum.to_be_bytes());
+
+        let header = BinHexHeader::try_from(header_bytes.clone())?;
+
+        assert_eq!("abc", header.filename());
+        assert_eq!(1, header.version());
+        assert_eq!(*b"TEXT", header.file_type());
+        assert_eq!(*b"ttxt", header.creator());
+        assert!(header.flags().is_invisible());
+        assert!(header.flags().has_custom_icon());
+        assert_eq!(5, header.data_fork_len());
+        assert_eq!(256, header.resource_fork_len());
+
+        assert_eq!(header_bytes, Vec::from(&header));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,713 @@
+use std::convert::TryInto;
+use std::io::{Read, Write};
+
+use super::any::BinHexFormat;
+use super::archive::{BinHexError, ChecksumSection, ErrorLocation};
+use super::expand::{BinHexExpander, RunLengthError};
+use super::header::BinHexHeader;
+use super::parts::find;
+use super::read::BANNER;
+use crate::finder::FinderFlags;
+
+const MARKER_PREFIX: &[u8] = b"***";
+const COMPRESSED_MARKER: &[u8] = b"COMPRESSED";
+const CHECKSUM_PREFIX: &[u8] = b"CHECKSUM:";
+const CRC_PREFIX: &[u8] = b"CRC:";
+
+/// An archive in one of the formats that preceded BinHex 4.0.
+///
+/// Before BinHex 4.0, BinHex wrote files in one of two text formats:
+///
+/// - BinHex 1.0 and 2.0 wrote each byte of the archived file as a pair of hexadecimal digits;
+///   these archives usually have a `.hex` extension.
+/// - BinHex 3.0 compressed runs of repeated bytes (in the same manner as BinHex 4.0) and wrote the
+///   compressed bytes as six-bit characters, with each group of four characters holding three
+///   bytes; these archives usually have a `.hcx` extension.
+///
+/// Both formats share the same layout: a banner naming the version of BinHex that wrote the file,
+/// an optional line containing the archived file's name, and a line containing its file type,
+/// creator, and Finder flags (e.g. `#TEXTttxt$0100`). The encoded data and resource forks follow,
+/// each introduced by a `***DATA` or `***RESOURCE` marker and followed by an optional
+/// `***CHECKSUM:` (a 16-bit sum of the fork's bytes) or `***CRC:` (a CRC-16/XMODEM of the fork's
+/// bytes) marker. A `***COMPRESSED` marker identifies BinHex 3.0 data regardless of the version in
+/// the banner, and an `***END` marker (if present) closes the archive.
+///
+/// Unlike [`super::BinHexArchive`], which reads forks from its source on demand, a legacy archive
+/// reads and decodes its entire source when it's created. Legacy archives don't record the lengths
+/// of their forks, and so the only way to learn the lengths reported by [`Self::header`] is to
+/// decode the forks; archives in these formats are also generally small.
+///
+/// To read an archive without knowing in advance whether it's a legacy archive or a BinHex 4.0
+/// archive, please see [`super::AnyBinHexArchive`].
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs::File;
+/// use clarus::binhex::{BinHexError, LegacyBinHexArchive};
+///
+/// fn main() -> Result<(), BinHexError> {
+///     let archive = LegacyBinHexArchive::new(File::open("example.hex")?)?;
+///
+///     println!("{} ({:?})", archive.filename(), archive.format());
+///
+///     archive.extract(
+///         &mut File::create("data_fork.bin")?,
+///         &mut File::create("resource_fork.bin")?,
+///     )?;
+///
+///     Ok(())
+/// }
+/// ```
+pub struct LegacyBinHexArchive {
+    format: BinHexFormat,
+    header: BinHexHeader,
+    data_fork: Vec<u8>,
+    resource_fork: Vec<u8>,
+}
+
+impl LegacyBinHexArchive {
+    /// Reads and decodes a legacy BinHex archive from the given source.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the source doesn't contain a BinHex 1.0, 2.0, or 3.0
+    /// banner followed by a type and creator line, if the archived file's name is longer than 63
+    /// bytes, if either fork contains malformed data or fails its checksum, or if an IO error
+    /// occurs while reading from the source.
+    pub fn new(mut source: impl Read) -> Result<Self, BinHexError> {
+        let mut text = vec![];
+        source.read_to_end(&mut text)?;
+
+        Self::from_text(&text)
+    }
+
+    /// Decodes a legacy BinHex archive from the entire text of its source.
+    pub(super) fn from_text(text: &[u8]) -> Result<Self, BinHexError> {
+        let mut lines = Lines::new(text);
+
+        // Skip everything up to and including the banner
+        let mut format = loop {
+            let line = lines.next().ok_or(BinHexError::InvalidHeader)?;
+
+            if let Some(start) = find(line.text, BANNER) {
+                match banner_format(&line.text[start + BANNER.len()..]) {
+                    Some(BinHexFormat::Hex) => break BinHexFormat::Hex,
+                    Some(BinHexFormat::Compressed) => break BinHexFormat::Compressed,
+                    _ => return Err(BinHexError::InvalidHeader),
+                }
+            }
+        };
+
+        // The type and creator line may be preceded by a line containing the file's name
+        let mut name: &[u8] = &[];
+
+        let info = loop {
+            let line = trim(lines.next().ok_or(BinHexError::InvalidHeader)?.text);
+
+            if line.first() == Some(&b'#') {
+                break line;
+            } else if line.starts_with(MARKER_PREFIX)
+                && trim(&line[MARKER_PREFIX.len()..]) == COMPRESSED_MARKER
+            {
+                format = BinHexFormat::Compressed;
+            } else if !line.is_empty() {
+                if !name.is_empty() {
+                    return Err(BinHexError::InvalidHeader);
+                }
+
+                name = line;
+            }
+        };
+
+        let (file_type, creator, flags) = parse_info(info)?;
+
+        let mut data_fork = EncodedFork::new(ChecksumSection::DataFork);
+        let mut resource_fork = EncodedFork::new(ChecksumSection::ResourceFork);
+        let mut current_fork = None;
+
+        for line in lines {
+            if line.text.starts_with(MARKER_PREFIX) {
+                let marker = &line.text[MARKER_PREFIX.len()..];
+
+                match trim(marker) {
+                    COMPRESSED_MARKER => format = BinHexFormat::Compressed,
+                    b"DATA" => current_fork = Some(ChecksumSection::DataFork),
+                    b"RESOURCE" => current_fork = Some(ChecksumSection::ResourceFork),
+                    b"END" => break,
+                    _ => {
+                        let (kind, value) = if marker.starts_with(CHECKSUM_PREFIX) {
+                            (ChecksumKind::Sum, &marker[CHECKSUM_PREFIX.len()..])
+                        } else if marker.starts_with(CRC_PREFIX) {
+                            (ChecksumKind::Crc, &marker[CRC_PREFIX.len()..])
+                        } else {
+                            // Ignore unrecognized markers
+                            continue;
+                        };
+
+                        let checksum = Checksum {
+                            kind,
+                            value: parse_hex(trim(value)),
+                            line: line.number,
+                            column: line.text.len() - value.len() + 1,
+                        };
+
+                        match current_fork {
+                            Some(ChecksumSection::DataFork) => data_fork.checksum = Some(checksum),
+                            Some(ChecksumSection::ResourceFork) => {
+                                resource_fork.checksum = Some(checksum)
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            } else {
+                match current_fork {
+                    Some(ChecksumSection::DataFork) => data_fork.push_line(&line),
+                    Some(ChecksumSection::ResourceFork) => resource_fork.push_line(&line),
+                    _ => {}
+                }
+            }
+        }
+
+        let data_fork = data_fork.decode(format)?;
+        let resource_fork = resource_fork.decode(format)?;
+
+        let header = BinHexHeader::from_legacy(name, file_type, creator, flags)?
+            .with_fork_lengths(data_fork.len(), resource_fork.len());
+
+        Ok(LegacyBinHexArchive {
+            format,
+            header,
+            data_fork,
+            resource_fork,
+        })
+    }
+
+    /// Returns the format of this archive (either [`BinHexFormat::Hex`] or
+    /// [`BinHexFormat::Compressed`]).
+    pub fn format(&self) -> BinHexFormat {
+        self.format
+    }
+
+    /// Returns this archive's header.
+    ///
+    /// Legacy archives don't have a header of their own; the returned header contains the metadata
+    /// found in the archive, a version byte of zero, and the lengths of the decoded forks. If the
+    /// archive doesn't include the archived file's name, the header's filename is empty.
+    pub fn header(&self) -> &BinHexHeader {
+        &self.header
+    }
+
+    /// Returns the original filename of the archived file, which may be empty for archives that
+    /// don't include a name.
+    pub fn filename(&self) -> &String {
+        self.header.filename()
+    }
+
+    /// Returns the file type identifier for the archived file.
+    pub fn file_type(&self) -> [u8; 4] {
+        self.header.file_type()
+    }
+
+    /// Returns the creator identifier for the archived file.
+    pub fn creator(&self) -> [u8; 4] {
+        self.header.creator()
+    }
+
+    /// Returns the Finder flags for the archived file.
+    pub fn flags(&self) -> FinderFlags {
+        self.header.flags()
+    }
+
+    /// Returns the decoded content of the archived file's data fork.
+    pub fn data_fork(&self) -> &[u8] {
+        &self.data_fork
+    }
+
+    /// Returns the decoded content of the archived file's resource fork.
+    pub fn resource_fork(&self) -> &[u8] {
+        &self.resource_fork
+    }
+
+    /// Writes the archived file's data and resource forks to the given writers.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error if an IO error occurs while writing either fork.
+    pub fn extract(
+        self,
+        data_writer: &mut impl Write,
+        resource_writer: &mut impl Write,
+    ) -> Result<(), BinHexError> {
+        data_writer.write_all(&self.data_fork)?;
+        resource_writer.write_all(&self.resource_fork)?;
+
+        Ok(())
+    }
+}
+
+/// Returns the format identified by the version in a BinHex banner, given the text immediately
+/// following the banner (e.g. `" 2.0)"`).
+pub(super) fn banner_format(text: &[u8]) -> Option<BinHexFormat> {
+    let end = memchr::memchr(b')', text)?;
+
+    match trim(&text[..end]).first() {
+        Some(b'1') | Some(b'2') => Some(BinHexFormat::Hex),
+        Some(b'3') => Some(BinHexFormat::Compressed),
+        Some(b'4') => Some(BinHexFormat::BinHex4),
+        _ => None,
+    }
+}
+
+/// Parses a type and creator line (e.g. `#TEXTttxt$0100`) into a file type, creator, and Finder
+/// flags; the flags may be omitted, in which case they're all cleared.
+fn parse_info(info: &[u8]) -> Result<([u8; 4], [u8; 4], FinderFlags), BinHexError> {
+    if info.len() < 9 {
+        return Err(BinHexError::InvalidHeader);
+    }
+
+    let file_type: [u8; 4] = info[1..5].try_into().unwrap();
+    let creator: [u8; 4] = info[5..9].try_into().unwrap();
+
+    let flags = match &info[9..] {
+        [] => 0,
+        [b'$', flags @ ..] => parse_hex(flags).ok_or(BinHexError::InvalidHeader)?,
+        _ => return Err(BinHexError::InvalidHeader),
+    };
+
+    Ok((file_type, creator, FinderFlags::from(flags)))
+}
+
+/// Parses between one and four hexadecimal digits.
+fn parse_hex(digits: &[u8]) -> Option<u16> {
+    if digits.is_empty() || digits.len() > 4 {
+        return None;
+    }
+
+    digits.iter().try_fold(0u16, |value, &digit| {
+        hex_value(digit).map(|digit_value| (value << 4) | digit_value as u16)
+    })
+}
+
+/// Returns the value of a single hexadecimal digit, which may be upper- or lowercase.
+pub(crate) fn hex_value(digit: u8) -> Option<u8> {
+    match digit {
+        b'0'..=b'9' => Some(digit - b'0'),
+        b'A'..=b'F' => Some(digit - b'A' + 10),
+        b'a'..=b'f' => Some(digit - b'a' + 10),
+        _ => None,
+    }
+}
+
+fn trim(text: &[u8]) -> &[u8] {
+    let start = text
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(text.len());
+
+    let end = text
+        .iter()
+        .rposition(|b| !b.is_ascii_whitespace())
+        .map_or(start, |pos| pos + 1);
+
+    &text[start..end]
+}
+
+/// The encoded characters of a single fork, along with enough information to locate each
+/// character in the archive's text.
+struct EncodedFork {
+    section: ChecksumSection,
+    chars: Vec<u8>,
+
+    /// The index of the first character of each line in `chars`, and the line's number.
+    lines: Vec<(usize, usize)>,
+
+    checksum: Option<Checksum>,
+}
+
+impl EncodedFork {
+    fn new(section: ChecksumSection) -> Self {
+        EncodedFork {
+            section,
+            chars: vec![],
+            lines: vec![],
+            checksum: None,
+        }
+    }
+
+    fn push_line(&mut self, line: &Line<'_>) {
+        self.lines.push((self.chars.len(), line.number));
+        self.chars.extend_from_slice(line.text);
+    }
+
+    /// Returns the location of the character at the given index.
+    fn locate(&self, char_index: usize, offset: usize) -> ErrorLocation {
+        let line = self
+            .lines
+            .partition_point(|&(start, _)| start <= char_index)
+            .saturating_sub(1);
+
+        let (start, number) = self.lines.get(line).copied().unwrap_or((0, 0));
+
+        ErrorLocation::new(number, char_index - start + 1, self.section, offset)
+    }
+
+    fn decode(&self, format: BinHexFormat) -> Result<Vec<u8>, BinHexError> {
+        let decoded = match format {
+            BinHexFormat::Compressed => self.decode_compressed()?,
+            _ => self.decode_hex()?,
+        };
+
+        if let Some(checksum) = &self.checksum {
+            let provided = checksum.value.ok_or_else(|| {
+                BinHexError::InvalidData(ErrorLocation::new(
+                    checksum.line,
+                    checksum.column,
+                    self.section,
+                    decoded.len(),
+                ))
+            })?;
+
+            let calculated = match checksum.kind {
+                ChecksumKind::Sum => decoded
+                    .iter()
+                    .fold(0u16, |sum, &byte| sum.wrapping_add(byte as u16)),
+                ChecksumKind::Crc => crc16::State::<crc16::XMODEM>::calculate(&decoded),
+            };
+
+            if provided != calculated {
+                return Err(BinHexError::InvalidChecksum(
+                    self.section,
+                    provided,
+                    calculated,
+                ));
+            }
+        }
+
+        Ok(decoded)
+    }
+
+    /// Decodes pairs of hexadecimal digits, ignoring spaces and tabs.
+    fn decode_hex(&self) -> Result<Vec<u8>, BinHexError> {
+        let mut decoded = Vec::with_capacity(self.chars.len() / 2);
+        let mut high_nibble = None;
+
+        for (i, &c) in self.chars.iter().enumerate() {
+            if c == b' ' || c == b'\t' {
+                continue;
+            }
+
+            let value = hex_value(c)
+                .ok_or_else(|| BinHexError::InvalidCharacter(c, self.locate(i, decoded.len())))?;
+
+            match high_nibble.take() {
+                Some(high_nibble) => decoded.push((high_nibble << 4) | value),
+                None => high_nibble = Some(value),
+            }
+        }
+
+        if high_nibble.is_some() {
+            return Err(BinHexError::InvalidData(
+                self.locate(self.chars.len() - 1, decoded.len()),
+            ));
+        }
+
+        Ok(decoded)
+    }
+
+    /// Decodes groups of four six-bit characters into three bytes and expands the result.
+    ///
+    /// Each character's value is its offset from a space (`0x20`); a grave accent (`0x60`) may also
+    /// stand for zero. As with BinHex 4.0, a trailing group of two or three characters holds one or
+    /// two bytes, respectively.
+    fn decode_compressed(&self) -> Result<Vec<u8>, BinHexError> {
+        let mut compressed = Vec::with_capacity(self.chars.len() * 3 / 4);
+        let mut bits = 0u32;
+        let mut group_len = 0;
+
+        for (i, &c) in self.chars.iter().enumerate() {
+            if !(0x20..=0x60).contains(&c) {
+                return Err(BinHexError::InvalidCharacter(
+                    c,
+                    self.locate(i, compressed.len()),
+                ));
+            }
+
+            bits = (bits << 6) | ((c - 0x20) & 0x3f) as u32;
+            group_len += 1;
+
+            if group_len == 4 {
+                compressed.extend_from_slice(&bits.to_be_bytes()[1..]);
+                bits = 0;
+                group_len = 0;
+            }
+        }
+
+        match group_len {
+            2 => compressed.push((bits >> 4) as u8),
+            3 => compressed.extend_from_slice(&((bits >> 2) as u16).to_be_bytes()),
+            _ => {}
+        }
+
+        let mut expanded = vec![];
+
+        if let Err(error) = BinHexExpander::new(compressed.as_slice()).read_to_end(&mut expanded) {
+            return Err(
+                match error
+                    .get_ref()
+                    .and_then(|inner| inner.downcast_ref::<RunLengthError>())
+                {
+                    // Every three compressed bytes are encoded as four characters
+                    Some(run_length_error) => BinHexError::InvalidData(
+                        self.locate(run_length_error.offset * 4 / 3, expanded.len()),
+                    ),
+                    None => BinHexError::from(error),
+                },
+            );
+        }
+
+        Ok(expanded)
+    }
+}
+
+struct Checksum {
+    kind: ChecksumKind,
+    value: Option<u16>,
+    line: usize,
+    column: usize,
+}
+
+enum ChecksumKind {
+    Sum,
+    Crc,
+}
+
+struct Line<'a> {
+    number: usize,
+    text: &'a [u8],
+}
+
+/// An iterator over lines separated by carriage returns, line feeds, or both.
+struct Lines<'a> {
+    text: &'a [u8],
+    number: usize,
+}
+
+impl<'a> Lines<'a> {
+    fn new(text: &'a [u8]) -> Self {
+        Lines { text, number: 0 }
+    }
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = Line<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.text.is_empty() {
+            return None;
+        }
+
+        let (line, remaining) = match memchr::memchr2(b'\r', b'\n', self.text) {
+            Some(end) if self.text[end..].starts_with(b"\r\n") => {
+                (&self.text[..end], &self.text[end + 2..])
+            }
+            Some(end) => (&self.text[..end], &self.text[end + 1..]),
+            None => (self.text, &self.text[self.text.len()..]),
+        };
+
+        self.text = remaining;
+        self.number += 1;
+
+        Some(Line {
+            number: self.number,
+            text: line,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use indoc::indoc;
+
+    const HEX_DATA: &[u8] = indoc! {b"
+        (This file must be converted with BinHex 2.0)
+
+        Hello.txt
+        #TEXTttxt$0100
+        ***DATA
+        48656C6C6F2C
+        20776F726C6421
+        ***CHECKSUM:0489
+        ***RESOURCE
+        00010203
+        ***CHECKSUM:0006
+        ***END
+    "};
+
+    const COMPRESSED_DATA: &[u8] = indoc! {b"
+        (This file must be converted with BinHex 3.0)
+        Hello.txt
+        #TEXTttxt$0100
+        ***DATA
+        2&5L;&\\L('=OD`5R;&0A
+        ***CRC:48C1
+        ***RESOURCE
+        ***END
+    "};
+
+    #[test]
+    fn read_hex() -> Result<(), BinHexError> {
+        let archive = LegacyBinHexArchive::new(HEX_DATA)?;
+
+        assert_eq!(BinHexFormat::Hex, archive.format());
+        assert_eq!(&String::from("Hello.txt"), archive.filename());
+        assert_eq!(*b"TEXT", archive.file_type());
+        assert_eq!(*b"ttxt", archive.creator());
+        assert!(archive.flags().has_been_inited());
+        assert_eq!(b"Hello, world!", archive.data_fork());
+        assert_eq!(&[0, 1, 2, 3], archive.resource_fork());
+        assert_eq!(13, archive.header().data_fork_len());
+        assert_eq!(4, archive.header().resource_fork_len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_hex_carriage_returns() -> Result<(), BinHexError> {
+        let text = String::from_utf8(HEX_DATA.to_vec())
+            .unwrap()
+            .replace('\n', "\r");
+
+        let archive = LegacyBinHexArchive::new(text.as_bytes())?;
+
+        assert_eq!(b"Hello, world!", archive.data_fork());
+        assert_eq!(&[0, 1, 2, 3], archive.resource_fork());
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_hex_without_name() -> Result<(), BinHexError> {
+        let text = String::from_utf8(HEX_DATA.to_vec())
+            .unwrap()
+            .replace("Hello.txt\n", "");
+
+        let archive = LegacyBinHexArchive::new(text.as_bytes())?;
+
+        assert!(archive.filename().is_empty());
+        assert_eq!(b"Hello, world!", archive.data_fork());
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_compressed() -> Result<(), BinHexError> {
+        let archive = LegacyBinHexArchive::new(COMPRESSED_DATA)?;
+
+        assert_eq!(BinHexFormat::Compressed, archive.format());
+        assert_eq!(&String::from("Hello.txt"), archive.filename());
+        assert_eq!(b"Hello, wooooorld!", archive.data_fork());
+        assert!(archive.resource_fork().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_compressed_marker() -> Result<(), BinHexError> {
+        let text = String::from_utf8(COMPRESSED_DATA.to_vec())
+            .unwrap()
+            .replace("BinHex 3.0)\n", "BinHex 2.0)\n***COMPRESSED\n");
+
+        let archive = LegacyBinHexArchive::new(text.as_bytes())?;
+
+        assert_eq!(BinHexFormat::Compressed, archive.format());
+        assert_eq!(b"Hello, wooooorld!", archive.data_fork());
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_binhex_4() {
+        assert!(matches!(
+            LegacyBinHexArchive::new(&b"(This file must be converted with BinHex 4.0)\n:!!!:"[..]),
+            Err(BinHexError::InvalidHeader)
+        ));
+    }
+
+    #[test]
+    fn invalid_character() {
+        let text = String::from_utf8(HEX_DATA.to_vec())
+            .unwrap()
+            .replace("20776F", "20776G");
+
+        match LegacyBinHexArchive::new(text.as_bytes()) {
+            Err(BinHexError::InvalidCharacter(b'G', location)) => {
+                assert_eq!(7, location.line());
+                assert_eq!(6, location.column());
+                assert_eq!(ChecksumSection::DataFork, location.section());
+                assert_eq!(8, location.offset());
+            }
+            result => panic!("Unexpected result: {:?}", result.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn odd_hex_digits() {
+        let text = String::from_utf8(HEX_DATA.to_vec())
+            .unwrap()
+            .replace("00010203", "0001020");
+
+        assert!(matches!(
+            LegacyBinHexArchive::new(text.as_bytes()),
+            Err(BinHexError::InvalidData(location)) if location.line() == 10 && location.column() == 7
+        ));
+    }
+
+    #[test]
+    fn invalid_checksum() {
+        let text = String::from_utf8(HEX_DATA.to_vec())
+            .unwrap()
+            .replace("CHECKSUM:0006", "CHECKSUM:0007");
+
+        assert!(matches!(
+            LegacyBinHexArchive::new(text.as_bytes()),
+            Err(BinHexError::InvalidChecksum(
+                ChecksumSection::ResourceFork,
+                0x0007,
+                0x0006
+            ))
+        ));
+    }
+
+    #[test]
+    fn parse_info() {
+        assert_eq!(
+            Ok((*b"TEXT", *b"ttxt", FinderFlags::from(0x0100))),
+            super::parse_info(b"#TEXTttxt$0100")
+        );
+        assert_eq!(
+            Ok((*b"APPL", *b"????", FinderFlags::default())),
+            super::parse_info(b"#APPL????")
+        );
+        assert_eq!(Err(BinHexError::InvalidHeader), super::parse_info(b"#TEXT"));
+        assert_eq!(
+            Err(BinHexError::InvalidHeader),
+            super::parse_info(b"#TEXTttxt$01000")
+        );
+    }
+
+    #[test]
+    fn banner_format() {
+        assert_eq!(Some(BinHexFormat::Hex), super::banner_format(b" 1.0)"));
+        assert_eq!(Some(BinHexFormat::Hex), super::banner_format(b" 2.0)"));
+        assert_eq!(
+            Some(BinHexFormat::Compressed),
+            super::banner_format(b" 3.0)")
+        );
+        assert_eq!(Some(BinHexFormat::BinHex4), super::banner_format(b" 4.0)"));
+        assert_eq!(None, super::banner_format(b" 4.0"));
+        assert_eq!(None, super::banner_format(b")"));
+    }
+}
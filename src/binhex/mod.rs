@@ -1,4 +1,5 @@
-//! Tools for extracting data from BinHex 4.0 archives.
+//! Tools for extracting data from and writing BinHex 4.0 archives, and for extracting data from
+//! archives in the formats that preceded BinHex 4.0.
 //!
 //! BinHex is an encoding system for "classic" Mac files that combines the binary data from a file's
 //! data and resource forks into a single ASCII-encoded file. BinHex was generally used to transfer
@@ -15,8 +16,30 @@
 //! - [BinHex 4.0 Definition - Peter N Lewis, Aug 1991.](https://files.stairways.com/other/binhex-40-specs-info.txt)
 //! - [RFC 1741 - MIME Content Type for BinHex Encoded Files](https://tools.ietf.org/html/rfc1741)
 
+mod any;
 mod archive;
+mod compress;
+mod decode;
 mod expand;
+mod header;
+mod legacy;
+mod parts;
 mod read;
+mod stream;
+mod write;
 
-pub use archive::{BinHexArchive, BinHexError, ChecksumSection};
+pub(crate) use any::sniff_format;
+pub use any::{AnyBinHexArchive, BinHexFormat};
+pub use archive::{
+    BinHexArchive, BinHexError, BinHexForkReader, BinHexOptions, BinHexWriter, ChecksumSection,
+    ErrorLocation,
+};
+pub use compress::BinHexCompressor;
+pub use expand::BinHexExpander;
+pub use header::BinHexHeader;
+pub(crate) use legacy::hex_value;
+pub use legacy::LegacyBinHexArchive;
+pub(crate) use parts::find;
+pub use parts::BinHexReassembler;
+pub(crate) use read::BANNER;
+pub use stream::BinHexStream;
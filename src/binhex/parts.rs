@@ -0,0 +1,514 @@
+use std::collections::BTreeMap;
+use std::io::Cursor;
+
+use super::archive::{BinHexArchive, BinHexError};
+use super::decode::is_encoded_char;
+use super::read::{BANNER, DATA_DELIMITER, FULL_BANNER};
+
+/// Reassembles BinHex archives that were split into multiple parts.
+///
+/// Large BinHex archives were often posted to Usenet newsgroups and mailing lists in several parts
+/// (e.g. "part 1/5," "part 2/5," and so on), each of which would be wrapped in message headers,
+/// signatures, and "cut here" or "end part" markers. A `BinHexReassembler` extracts the encoded
+/// lines from each part, discarding everything else, and joins them (in part-number order) into a
+/// single continuous BinHex archive.
+///
+/// Part numbers may be given explicitly with [`BinHexReassembler::insert_part`] or detected from
+/// text like "part 2/5," "part 2 of 5," "[2/5]," or "(2/5)" with [`BinHexReassembler::add_part`].
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs;
+/// use clarus::binhex::{BinHexError, BinHexReassembler};
+///
+/// fn main() -> Result<(), BinHexError> {
+///     let mut reassembler = BinHexReassembler::new();
+///
+///     // Parts may be added in any order as long as their part numbers can be detected
+///     for message in ["part3.txt", "part1.txt", "part2.txt"] {
+///         reassembler.add_part(&fs::read(message)?)?;
+///     }
+///
+///     let mut archive = reassembler.into_archive()?;
+///     println!("Reassembled {}", archive.filename());
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct BinHexReassembler {
+    parts: BTreeMap<usize, Vec<u8>>,
+    total_parts: Option<usize>,
+}
+
+impl BinHexReassembler {
+    /// Creates a new, empty reassembler.
+    pub fn new() -> Self {
+        BinHexReassembler::default()
+    }
+
+    /// Adds a part whose part number is detected from its text, returning the part number.
+    ///
+    /// If no part number can be found in the given text, the part is assumed to follow the
+    /// highest-numbered part added so far (or to be the first part if no parts have been added).
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error if a part with the same number has already been added.
+    pub fn add_part(&mut self, text: &[u8]) -> Result<usize, BinHexError> {
+        let (number, total_parts) = find_part_number(text).unwrap_or_else(|| {
+            (
+                self.parts.keys().next_back().map_or(1, |last| last + 1),
+                None,
+            )
+        });
+
+        self.insert_part(number, text)?;
+
+        if total_parts.is_some() {
+            self.total_parts = total_parts;
+        }
+
+        Ok(number)
+    }
+
+    /// Adds a part with the given part number. Part numbers start at 1.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error if a part with the same number has already been added.
+    pub fn insert_part(&mut self, number: usize, text: &[u8]) -> Result<(), BinHexError> {
+        if self.parts.contains_key(&number) {
+            return Err(BinHexError::DuplicatePart(number));
+        }
+
+        self.parts.insert(number, encoded_lines(text));
+
+        Ok(())
+    }
+
+    /// Returns the reassembled BinHex archive as a single, continuous encoded stream.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error if any parts are missing. Parts are considered missing if
+    /// there are gaps in the sequence of part numbers starting at 1, or if the total number of
+    /// parts detected from the text of any part (as in "part 2 of 5") is greater than the number of
+    /// the highest-numbered part.
+    pub fn into_reader(self) -> Result<Cursor<Vec<u8>>, BinHexError> {
+        let last_part = std::cmp::max(
+            self.total_parts.unwrap_or(0),
+            self.parts.keys().next_back().copied().unwrap_or(0),
+        );
+
+        if let Some(missing) = (1..=last_part).find(|number| !self.parts.contains_key(number)) {
+            return Err(BinHexError::MissingPart(missing));
+        }
+
+        if last_part == 0 {
+            return Err(BinHexError::MissingPart(1));
+        }
+
+        let mut encoded = Vec::from(FULL_BANNER);
+
+        self.parts.values().for_each(|lines| {
+            encoded.push(b'\n');
+            encoded.extend_from_slice(lines);
+        });
+
+        Ok(Cursor::new(encoded))
+    }
+
+    /// Returns the reassembled BinHex archive.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error if any parts are missing (please see
+    /// [`BinHexReassembler::into_reader`]) or if the reassembled archive's header could not be
+    /// read.
+    pub fn into_archive(self) -> Result<BinHexArchive<Cursor<Vec<u8>>>, BinHexError> {
+        BinHexArchive::new(self.into_reader()?)
+    }
+}
+
+/// Finds a part number (and, if present, the total number of parts) in the given text.
+fn find_part_number(text: &[u8]) -> Option<(usize, Option<usize>)> {
+    let lowercase = text.to_ascii_lowercase();
+
+    // Look for "part 2," "part 2/5," "part 2 of 5," and similar first
+    let mut scan = 0;
+
+    while let Some(pos) = find(&lowercase[scan..], b"part") {
+        let start = scan + pos + 4;
+
+        // "part" must start a word, so that words like "department" aren't taken as part numbers
+        if scan + pos > 0 && lowercase[scan + pos - 1].is_ascii_alphanumeric() {
+            scan = start;
+            continue;
+        }
+
+        let number_start = start
+            + lowercase[start..]
+                .iter()
+                .take_while(|b| b" \t:#-".contains(b))
+                .count();
+
+        if let Some((number, end)) = parse_number(&lowercase, number_start) {
+            if number > 0 {
+                return Some((number, parse_total(&lowercase, end)));
+            }
+        }
+
+        scan = start;
+    }
+
+    // ...then fall back to bracketed forms like "[2/5]" or "(2/5)"
+    for (i, &b) in lowercase.iter().enumerate() {
+        if b == b'[' || b == b'(' {
+            let close = if b == b'[' { b']' } else { b')' };
+
+            if let Some((number, end)) = parse_number(&lowercase, i + 1) {
+                if lowercase.get(end) == Some(&b'/') {
+                    if let Some((total, end)) = parse_number(&lowercase, end + 1) {
+                        if lowercase.get(end) == Some(&close) && number > 0 && number <= total {
+                            return Some((number, Some(total)));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Parses a total part count (as in "/5" or " of 5") starting at the given position.
+fn parse_total(text: &[u8], start: usize) -> Option<usize> {
+    let rest = &text[start..];
+    let spaces = rest.iter().take_while(|&&b| b == b' ').count();
+
+    let total_start = if rest[spaces..].starts_with(b"/") {
+        start + spaces + 1
+    } else if rest[spaces..].starts_with(b"of ") {
+        start + spaces + 3
+    } else {
+        return None;
+    };
+
+    let total_start = total_start
+        + text[total_start..]
+            .iter()
+            .take_while(|&&b| b == b' ')
+            .count();
+
+    parse_number(text, total_start).map(|(total, _)| total)
+}
+
+/// Parses a decimal number starting at the given position, returning the number and the position
+/// of the first byte after the number.
+fn parse_number(text: &[u8], start: usize) -> Option<(usize, usize)> {
+    let digits = text
+        .get(start..)?
+        .iter()
+        .take_while(|b| b.is_ascii_digit())
+        .count();
+
+    if digits == 0 || digits > 6 {
+        return None;
+    }
+
+    let number = std::str::from_utf8(&text[start..start + digits])
+        .ok()?
+        .parse()
+        .ok()?;
+
+    Some((number, start + digits))
+}
+
+/// Returns the index of the first occurrence of `needle` in `haystack`.
+pub(crate) fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Extracts the lines of encoded BinHex data from a single part of a multi-part archive, discarding
+/// headers, signatures, and other surrounding text.
+///
+/// Encoded data appears as a contiguous block of lines that contain only characters from the
+/// BinHex alphabet (and delimiters). Archives are split at line boundaries, so all lines in the
+/// block have the same length except for the first line of the archive (which starts with a
+/// delimiter) and the last line of the archive (which ends with a delimiter).
+fn encoded_lines(text: &[u8]) -> Vec<u8> {
+    // If this is the first part, skip everything up to and including the banner
+    let text = match find(text, BANNER) {
+        Some(banner_start) => {
+            let data_start = memchr::memchr(DATA_DELIMITER, &text[banner_start..])
+                .map_or(text.len(), |pos| banner_start + pos);
+
+            let line_start = text[..data_start]
+                .iter()
+                .rposition(|b| *b == b'\n' || *b == b'\r')
+                .map_or(0, |pos| pos + 1);
+
+            &text[line_start..]
+        }
+        None => text,
+    };
+
+    let lines: Vec<&[u8]> = text
+        .split(|b| *b == b'\n' || *b == b'\r')
+        .map(|line| {
+            let len = line.len() - line.iter().rev().take_while(|b| b" \t".contains(b)).count();
+            &line[..len]
+        })
+        .collect();
+
+    // Find contiguous runs of lines that could be encoded data
+    let mut runs: Vec<&[&[u8]]> = vec![];
+    let mut run_start = None;
+
+    for (i, line) in lines.iter().enumerate() {
+        match (is_encoded_line(line), run_start) {
+            (true, None) => run_start = Some(i),
+            (false, Some(start)) => {
+                runs.push(&lines[start..i]);
+                run_start = None;
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(start) = run_start {
+        runs.push(&lines[start..]);
+    }
+
+    let run_len = |run: &&[&[u8]]| run.iter().map(|line| line.len()).sum::<usize>();
+    let has_delimiter = |run: &&[&[u8]]| run.iter().any(|line| line.contains(&DATA_DELIMITER));
+
+    // Prefer runs that contain a delimiter (i.e. the start or end of the archive), then the longest
+    let run = runs
+        .iter()
+        .max_by_key(|run| (has_delimiter(run), run_len(run)))
+        .copied()
+        .unwrap_or(&[]);
+
+    let run = trim_run(run);
+
+    let mut encoded = Vec::with_capacity(run_len(&run) + run.len());
+
+    run.iter().enumerate().for_each(|(i, line)| {
+        if i > 0 {
+            encoded.push(b'\n');
+        }
+
+        encoded.extend_from_slice(line);
+    });
+
+    encoded
+}
+
+/// Trims lines from the start and end of a run of encoded lines that don't match the length of the
+/// other lines in the run and don't contain a delimiter.
+fn trim_run<'a>(run: &'a [&'a [u8]]) -> &'a [&'a [u8]] {
+    let mut length_counts = BTreeMap::new();
+
+    run.iter()
+        .for_each(|line| *length_counts.entry(line.len()).or_insert(0) += 1);
+
+    let line_length = match length_counts.iter().max_by_key(|(_, count)| **count) {
+        Some((length, _)) => *length,
+        None => return run,
+    };
+
+    let keep = |line: &&[u8]| line.len() == line_length || line.contains(&DATA_DELIMITER);
+
+    let start = run.iter().position(keep).unwrap_or(run.len());
+    let end = run.iter().rposition(keep).map_or(start, |pos| pos + 1);
+
+    &run[start..end]
+}
+
+/// Returns `true` if the given line could be a line of encoded BinHex data.
+fn is_encoded_line(line: &[u8]) -> bool {
+    !line.is_empty()
+        && !line.iter().all(|b| *b == b'-')
+        && line
+            .iter()
+            .all(|&b| b == DATA_DELIMITER || is_encoded_char(b))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use indoc::indoc;
+
+    const DATA_FORK: &[u8] = b"===== Hello from the data fork! =====";
+    const RESOURCE_FORK: &[u8] = b"----- Hello from the resource fork! -----";
+
+    const PART_1: &[u8] = indoc! {br#"
+        From: someone@example.com
+        Subject: binhex-test.txt (part 1/3)
+
+        --- cut here ---
+        (This file must be converted with BinHex 4.0)
+        :$f*TEQKPH#edCA0d,R4iG!#3$L8!N!-TR@dpN!8J5'9XE'mJCR*[E5"dD'8JC'&
+        --- end part 1 ---
+
+        --
+        Signature
+    "#};
+
+    const PART_2: &[u8] = indoc! {br#"
+        From: someone@example.com
+        Subject: binhex-test.txt (part 2 of 3)
+
+        --- part 2 ---
+        dB5"QEh*V)5!pN!9Bm5f3"5")C@aXEb"QFQpY)(4SC5"bCA0[GA*MC5"QEh*V)5!
+        --- end part 2 ---
+
+        Cheers!
+    "#};
+
+    const PART_3: &[u8] = indoc! {br#"
+        From: someone@example.com
+        Subject: Re: binhex-test.txt [3/3]
+
+        -----
+        YN!8SI!:
+        -----
+        Enjoy
+    "#};
+
+    fn extract(reassembler: BinHexReassembler) -> Result<(Vec<u8>, Vec<u8>), BinHexError> {
+        let mut data_fork = vec![];
+        let mut resource_fork = vec![];
+
+        reassembler
+            .into_archive()?
+            .extract(&mut data_fork, &mut resource_fork)?;
+
+        Ok((data_fork, resource_fork))
+    }
+
+    #[test]
+    fn reassemble_in_order() -> Result<(), BinHexError> {
+        let mut reassembler = BinHexReassembler::new();
+
+        for (number, part) in [PART_1, PART_2, PART_3].iter().enumerate() {
+            reassembler.insert_part(number + 1, part)?;
+        }
+
+        let (data_fork, resource_fork) = extract(reassembler)?;
+
+        assert_eq!(DATA_FORK, data_fork.as_slice());
+        assert_eq!(RESOURCE_FORK, resource_fork.as_slice());
+
+        Ok(())
+    }
+
+    #[test]
+    fn reassemble_out_of_order() -> Result<(), BinHexError> {
+        let mut reassembler = BinHexReassembler::new();
+
+        assert_eq!(3, reassembler.add_part(PART_3)?);
+        assert_eq!(1, reassembler.add_part(PART_1)?);
+        assert_eq!(2, reassembler.add_part(PART_2)?);
+
+        let (data_fork, resource_fork) = extract(reassembler)?;
+
+        assert_eq!(DATA_FORK, data_fork.as_slice());
+        assert_eq!(RESOURCE_FORK, resource_fork.as_slice());
+
+        Ok(())
+    }
+
+    #[test]
+    fn reassemble_missing_part() -> Result<(), BinHexError> {
+        let mut reassembler = BinHexReassembler::new();
+        reassembler.add_part(PART_1)?;
+        reassembler.add_part(PART_3)?;
+
+        assert!(matches!(
+            reassembler.into_reader(),
+            Err(BinHexError::MissingPart(2))
+        ));
+
+        // The total part count should be respected even if the last part is missing
+        let mut reassembler = BinHexReassembler::new();
+        reassembler.add_part(PART_1)?;
+        reassembler.add_part(PART_2)?;
+
+        assert!(matches!(
+            reassembler.into_reader(),
+            Err(BinHexError::MissingPart(3))
+        ));
+
+        assert!(matches!(
+            BinHexReassembler::new().into_reader(),
+            Err(BinHexError::MissingPart(1))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn reassemble_duplicate_part() -> Result<(), BinHexError> {
+        let mut reassembler = BinHexReassembler::new();
+        reassembler.add_part(PART_1)?;
+
+        assert_eq!(
+            Err(BinHexError::DuplicatePart(1)),
+            reassembler.add_part(PART_1)
+        );
+
+        // A rejected part doesn't change the expected number of parts
+        assert_eq!(
+            Err(BinHexError::DuplicatePart(1)),
+            reassembler.add_part(b"part 1 of 9")
+        );
+        assert_eq!(Some(3), reassembler.total_parts);
+
+        Ok(())
+    }
+
+    #[test]
+    fn find_part_number() {
+        assert_eq!(
+            Some((2, Some(5))),
+            super::find_part_number(b"Foo (Part 2/5)")
+        );
+        assert_eq!(
+            Some((2, Some(5))),
+            super::find_part_number(b"foo part 2 of 5")
+        );
+        assert_eq!(Some((12, None)), super::find_part_number(b"PART #12"));
+        assert_eq!(
+            Some((3, Some(4))),
+            super::find_part_number(b"foo.hqx [3/4]")
+        );
+        assert_eq!(
+            Some((3, Some(4))),
+            super::find_part_number(b"foo.hqx (3/4)")
+        );
+        assert_eq!(None, super::find_part_number(b"Posted 10/17/89"));
+        assert_eq!(None, super::find_part_number(b"Department 3/5"));
+        assert_eq!(None, super::find_part_number(b"Its counterpart 2"));
+        assert_eq!(
+            Some((4, None)),
+            super::find_part_number(b"Counterpart to part 4")
+        );
+        assert_eq!(None, super::find_part_number(b"Nothing to see here"));
+    }
+
+    #[test]
+    fn encoded_lines() {
+        assert_eq!(
+            br#"dB5"QEh*V)5!pN!9Bm5f3"5")C@aXEb"QFQpY)(4SC5"bCA0[GA*MC5"QEh*V)5!"#[..],
+            super::encoded_lines(PART_2)[..]
+        );
+
+        assert_eq!(b"YN!8SI!:"[..], super::encoded_lines(PART_3)[..]);
+    }
+}
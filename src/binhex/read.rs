@@ -1,17 +1,38 @@
 use std::cmp;
+use std::error;
+use std::fmt::{self, Display, Formatter};
 use std::io::{Error, ErrorKind, Read, Result};
 
-const BANNER: &[u8] = b"(This file must be converted with BinHex";
-const DATA_DELIMITER: u8 = b':';
+use super::decode::is_encoded_char;
+
+pub(crate) const BANNER: &[u8] = b"(This file must be converted with BinHex";
+
+/// The complete banner that precedes BinHex 4.0 data; readers only look for its version-agnostic
+/// prefix ([`BANNER`]).
+pub(crate) const FULL_BANNER: &[u8] = b"(This file must be converted with BinHex 4.0)";
+pub(super) const DATA_DELIMITER: u8 = b':';
 
 /// A `Read` implementation that extracts BinHex-encoded data from an underlying reader.
 ///
 /// The data produced by an `EncodedBinHexReader` is the still-encoded data contained within a
 /// BinHex source (usually a file) stripped of extraneous banners, delimiters, and whitespace.
 /// Callers will almost certainly need to pass the data through a BinHex decoder.
+///
+/// The reader keeps track of the line and column of each encoded character it produces so that
+/// errors found by later decoding stages can be traced back to the original text. If the encoded
+/// data contains a character that isn't part of the BinHex alphabet, the reader returns an
+/// [`std::io::Error`] with a kind of [`std::io::ErrorKind::InvalidData`] that wraps an
+/// [`InvalidCharacterError`] after producing all of the valid data that precedes the invalid
+/// character.
 pub struct EncodedBinHexReader<R: Read> {
     source: R,
     state: State,
+    position: TextPosition,
+    segments: Vec<Segment>,
+    chars_read: usize,
+    error: Option<Error>,
+    recovery: bool,
+    anomalies: Vec<Anomaly>,
 }
 
 impl<R: Read> EncodedBinHexReader<R> {
@@ -19,12 +40,87 @@ impl<R: Read> EncodedBinHexReader<R> {
         EncodedBinHexReader {
             source,
             state: State::FindBannerStart,
+            position: TextPosition::default(),
+            segments: vec![],
+            chars_read: 0,
+            error: None,
+            recovery: false,
+            anomalies: vec![],
         }
     }
+
+    /// Creates a lenient reader that skips characters that aren't part of the BinHex alphabet and
+    /// treats the end of the source as the end of the encoded data if the closing delimiter is
+    /// missing. Each problem is recorded as an [`Anomaly`] instead of being returned as an error.
+    pub fn with_recovery(source: R) -> Self {
+        EncodedBinHexReader {
+            recovery: true,
+            ..EncodedBinHexReader::new(source)
+        }
+    }
+
+    /// Removes and returns the anomalies this reader has worked around since the last call to this
+    /// method.
+    pub fn take_anomalies(&mut self) -> Vec<Anomaly> {
+        std::mem::take(&mut self.anomalies)
+    }
+
+    /// Returns the (one-based) line and column in the source text of the encoded character with
+    /// the given index in this reader's output.
+    ///
+    /// Indices beyond the end of the encoded data refer to the closing delimiter if it has been
+    /// found, or to the current position in the source text otherwise.
+    pub fn position_of(&self, char_index: usize) -> (usize, usize) {
+        let segment_index = self
+            .segments
+            .partition_point(|segment| segment.char_index <= char_index);
+
+        match segment_index.checked_sub(1) {
+            Some(segment_index) => {
+                let segment = &self.segments[segment_index];
+                let offset = cmp::min(char_index, self.chars_read) - segment.char_index;
+
+                (segment.line, segment.column + offset)
+            }
+            None => (self.position.line, self.position.column),
+        }
+    }
+
+    /// Returns the (one-based) line and column in the source text of the closing delimiter if it
+    /// has been found, or of the current position in the source text otherwise.
+    pub fn end_position(&self) -> (usize, usize) {
+        self.position_of(self.chars_read)
+    }
+
+    /// Forgets the locations of encoded characters before the given index, which callers promise
+    /// not to pass to [`EncodedBinHexReader::position_of`] again. Readers would otherwise remember
+    /// the location of every line of encoded data they've read.
+    pub fn discard_positions_before(&mut self, char_index: usize) {
+        let segment_index = self
+            .segments
+            .partition_point(|segment| segment.char_index <= char_index);
+
+        if segment_index > 1 {
+            self.segments.drain(..segment_index - 1);
+        }
+    }
+
+    /// Records the start of a contiguous run of encoded characters at the current position.
+    fn start_segment(&mut self) {
+        self.segments.push(Segment {
+            char_index: self.chars_read,
+            line: self.position.line,
+            column: self.position.column,
+        });
+    }
 }
 
 impl<R: Read> Read for EncodedBinHexReader<R> {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+
         if buf.is_empty() {
             return Ok(0);
         }
@@ -34,6 +130,12 @@ impl<R: Read> Read for EncodedBinHexReader<R> {
         while bytes_copied == 0 && self.state != State::Done {
             let bytes_read = match self.source.read(buf) {
                 Err(e) => return Err(e),
+                Ok(0) if self.recovery && self.state == State::ReadData => {
+                    self.anomalies.push(Anomaly::MissingDataEnd);
+                    self.state = State::Done;
+
+                    return Ok(0);
+                }
                 Ok(0) => return Err(Error::from(ErrorKind::UnexpectedEof)),
                 Ok(bytes_read) => bytes_read,
             };
@@ -43,6 +145,8 @@ impl<R: Read> Read for EncodedBinHexReader<R> {
             while bytes_consumed < bytes_read && self.state != State::Done {
                 debug_assert!(!buf[bytes_consumed..bytes_read].is_empty());
 
+                let scan_start = bytes_consumed;
+
                 let event = match self.state {
                     State::FindBannerStart => {
                         match memchr::memchr(BANNER[0], &buf[bytes_consumed..bytes_read]) {
@@ -80,41 +184,101 @@ impl<R: Read> Read for EncodedBinHexReader<R> {
                             }
                         }
                     }
-                    State::ReadData => match next_data_byte(&buf[bytes_consumed..bytes_read]) {
-                        Some(start) => {
-                            let data_bytes = compact(&mut buf[bytes_consumed + start..bytes_read]);
+                    State::ReadData => {
+                        let mut event = Event::ConsumedBytes;
+
+                        while bytes_consumed < bytes_read {
+                            let whitespace_len = next_data_byte(&buf[bytes_consumed..bytes_read])
+                                .unwrap_or(bytes_read - bytes_consumed);
+
+                            self.position
+                                .advance(&buf[bytes_consumed..bytes_consumed + whitespace_len]);
+                            bytes_consumed += whitespace_len;
+
+                            if bytes_consumed == bytes_read {
+                                break;
+                            }
+
+                            let chunk = &buf[bytes_consumed..bytes_read];
+                            let chunk_len = next_whitespace(chunk).unwrap_or(chunk.len());
+
+                            let (data_len, invalid_byte) = match chunk[..chunk_len]
+                                .iter()
+                                .position(|&b| !is_encoded_char(b))
+                            {
+                                Some(pos) => (pos, Some(chunk[pos])),
+                                None => (chunk_len, None),
+                            };
+
+                            if data_len > 0 {
+                                self.start_segment();
 
-                            if bytes_consumed + start > 0 {
                                 buf.copy_within(
-                                    bytes_consumed + start..bytes_consumed + start + data_bytes,
-                                    0,
+                                    bytes_consumed..bytes_consumed + data_len,
+                                    bytes_copied,
                                 );
+
+                                bytes_consumed += data_len;
+                                bytes_copied += data_len;
+                                self.chars_read += data_len;
+                                self.position.column += data_len;
                             }
 
-                            match memchr::memchr(DATA_DELIMITER, &buf[..data_bytes]) {
-                                Some(data_end) => {
-                                    bytes_copied += data_end;
+                            match invalid_byte {
+                                Some(DATA_DELIMITER) => {
+                                    self.start_segment();
+                                    self.position.advance(&[DATA_DELIMITER]);
+                                    bytes_consumed += 1;
 
-                                    Event::FoundDataEnd
+                                    event = Event::FoundDataEnd;
+                                    break;
                                 }
-                                None => {
-                                    bytes_consumed = bytes_read;
-                                    bytes_copied += data_bytes;
-
-                                    Event::ConsumedBytes
+                                Some(byte) => {
+                                    let invalid_character = InvalidCharacterError {
+                                        byte,
+                                        line: self.position.line,
+                                        column: self.position.column,
+                                    };
+
+                                    if self.recovery {
+                                        self.anomalies.push(Anomaly::SkippedCharacter(
+                                            self.chars_read,
+                                            invalid_character,
+                                        ));
+
+                                        self.position.column += 1;
+                                        bytes_consumed += 1;
+
+                                        continue;
+                                    }
+
+                                    let error =
+                                        Error::new(ErrorKind::InvalidData, invalid_character);
+
+                                    // Hand over any valid data we've found before reporting the
+                                    // error
+                                    return if bytes_copied > 0 {
+                                        self.error = Some(error);
+                                        Ok(bytes_copied)
+                                    } else {
+                                        Err(error)
+                                    };
                                 }
+                                None => {}
                             }
                         }
-                        None => {
-                            bytes_consumed = bytes_read;
-                            Event::ConsumedBytes
-                        }
-                    },
+
+                        event
+                    }
                     State::Done => {
                         return Ok(bytes_copied);
                     }
                 };
 
+                if self.state != State::ReadData {
+                    self.position.advance(&buf[scan_start..bytes_consumed]);
+                }
+
                 self.state = self.state.advance(event)?;
             }
         }
@@ -123,6 +287,89 @@ impl<R: Read> Read for EncodedBinHexReader<R> {
     }
 }
 
+/// An error indicating that the encoded data in a BinHex source contained a character that is not
+/// part of the BinHex alphabet.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(super) struct InvalidCharacterError {
+    pub byte: u8,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Display for InvalidCharacterError {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            fmt,
+            "Invalid character 0x{:02x} at line {}, column {}",
+            self.byte, self.line, self.column
+        )
+    }
+}
+
+impl error::Error for InvalidCharacterError {}
+
+/// A problem with a BinHex source that a lenient reader worked around.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(super) enum Anomaly {
+    /// A character that isn't part of the BinHex alphabet was skipped; the index of the encoded
+    /// character that followed it is included.
+    SkippedCharacter(usize, InvalidCharacterError),
+
+    /// The source ended before the closing delimiter.
+    MissingDataEnd,
+}
+
+/// A (one-based) line and column in BinHex source text.
+#[derive(Copy, Clone, Debug)]
+struct TextPosition {
+    line: usize,
+    column: usize,
+
+    /// Whether the last byte was a carriage return; a line feed that immediately follows a
+    /// carriage return doesn't start a new line.
+    after_carriage_return: bool,
+}
+
+impl Default for TextPosition {
+    fn default() -> Self {
+        TextPosition {
+            line: 1,
+            column: 1,
+            after_carriage_return: false,
+        }
+    }
+}
+
+impl TextPosition {
+    /// Moves this position past the given bytes.
+    fn advance(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            match byte {
+                b'\r' => {
+                    self.line += 1;
+                    self.column = 1;
+                }
+                b'\n' if !self.after_carriage_return => {
+                    self.line += 1;
+                    self.column = 1;
+                }
+                b'\n' => {}
+                _ => self.column += 1,
+            }
+
+            self.after_carriage_return = byte == b'\r';
+        }
+    }
+}
+
+/// The location in the source text of a contiguous run of encoded characters.
+#[derive(Copy, Clone, Debug)]
+struct Segment {
+    char_index: usize,
+    line: usize,
+    column: usize,
+}
+
 /// The internal state of a BinHex reader.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 enum State {
@@ -212,34 +459,6 @@ fn next_data_byte(bytes: &[u8]) -> Option<usize> {
     }
 }
 
-/// Removes whitespace from a slice by shifting non-whitespace bytes toward the beginning of the
-/// slice and returning the number of non-whitespace bytes in the buffer.
-///
-/// When this function returns, the given slice _may_ contain "junk" data after the end of the
-/// compacted non-whitespace bytes. Callers should ignore any bytes beyond the returned number of
-/// non-whitespace bytes.
-fn compact(bytes: &mut [u8]) -> usize {
-    let mut scan = 0;
-    let mut bytes_written = 0;
-
-    while let Some(start_offset) = next_data_byte(&bytes[scan..]) {
-        let chunk_start = scan + start_offset;
-
-        let chunk_end = next_whitespace(&bytes[chunk_start..])
-            .map(|whitespace_offset| chunk_start + whitespace_offset)
-            .unwrap_or_else(|| bytes.len());
-
-        bytes.copy_within(chunk_start..chunk_end, bytes_written);
-
-        let len = chunk_end - chunk_start;
-
-        bytes_written += len;
-        scan += start_offset + len;
-    }
-
-    bytes_written
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -381,23 +600,128 @@ mod tests {
     }
 
     #[test]
-    fn compact() {
-        let test_strings = [
-            "compaction!",
-            "    compaction!",
-            "compaction!    ",
-            "    compaction!    ",
-            "c  omp a       ction !",
-            "  c  omp a       ction !",
-            "c  omp a       ction !    ",
-            "  c  omp a   ction !      ",
-        ];
-
-        test_strings.iter().for_each(|&string| {
-            let mut bytes = Vec::from(string);
-
-            assert_eq!(11, super::compact(bytes.as_mut_slice()));
-            assert_eq!(b"compaction!"[..], bytes.as_slice()[..11]);
+    fn read_invalid_character() {
+        let cursor = Cursor::new(
+            indoc! {r#"
+                (This file must be converted with BinHex 4.0)
+                :$f*TEQKPH#edCA0d,R4iG!#3$L8!N!-TR@dpN!8J5'9XE'mJCR*[E5"dD'8JC'&
+                dB5"QEh*V)5!pN!9Bm5f3"5")C@aXEb"QFQpY)(4SC5"bCA0[GA*MC5"QEh*V)5!
+                YN!8SI!:"#
+            }
+            .replace("QFQ", "QFs"),
+        );
+
+        let mut binhex_reader = EncodedBinHexReader::new(cursor);
+        let mut binhex_data = vec![];
+
+        let error = binhex_reader.read_to_end(&mut binhex_data).unwrap_err();
+
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+        assert_eq!(
+            Some(&InvalidCharacterError {
+                byte: b's',
+                line: 3,
+                column: 35,
+            }),
+            error
+                .get_ref()
+                .and_then(|inner| inner.downcast_ref::<InvalidCharacterError>())
+        );
+
+        // All of the valid data before the invalid character should still be available
+        assert_eq!(br#"$f*TEQKPH#edCA0d,R4iG!#3$L8!N!-TR@dpN!8J5'9XE'mJCR*[E5"dD'8JC'&dB5"QEh*V)5!pN!9Bm5f3"5")C@aXEb"QF"#[..], binhex_data[..]);
+    }
+
+    #[test]
+    fn position_of() {
+        let cursor = Cursor::new(
+            &b"Some text\r\n(This file must be converted with BinHex 4.0)\r\n\r\n:$f*TEQ\r\n  KPH#:"[..],
+        );
+
+        let mut binhex_reader = EncodedBinHexReader::new(cursor);
+        let mut binhex_data = vec![];
+
+        binhex_reader.read_to_end(&mut binhex_data).unwrap();
+
+        assert_eq!(b"$f*TEQKPH#"[..], binhex_data[..]);
+        assert_eq!((4, 2), binhex_reader.position_of(0));
+        assert_eq!((4, 7), binhex_reader.position_of(5));
+        assert_eq!((5, 3), binhex_reader.position_of(6));
+        assert_eq!((5, 6), binhex_reader.position_of(9));
+        assert_eq!((5, 7), binhex_reader.position_of(10));
+        assert_eq!((5, 7), binhex_reader.position_of(100));
+
+        let segment_count = binhex_reader.segments.len();
+        binhex_reader.discard_positions_before(7);
+        assert!(binhex_reader.segments.len() < segment_count);
+        assert_eq!((5, 4), binhex_reader.position_of(7));
+        assert_eq!((5, 7), binhex_reader.position_of(10));
+    }
+
+    #[test]
+    fn discard_positions() {
+        let mut text = b"(This file must be converted with BinHex 4.0)\n:".to_vec();
+
+        for _ in 0..1000 {
+            text.extend_from_slice(b"KPH#KPH#\n");
+        }
+
+        text.push(b':');
+
+        let mut binhex_reader = EncodedBinHexReader::new(Cursor::new(text));
+        let mut buf = [0; 64];
+        let mut chars_read = 0;
+
+        loop {
+            let len = binhex_reader.read(&mut buf).unwrap();
+
+            if len == 0 {
+                break;
+            }
+
+            chars_read += len;
+            binhex_reader.discard_positions_before(chars_read - 1);
+
+            assert!(binhex_reader.segments.len() <= 10);
+            assert_eq!(
+                (2 + (chars_read - 1) / 8, 1 + (chars_read - 1) % 8),
+                binhex_reader.position_of(chars_read - 1)
+            );
+        }
+
+        assert_eq!(8000, chars_read);
+    }
+
+    #[test]
+    fn read_recovery() {
+        let cursor = Cursor::new(indoc! {br#"
+            (This file must be converted with BinHex 4.0)
+            :$f*TEQKPH#edCA0d,R4iG!#3$L8!N!-TR@dpN!8J5'9XE'mJCR*[E5"dD'8JC'&
+            >dB5"QEh*V)5!pN!9Bm5f3"5")C@aXEb"QFQpY)(4SC5"bCA0[GA*MC5"QEh*V)5!
+            YN!8SI!"#
         });
+
+        let mut binhex_reader = EncodedBinHexReader::with_recovery(cursor);
+        let mut binhex_data = vec![];
+
+        assert_eq!(binhex_reader.read_to_end(&mut binhex_data).unwrap(), 134);
+        assert_eq!(binhex_data.as_slice(), br#"$f*TEQKPH#edCA0d,R4iG!#3$L8!N!-TR@dpN!8J5'9XE'mJCR*[E5"dD'8JC'&dB5"QEh*V)5!pN!9Bm5f3"5")C@aXEb"QFQpY)(4SC5"bCA0[GA*MC5"QEh*V)5!YN!8SI!"#);
+
+        assert_eq!(
+            vec![
+                Anomaly::SkippedCharacter(
+                    63,
+                    InvalidCharacterError {
+                        byte: b'>',
+                        line: 3,
+                        column: 1
+                    }
+                ),
+                Anomaly::MissingDataEnd
+            ],
+            binhex_reader.take_anomalies()
+        );
+
+        assert_eq!((3, 2), binhex_reader.position_of(63));
     }
 }
@@ -0,0 +1,293 @@
+use std::io::{Cursor, ErrorKind, Read};
+
+use super::archive::{BinHexArchive, BinHexError};
+use super::read::{BANNER, DATA_DELIMITER};
+
+const READ_SIZE: usize = 8192;
+
+/// An iterator over the BinHex archives contained in a single source.
+///
+/// Mailing list digests, Usenet dumps, and archive indices often contain many BinHex archives
+/// separated by arbitrary human-readable text. A `BinHexStream` locates each archive in turn,
+/// skipping any text between archives, and yields each archive along with the offset (in bytes
+/// from the start of the source) of the archive's banner.
+///
+/// The encoded content of each archive (though not the content of any text between archives) is
+/// buffered in memory before the archive is yielded.
+///
+/// If an archive is malformed (i.e. if its header can't be read), the stream yields an error for
+/// that archive and continues on to the next archive. If the underlying source returns an error or
+/// ends in the middle of an archive, the stream yields that error and then ends.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs::File;
+/// use std::io;
+/// use clarus::binhex::{BinHexError, BinHexStream};
+///
+/// fn main() -> Result<(), BinHexError> {
+///     let digest = File::open("digest.txt")?;
+///
+///     for (offset, archive) in BinHexStream::new(digest) {
+///         match archive {
+///             Ok(mut archive) => println!("Found {} at offset {}", archive.filename(), offset),
+///             Err(error) => println!("Bad archive at offset {}: {}", offset, error),
+///         }
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+pub struct BinHexStream<R: Read> {
+    source: R,
+    buffer: Vec<u8>,
+    buffer_offset: u64,
+    done: bool,
+}
+
+impl<R: Read> BinHexStream<R> {
+    /// Creates a new BinHex stream that will find archives in the given source.
+    pub fn new(source: R) -> Self {
+        BinHexStream {
+            source,
+            buffer: Vec::with_capacity(READ_SIZE),
+            buffer_offset: 0,
+            done: false,
+        }
+    }
+
+    /// Reads more data from the source into this stream's buffer, returning `false` if the source
+    /// has been exhausted.
+    fn fill_buffer(&mut self) -> std::io::Result<bool> {
+        let len = self.buffer.len();
+        self.buffer.resize(len + READ_SIZE, 0);
+
+        let result = loop {
+            match self.source.read(&mut self.buffer[len..]) {
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                result => break result,
+            }
+        };
+
+        let bytes_read = *result.as_ref().unwrap_or(&0);
+        self.buffer.truncate(len + bytes_read);
+
+        result.map(|bytes_read| bytes_read > 0)
+    }
+
+    /// Discards the first `len` bytes of this stream's buffer.
+    fn discard(&mut self, len: usize) {
+        self.buffer.drain(..len);
+        self.buffer_offset += len as u64;
+    }
+
+    /// Locates the next archive in the source and returns its encoded content, including its
+    /// banner and delimiters.
+    fn next_encoded_archive(&mut self) -> std::io::Result<Option<Vec<u8>>> {
+        // Find the start of the next banner, discarding everything before it
+        let banner_start = loop {
+            if let Some(start) = find_banner(&self.buffer) {
+                break start;
+            }
+
+            // Keep enough bytes around to complete a partial banner match at the end of the buffer
+            let discard_len = self.buffer.len().saturating_sub(BANNER.len() - 1);
+            self.discard(discard_len);
+
+            if !self.fill_buffer()? {
+                return Ok(None);
+            }
+        };
+
+        self.discard(banner_start);
+
+        // Find the opening and closing delimiters
+        let mut delimiters_found = 0;
+        let mut scan = BANNER.len();
+
+        loop {
+            match memchr::memchr(DATA_DELIMITER, &self.buffer[scan..]) {
+                Some(pos) => {
+                    scan += pos + 1;
+                    delimiters_found += 1;
+
+                    if delimiters_found == 2 {
+                        let encoded_archive = self.buffer[..scan].to_vec();
+                        self.discard(scan);
+
+                        return Ok(Some(encoded_archive));
+                    }
+                }
+                None => {
+                    scan = self.buffer.len();
+
+                    if !self.fill_buffer()? {
+                        return Err(std::io::Error::from(ErrorKind::UnexpectedEof));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for BinHexStream<R> {
+    type Item = (u64, Result<BinHexArchive<Cursor<Vec<u8>>>, BinHexError>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.next_encoded_archive() {
+            Ok(Some(encoded_archive)) => {
+                // `next_encoded_archive` discards the archive's content from the buffer, so we
+                // need to work backward to find the start of the archive
+                let offset = self.buffer_offset - encoded_archive.len() as u64;
+
+                Some((offset, BinHexArchive::new(Cursor::new(encoded_archive))))
+            }
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(error) => {
+                self.done = true;
+                Some((self.buffer_offset, Err(BinHexError::from(error))))
+            }
+        }
+    }
+}
+
+/// Returns the index of the first complete BinHex banner in the given slice.
+fn find_banner(bytes: &[u8]) -> Option<usize> {
+    let mut scan = 0;
+
+    while let Some(pos) = memchr::memchr(BANNER[0], &bytes[scan..]) {
+        let start = scan + pos;
+
+        if bytes[start..].starts_with(BANNER) {
+            return Some(start);
+        }
+
+        scan = start + 1;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use indoc::indoc;
+
+    const DIGEST: &[u8] = indoc! {br##"
+        Today's digest contains two files; (there's a note in parentheses, too).
+
+        (This file must be converted with BinHex 4.0)
+        :$f*TEQKPH#edCA0d,R4iG!#3$L8!N!-TR@dpN!8J5'9XE'mJCR*[E5"dD'8JC'&
+        dB5"QEh*V)5!pN!9Bm5f3"5")C@aXEb"QFQpY)(4SC5"bCA0[GA*MC5"QEh*V)5!
+        YN!8SI!:
+
+        ------------------------------
+        Here's another one:
+
+        (This file must be converted with BinHex 4.0)
+
+        :&&0TEA"XC94PH(5U)%4[Bh9YC@jd!&4&@&4dG(Kd!*!&&J!!!8c328KPE'a[)'C
+        bEfdJ8fPYF'aP9'9iG#'`*3!!!3#3!`%D!*!$'J#3!c*J6qm!%'!',`a1Z[@161i
+        B`2r`6PiJAdr[!"41d#)[!"46D@e`E'98CAKdUL"%Ef0eE@9ZG'CcFfPd)(*dFf&
+        X!'T849K8G(4iG!#3%)!!N!IFK-H8!*!'!4iF0J"#3%K!C`5!`63!5%)`!i$"0!!
+        L!N*!5%"J)L3!3N")3%K#N!-Q!A)!H!r8JY'!dS'`Jf8%N!#$8J&4c2r`60m!(%j
+        e)PmJAk!P,S"U!N+A6Y%!N!1U!!)!!!%!!!J!!!%)!!J!!!%8!!%!!!&F!!)!!!*
+        i!!%!!!,i!!3!!!-@!*!%&J!"!*!&%!!-!!-!N!--!*!)!3#3!`%D!*!$'J#3!c)
+        2`&')%J#3""`!-J!!Fh4jE!#3!`S!J2rr!*!%$m#lc&2h!:
+
+        End of digest
+    "##};
+
+    #[test]
+    fn stream() -> Result<(), BinHexError> {
+        let archives: Vec<(u64, String)> = BinHexStream::new(Cursor::new(DIGEST))
+            .map(|(offset, archive)| {
+                archive.map(|mut archive| (offset, archive.filename().clone()))
+            })
+            .collect::<Result<_, _>>()?;
+
+        assert_eq!(
+            vec![
+                (74, String::from("binhex-test.txt")),
+                (312, String::from("SimpleText™ Document"))
+            ],
+            archives
+        );
+
+        archives.iter().for_each(|(offset, _)| {
+            assert!(DIGEST[*offset as usize..].starts_with(BANNER));
+        });
+
+        Ok(())
+    }
+
+    #[test]
+    fn stream_extract() -> Result<(), BinHexError> {
+        let (_, archive) = BinHexStream::new(Cursor::new(DIGEST)).next().unwrap();
+
+        let mut data_fork = vec![];
+        let mut resource_fork = vec![];
+
+        archive?.extract(&mut data_fork, &mut resource_fork)?;
+
+        assert_eq!(
+            b"===== Hello from the data fork! =====",
+            data_fork.as_slice()
+        );
+        assert_eq!(
+            b"----- Hello from the resource fork! -----",
+            resource_fork.as_slice()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn stream_tiny_reads() {
+        struct TinyReader<'a>(&'a [u8]);
+
+        impl<'a> Read for TinyReader<'a> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let len = std::cmp::min(3, std::cmp::min(buf.len(), self.0.len()));
+                buf[..len].copy_from_slice(&self.0[..len]);
+                self.0 = &self.0[len..];
+
+                Ok(len)
+            }
+        }
+
+        let offsets: Vec<u64> = BinHexStream::new(TinyReader(DIGEST))
+            .map(|(offset, _)| offset)
+            .collect();
+
+        assert_eq!(vec![74, 312], offsets);
+    }
+
+    #[test]
+    fn stream_no_archives() {
+        assert_eq!(
+            0,
+            BinHexStream::new(Cursor::new(b"Nothing (to see) here")).count()
+        );
+    }
+
+    #[test]
+    fn stream_truncated() {
+        let mut stream = BinHexStream::new(Cursor::new(&DIGEST[..400]));
+
+        assert!(matches!(stream.next(), Some((74, Ok(_)))));
+        assert!(matches!(
+            stream.next(),
+            Some((312, Err(BinHexError::IoError(ErrorKind::UnexpectedEof))))
+        ));
+        assert!(stream.next().is_none());
+    }
+}
@@ -0,0 +1,126 @@
+use std::io::{Result, Write};
+
+use super::read::{DATA_DELIMITER, FULL_BANNER};
+
+const LINE_LENGTH: usize = 64;
+
+/// A `Write` implementation that wraps BinHex-encoded data in the banner, delimiters, and line
+/// breaks expected by BinHex decoders.
+///
+/// This is the counterpart to [`super::read::EncodedBinHexReader`]. Data written to an
+/// `EncodedBinHexWriter` must already be BinHex-encoded; the writer emits the banner and opening
+/// delimiter before the first encoded byte, breaks the encoded data into 64-column lines (the first
+/// of which includes the opening delimiter), and emits the closing delimiter when
+/// [`EncodedBinHexWriter::finish`] is called.
+pub struct EncodedBinHexWriter<W: Write> {
+    dest: W,
+    column: Option<usize>,
+}
+
+impl<W: Write> EncodedBinHexWriter<W> {
+    pub fn new(dest: W) -> Self {
+        EncodedBinHexWriter { dest, column: None }
+    }
+
+    /// Writes the closing delimiter (and the banner and opening delimiter, if no data has been
+    /// written yet) and returns the underlying writer.
+    pub fn finish(mut self) -> Result<W> {
+        let column = self.start()?;
+
+        if column == LINE_LENGTH {
+            self.dest.write_all(b"\n")?;
+        }
+
+        self.dest.write_all(&[DATA_DELIMITER])?;
+        self.dest.flush()?;
+
+        Ok(self.dest)
+    }
+
+    /// Writes the banner and opening delimiter if they haven't already been written and returns the
+    /// current column.
+    fn start(&mut self) -> Result<usize> {
+        match self.column {
+            Some(column) => Ok(column),
+            None => {
+                self.dest.write_all(FULL_BANNER)?;
+                self.dest.write_all(&[b'\n', DATA_DELIMITER])?;
+                self.column = Some(1);
+
+                Ok(1)
+            }
+        }
+    }
+}
+
+impl<W: Write> Write for EncodedBinHexWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let mut column = self.start()?;
+
+        if column == LINE_LENGTH {
+            self.dest.write_all(b"\n")?;
+            column = 0;
+        }
+
+        let len = std::cmp::min(buf.len(), LINE_LENGTH - column);
+
+        self.dest.write_all(&buf[..len])?;
+        self.column = Some(column + len);
+
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.dest.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn write() {
+        let mut writer = EncodedBinHexWriter::new(vec![]);
+
+        writer
+            .write_all(br#"$f*TEQKPH#edCA0d,R4iG!#3$L8!N!-TR@dpN!8J5'9XE'mJCR*[E5"dD'8JC'&dB5"QEh*V)5!pN!9Bm5f3"5")C@aXEb"QFQpY)(4SC5"bCA0[GA*MC5"QEh*V)5!YN!8SI!"#)
+            .unwrap();
+
+        assert_eq!(
+            indoc! {br#"
+                (This file must be converted with BinHex 4.0)
+                :$f*TEQKPH#edCA0d,R4iG!#3$L8!N!-TR@dpN!8J5'9XE'mJCR*[E5"dD'8JC'&
+                dB5"QEh*V)5!pN!9Bm5f3"5")C@aXEb"QFQpY)(4SC5"bCA0[GA*MC5"QEh*V)5!
+                YN!8SI!:"#},
+            writer.finish().unwrap().as_slice()
+        );
+    }
+
+    #[test]
+    fn write_full_line() {
+        let mut writer = EncodedBinHexWriter::new(vec![]);
+        writer.write_all(&[b'!'; 63]).unwrap();
+
+        let mut expected = Vec::from(&b"(This file must be converted with BinHex 4.0)\n:"[..]);
+        expected.extend_from_slice(&[b'!'; 63]);
+        expected.extend_from_slice(b"\n:");
+
+        assert_eq!(expected, writer.finish().unwrap());
+    }
+
+    #[test]
+    fn write_empty() {
+        let writer = EncodedBinHexWriter::new(vec![]);
+
+        assert_eq!(
+            b"(This file must be converted with BinHex 4.0)\n::"[..],
+            writer.finish().unwrap()[..]
+        );
+    }
+}
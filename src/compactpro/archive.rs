@@ -0,0 +1,720 @@
+use std::convert::TryInto;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Read, Seek, SeekFrom};
+
+use super::entry::{CompactProEntry, ForkInfo};
+use super::{lzh, rle};
+use crate::finder::FinderFlags;
+use crate::util::{u16_at, u32_at};
+
+/// The first byte of every Compact Pro archive.
+const MAGIC: u8 = 0x01;
+
+const ARCHIVE_HEADER_LENGTH: usize = 8;
+
+/// The length, in bytes, of the fixed portion of the directory that precedes the archive's
+/// comment.
+const DIRECTORY_HEADER_LENGTH: usize = 7;
+
+/// The length, in bytes, of the metadata that follows each file's name in the directory.
+const FILE_METADATA_LENGTH: usize = 45;
+
+const FOLDER_FLAG: u8 = 0x80;
+const NAME_LENGTH_MASK: u8 = 0x7f;
+
+const ENCRYPTED_FLAG: u16 = 0x0001;
+const RESOURCE_FORK_LZH_FLAG: u16 = 0x0002;
+const DATA_FORK_LZH_FLAG: u16 = 0x0004;
+
+/// A Compact Pro archive.
+///
+/// Compact Pro archives contain a tree of files and folders. The archive's directory is read when
+/// the archive is created, and each file's forks are decompressed on demand.
+pub struct CompactProArchive<R: Read + Seek> {
+    source: R,
+    base_offset: u64,
+    volume: u8,
+    comment: Vec<u8>,
+    entries: Vec<CompactProEntry>,
+}
+
+impl<R: Read + Seek> CompactProArchive<R> {
+    /// Creates a new Compact Pro archive that will extract data from the given source, starting at
+    /// the source's current position, and reads the archive's directory.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the archive's header or directory could not be read
+    /// or was malformed, or if the directory doesn't match its checksum.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use clarus::compactpro::{CompactProArchive, CompactProError};
+    ///
+    /// fn main() -> Result<(), CompactProError> {
+    ///     let mut archive = CompactProArchive::new(File::open("example.cpt")?)?;
+    ///
+    ///     for entry in archive.entries().to_vec() {
+    ///         if !entry.is_folder() {
+    ///             let data_fork = archive.data_fork(&entry)?;
+    ///             println!("{}: {} bytes", entry.path().join("/"), data_fork.len());
+    ///         }
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn new(mut source: R) -> Result<Self, CompactProError> {
+        let base_offset = source.stream_position()?;
+
+        let mut header = [0; ARCHIVE_HEADER_LENGTH];
+        source.read_exact(&mut header)?;
+
+        if header[0] != MAGIC {
+            return Err(CompactProError::InvalidHeader);
+        }
+
+        let volume = header[1];
+        let directory_offset = u32_at(&header, 4) as u64;
+
+        if directory_offset < ARCHIVE_HEADER_LENGTH as u64 {
+            return Err(CompactProError::InvalidHeader);
+        }
+
+        source.seek(SeekFrom::Start(base_offset + directory_offset))?;
+
+        let mut directory = vec![];
+        source.read_to_end(&mut directory)?;
+
+        let (comment, entries, directory_len) = read_directory(&directory, volume)?;
+
+        // The directory's checksum covers everything after the checksum itself
+        let provided_crc = u32_at(&directory, 0);
+        let calculated_crc = crc(&[&directory[4..directory_len]]);
+
+        if provided_crc != calculated_crc {
+            return Err(CompactProError::InvalidChecksum(
+                provided_crc,
+                calculated_crc,
+            ));
+        }
+
+        Ok(CompactProArchive {
+            source,
+            base_offset,
+            volume,
+            comment,
+            entries,
+        })
+    }
+
+    /// Returns the number of this volume within a (possibly) multi-volume archive.
+    pub fn volume(&self) -> u8 {
+        self.volume
+    }
+
+    /// Returns the archive's comment, which is usually empty.
+    pub fn comment(&self) -> &[u8] {
+        &self.comment
+    }
+
+    /// Returns all of the files and folders in this archive, in the order in which they appear in
+    /// the archive's directory. Folders precede their contents.
+    pub fn entries(&self) -> &[CompactProEntry] {
+        &self.entries
+    }
+
+    /// Reads and decompresses the given entry's data fork.
+    ///
+    /// Because a single checksum covers both of a file's forks, both forks are decompressed and
+    /// checked, but only the data fork is returned.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if the entry is encrypted or stored in another volume, if
+    /// either fork's compressed data is malformed, or if the forks' content doesn't match the
+    /// entry's checksum.
+    pub fn data_fork(&mut self, entry: &CompactProEntry) -> Result<Vec<u8>, CompactProError> {
+        Ok(self.read_forks(entry)?.1)
+    }
+
+    /// Reads and decompresses the given entry's resource fork.
+    ///
+    /// Because a single checksum covers both of a file's forks, both forks are decompressed and
+    /// checked, but only the resource fork is returned. The returned fork may be opened with
+    /// [`crate::rsrc::ResourceFork`].
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if the entry is encrypted or stored in another volume, if
+    /// either fork's compressed data is malformed, or if the forks' content doesn't match the
+    /// entry's checksum.
+    pub fn resource_fork(&mut self, entry: &CompactProEntry) -> Result<Vec<u8>, CompactProError> {
+        Ok(self.read_forks(entry)?.0)
+    }
+
+    /// Reads, decompresses, and checks both of the given entry's forks, returning the resource fork
+    /// followed by the data fork.
+    fn read_forks(
+        &mut self,
+        entry: &CompactProEntry,
+    ) -> Result<(Vec<u8>, Vec<u8>), CompactProError> {
+        if entry.is_folder {
+            return Ok((vec![], vec![]));
+        }
+
+        if entry.encrypted {
+            return Err(CompactProError::Encrypted);
+        }
+
+        if entry.volume != self.volume {
+            return Err(CompactProError::OtherVolume(entry.volume));
+        }
+
+        let resource_fork = self.read_fork(entry.resource_fork)?;
+        let data_fork = self.read_fork(entry.data_fork)?;
+
+        let calculated_crc = crc(&[&resource_fork, &data_fork]);
+
+        if entry.crc != calculated_crc {
+            return Err(CompactProError::InvalidChecksum(entry.crc, calculated_crc));
+        }
+
+        Ok((resource_fork, data_fork))
+    }
+
+    fn read_fork(&mut self, fork: ForkInfo) -> Result<Vec<u8>, CompactProError> {
+        self.source
+            .seek(SeekFrom::Start(self.base_offset + fork.offset))?;
+
+        // The compressed length comes from the directory and can't be trusted, so the buffer
+        // only grows as data actually arrives from the source
+        let mut compressed = vec![];
+        (&mut self.source)
+            .take(fork.compressed_len as u64)
+            .read_to_end(&mut compressed)?;
+
+        if compressed.len() < fork.compressed_len as usize {
+            return Err(CompactProError::IoError(io::ErrorKind::UnexpectedEof));
+        }
+
+        if fork.lzh {
+            lzh::decompress(&compressed, fork.len as usize)
+        } else {
+            rle::expand(&compressed, fork.len as usize)
+        }
+    }
+}
+
+/// Calculates the CRC used by Compact Pro over the concatenation of the given chunks.
+///
+/// Compact Pro uses the same polynomial and initial value as the common CRC-32 (as used by zip and
+/// others), but doesn't invert the final value.
+fn crc(chunks: &[&[u8]]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+
+    for chunk in chunks {
+        hasher.update(chunk);
+    }
+
+    !hasher.finalize()
+}
+
+/// Parses the given directory (and anything that follows it), returning the archive's comment, its
+/// entries, and the length of the directory.
+fn read_directory(
+    directory: &[u8],
+    volume: u8,
+) -> Result<(Vec<u8>, Vec<CompactProEntry>, usize), CompactProError> {
+    if directory.len() < DIRECTORY_HEADER_LENGTH {
+        return Err(CompactProError::InvalidHeader);
+    }
+
+    let entry_count = u16_at(directory, 4) as usize;
+    let comment_len = directory[6] as usize;
+
+    let comment = directory
+        .get(DIRECTORY_HEADER_LENGTH..DIRECTORY_HEADER_LENGTH + comment_len)
+        .ok_or(CompactProError::InvalidHeader)?
+        .to_vec();
+
+    let mut offset = DIRECTORY_HEADER_LENGTH + comment_len;
+    let mut entries = Vec::with_capacity(entry_count);
+
+    let mut folders: Vec<String> = vec![];
+
+    // The number of entries remaining in each folder that's currently being read (including the
+    // contents of nested folders), starting with the whole archive
+    let mut remaining = vec![entry_count];
+
+    while remaining[0] > 0 {
+        while remaining.len() > 1 && *remaining.last().unwrap() == 0 {
+            remaining.pop();
+            folders.pop();
+        }
+
+        for count in remaining.iter_mut() {
+            *count = count.checked_sub(1).ok_or(CompactProError::InvalidHeader)?;
+        }
+
+        let name_byte = *directory
+            .get(offset)
+            .ok_or(CompactProError::InvalidHeader)?;
+        let name_len = (name_byte & NAME_LENGTH_MASK) as usize;
+
+        let name_bytes = directory
+            .get(offset + 1..offset + 1 + name_len)
+            .ok_or(CompactProError::InvalidHeader)?;
+        let (name, _, _) = encoding_rs::MACINTOSH.decode(name_bytes);
+        let name = name.to_string();
+
+        offset += 1 + name_len;
+
+        let mut path = folders.clone();
+        path.push(name.clone());
+
+        if name_byte & FOLDER_FLAG != 0 {
+            let child_count = directory
+                .get(offset..offset + 2)
+                .ok_or(CompactProError::InvalidHeader)?;
+
+            remaining.push(u16_at(child_count, 0) as usize);
+            folders.push(name);
+            offset += 2;
+
+            entries.push(CompactProEntry {
+                path,
+                is_folder: true,
+                volume,
+                file_type: [0; 4],
+                creator: [0; 4],
+                flags: FinderFlags::from(0),
+                created: 0,
+                modified: 0,
+                crc: 0,
+                encrypted: false,
+                data_fork: ForkInfo::empty(),
+                resource_fork: ForkInfo::empty(),
+            });
+        } else {
+            let metadata = directory
+                .get(offset..offset + FILE_METADATA_LENGTH)
+                .ok_or(CompactProError::InvalidHeader)?;
+
+            offset += FILE_METADATA_LENGTH;
+
+            let flags = u16_at(metadata, 27);
+            let fork_offset = u32_at(metadata, 1) as u64;
+
+            // The resource fork comes first, followed immediately by the data fork
+            let resource_fork = ForkInfo {
+                offset: fork_offset,
+                len: u32_at(metadata, 29),
+                compressed_len: u32_at(metadata, 37),
+                lzh: flags & RESOURCE_FORK_LZH_FLAG != 0,
+            };
+
+            let data_fork = ForkInfo {
+                offset: fork_offset + resource_fork.compressed_len as u64,
+                len: u32_at(metadata, 33),
+                compressed_len: u32_at(metadata, 41),
+                lzh: flags & DATA_FORK_LZH_FLAG != 0,
+            };
+
+            entries.push(CompactProEntry {
+                path,
+                is_folder: false,
+                volume: metadata[0],
+                file_type: metadata[5..9].try_into().unwrap(),
+                creator: metadata[9..13].try_into().unwrap(),
+                flags: FinderFlags::from(u16_at(metadata, 21)),
+                created: u32_at(metadata, 13),
+                modified: u32_at(metadata, 17),
+                crc: u32_at(metadata, 23),
+                encrypted: flags & ENCRYPTED_FLAG != 0,
+                data_fork,
+                resource_fork,
+            });
+        }
+    }
+
+    Ok((comment, entries, offset))
+}
+
+/// The error type for operations on Compact Pro archives.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CompactProError {
+    /// An [`std::io::Error`] occurred while reading some part of the archive.
+    ///
+    /// The kind of IO error is included.
+    IoError(io::ErrorKind),
+
+    /// The archive's header or directory was malformed.
+    InvalidHeader,
+
+    /// The checksum included in the archive's directory did not match the checksum calculated from
+    /// the directory's content or from the content of a file's forks.
+    ///
+    /// The provided checksum and the calculated checksum are included.
+    InvalidChecksum(u32, u32),
+
+    /// An entry's forks were stored in a different volume of a multi-volume archive.
+    ///
+    /// The number of the volume that contains the entry's forks is included.
+    OtherVolume(u8),
+
+    /// An entry's forks were encrypted.
+    Encrypted,
+
+    /// A fork's compressed data was malformed or didn't expand to the expected length.
+    CorruptData,
+}
+
+impl Display for CompactProError {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CompactProError::IoError(kind) => write!(fmt, "IO error: {:?}", kind),
+            CompactProError::InvalidHeader => write!(fmt, "Invalid Compact Pro header"),
+            CompactProError::InvalidChecksum(provided, calculated) => write!(
+                fmt,
+                "Invalid checksum (provided: {:08x}, calculated: {:08x})",
+                provided, calculated
+            ),
+            CompactProError::OtherVolume(volume) => {
+                write!(fmt, "Forks stored in another volume: {}", volume)
+            }
+            CompactProError::Encrypted => write!(fmt, "Encrypted entry"),
+            CompactProError::CorruptData => write!(fmt, "Corrupt compressed data"),
+        }
+    }
+}
+
+impl From<io::Error> for CompactProError {
+    fn from(error: io::Error) -> Self {
+        CompactProError::IoError(error.kind())
+    }
+}
+
+impl error::Error for CompactProError {}
+
+#[cfg(test)]
+mod test {
+    use super::super::lzh::test::{compress, Token};
+    use super::*;
+    use crate::rsrc::ResourceFork;
+    use std::io::Cursor;
+
+    const RESOURCE_FORK: &[u8] = include_bytes!("../rsrc/string-table.rsrc");
+
+    enum Node<'a> {
+        File(&'a str, &'a [u8], &'a [u8], bool),
+        Folder(&'a str, Vec<Node<'a>>),
+    }
+
+    /// Run-length encodes the given bytes, which must not contain the escape byte.
+    fn rle(bytes: &[u8]) -> Vec<u8> {
+        assert!(!bytes.contains(&0x81));
+
+        let mut encoded = vec![];
+        let mut i = 0;
+
+        while i < bytes.len() {
+            let run = bytes[i..]
+                .iter()
+                .take(255)
+                .take_while(|byte| **byte == bytes[i])
+                .count();
+
+            if run > 3 {
+                encoded.extend_from_slice(&[bytes[i], 0x81, 0x82, run as u8]);
+            } else {
+                encoded.extend_from_slice(&bytes[i..i + run]);
+            }
+
+            i += run;
+        }
+
+        encoded
+    }
+
+    fn lzh(bytes: &[u8]) -> Vec<u8> {
+        let tokens: Vec<Token> = rle(bytes).into_iter().map(Token::Literal).collect();
+        compress(&tokens)
+    }
+
+    fn count(nodes: &[Node]) -> usize {
+        nodes
+            .iter()
+            .map(|node| match node {
+                Node::File(..) => 1,
+                Node::Folder(_, children) => 1 + count(children),
+            })
+            .sum()
+    }
+
+    fn write_nodes(nodes: &[Node], forks: &mut Vec<u8>, directory: &mut Vec<u8>) {
+        for node in nodes {
+            match node {
+                Node::Folder(name, children) => {
+                    directory.push(name.len() as u8 | FOLDER_FLAG);
+                    directory.extend_from_slice(name.as_bytes());
+                    directory.extend_from_slice(&(count(children) as u16).to_be_bytes());
+
+                    write_nodes(children, forks, directory);
+                }
+                Node::File(name, data_fork, resource_fork, use_lzh) => {
+                    let compress = if *use_lzh { lzh } else { rle };
+                    let compressed_resource_fork = compress(resource_fork);
+                    let compressed_data_fork = compress(data_fork);
+
+                    let flags = if *use_lzh {
+                        RESOURCE_FORK_LZH_FLAG | DATA_FORK_LZH_FLAG
+                    } else {
+                        0
+                    };
+
+                    directory.push(name.len() as u8);
+                    directory.extend_from_slice(name.as_bytes());
+                    directory.push(1);
+                    directory.extend_from_slice(&(forks.len() as u32).to_be_bytes());
+                    directory.extend_from_slice(b"TEXTttxt");
+                    directory.extend_from_slice(&0xb000_0000u32.to_be_bytes());
+                    directory.extend_from_slice(&0xb000_0001u32.to_be_bytes());
+                    directory.extend_from_slice(&0x4000u16.to_be_bytes());
+                    directory.extend_from_slice(&crc(&[resource_fork, data_fork]).to_be_bytes());
+                    directory.extend_from_slice(&flags.to_be_bytes());
+                    directory.extend_from_slice(&(resource_fork.len() as u32).to_be_bytes());
+                    directory.extend_from_slice(&(data_fork.len() as u32).to_be_bytes());
+                    directory
+                        .extend_from_slice(&(compressed_resource_fork.len() as u32).to_be_bytes());
+                    directory.extend_from_slice(&(compressed_data_fork.len() as u32).to_be_bytes());
+
+                    forks.extend(compressed_resource_fork);
+                    forks.extend(compressed_data_fork);
+                }
+            }
+        }
+    }
+
+    fn archive_bytes(nodes: &[Node], comment: &[u8]) -> Vec<u8> {
+        let mut archive = vec![MAGIC, 1, 0, 0, 0, 0, 0, 0];
+
+        let mut directory = vec![0; 4];
+        directory.extend_from_slice(&(count(nodes) as u16).to_be_bytes());
+        directory.push(comment.len() as u8);
+        directory.extend_from_slice(comment);
+
+        write_nodes(nodes, &mut archive, &mut directory);
+
+        let crc = crc(&[&directory[4..]]);
+        directory[0..4].copy_from_slice(&crc.to_be_bytes());
+
+        let directory_offset = archive.len() as u32;
+        archive[4..8].copy_from_slice(&directory_offset.to_be_bytes());
+        archive.extend(directory);
+
+        archive
+    }
+
+    fn sample_nodes<'a>() -> Vec<Node<'a>> {
+        vec![
+            Node::Folder(
+                "Folder",
+                vec![
+                    Node::File(
+                        "Inner",
+                        b"Hello from the data fork!!!!!!!!!!",
+                        RESOURCE_FORK,
+                        true,
+                    ),
+                    Node::Folder(
+                        "Nested",
+                        vec![Node::File("Deep", b"Deep data fork", &[], false)],
+                    ),
+                    Node::Folder("Empty", vec![]),
+                ],
+            ),
+            Node::File("Outer", b"Outer data fork", RESOURCE_FORK, false),
+        ]
+    }
+
+    #[test]
+    fn read_archive() -> Result<(), CompactProError> {
+        let mut archive =
+            CompactProArchive::new(Cursor::new(archive_bytes(&sample_nodes(), b"Comment")))?;
+
+        assert_eq!(1, archive.volume());
+        assert_eq!(b"Comment", archive.comment());
+
+        let paths: Vec<String> = archive
+            .entries()
+            .iter()
+            .map(|entry| entry.path().join("/"))
+            .collect();
+
+        assert_eq!(
+            vec![
+                "Folder",
+                "Folder/Inner",
+                "Folder/Nested",
+                "Folder/Nested/Deep",
+                "Folder/Empty",
+                "Outer"
+            ],
+            paths
+        );
+
+        let inner = archive.entries()[1].clone();
+
+        assert!(!inner.is_folder());
+        assert_eq!(*b"TEXT", inner.file_type());
+        assert_eq!(*b"ttxt", inner.creator());
+        assert!(inner.flags().is_invisible());
+        assert_eq!(0xb000_0000, inner.created());
+        assert_eq!(0xb000_0001, inner.modified());
+        assert_eq!(RESOURCE_FORK.len(), inner.resource_fork_len());
+
+        assert_eq!(
+            b"Hello from the data fork!!!!!!!!!!".to_vec(),
+            archive.data_fork(&inner)?
+        );
+
+        let resource_fork = archive.resource_fork(&inner)?;
+        assert_eq!(RESOURCE_FORK.to_vec(), resource_fork);
+        assert!(ResourceFork::new(Cursor::new(resource_fork)).is_ok());
+
+        for (index, expected) in [(3, &b"Deep data fork"[..]), (5, &b"Outer data fork"[..])].iter()
+        {
+            let entry = archive.entries()[*index].clone();
+            assert_eq!(expected.to_vec(), archive.data_fork(&entry)?);
+        }
+
+        let outer = archive.entries()[5].clone();
+        assert_eq!(RESOURCE_FORK.to_vec(), archive.resource_fork(&outer)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn checksums() -> Result<(), CompactProError> {
+        // The standard CRC-32 check value, without its final inversion
+        assert_eq!(0x340b_c6d9, crc(&[b"123456789"]));
+        assert_eq!(crc(&[b"123456789"]), crc(&[b"1234", b"56789"]));
+
+        // An archive built by hand, with checksums calculated independently
+        let bytes = [
+            // Archive header (volume 1, directory at offset 10)
+            &[0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0a][..],
+            // Uncompressed data fork
+            b"Hi",
+            // Directory checksum, entry count, and comment length
+            &[0x75, 0x38, 0x8f, 0x02, 0x00, 0x01, 0x00],
+            // A file named "A" on volume 1 with forks at offset 8
+            &[0x01, b'A', 0x01, 0x00, 0x00, 0x00, 0x08],
+            // Type, creator, dates, and Finder flags
+            b"TEXTttxt",
+            &[0xb0, 0x00, 0x00, 0x00, 0xb0, 0x00, 0x00, 0x01, 0x00, 0x00],
+            // Fork checksum and flags
+            &[0xb2, 0xe8, 0xf1, 0xf1, 0x00, 0x00],
+            // Fork lengths (resource, data, compressed resource, compressed data)
+            &[0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 2],
+        ]
+        .concat();
+
+        let mut archive = CompactProArchive::new(Cursor::new(bytes))?;
+        let entry = archive.entries()[0].clone();
+
+        assert_eq!(b"Hi".to_vec(), archive.data_fork(&entry)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn oversized_fork() -> Result<(), CompactProError> {
+        let mut bytes = archive_bytes(&[Node::File("File", b"Data", &[], false)], b"");
+
+        // Claim that the compressed data fork (the last field of the directory) is nearly 4 GiB
+        let len = bytes.len();
+        bytes[len - 4..].copy_from_slice(&0xffff_fff0u32.to_be_bytes());
+
+        let directory_offset = u32_at(&bytes, 4) as usize;
+        let directory_crc = crc(&[&bytes[directory_offset + 4..]]);
+        bytes[directory_offset..directory_offset + 4].copy_from_slice(&directory_crc.to_be_bytes());
+
+        let mut archive = CompactProArchive::new(Cursor::new(bytes))?;
+        let entry = archive.entries()[0].clone();
+
+        assert_eq!(
+            Err(CompactProError::IoError(io::ErrorKind::UnexpectedEof)),
+            archive.data_fork(&entry)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_archives() {
+        let bytes = archive_bytes(&sample_nodes(), b"");
+
+        let mut bad_directory_crc = bytes.clone();
+        *bad_directory_crc.last_mut().unwrap() ^= 0xff;
+
+        assert!(matches!(
+            CompactProArchive::new(Cursor::new(bad_directory_crc)),
+            Err(CompactProError::InvalidChecksum(_, _))
+        ));
+
+        let mut truncated = bytes.clone();
+        truncated.truncate(bytes.len() - 1);
+
+        assert_eq!(
+            Err(CompactProError::InvalidHeader),
+            CompactProArchive::new(Cursor::new(truncated)).map(|_| ())
+        );
+
+        assert_eq!(
+            Err(CompactProError::InvalidHeader),
+            CompactProArchive::new(Cursor::new(vec![0; 16])).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn invalid_forks() -> Result<(), CompactProError> {
+        let mut bytes = archive_bytes(&sample_nodes(), b"");
+
+        // Corrupt the first byte of the outer file's data fork
+        let data_fork_start = bytes
+            .windows(15)
+            .position(|window| window == b"Outer data fork")
+            .unwrap();
+
+        bytes[data_fork_start] = b'o';
+
+        let mut archive = CompactProArchive::new(Cursor::new(bytes))?;
+        let outer = archive.entries()[5].clone();
+
+        assert!(matches!(
+            archive.resource_fork(&outer),
+            Err(CompactProError::InvalidChecksum(_, _))
+        ));
+
+        let mut encrypted = outer.clone();
+        encrypted.encrypted = true;
+        assert_eq!(
+            Err(CompactProError::Encrypted),
+            archive.data_fork(&encrypted)
+        );
+
+        let mut other_volume = outer;
+        other_volume.volume = 2;
+        assert_eq!(
+            Err(CompactProError::OtherVolume(2)),
+            archive.data_fork(&other_volume)
+        );
+
+        Ok(())
+    }
+}
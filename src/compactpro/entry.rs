@@ -0,0 +1,108 @@
+use crate::finder::FinderFlags;
+
+/// The location and encoding of one fork of a file in a Compact Pro archive.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(super) struct ForkInfo {
+    pub offset: u64,
+    pub len: u32,
+    pub compressed_len: u32,
+    pub lzh: bool,
+}
+
+impl ForkInfo {
+    /// Returns the description of an empty fork.
+    pub fn empty() -> Self {
+        ForkInfo {
+            offset: 0,
+            len: 0,
+            compressed_len: 0,
+            lzh: false,
+        }
+    }
+}
+
+/// A file or folder in a Compact Pro archive.
+///
+/// Entries for files carry the file's Finder metadata and describe its forks, which may be read
+/// with [`super::CompactProArchive::data_fork`] and [`super::CompactProArchive::resource_fork`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CompactProEntry {
+    pub(super) path: Vec<String>,
+    pub(super) is_folder: bool,
+    pub(super) volume: u8,
+    pub(super) file_type: [u8; 4],
+    pub(super) creator: [u8; 4],
+    pub(super) flags: FinderFlags,
+    pub(super) created: u32,
+    pub(super) modified: u32,
+    pub(super) crc: u32,
+    pub(super) encrypted: bool,
+    pub(super) data_fork: ForkInfo,
+    pub(super) resource_fork: ForkInfo,
+}
+
+impl CompactProEntry {
+    /// Returns the names of the folders that contain this entry, starting with the outermost
+    /// folder, followed by the name of this entry itself.
+    pub fn path(&self) -> &[String] {
+        &self.path
+    }
+
+    /// Returns the name of this entry.
+    pub fn name(&self) -> &String {
+        // Paths always contain at least the entry's own name
+        self.path.last().unwrap()
+    }
+
+    /// Returns `true` if this entry is a folder rather than a file.
+    pub fn is_folder(&self) -> bool {
+        self.is_folder
+    }
+
+    /// Returns the number of the volume (i.e. the part of a multi-volume archive) that contains
+    /// this entry's forks.
+    pub fn volume(&self) -> u8 {
+        self.volume
+    }
+
+    /// Returns this entry's file type identifier.
+    pub fn file_type(&self) -> [u8; 4] {
+        self.file_type
+    }
+
+    /// Returns this entry's creator identifier.
+    pub fn creator(&self) -> [u8; 4] {
+        self.creator
+    }
+
+    /// Returns this entry's Finder flags.
+    pub fn flags(&self) -> FinderFlags {
+        self.flags
+    }
+
+    /// Returns this entry's creation date, expressed as seconds since midnight, January 1, 1904.
+    pub fn created(&self) -> u32 {
+        self.created
+    }
+
+    /// Returns this entry's modification date, expressed as seconds since midnight, January 1,
+    /// 1904.
+    pub fn modified(&self) -> u32 {
+        self.modified
+    }
+
+    /// Returns the length, in bytes, of this entry's uncompressed data fork.
+    pub fn data_fork_len(&self) -> usize {
+        self.data_fork.len as usize
+    }
+
+    /// Returns the length, in bytes, of this entry's uncompressed resource fork.
+    pub fn resource_fork_len(&self) -> usize {
+        self.resource_fork.len as usize
+    }
+
+    /// Returns `true` if this entry's forks are encrypted.
+    pub fn is_encrypted(&self) -> bool {
+        self.encrypted
+    }
+}
@@ -0,0 +1,320 @@
+//! Decompression for Compact Pro's LZH method: LZ77 with an 8 KiB window, whose literals, match
+//! lengths, and the upper bits of match offsets are encoded with static Huffman codes that are
+//! replaced at the start of each block. The decompressed stream is itself run-length encoded.
+
+use super::archive::CompactProError;
+use super::rle::RleExpander;
+use crate::stuffit::bits::BitReader;
+use crate::stuffit::prefix::PrefixCode;
+use crate::stuffit::StuffItError;
+
+const WINDOW_SIZE: usize = 8192;
+
+const LITERAL_SYMBOLS: usize = 256;
+const LENGTH_SYMBOLS: usize = 64;
+const OFFSET_SYMBOLS: usize = 128;
+
+/// The "cost" at which a block ends; each literal costs two units and each match costs three.
+const BLOCK_LIMIT: usize = 0x1fff0;
+
+const LITERAL_COST: usize = 2;
+const MATCH_COST: usize = 3;
+
+/// The bit reader and prefix codes are shared with the StuffIt decompressors, which can only fail
+/// because of corrupt data.
+fn corrupt(_: StuffItError) -> CompactProError {
+    CompactProError::CorruptData
+}
+
+/// Reads a prefix code stored as a byte count followed by pairs of four-bit code lengths.
+fn read_code(bits: &mut BitReader, symbols: usize) -> Result<PrefixCode, CompactProError> {
+    let byte_count = bits.read_bits(8).map_err(corrupt)? as usize;
+
+    if byte_count * 2 > symbols {
+        return Err(CompactProError::CorruptData);
+    }
+
+    let mut lengths = vec![0; symbols];
+
+    for i in 0..byte_count {
+        let byte = bits.read_bits(8).map_err(corrupt)? as i32;
+
+        lengths[i * 2] = byte >> 4;
+        lengths[i * 2 + 1] = byte & 0x0f;
+    }
+
+    PrefixCode::from_lengths(&lengths).map_err(corrupt)
+}
+
+pub(super) fn decompress(compressed: &[u8], len: usize) -> Result<Vec<u8>, CompactProError> {
+    let mut bits = BitReader::msb_first(compressed);
+    let mut expander = RleExpander::new(len, compressed.len());
+
+    let mut window = vec![0u8; WINDOW_SIZE];
+    let mut window_position = 0;
+
+    let mut codes = None;
+    let mut block_cost = 0;
+    let mut block_start = 0;
+
+    while !expander.is_full() {
+        if codes.is_none() {
+            block_start = bits.position() / 8;
+            block_cost = 0;
+
+            codes = Some((
+                read_code(&mut bits, LITERAL_SYMBOLS)?,
+                read_code(&mut bits, LENGTH_SYMBOLS)?,
+                read_code(&mut bits, OFFSET_SYMBOLS)?,
+            ));
+        }
+
+        let (literal_code, length_code, offset_code) = codes.as_ref().unwrap();
+
+        if bits.read_bit().map_err(corrupt)? == 1 {
+            let byte = literal_code.decode(&mut bits).map_err(corrupt)? as u8;
+
+            window[window_position % WINDOW_SIZE] = byte;
+            window_position += 1;
+            expander.push(byte);
+
+            block_cost += LITERAL_COST;
+        } else {
+            let length = length_code.decode(&mut bits).map_err(corrupt)?;
+            let offset = (offset_code.decode(&mut bits).map_err(corrupt)? << 6)
+                | bits.read_bits(6).map_err(corrupt)? as usize;
+
+            if offset == 0 || offset > window_position.min(WINDOW_SIZE) {
+                return Err(CompactProError::CorruptData);
+            }
+
+            for _ in 0..length {
+                let byte = window[(window_position - offset) % WINDOW_SIZE];
+
+                window[window_position % WINDOW_SIZE] = byte;
+                window_position += 1;
+                expander.push(byte);
+            }
+
+            block_cost += MATCH_COST;
+        }
+
+        if block_cost >= BLOCK_LIMIT {
+            // Blocks are followed by two or three bytes of padding, which leave the next block an
+            // even number of bytes after the start of this one.
+            let mut next_block = (bits.position() + 7) / 8 + 2;
+
+            if (next_block - block_start) % 2 != 0 {
+                next_block += 1;
+            }
+
+            bits.set_position(next_block * 8);
+            codes = None;
+        }
+    }
+
+    expander.finish()
+}
+
+#[cfg(test)]
+pub(super) mod test {
+    use super::*;
+
+    pub enum Token {
+        Literal(u8),
+        Match(usize, usize),
+    }
+
+    struct BitWriter {
+        bytes: Vec<u8>,
+        position: usize,
+    }
+
+    impl BitWriter {
+        fn write(&mut self, value: usize, width: usize) {
+            for i in (0..width).rev() {
+                if self.position % 8 == 0 {
+                    self.bytes.push(0);
+                }
+
+                if value & (1 << i) != 0 {
+                    *self.bytes.last_mut().unwrap() |= 0x80 >> (self.position % 8);
+                }
+
+                self.position += 1;
+            }
+        }
+    }
+
+    /// Literals 0x00 through 0x7f have 8-bit codes and the rest have 9-bit codes; all lengths and
+    /// offsets have fixed-length codes.
+    fn literal_code_len(byte: u8) -> usize {
+        if byte < 0x80 {
+            8
+        } else {
+            9
+        }
+    }
+
+    fn literal_code(byte: u8) -> usize {
+        match byte {
+            0x00..=0x7f => byte as usize,
+            _ => 0x100 + (byte as usize - 0x80),
+        }
+    }
+
+    fn write_tables(writer: &mut BitWriter) {
+        writer.write(LITERAL_SYMBOLS / 2, 8);
+
+        for i in 0..LITERAL_SYMBOLS / 2 {
+            writer.write(if i < 0x40 { 0x88 } else { 0x99 }, 8);
+        }
+
+        writer.write(LENGTH_SYMBOLS / 2, 8);
+
+        for _ in 0..LENGTH_SYMBOLS / 2 {
+            writer.write(0x66, 8);
+        }
+
+        writer.write(OFFSET_SYMBOLS / 2, 8);
+
+        for _ in 0..OFFSET_SYMBOLS / 2 {
+            writer.write(0x77, 8);
+        }
+    }
+
+    /// Encodes the given tokens in the same way as Compact Pro's LZH compressor (albeit with fixed
+    /// codes).
+    pub fn compress(tokens: &[Token]) -> Vec<u8> {
+        let mut writer = BitWriter {
+            bytes: vec![],
+            position: 0,
+        };
+
+        let mut block_start = None;
+        let mut block_cost = 0;
+
+        for token in tokens {
+            if block_start.is_none() {
+                block_start = Some(writer.bytes.len());
+                block_cost = 0;
+                write_tables(&mut writer);
+            }
+
+            match token {
+                Token::Literal(byte) => {
+                    writer.write(1, 1);
+                    writer.write(literal_code(*byte), literal_code_len(*byte));
+                    block_cost += LITERAL_COST;
+                }
+                Token::Match(offset, length) => {
+                    writer.write(0, 1);
+                    writer.write(*length, 6);
+                    writer.write(offset >> 6, 7);
+                    writer.write(offset & 0x3f, 6);
+                    block_cost += MATCH_COST;
+                }
+            }
+
+            if block_cost >= BLOCK_LIMIT {
+                writer.bytes.extend_from_slice(&[0, 0]);
+
+                if (writer.bytes.len() - block_start.unwrap()) % 2 != 0 {
+                    writer.bytes.push(0);
+                }
+
+                writer.position = writer.bytes.len() * 8;
+                block_start = None;
+            }
+        }
+
+        writer.bytes
+    }
+
+    /// Applies the given tokens to produce the (still run-length encoded) stream they represent.
+    pub fn expand_tokens(tokens: &[Token]) -> Vec<u8> {
+        let mut expanded = vec![];
+
+        for token in tokens {
+            match token {
+                Token::Literal(byte) => expanded.push(*byte),
+                Token::Match(offset, length) => {
+                    for _ in 0..*length {
+                        expanded.push(expanded[expanded.len() - offset]);
+                    }
+                }
+            }
+        }
+
+        expanded
+    }
+
+    #[test]
+    fn decompress_lzh() -> Result<(), CompactProError> {
+        let mut tokens = vec![];
+
+        for byte in b"Hello, \xa5 world! " {
+            tokens.push(Token::Literal(*byte));
+        }
+
+        tokens.push(Token::Match(16, 40));
+
+        // Enough tokens to span several blocks
+        let mut state = 11u32;
+
+        let mut len = 56;
+
+        for i in 0..150_000 {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+
+            if i % 50 == 49 {
+                let offset = 1 + (state >> 8) as usize % len.min(WINDOW_SIZE - 1);
+
+                tokens.push(Token::Match(offset, 63));
+                len += 63;
+            } else {
+                len += 1;
+                tokens.push(Token::Literal(
+                    b"etaoin \xd0\xe9\xff"[(state >> 16) as usize % 10],
+                ));
+            }
+        }
+
+        let expected = expand_tokens(&tokens);
+        assert!(!expected.contains(&0x81));
+
+        assert_eq!(expected, decompress(&compress(&tokens), expected.len())?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn decompress_with_runs() -> Result<(), CompactProError> {
+        let tokens = vec![
+            Token::Literal(b'a'),
+            Token::Literal(0x81),
+            Token::Literal(0x82),
+            Token::Literal(0x05),
+            Token::Match(4, 8),
+        ];
+
+        assert_eq!(vec![b'a'; 15], decompress(&compress(&tokens), 15)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn decompress_corrupt() {
+        let tokens = vec![Token::Literal(b'a'), Token::Match(2, 4)];
+
+        assert_eq!(
+            Err(CompactProError::CorruptData),
+            decompress(&compress(&tokens), 5)
+        );
+
+        assert_eq!(
+            Err(CompactProError::CorruptData),
+            decompress(&compress(&[Token::Literal(b'a')]), 2)
+        );
+    }
+}
@@ -0,0 +1,20 @@
+//! Tools for reading Compact Pro archives.
+//!
+//! Compact Pro was StuffIt's main competitor on the classic Mac OS. A Compact Pro archive begins
+//! with a short header that points to the archive's directory, which usually appears at the end of
+//! the archive. The directory lists every file and folder in the archive; folders record the total
+//! number of entries they contain (including the contents of nested folders), and files record
+//! their Finder metadata and the location of their forks.
+//!
+//! Each fork is compressed with a run-length encoding scheme and, optionally, with an LZ77 variant
+//! whose output is encoded with per-block static Huffman codes ("LZH"). A single CRC-32 (without
+//! the usual final inversion) covers both of a file's uncompressed forks. Encrypted files and
+//! archives that span multiple volumes can't be read.
+
+mod archive;
+mod entry;
+mod lzh;
+mod rle;
+
+pub use archive::{CompactProArchive, CompactProError};
+pub use entry::CompactProEntry;
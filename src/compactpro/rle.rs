@@ -0,0 +1,138 @@
+//! Decompression for Compact Pro's run-length encoding scheme.
+//!
+//! Runs are encoded as the byte to be repeated, followed by the escape sequence `0x81 0x82` and a
+//! count that includes the byte that preceded the escape sequence. A count of zero represents a
+//! literal `0x81 0x82` pair.
+
+use super::archive::CompactProError;
+use crate::util::output_capacity;
+
+const ESCAPE: u8 = 0x81;
+const RUN: u8 = 0x82;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum State {
+    Normal,
+    Escape,
+    Run,
+}
+
+/// Expands a run-length encoded stream one byte at a time, stopping once a given number of bytes
+/// have been produced.
+pub(super) struct RleExpander {
+    output: Vec<u8>,
+    len: usize,
+    state: State,
+    previous: u8,
+}
+
+impl RleExpander {
+    /// Creates a new expander that will produce exactly `len` bytes from (at most) `compressed_len`
+    /// bytes of compressed input.
+    pub fn new(len: usize, compressed_len: usize) -> Self {
+        RleExpander {
+            output: Vec::with_capacity(output_capacity(len, compressed_len)),
+            len,
+            state: State::Normal,
+            previous: 0,
+        }
+    }
+
+    /// Returns `true` if this expander has produced all of the bytes it was expected to produce.
+    pub fn is_full(&self) -> bool {
+        self.output.len() >= self.len
+    }
+
+    /// Expands the next byte of the encoded stream.
+    pub fn push(&mut self, byte: u8) {
+        match (self.state, byte) {
+            (State::Normal, ESCAPE) => self.state = State::Escape,
+            (State::Normal, _) => self.emit(byte),
+            (State::Escape, RUN) => self.state = State::Run,
+            (State::Escape, ESCAPE) => {
+                // The first escape byte was a literal, but the second may still start a sequence
+                self.emit(ESCAPE);
+            }
+            (State::Escape, _) => {
+                self.emit(ESCAPE);
+                self.emit(byte);
+                self.state = State::Normal;
+            }
+            (State::Run, 0) => {
+                self.emit(ESCAPE);
+                self.emit(RUN);
+                self.state = State::Normal;
+            }
+            (State::Run, count) => {
+                for _ in 1..count {
+                    self.emit(self.previous);
+                }
+
+                self.state = State::Normal;
+            }
+        }
+    }
+
+    fn emit(&mut self, byte: u8) {
+        if !self.is_full() {
+            self.output.push(byte);
+        }
+
+        self.previous = byte;
+    }
+
+    /// Returns the expanded bytes, or an error if the encoded stream ended before all of the
+    /// expected bytes were produced.
+    pub fn finish(self) -> Result<Vec<u8>, CompactProError> {
+        if self.is_full() {
+            Ok(self.output)
+        } else {
+            Err(CompactProError::CorruptData)
+        }
+    }
+}
+
+/// Expands the given run-length encoded fork, which should expand to exactly `len` bytes.
+pub(super) fn expand(compressed: &[u8], len: usize) -> Result<Vec<u8>, CompactProError> {
+    let mut expander = RleExpander::new(len, compressed.len());
+
+    for &byte in compressed {
+        if expander.is_full() {
+            break;
+        }
+
+        expander.push(byte);
+    }
+
+    expander.finish()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn expand_runs() -> Result<(), CompactProError> {
+        assert_eq!(b"abbbbbc".to_vec(), expand(b"ab\x81\x82\x05c", 7)?);
+        assert_eq!(b"a\x81\x82b".to_vec(), expand(b"a\x81\x82\x00b", 4)?);
+        assert_eq!(b"\x81x".to_vec(), expand(b"\x81x", 2)?);
+        assert_eq!(b"\x81\x81\x81".to_vec(), expand(b"\x81\x81\x82\x03", 3)?);
+        assert_eq!(b"zz".to_vec(), expand(b"z\x81\x82\x07", 2)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn expand_truncated() {
+        assert_eq!(
+            Err(CompactProError::CorruptData),
+            expand(b"ab\x81\x82\x05c", 8)
+        );
+
+        // An implausibly large expected length
+        assert_eq!(
+            Err(CompactProError::CorruptData),
+            expand(b"ab\x81\x82\x05c", usize::MAX)
+        );
+    }
+}
@@ -0,0 +1,1048 @@
+//! Tools for identifying the format of a file whose name or extension can't be trusted.
+//!
+//! [`detect`] inspects the beginning of a seekable source (and, where a format's header describes
+//! the file's overall layout, checks that layout against the source's length) and returns the most
+//! likely [`Format`] along with a [`Confidence`] level. [`detect_prefix`] performs the same checks
+//! on a byte prefix alone, which is useful when only the beginning of a file is available, but
+//! generally produces less confident results.
+//!
+//! Formats that this crate can read may then be opened with [`Detection::open`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::fs::File;
+//! use clarus::detect::{self, OpenError, Opened};
+//!
+//! fn main() -> Result<(), OpenError> {
+//!     let mut file = File::open("mystery.bin")?;
+//!
+//!     if let Some(detection) = detect::detect(&mut file)? {
+//!         println!("Looks like {:?} ({:?} confidence)", detection.format(), detection.confidence());
+//!
+//!         if let Opened::MacBinary(archive) = detection.open(file)? {
+//!             println!("Contains {}", archive.filename());
+//!         }
+//!     }
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use std::convert::TryFrom;
+use std::convert::TryInto;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+
+use crate::applesingle::{AppleSingleArchive, AppleSingleError};
+use crate::binhex::{self, AnyBinHexArchive, BinHexError, BinHexFormat};
+use crate::compactpro::{CompactProArchive, CompactProError};
+use crate::diskcopy::{DartImage, DiskCopyError, DiskCopyImage};
+use crate::hfs::{HfsError, HfsVolume};
+use crate::macbinary::{MacBinaryArchive, MacBinaryError, MacBinaryHeader, MacBinaryVersion};
+use crate::mfs::{MfsError, MfsVolume};
+use crate::partition::{PartitionError, PartitionMap};
+use crate::rsrc::{ResourceError, ResourceFork};
+use crate::stuffit::{self, StuffIt5Archive, StuffItArchive, StuffItError};
+use crate::util::{get_u16_at, get_u32_at};
+
+/// The number of bytes read from the start of a source for detection. BinHex archives may be
+/// preceded by a fair amount of prose (or mail headers), and HFS volume headers don't begin until
+/// 1024 bytes into a volume.
+const PREFIX_LENGTH: usize = 16384;
+
+/// The length, in bytes, of a MacBinary header.
+const MACBINARY_HEADER_LENGTH: usize = 128;
+
+/// The offset of the master directory block (or volume header) within an HFS volume.
+const HFS_MDB_OFFSET: usize = 1024;
+
+/// The length, in bytes, of a DiskCopy 4.2 header.
+const DISKCOPY_HEADER_LENGTH: u64 = 84;
+
+/// A file format that can be identified by [`detect`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Format {
+    /// A BinHex archive in the given format.
+    BinHex(BinHexFormat),
+
+    /// A MacBinary archive in the given version of the format.
+    MacBinary(MacBinaryVersion),
+
+    /// An AppleSingle file.
+    AppleSingle,
+
+    /// An AppleDouble header file.
+    AppleDouble,
+
+    /// A raw resource fork.
+    ResourceFork,
+
+    /// A StuffIt archive written by StuffIt 1.5 through 4.5.
+    StuffIt,
+
+    /// A StuffIt archive written by StuffIt 5 or later.
+    StuffIt5,
+
+    /// A Compact Pro archive.
+    CompactPro,
+
+    /// A DiskCopy 4.2 disk image.
+    DiskCopy,
+
+    /// A DART disk image.
+    Dart,
+
+    /// A raw HFS volume.
+    Hfs,
+
+    /// A raw HFS+ volume, or an HFS wrapper volume with an embedded HFS+ volume.
+    HfsPlus,
+
+    /// A raw MFS volume.
+    Mfs,
+
+    /// A disk or CD-ROM image with an Apple Partition Map.
+    PartitionMap,
+}
+
+/// How confident [`detect`] is that a source is in the detected format.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Confidence {
+    /// The source is consistent with the format, but the format has no distinctive signature and
+    /// the source's structure couldn't be verified.
+    Low,
+
+    /// The source has the format's signature or a plausible header, but its structure couldn't be
+    /// fully verified.
+    Medium,
+
+    /// The source has the format's signature and its structure was verified (for example, a
+    /// header checksum matched or the header's lengths agreed with the source's length).
+    High,
+}
+
+/// The result of identifying the format of a source.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Detection {
+    format: Format,
+    confidence: Confidence,
+}
+
+impl Detection {
+    fn new(format: Format, confidence: Confidence) -> Self {
+        Detection { format, confidence }
+    }
+
+    /// Returns the detected format.
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    /// Returns how confident the detection is.
+    pub fn confidence(&self) -> Confidence {
+        self.confidence
+    }
+
+    /// Opens the given source (which should be positioned where it was when the format was
+    /// detected) with the reader for the detected format.
+    ///
+    /// # Errors
+    ///
+    /// This method returns any error produced by the detected format's reader.
+    pub fn open<R: Read + Seek>(&self, source: R) -> Result<Opened<R>, OpenError> {
+        match self.format {
+            Format::BinHex(_) => Ok(Opened::BinHex(AnyBinHexArchive::new(source)?)),
+            Format::MacBinary(_) => Ok(Opened::MacBinary(MacBinaryArchive::new(source)?)),
+            Format::AppleSingle | Format::AppleDouble => {
+                Ok(Opened::AppleSingle(AppleSingleArchive::new(source)?))
+            }
+            Format::ResourceFork => Ok(Opened::ResourceFork(ResourceFork::new(source)?)),
+            Format::StuffIt => Ok(Opened::StuffIt(StuffItArchive::new(source)?)),
+            Format::StuffIt5 => Ok(Opened::StuffIt5(StuffIt5Archive::new(source)?)),
+            Format::CompactPro => Ok(Opened::CompactPro(CompactProArchive::new(source)?)),
+            Format::DiskCopy => Ok(Opened::DiskCopy(DiskCopyImage::new(source)?)),
+            Format::Dart => Ok(Opened::Dart(DartImage::new(source)?)),
+            Format::Hfs | Format::HfsPlus => Ok(Opened::Hfs(HfsVolume::new(source)?)),
+            Format::Mfs => Ok(Opened::Mfs(MfsVolume::new(source)?)),
+            Format::PartitionMap => Ok(Opened::PartitionMap(PartitionMap::new(source)?)),
+        }
+    }
+}
+
+/// A source opened by [`Detection::open`].
+pub enum Opened<R: Read + Seek> {
+    /// A BinHex archive in any format.
+    BinHex(AnyBinHexArchive<R>),
+
+    /// A MacBinary archive.
+    MacBinary(MacBinaryArchive<R>),
+
+    /// An AppleSingle file or AppleDouble header file.
+    AppleSingle(AppleSingleArchive<R>),
+
+    /// A raw resource fork.
+    ResourceFork(ResourceFork<R>),
+
+    /// A classic StuffIt archive.
+    StuffIt(StuffItArchive<R>),
+
+    /// A StuffIt 5 archive.
+    StuffIt5(StuffIt5Archive<R>),
+
+    /// A Compact Pro archive.
+    CompactPro(CompactProArchive<R>),
+
+    /// A DiskCopy 4.2 disk image.
+    DiskCopy(DiskCopyImage<R>),
+
+    /// A DART disk image.
+    Dart(DartImage),
+
+    /// An HFS or HFS+ volume.
+    Hfs(HfsVolume<R>),
+
+    /// An MFS volume.
+    Mfs(MfsVolume<R>),
+
+    /// A disk or CD-ROM image with an Apple Partition Map.
+    PartitionMap(PartitionMap<R>),
+}
+
+/// Identifies the format of the given source.
+///
+/// Up to 16 KiB are read from the source's current position, and the source's length is checked
+/// against any lengths found in the detected format's header. Sources that look like raw resource
+/// forks are validated by loading their resource map. The source is returned to its original
+/// position before this function returns.
+///
+/// Returns `None` if the source doesn't match any known format.
+///
+/// # Errors
+///
+/// This function will return an error if an IO error occurs while reading from or seeking within
+/// the source.
+pub fn detect<R: Read + Seek>(source: &mut R) -> io::Result<Option<Detection>> {
+    let start = source.stream_position()?;
+    let len = source.seek(SeekFrom::End(0))? - start;
+    source.seek(SeekFrom::Start(start))?;
+
+    let mut prefix = Vec::with_capacity(PREFIX_LENGTH);
+    source
+        .by_ref()
+        .take(PREFIX_LENGTH as u64)
+        .read_to_end(&mut prefix)?;
+
+    let mut candidates = sniff(&prefix, Some(len));
+
+    if is_plausible_resource_fork(&prefix, Some(len)) {
+        source.seek(SeekFrom::Start(start))?;
+
+        if ResourceFork::new(&mut *source).is_ok() {
+            candidates.push(Detection::new(Format::ResourceFork, Confidence::High));
+        }
+    }
+
+    source.seek(SeekFrom::Start(start))?;
+
+    Ok(best(candidates))
+}
+
+/// Identifies the format of a file from a prefix of its content.
+///
+/// Because the file's overall length is unknown, formats that are only distinguished by lengths in
+/// their headers are reported with lower confidence than [`detect`] would report for the complete
+/// file.
+///
+/// Returns `None` if the prefix doesn't match any known format.
+pub fn detect_prefix(prefix: &[u8]) -> Option<Detection> {
+    let mut candidates = sniff(prefix, None);
+
+    if is_plausible_resource_fork(prefix, None) {
+        // The prefix may not include the resource map, in which case the header is all there is
+        // to go on
+        let confidence = match ResourceFork::new(Cursor::new(prefix)) {
+            Ok(_) => Confidence::High,
+            Err(_) => Confidence::Low,
+        };
+
+        candidates.push(Detection::new(Format::ResourceFork, confidence));
+    }
+
+    best(candidates)
+}
+
+/// Returns the most confident of the given candidates, preferring earlier candidates in case of a
+/// tie.
+fn best(candidates: Vec<Detection>) -> Option<Detection> {
+    candidates
+        .into_iter()
+        .fold(None, |best, candidate| match best {
+            Some(best) if best.confidence >= candidate.confidence => Some(best),
+            _ => Some(candidate),
+        })
+}
+
+/// Returns every format (except raw resource forks) that the given prefix could plausibly
+/// represent, in order of precedence.
+fn sniff(prefix: &[u8], len: Option<u64>) -> Vec<Detection> {
+    let mut candidates = vec![];
+
+    if let Some(magic) = prefix.get(0..8) {
+        match magic {
+            [0x00, 0x05, 0x16, 0x00, 0x00, 0x01 | 0x02, 0x00, 0x00] => {
+                candidates.push(Detection::new(Format::AppleSingle, Confidence::High))
+            }
+            [0x00, 0x05, 0x16, 0x07, 0x00, 0x01 | 0x02, 0x00, 0x00] => {
+                candidates.push(Detection::new(Format::AppleDouble, Confidence::High))
+            }
+            _ => {}
+        }
+    }
+
+    if prefix.starts_with(stuffit::STUFFIT5_SIGNATURE) {
+        candidates.push(Detection::new(Format::StuffIt5, Confidence::High));
+    }
+
+    if let Some(confidence) = sniff_stuffit(prefix) {
+        candidates.push(Detection::new(Format::StuffIt, confidence));
+    }
+
+    if let Some(confidence) = sniff_diskcopy(prefix, len) {
+        candidates.push(Detection::new(Format::DiskCopy, confidence));
+    }
+
+    if let Some(confidence) = sniff_dart(prefix, len) {
+        candidates.push(Detection::new(Format::Dart, confidence));
+    }
+
+    if let Some((version, confidence)) = sniff_macbinary(prefix, len) {
+        candidates.push(Detection::new(Format::MacBinary(version), confidence));
+    }
+
+    if let Some((format, confidence)) = sniff_hfs(prefix) {
+        candidates.push(Detection::new(format, confidence));
+    }
+
+    if let Some(confidence) = sniff_mfs(prefix) {
+        candidates.push(Detection::new(Format::Mfs, confidence));
+    }
+
+    if let Some(confidence) = sniff_partition_map(prefix) {
+        candidates.push(Detection::new(Format::PartitionMap, confidence));
+    }
+
+    if let Some(format) = binhex::sniff_format(prefix) {
+        candidates.push(Detection::new(Format::BinHex(format), Confidence::High));
+    }
+
+    if let Some(confidence) = sniff_compact_pro(prefix, len) {
+        candidates.push(Detection::new(Format::CompactPro, confidence));
+    }
+
+    candidates
+}
+
+/// Classic StuffIt archives begin with one of several signatures, followed by a file count, the
+/// archive's length, and a second signature (`rLau`).
+fn sniff_stuffit(prefix: &[u8]) -> Option<Confidence> {
+    let signature = prefix.get(0..4)?;
+
+    if !stuffit::SIGNATURES.iter().any(|s| s[..] == *signature) {
+        return None;
+    }
+
+    match prefix.get(10..14) {
+        Some(second_signature) if second_signature == stuffit::SECOND_SIGNATURE => {
+            Some(Confidence::High)
+        }
+        _ => None,
+    }
+}
+
+/// DiskCopy 4.2 images begin with a Pascal string naming the disk, followed by the lengths of the
+/// image's data and tag sections and a "private" field that's always 0x0100.
+fn sniff_diskcopy(prefix: &[u8], len: Option<u64>) -> Option<Confidence> {
+    let name_length = *prefix.first()?;
+
+    if name_length > 63 || get_u16_at(prefix, 0x52)? != 0x0100 || prefix[0x50] > 3 {
+        return None;
+    }
+
+    let data_length = get_u32_at(prefix, 0x40)? as u64;
+    let tag_length = get_u32_at(prefix, 0x44)? as u64;
+
+    if data_length == 0 || data_length % 512 != 0 {
+        return None;
+    }
+
+    match len {
+        Some(len) if len == DISKCOPY_HEADER_LENGTH + data_length + tag_length => {
+            Some(Confidence::High)
+        }
+        Some(_) => None,
+        None => Some(Confidence::Medium),
+    }
+}
+
+/// DART images begin with a compression method, a disk type, and a disk size (in kilobytes) that
+/// must agree with the disk type, followed by a table of compressed chunk lengths from which the
+/// image's total length can be calculated.
+fn sniff_dart(prefix: &[u8], len: Option<u64>) -> Option<Confidence> {
+    let (method, disk_type, disk_kb) = (*prefix.first()?, *prefix.get(1)?, get_u16_at(prefix, 2)?);
+
+    if method > 2
+        || !matches!(
+            (disk_type, disk_kb),
+            (1, 400) | (2, 400) | (2, 800) | (3, 800) | (16, 1440) | (17, 720) | (18, 1440)
+        )
+    {
+        return None;
+    }
+
+    let table_len = if disk_kb > 800 { 72 } else { 40 };
+    let chunk_count = (disk_kb as usize + 19) / 20;
+    let mut expected_len = 4 + table_len as u64 * 2;
+
+    for i in 0..chunk_count {
+        expected_len += match (method, get_u16_at(prefix, 4 + i * 2)?) {
+            (_, 0xffff) => 20960,
+            (_, 0) | (2, _) => return None,
+            (0, words) => words as u64 * 2,
+            (_, bytes) => bytes as u64,
+        };
+    }
+
+    match len {
+        Some(len) if len == expected_len => Some(Confidence::High),
+        Some(_) => None,
+        None => Some(Confidence::Low),
+    }
+}
+
+/// MacBinary II and III headers include a checksum; MacBinary I headers are only identified by a
+/// handful of zero bytes and plausible lengths, and so are reported with less confidence.
+fn sniff_macbinary(prefix: &[u8], len: Option<u64>) -> Option<(MacBinaryVersion, Confidence)> {
+    let header_bytes: &[u8; MACBINARY_HEADER_LENGTH] =
+        prefix.get(..MACBINARY_HEADER_LENGTH)?.try_into().unwrap();
+    let header = MacBinaryHeader::try_from(header_bytes).ok()?;
+
+    let padded_len =
+        |len: usize| ((len + MACBINARY_HEADER_LENGTH - 1) / MACBINARY_HEADER_LENGTH) as u64 * 128;
+    let expected_len = MACBINARY_HEADER_LENGTH as u64
+        + padded_len(header.secondary_header_len())
+        + padded_len(header.data_fork_len())
+        + header.resource_fork_len() as u64;
+
+    // Some writers don't pad the last fork, and comments may follow the resource fork
+    let plausible_len = len.map(|len| len >= expected_len);
+
+    let confidence = match (header.version(), plausible_len) {
+        (_, Some(false)) => return None,
+        (MacBinaryVersion::MacBinaryI, Some(true)) => Confidence::Medium,
+        (MacBinaryVersion::MacBinaryI, None) => Confidence::Low,
+        _ => Confidence::High,
+    };
+
+    Some((header.version(), confidence))
+}
+
+/// HFS volumes have a master directory block 1024 bytes into the volume, which starts with the
+/// signature `BD` and includes the volume's allocation block size; an HFS wrapper volume's master
+/// directory block also includes the signature (`H+`) of its embedded HFS+ volume. HFS+ volumes
+/// have a volume header in the same place, which starts with the signature `H+` (or `HX` for
+/// HFSX) and a version number.
+fn sniff_hfs(prefix: &[u8]) -> Option<(Format, Confidence)> {
+    match get_u16_at(prefix, HFS_MDB_OFFSET)? {
+        0x4244 => {
+            let block_size = get_u32_at(prefix, HFS_MDB_OFFSET + 20)?;
+
+            let format = if get_u16_at(prefix, HFS_MDB_OFFSET + 124) == Some(0x482b) {
+                Format::HfsPlus
+            } else {
+                Format::Hfs
+            };
+
+            if block_size != 0 && block_size % 512 == 0 {
+                Some((format, Confidence::High))
+            } else {
+                Some((format, Confidence::Medium))
+            }
+        }
+        signature @ (0x482b | 0x4858) => {
+            let version = get_u16_at(prefix, HFS_MDB_OFFSET + 2)?;
+            let block_size = get_u32_at(prefix, HFS_MDB_OFFSET + 40)?;
+
+            if (signature, version) != (0x482b, 4) && (signature, version) != (0x4858, 5) {
+                Some((Format::HfsPlus, Confidence::Low))
+            } else if block_size >= 512 && block_size.is_power_of_two() {
+                Some((Format::HfsPlus, Confidence::High))
+            } else {
+                Some((Format::HfsPlus, Confidence::Medium))
+            }
+        }
+        _ => None,
+    }
+}
+
+/// MFS volumes have a master directory block in the same place as HFS volumes, which starts with
+/// the signature `0xd2d7` and includes the volume's allocation block size.
+fn sniff_mfs(prefix: &[u8]) -> Option<Confidence> {
+    if get_u16_at(prefix, HFS_MDB_OFFSET)? != 0xd2d7 {
+        return None;
+    }
+
+    let block_size = get_u32_at(prefix, HFS_MDB_OFFSET + 20)?;
+
+    if block_size != 0 && block_size % 512 == 0 {
+        Some(Confidence::High)
+    } else {
+        Some(Confidence::Medium)
+    }
+}
+
+/// Devices with an Apple Partition Map begin with a driver descriptor map, whose signature is only
+/// two bytes long; the first partition map entry follows in the next block (or, on some CD-ROMs,
+/// the next 512-byte sector).
+fn sniff_partition_map(prefix: &[u8]) -> Option<Confidence> {
+    if get_u16_at(prefix, 0)? != 0x4552 {
+        return None;
+    }
+
+    let block_size = get_u16_at(prefix, 2)? as usize;
+
+    if block_size == 0 || block_size % 512 != 0 {
+        return None;
+    }
+
+    match (get_u16_at(prefix, block_size), get_u16_at(prefix, 512)) {
+        (Some(0x504d), _) | (_, Some(0x504d)) => Some(Confidence::High),
+        (None, _) => Some(Confidence::Medium),
+        _ => None,
+    }
+}
+
+/// Compact Pro archives begin with the byte 0x01 and a volume number, followed by the offset of
+/// the archive's directory; there's no other signature, and so Compact Pro archives are only ever
+/// detected with low confidence.
+fn sniff_compact_pro(prefix: &[u8], len: Option<u64>) -> Option<Confidence> {
+    if *prefix.first()? != 0x01 {
+        return None;
+    }
+
+    let volume = *prefix.get(1)?;
+    let directory_offset = get_u32_at(prefix, 4)? as u64;
+
+    if volume == 0 || directory_offset < 8 || len.map_or(false, |len| directory_offset >= len) {
+        return None;
+    }
+
+    Some(Confidence::Low)
+}
+
+/// Resource forks begin with the offsets and lengths of their data and map sections; the map must
+/// be at least 30 bytes long and the sections must fit within the fork.
+fn is_plausible_resource_fork(prefix: &[u8], len: Option<u64>) -> bool {
+    let (data_offset, map_offset, data_length, map_length) = match (
+        get_u32_at(prefix, 0),
+        get_u32_at(prefix, 4),
+        get_u32_at(prefix, 8),
+        get_u32_at(prefix, 12),
+    ) {
+        (Some(data_offset), Some(map_offset), Some(data_length), Some(map_length)) => (
+            data_offset as u64,
+            map_offset as u64,
+            data_length as u64,
+            map_length as u64,
+        ),
+        _ => return false,
+    };
+
+    let data_end = data_offset + data_length;
+    let map_end = map_offset + map_length;
+
+    data_offset >= 16
+        && map_length >= 30
+        && (data_end <= map_offset || map_end <= data_offset)
+        && len.map_or(true, |len| data_end <= len && map_end <= len)
+}
+
+/// The error type for [`Detection::open`].
+#[derive(Debug)]
+pub enum OpenError {
+    /// An [`std::io::Error`] occurred while reading from the source.
+    ///
+    /// The kind of IO error is included.
+    IoError(io::ErrorKind),
+
+    /// The source could not be opened as a BinHex archive.
+    BinHex(BinHexError),
+
+    /// The source could not be opened as a MacBinary archive.
+    MacBinary(MacBinaryError),
+
+    /// The source could not be opened as an AppleSingle or AppleDouble file.
+    AppleSingle(AppleSingleError),
+
+    /// The source could not be opened as a resource fork.
+    ResourceFork(ResourceError),
+
+    /// The source could not be opened as a classic StuffIt or StuffIt 5 archive.
+    StuffIt(StuffItError),
+
+    /// The source could not be opened as a Compact Pro archive.
+    CompactPro(CompactProError),
+
+    /// The source could not be opened as a DiskCopy 4.2 or DART image.
+    DiskCopy(DiskCopyError),
+
+    /// The source could not be opened as an HFS or HFS+ volume.
+    Hfs(HfsError),
+
+    /// The source could not be opened as an MFS volume.
+    Mfs(MfsError),
+
+    /// The source's Apple Partition Map could not be read.
+    PartitionMap(PartitionError),
+}
+
+impl Display for OpenError {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            OpenError::IoError(kind) => write!(fmt, "IO error: {:?}", kind),
+            OpenError::BinHex(error) => write!(fmt, "BinHex error: {}", error),
+            OpenError::MacBinary(error) => write!(fmt, "MacBinary error: {}", error),
+            OpenError::AppleSingle(error) => write!(fmt, "AppleSingle error: {}", error),
+            OpenError::ResourceFork(error) => write!(fmt, "Resource fork error: {:?}", error),
+            OpenError::StuffIt(error) => write!(fmt, "StuffIt error: {}", error),
+            OpenError::CompactPro(error) => write!(fmt, "Compact Pro error: {}", error),
+            OpenError::DiskCopy(error) => write!(fmt, "DiskCopy error: {}", error),
+            OpenError::Hfs(error) => write!(fmt, "HFS error: {}", error),
+            OpenError::Mfs(error) => write!(fmt, "MFS error: {}", error),
+            OpenError::PartitionMap(error) => write!(fmt, "Partition map error: {}", error),
+        }
+    }
+}
+
+impl From<io::Error> for OpenError {
+    fn from(error: io::Error) -> Self {
+        OpenError::IoError(error.kind())
+    }
+}
+
+impl From<BinHexError> for OpenError {
+    fn from(error: BinHexError) -> Self {
+        OpenError::BinHex(error)
+    }
+}
+
+impl From<MacBinaryError> for OpenError {
+    fn from(error: MacBinaryError) -> Self {
+        OpenError::MacBinary(error)
+    }
+}
+
+impl From<AppleSingleError> for OpenError {
+    fn from(error: AppleSingleError) -> Self {
+        OpenError::AppleSingle(error)
+    }
+}
+
+impl From<ResourceError> for OpenError {
+    fn from(error: ResourceError) -> Self {
+        OpenError::ResourceFork(error)
+    }
+}
+
+impl From<StuffItError> for OpenError {
+    fn from(error: StuffItError) -> Self {
+        OpenError::StuffIt(error)
+    }
+}
+
+impl From<CompactProError> for OpenError {
+    fn from(error: CompactProError) -> Self {
+        OpenError::CompactPro(error)
+    }
+}
+
+impl From<DiskCopyError> for OpenError {
+    fn from(error: DiskCopyError) -> Self {
+        OpenError::DiskCopy(error)
+    }
+}
+
+impl From<HfsError> for OpenError {
+    fn from(error: HfsError) -> Self {
+        OpenError::Hfs(error)
+    }
+}
+
+impl From<MfsError> for OpenError {
+    fn from(error: MfsError) -> Self {
+        OpenError::Mfs(error)
+    }
+}
+
+impl From<PartitionError> for OpenError {
+    fn from(error: PartitionError) -> Self {
+        OpenError::PartitionMap(error)
+    }
+}
+
+impl error::Error for OpenError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::applesingle::{AppleSingleKind, AppleSingleVersion, AppleSingleWriter};
+    use crate::binhex::BinHexWriter;
+    use crate::macbinary::MacBinaryWriter;
+    use indoc::indoc;
+
+    const DATA_FORK: &[u8] = b"===== Hello from the data fork! =====";
+    const RESOURCE_FORK: &[u8] = include_bytes!("../rsrc/string-table.rsrc");
+
+    fn detect_bytes(bytes: &[u8]) -> Option<Detection> {
+        let mut cursor = Cursor::new(bytes);
+
+        let detection = detect(&mut cursor).unwrap();
+
+        // The source should always be returned to its original position
+        assert_eq!(0, cursor.position());
+
+        detection
+    }
+
+    #[test]
+    fn binhex() {
+        let mut encoded = b"Here's the file you asked for!\r\n\r\n".to_vec();
+
+        encoded = BinHexWriter::new(encoded, "Example", *b"TEXT", *b"ttxt", 0)
+            .unwrap()
+            .encode(
+                &mut Cursor::new(DATA_FORK),
+                DATA_FORK.len(),
+                &mut io::empty(),
+                0,
+            )
+            .unwrap();
+
+        let detection = detect_bytes(&encoded).unwrap();
+
+        assert_eq!(Format::BinHex(BinHexFormat::BinHex4), detection.format());
+        assert_eq!(Confidence::High, detection.confidence());
+
+        match detection.open(Cursor::new(&encoded)).unwrap() {
+            Opened::BinHex(archive) => assert_eq!("Example", archive.header().filename()),
+            _ => panic!("Expected a BinHex archive"),
+        }
+
+        let legacy = indoc! {"
+            (This file must be converted with BinHex 2.0)
+            ***RESOURCE
+        "};
+
+        assert_eq!(
+            Some(Format::BinHex(BinHexFormat::Hex)),
+            detect_prefix(legacy.as_bytes()).map(|detection| detection.format())
+        );
+    }
+
+    #[test]
+    fn macbinary() {
+        let encoded = MacBinaryWriter::new(vec![], "Example", *b"TEXT", *b"ttxt", 0)
+            .unwrap()
+            .encode(
+                &mut Cursor::new(DATA_FORK),
+                DATA_FORK.len(),
+                &mut Cursor::new(RESOURCE_FORK),
+                RESOURCE_FORK.len(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            Some(Detection::new(
+                Format::MacBinary(MacBinaryVersion::MacBinaryIII),
+                Confidence::High
+            )),
+            detect_bytes(&encoded)
+        );
+
+        match detect_bytes(&encoded)
+            .unwrap()
+            .open(Cursor::new(&encoded))
+            .unwrap()
+        {
+            Opened::MacBinary(archive) => assert_eq!("Example", archive.filename()),
+            _ => panic!("Expected a MacBinary archive"),
+        }
+
+        // A truncated archive is no longer a plausible MacBinary archive
+        assert_eq!(None, detect_bytes(&encoded[..200]));
+    }
+
+    #[test]
+    fn apple_single() {
+        for (kind, format) in &[
+            (AppleSingleKind::AppleSingle, Format::AppleSingle),
+            (AppleSingleKind::AppleDouble, Format::AppleDouble),
+        ] {
+            let encoded = AppleSingleWriter::new(vec![], *kind, AppleSingleVersion::V2)
+                .encode(
+                    &mut io::empty(),
+                    0,
+                    &mut Cursor::new(RESOURCE_FORK),
+                    RESOURCE_FORK.len(),
+                )
+                .unwrap();
+
+            assert_eq!(
+                Some(Detection::new(*format, Confidence::High)),
+                detect_bytes(&encoded)
+            );
+
+            assert!(matches!(
+                detect_bytes(&encoded)
+                    .unwrap()
+                    .open(Cursor::new(&encoded))
+                    .unwrap(),
+                Opened::AppleSingle(_)
+            ));
+        }
+    }
+
+    #[test]
+    fn resource_fork() {
+        assert_eq!(
+            Some(Detection::new(Format::ResourceFork, Confidence::High)),
+            detect_bytes(RESOURCE_FORK)
+        );
+
+        assert_eq!(
+            Some(Detection::new(Format::ResourceFork, Confidence::High)),
+            detect_prefix(RESOURCE_FORK)
+        );
+
+        assert_eq!(
+            Some(Detection::new(Format::ResourceFork, Confidence::Low)),
+            detect_prefix(&RESOURCE_FORK[..64])
+        );
+
+        // A resource map whose type list lies outside of the map should fail validation
+        let mut corrupt = RESOURCE_FORK.to_vec();
+        corrupt[0x12a..0x12c].copy_from_slice(&[0x00, 0xff]);
+
+        assert_ne!(
+            Some(Format::ResourceFork),
+            detect_bytes(&corrupt).map(|detection| detection.format())
+        );
+    }
+
+    #[test]
+    fn stuffit() {
+        let mut archive = b"SIT!\x00\x01\x00\x00\x00\x16rLau".to_vec();
+        archive.resize(22, 0);
+
+        assert_eq!(
+            Some(Detection::new(Format::StuffIt, Confidence::High)),
+            detect_bytes(&archive)
+        );
+
+        match detect_bytes(&archive).unwrap().open(Cursor::new(&archive)) {
+            Ok(Opened::StuffIt(archive)) => assert!(archive.entries().is_empty()),
+            _ => panic!("Expected a StuffIt archive"),
+        }
+
+        let mut archive = stuffit::STUFFIT5_SIGNATURE.to_vec();
+        archive.extend_from_slice(b"2001 Aladdin Systems, Inc.");
+        archive.resize(100, 0);
+        archive[84..88].copy_from_slice(&100u32.to_be_bytes());
+        archive[94..98].copy_from_slice(&100u32.to_be_bytes());
+
+        assert_eq!(
+            Some(Detection::new(Format::StuffIt5, Confidence::High)),
+            detect_bytes(&archive)
+        );
+
+        match detect_bytes(&archive).unwrap().open(Cursor::new(&archive)) {
+            Ok(Opened::StuffIt5(archive)) => assert!(archive.entries().is_empty()),
+            _ => panic!("Expected a StuffIt 5 archive"),
+        }
+    }
+
+    #[test]
+    fn diskcopy() {
+        let mut image = vec![0; 84 + 819_200];
+        image[0] = 5;
+        image[1..6].copy_from_slice(b"Disk1");
+        image[0x40..0x44].copy_from_slice(&819_200u32.to_be_bytes());
+        image[0x50] = 1;
+        image[0x51] = 0x22;
+        image[0x52..0x54].copy_from_slice(&[0x01, 0x00]);
+
+        assert_eq!(
+            Some(Detection::new(Format::DiskCopy, Confidence::High)),
+            detect_bytes(&image)
+        );
+
+        match detect_bytes(&image).unwrap().open(Cursor::new(&image)) {
+            Ok(Opened::DiskCopy(image)) => assert_eq!("Disk1", image.header().name()),
+            _ => panic!("Expected a DiskCopy image"),
+        }
+
+        assert_eq!(
+            Some(Detection::new(Format::DiskCopy, Confidence::Medium)),
+            detect_prefix(&image[..1024])
+        );
+
+        assert_ne!(
+            Some(Format::DiskCopy),
+            detect_bytes(&image[..1024]).map(|detection| detection.format())
+        );
+    }
+
+    #[test]
+    fn dart() {
+        let mut image = vec![2, 1, 0x01, 0x90];
+        image.extend(vec![0xff; 40]);
+        image.extend(vec![0; 40]);
+        image.extend(vec![0xaa; 20 * 20960]);
+
+        assert_eq!(
+            Some(Detection::new(Format::Dart, Confidence::High)),
+            detect_bytes(&image)
+        );
+
+        match detect_bytes(&image).unwrap().open(Cursor::new(&image)) {
+            Ok(Opened::Dart(image)) => assert_eq!(400 * 1024, image.data_len()),
+            _ => panic!("Expected a DART image"),
+        }
+
+        assert_eq!(
+            Some(Detection::new(Format::Dart, Confidence::Low)),
+            detect_prefix(&image[..1024])
+        );
+
+        assert_eq!(None, detect_bytes(&image[..1024]));
+    }
+
+    #[test]
+    fn hfs() {
+        let mut volume = vec![0; 2048];
+        volume[1024..1026].copy_from_slice(b"BD");
+        volume[1044..1048].copy_from_slice(&512u32.to_be_bytes());
+
+        assert_eq!(
+            Some(Detection::new(Format::Hfs, Confidence::High)),
+            detect_bytes(&volume)
+        );
+
+        // The volume has no catalog, and so can't be opened
+        assert!(matches!(
+            detect_bytes(&volume).unwrap().open(Cursor::new(&volume)),
+            Err(OpenError::Hfs(_))
+        ));
+
+        volume[1148..1150].copy_from_slice(b"H+");
+
+        assert_eq!(
+            Some(Detection::new(Format::HfsPlus, Confidence::High)),
+            detect_bytes(&volume)
+        );
+    }
+
+    #[test]
+    fn mfs() {
+        let mut volume = vec![0; 2048];
+        volume[1024..1026].copy_from_slice(&0xd2d7u16.to_be_bytes());
+        volume[1044..1048].copy_from_slice(&1024u32.to_be_bytes());
+
+        assert_eq!(
+            Some(Detection::new(Format::Mfs, Confidence::High)),
+            detect_bytes(&volume)
+        );
+
+        // The volume has no directory, and so opens as an empty volume
+        match detect_bytes(&volume).unwrap().open(Cursor::new(&volume)) {
+            Ok(Opened::Mfs(volume)) => assert!(volume.entries().is_empty()),
+            _ => panic!("Expected an MFS volume"),
+        }
+    }
+
+    #[test]
+    fn partition_map() {
+        let mut device = vec![0; 4096];
+        device[0..2].copy_from_slice(b"ER");
+        device[2..4].copy_from_slice(&512u16.to_be_bytes());
+
+        device[512..514].copy_from_slice(b"PM");
+        device[516..520].copy_from_slice(&1u32.to_be_bytes());
+        device[520..524].copy_from_slice(&1u32.to_be_bytes());
+        device[524..528].copy_from_slice(&7u32.to_be_bytes());
+        device[560..579].copy_from_slice(b"Apple_partition_map");
+
+        assert_eq!(
+            Some(Detection::new(Format::PartitionMap, Confidence::High)),
+            detect_bytes(&device)
+        );
+
+        match detect_bytes(&device).unwrap().open(Cursor::new(&device)) {
+            Ok(Opened::PartitionMap(map)) => assert_eq!(1, map.partitions().len()),
+            _ => panic!("Expected a partition map"),
+        }
+
+        // A driver descriptor map without a partition map
+        device[512..514].copy_from_slice(&[0, 0]);
+        assert_eq!(None, detect_bytes(&device));
+    }
+
+    #[test]
+    fn hfs_plus() {
+        let mut volume = vec![0; 2048];
+        volume[1024..1026].copy_from_slice(b"H+");
+        volume[1026..1028].copy_from_slice(&4u16.to_be_bytes());
+        volume[1064..1068].copy_from_slice(&4096u32.to_be_bytes());
+
+        assert_eq!(
+            Some(Detection::new(Format::HfsPlus, Confidence::High)),
+            detect_bytes(&volume)
+        );
+
+        volume[1024..1026].copy_from_slice(b"HX");
+
+        assert_eq!(
+            Some(Detection::new(Format::HfsPlus, Confidence::Low)),
+            detect_bytes(&volume)
+        );
+    }
+
+    #[test]
+    fn compact_pro() {
+        let mut archive = vec![0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20];
+        archive.resize(64, 0xaa);
+
+        assert_eq!(
+            Some(Detection::new(Format::CompactPro, Confidence::Low)),
+            detect_bytes(&archive)
+        );
+
+        let mut archive = vec![0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08];
+        // Compact Pro's checksum is a CRC-32 without the final inversion
+        archive.extend_from_slice(&(!crc32fast::hash(&[0; 3])).to_be_bytes());
+        archive.extend_from_slice(&[0; 3]);
+
+        match detect_bytes(&archive).unwrap().open(Cursor::new(&archive)) {
+            Ok(Opened::CompactPro(archive)) => assert!(archive.entries().is_empty()),
+            _ => panic!("Expected a Compact Pro archive"),
+        }
+    }
+
+    #[test]
+    fn unknown() {
+        assert_eq!(None, detect_bytes(b""));
+        assert_eq!(None, detect_bytes(b"Just some text"));
+        assert_eq!(None, detect_bytes(&[0xff; 4096]));
+    }
+}
@@ -0,0 +1,114 @@
+//! Decompression for Apple Data Compression (ADC), which NDIF images use for compressed chunks.
+//!
+//! ADC is a simple LZ77 variant. Each chunk of compressed data starts with a byte whose high bits
+//! identify the chunk's kind:
+//!
+//! - `1xxxxxxx`: a run of `xxxxxxx + 1` literal bytes follows
+//! - `01xxxxxx`: a match of `xxxxxx + 4` bytes, whose distance (minus one) is in the next two bytes
+//! - `00xxxxyy`: a match of `xxxx + 3` bytes, whose distance (minus one) is `yy` followed by the next
+//!   byte
+
+use super::image::DiskCopyError;
+use crate::util::output_capacity;
+
+/// Expands ADC-compressed data into exactly `len` bytes.
+pub(super) fn expand(compressed: &[u8], len: usize) -> Result<Vec<u8>, DiskCopyError> {
+    let mut expanded = Vec::with_capacity(output_capacity(len, compressed.len()));
+    let mut i = 0;
+
+    let byte_at = |i: usize| compressed.get(i).copied().ok_or(DiskCopyError::CorruptData);
+
+    while expanded.len() < len {
+        let control = byte_at(i)? as usize;
+
+        let (match_len, distance) = if control & 0x80 != 0 {
+            let literal_len = (control & 0x7f) + 1;
+
+            expanded.extend_from_slice(
+                compressed
+                    .get(i + 1..i + 1 + literal_len)
+                    .ok_or(DiskCopyError::CorruptData)?,
+            );
+
+            i += 1 + literal_len;
+            continue;
+        } else if control & 0x40 != 0 {
+            let distance = ((byte_at(i + 1)? as usize) << 8) | byte_at(i + 2)? as usize;
+            i += 3;
+
+            ((control & 0x3f) + 4, distance + 1)
+        } else {
+            let distance = ((control & 0x03) << 8) | byte_at(i + 1)? as usize;
+            i += 2;
+
+            (((control & 0x3c) >> 2) + 3, distance + 1)
+        };
+
+        let start = expanded
+            .len()
+            .checked_sub(distance)
+            .ok_or(DiskCopyError::CorruptData)?;
+
+        // Matches may overlap the bytes they produce
+        for j in 0..match_len {
+            expanded.push(expanded[start + j]);
+        }
+    }
+
+    if expanded.len() != len {
+        return Err(DiskCopyError::CorruptData);
+    }
+
+    Ok(expanded)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn expand_compressed_data() -> Result<(), DiskCopyError> {
+        // Five literal bytes
+        let mut compressed = vec![0x84, b'a', b'b', b'c', b'd', b'e'];
+
+        // A short match of five bytes at distance 5
+        compressed.extend_from_slice(&[0x08, 0x04]);
+
+        // A long match of eight bytes at distance 1 (an overlapping run)
+        compressed.extend_from_slice(&[0x44, 0x00, 0x00]);
+
+        // A single literal byte
+        compressed.extend_from_slice(&[0x80, b'!']);
+
+        assert_eq!(b"abcdeabcdeeeeeeeee!".to_vec(), expand(&compressed, 19)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn expand_corrupt_data() {
+        // A match that refers to data before the start of the output
+        assert_eq!(
+            Err(DiskCopyError::CorruptData),
+            expand(&[0x80, b'a', 0x00, 0x04], 4)
+        );
+
+        // Truncated literals
+        assert_eq!(
+            Err(DiskCopyError::CorruptData),
+            expand(&[0x84, b'a', b'b'], 5)
+        );
+
+        // Output that overruns the expected length
+        assert_eq!(
+            Err(DiskCopyError::CorruptData),
+            expand(&[0x84, b'a', b'b', b'c', b'd', b'e'], 4)
+        );
+
+        // An implausibly large expected length
+        assert_eq!(
+            Err(DiskCopyError::CorruptData),
+            expand(&[0x80, b'a'], usize::MAX)
+        );
+    }
+}
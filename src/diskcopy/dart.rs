@@ -0,0 +1,359 @@
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+
+use super::image::DiskCopyError;
+use super::lzhuf;
+use crate::util::output_capacity;
+
+/// The length of the fixed part of a DART header, which is followed by a table of chunk lengths.
+const HEADER_LENGTH: usize = 4;
+
+/// Each chunk holds 40 blocks, followed by their tags.
+const CHUNK_DATA_LENGTH: usize = 40 * 512;
+const CHUNK_TAG_LENGTH: usize = 40 * 12;
+const CHUNK_LENGTH: usize = CHUNK_DATA_LENGTH + CHUNK_TAG_LENGTH;
+
+/// Marks an uncompressed chunk in the table of chunk lengths.
+const UNCOMPRESSED_CHUNK: u16 = 0xffff;
+
+/// The compression method used by a DART image.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum DartCompression {
+    /// DART's "fast" compression, which run-length encodes 16-bit words.
+    Rle,
+
+    /// DART's "best" compression, which is LZHUF.
+    Lzh,
+
+    /// No compression.
+    None,
+}
+
+/// A DART (Disk Archive/Retrieval Tool) disk image.
+///
+/// DART images are divided into chunks of 40 blocks (plus their tags), each of which may be
+/// compressed. The entire image is expanded when it's opened, after which images act as a
+/// `Read + Seek` view of the disk's block data.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs::File;
+/// use clarus::diskcopy::DartImage;
+/// use clarus::hfs::HfsVolume;
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let image = DartImage::new(File::open("Disk Tools.dart")?)?;
+///     let volume = HfsVolume::new(image)?;
+///
+///     println!("{}", volume.name());
+///
+///     Ok(())
+/// }
+/// ```
+pub struct DartImage {
+    compression: DartCompression,
+    disk_type: u8,
+    data: Cursor<Vec<u8>>,
+    tag_data: Vec<u8>,
+}
+
+impl DartImage {
+    /// Reads and expands a DART image from the given source, starting at the source's current
+    /// position.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the image's header could not be read or was
+    /// malformed, if the image uses an unknown compression method, or if any of the image's chunks
+    /// could not be read or expanded.
+    pub fn new<R: Read>(mut source: R) -> Result<Self, DiskCopyError> {
+        let mut header = [0; HEADER_LENGTH];
+        source.read_exact(&mut header)?;
+
+        let compression = match header[0] {
+            0 => DartCompression::Rle,
+            1 => DartCompression::Lzh,
+            2 => DartCompression::None,
+            method => return Err(DiskCopyError::UnsupportedCompression(method)),
+        };
+
+        let disk_len = u16::from_be_bytes([header[2], header[3]]) as usize * 1024;
+
+        // 1440K disks need more chunks than fit in the table used for smaller disks
+        let table_len = if disk_len > 800 * 1024 { 72 } else { 40 };
+        let chunk_count = (disk_len + CHUNK_DATA_LENGTH - 1) / CHUNK_DATA_LENGTH;
+
+        if disk_len == 0 || chunk_count > table_len {
+            return Err(DiskCopyError::InvalidHeader);
+        }
+
+        let mut table = vec![0; table_len * 2];
+        source.read_exact(&mut table)?;
+
+        let mut data = Vec::with_capacity(chunk_count * CHUNK_DATA_LENGTH);
+        let mut tag_data = Vec::with_capacity(chunk_count * CHUNK_TAG_LENGTH);
+
+        for entry in table.chunks_exact(2).take(chunk_count) {
+            let entry = u16::from_be_bytes([entry[0], entry[1]]);
+
+            let chunk = match (compression, entry) {
+                (_, UNCOMPRESSED_CHUNK) => {
+                    let mut chunk = vec![0; CHUNK_LENGTH];
+                    source.read_exact(&mut chunk)?;
+                    chunk
+                }
+                (_, 0) | (DartCompression::None, _) => return Err(DiskCopyError::InvalidHeader),
+                (DartCompression::Rle, words) => {
+                    let mut compressed = vec![0; words as usize * 2];
+                    source.read_exact(&mut compressed)?;
+                    expand_rle(&compressed, CHUNK_LENGTH)?
+                }
+                (DartCompression::Lzh, len) => {
+                    let mut compressed = vec![0; len as usize];
+                    source.read_exact(&mut compressed)?;
+                    lzhuf::expand(&compressed, CHUNK_LENGTH)?
+                }
+            };
+
+            data.extend_from_slice(&chunk[..CHUNK_DATA_LENGTH]);
+            tag_data.extend_from_slice(&chunk[CHUNK_DATA_LENGTH..]);
+        }
+
+        data.truncate(disk_len);
+        tag_data.truncate(disk_len / 512 * 12);
+
+        Ok(DartImage {
+            compression,
+            disk_type: header[1],
+            data: Cursor::new(data),
+            tag_data,
+        })
+    }
+
+    /// Returns the compression method used by this image.
+    pub fn compression(&self) -> DartCompression {
+        self.compression
+    }
+
+    /// Returns this image's disk type identifier: 1 for Macintosh 400K disks, 2 for Lisa disks, 3
+    /// for Macintosh 800K disks, 16 for Macintosh 1440K disks, and 17 or 18 for MS-DOS 720K or
+    /// 1440K disks.
+    pub fn disk_type(&self) -> u8 {
+        self.disk_type
+    }
+
+    /// Returns the length, in bytes, of the disk's block data.
+    pub fn data_len(&self) -> u64 {
+        self.data.get_ref().len() as u64
+    }
+
+    /// Returns the image's tag data, which contains 12 bytes for each block; tags are zero on
+    /// disks that don't have them.
+    pub fn tag_data(&self) -> &[u8] {
+        &self.tag_data
+    }
+
+    /// Returns the disk's block data.
+    pub fn into_data(self) -> Vec<u8> {
+        self.data.into_inner()
+    }
+}
+
+/// Expands DART's word-oriented run-length encoding into exactly `len` bytes. Each run starts with
+/// a signed 16-bit count: a positive count is followed by that many literal words, and a negative
+/// count is followed by a single word that's repeated as many times.
+fn expand_rle(compressed: &[u8], len: usize) -> Result<Vec<u8>, DiskCopyError> {
+    let mut words = compressed.chunks_exact(2).map(|word| [word[0], word[1]]);
+    let mut next_word = || words.next().ok_or(DiskCopyError::CorruptData);
+
+    let mut expanded = Vec::with_capacity(output_capacity(len, compressed.len()));
+
+    while expanded.len() < len {
+        let count = i16::from_be_bytes(next_word()?);
+
+        if count >= 0 {
+            for _ in 0..count {
+                expanded.extend_from_slice(&next_word()?);
+            }
+        } else {
+            let word = next_word()?;
+
+            for _ in 0..count.unsigned_abs() {
+                expanded.extend_from_slice(&word);
+            }
+        }
+    }
+
+    if expanded.len() != len {
+        return Err(DiskCopyError::CorruptData);
+    }
+
+    Ok(expanded)
+}
+
+impl Read for DartImage {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.data.read(buf)
+    }
+}
+
+impl Seek for DartImage {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.data.seek(pos)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn compress_rle(data: &[u8]) -> Vec<u8> {
+        let words: Vec<&[u8]> = data.chunks(2).collect();
+        let mut compressed = vec![];
+        let mut i = 0;
+
+        while i < words.len() {
+            let run = words[i..]
+                .iter()
+                .take(0x7fff)
+                .take_while(|word| **word == words[i])
+                .count();
+
+            if run > 1 {
+                compressed.extend_from_slice(&(-(run as i16)).to_be_bytes());
+                compressed.extend_from_slice(words[i]);
+                i += run;
+            } else {
+                compressed.extend_from_slice(&1i16.to_be_bytes());
+                compressed.extend_from_slice(words[i]);
+                i += 1;
+            }
+        }
+
+        compressed
+    }
+
+    fn disk_data(len: usize) -> Vec<u8> {
+        let mut data: Vec<u8> = (0..len / 2).map(|i| (i / 300) as u8).collect();
+        data.extend((0..len / 2).map(|i| (i * 7 / 3) as u8));
+        data
+    }
+
+    fn image_bytes(compression: u8, data: &[u8], tags: &[u8]) -> Vec<u8> {
+        let disk_kb = data.len() / 1024;
+        let table_len = if disk_kb > 800 { 72 } else { 40 };
+
+        let mut image = vec![compression, 1];
+        image.extend_from_slice(&(disk_kb as u16).to_be_bytes());
+
+        let mut table = vec![0; table_len * 2];
+        let mut chunks = vec![];
+
+        for (i, (data, tags)) in data
+            .chunks(CHUNK_DATA_LENGTH)
+            .zip(tags.chunks(CHUNK_TAG_LENGTH))
+            .enumerate()
+        {
+            let mut chunk = data.to_vec();
+            chunk.extend_from_slice(tags);
+
+            let (entry, compressed) = match compression {
+                0 => {
+                    let compressed = compress_rle(&chunk);
+                    ((compressed.len() / 2) as u16, compressed)
+                }
+                1 => {
+                    let compressed = lzhuf::test::compress(&chunk);
+                    (compressed.len() as u16, compressed)
+                }
+                _ => (UNCOMPRESSED_CHUNK, chunk),
+            };
+
+            table[i * 2..i * 2 + 2].copy_from_slice(&entry.to_be_bytes());
+            chunks.extend(compressed);
+        }
+
+        image.extend(table);
+        image.extend(chunks);
+        image
+    }
+
+    #[test]
+    fn read_image() -> Result<(), DiskCopyError> {
+        let data = disk_data(400 * 1024);
+        let tags: Vec<u8> = (0..800 * 12).map(|i| (i % 12) as u8).collect();
+
+        for (method, compression) in [
+            (0, DartCompression::Rle),
+            (1, DartCompression::Lzh),
+            (2, DartCompression::None),
+        ]
+        .iter()
+        {
+            let mut image = DartImage::new(Cursor::new(image_bytes(*method, &data, &tags)))?;
+
+            assert_eq!(*compression, image.compression());
+            assert_eq!(1, image.disk_type());
+            assert_eq!(data.len() as u64, image.data_len());
+            assert_eq!(&tags[..], image.tag_data());
+
+            let mut block = [0; 512];
+            image.seek(SeekFrom::Start(1024))?;
+            image.read_exact(&mut block)?;
+            assert_eq!(data[1024..1536], block[..]);
+
+            assert_eq!(data, image.into_data());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_1440k_image() -> Result<(), DiskCopyError> {
+        let data = disk_data(1440 * 1024);
+        let tags = vec![0; 2880 * 12];
+
+        let image = DartImage::new(Cursor::new(image_bytes(0, &data, &tags)))?;
+        assert_eq!(data, image.into_data());
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_images() {
+        let data = disk_data(400 * 1024);
+        let tags = vec![0; 800 * 12];
+
+        let mut bytes = image_bytes(0, &data, &tags);
+        bytes[0] = 3;
+
+        assert!(matches!(
+            DartImage::new(Cursor::new(&bytes)),
+            Err(DiskCopyError::UnsupportedCompression(3))
+        ));
+
+        // Claim that the first RLE chunk is shorter than it really is
+        let mut bytes = image_bytes(0, &data, &tags);
+        bytes[4..6].copy_from_slice(&10u16.to_be_bytes());
+
+        assert!(matches!(
+            DartImage::new(Cursor::new(&bytes)),
+            Err(DiskCopyError::CorruptData)
+        ));
+
+        // A disk too large for its chunk table
+        let mut bytes = image_bytes(0, &data, &tags);
+        bytes[2..4].copy_from_slice(&2000u16.to_be_bytes());
+
+        assert!(matches!(
+            DartImage::new(Cursor::new(&bytes)),
+            Err(DiskCopyError::InvalidHeader)
+        ));
+
+        // An RLE chunk with an implausibly large expected length
+        assert_eq!(
+            Err(DiskCopyError::CorruptData),
+            expand_rle(&[0x00, 0x01, 0xab, 0xcd], usize::MAX)
+        );
+    }
+}
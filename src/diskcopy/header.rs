@@ -0,0 +1,183 @@
+use std::convert::TryFrom;
+
+use super::image::DiskCopyError;
+use crate::util::u32_at;
+
+pub(super) const HEADER_LENGTH: usize = 84;
+
+const MAX_NAME_LENGTH: usize = 63;
+
+/// The value of the "private" field at the end of every DiskCopy 4.2 header.
+const PRIVATE_VALUE: u16 = 0x0100;
+
+/// The physical format of the disk from which a DiskCopy image was made.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum DiskFormat {
+    /// A single-sided 400K GCR disk.
+    Gcr400K,
+
+    /// A double-sided 800K GCR disk.
+    Gcr800K,
+
+    /// A double-sided, double-density 720K MFM disk.
+    Mfm720K,
+
+    /// A double-sided, high-density 1440K MFM disk.
+    Mfm1440K,
+
+    /// Some other kind of disk.
+    ///
+    /// The disk format byte is included.
+    Other(u8),
+}
+
+impl From<u8> for DiskFormat {
+    fn from(format: u8) -> Self {
+        match format {
+            0 => DiskFormat::Gcr400K,
+            1 => DiskFormat::Gcr800K,
+            2 => DiskFormat::Mfm720K,
+            3 => DiskFormat::Mfm1440K,
+            _ => DiskFormat::Other(format),
+        }
+    }
+}
+
+/// The header of a DiskCopy 4.2 image, which describes the disk and the layout of the image.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DiskCopyHeader {
+    name: String,
+    name_bytes: Vec<u8>,
+    data_len: u32,
+    tag_len: u32,
+    data_checksum: u32,
+    tag_checksum: u32,
+    disk_format: DiskFormat,
+    format_byte: u8,
+}
+
+impl DiskCopyHeader {
+    /// Returns the name of the disk from which the image was made.
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    /// Returns the disk's name as it appears in the header (i.e. encoded in the Macintosh
+    /// character set).
+    pub fn name_bytes(&self) -> &[u8] {
+        &self.name_bytes
+    }
+
+    /// Returns the length, in bytes, of the image's block data.
+    pub fn data_len(&self) -> u64 {
+        self.data_len as u64
+    }
+
+    /// Returns the length, in bytes, of the image's tag data, which may be zero.
+    pub fn tag_len(&self) -> u64 {
+        self.tag_len as u64
+    }
+
+    /// Returns the checksum of the image's block data.
+    pub fn data_checksum(&self) -> u32 {
+        self.data_checksum
+    }
+
+    /// Returns the checksum of the image's tag data.
+    pub fn tag_checksum(&self) -> u32 {
+        self.tag_checksum
+    }
+
+    /// Returns the physical format of the disk from which the image was made.
+    pub fn disk_format(&self) -> DiskFormat {
+        self.disk_format
+    }
+
+    /// Returns the "format byte" that describes the disk's sector layout (e.g. `0x12` for 400K Mac
+    /// disks, `0x22` for larger Mac disks, and `0x24` for 800K Apple II disks).
+    pub fn format_byte(&self) -> u8 {
+        self.format_byte
+    }
+}
+
+impl TryFrom<&[u8; HEADER_LENGTH]> for DiskCopyHeader {
+    type Error = DiskCopyError;
+
+    fn try_from(bytes: &[u8; HEADER_LENGTH]) -> Result<Self, Self::Error> {
+        let name_length = bytes[0] as usize;
+
+        if name_length > MAX_NAME_LENGTH
+            || u16::from_be_bytes([bytes[0x52], bytes[0x53]]) != PRIVATE_VALUE
+        {
+            return Err(DiskCopyError::InvalidHeader);
+        }
+
+        let data_len = u32_at(bytes, 0x40);
+        let tag_len = u32_at(bytes, 0x44);
+
+        // Checksums are calculated over 16-bit words
+        if data_len % 2 != 0 || tag_len % 2 != 0 {
+            return Err(DiskCopyError::InvalidHeader);
+        }
+
+        let name_bytes = &bytes[1..1 + name_length];
+        let (name, _, _) = encoding_rs::MACINTOSH.decode(name_bytes);
+
+        Ok(DiskCopyHeader {
+            name: name.to_string(),
+            name_bytes: name_bytes.to_vec(),
+            data_len,
+            tag_len,
+            data_checksum: u32_at(bytes, 0x48),
+            tag_checksum: u32_at(bytes, 0x4c),
+            disk_format: DiskFormat::from(bytes[0x50]),
+            format_byte: bytes[0x51],
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_header() -> Result<(), DiskCopyError> {
+        let mut bytes = [0; HEADER_LENGTH];
+        bytes[0] = 10;
+        bytes[1..11].copy_from_slice(b"Disk \xa5 One");
+        bytes[0x40..0x44].copy_from_slice(&819_200u32.to_be_bytes());
+        bytes[0x44..0x48].copy_from_slice(&19_200u32.to_be_bytes());
+        bytes[0x48..0x4c].copy_from_slice(&0x1234_5678u32.to_be_bytes());
+        bytes[0x4c..0x50].copy_from_slice(&0x9abc_def0u32.to_be_bytes());
+        bytes[0x50] = 1;
+        bytes[0x51] = 0x22;
+        bytes[0x52..0x54].copy_from_slice(&PRIVATE_VALUE.to_be_bytes());
+
+        let header = DiskCopyHeader::try_from(&bytes)?;
+
+        assert_eq!("Disk • One", header.name());
+        assert_eq!(b"Disk \xa5 One", header.name_bytes());
+        assert_eq!(819_200, header.data_len());
+        assert_eq!(19_200, header.tag_len());
+        assert_eq!(0x1234_5678, header.data_checksum());
+        assert_eq!(0x9abc_def0, header.tag_checksum());
+        assert_eq!(DiskFormat::Gcr800K, header.disk_format());
+        assert_eq!(0x22, header.format_byte());
+
+        let mut bad_private = bytes;
+        bad_private[0x53] = 1;
+        assert_eq!(
+            Err(DiskCopyError::InvalidHeader),
+            DiskCopyHeader::try_from(&bad_private)
+        );
+
+        let mut bad_name = bytes;
+        bad_name[0] = 64;
+        assert_eq!(
+            Err(DiskCopyError::InvalidHeader),
+            DiskCopyHeader::try_from(&bad_name)
+        );
+
+        Ok(())
+    }
+}
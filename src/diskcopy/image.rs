@@ -0,0 +1,373 @@
+use std::cmp;
+use std::convert::TryFrom;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Read, Seek, SeekFrom};
+
+use super::header::{DiskCopyHeader, HEADER_LENGTH};
+use crate::util::seek_position;
+
+/// The number of bytes at the start of the tag data that are (for historical reasons) excluded
+/// from the tag checksum.
+const UNCHECKED_TAG_LENGTH: usize = 12;
+
+const CHECKSUM_BUFFER_LENGTH: usize = 65536;
+
+/// A DiskCopy 4.2 disk image.
+///
+/// Images act as a `Read + Seek` view of the disk's block data: reads start at the disk's first
+/// block, seeking is relative to the start of the block data, and reads never extend into the
+/// image's tag data.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs::File;
+/// use std::io::{Read, Seek, SeekFrom};
+/// use clarus::diskcopy::{DiskCopyError, DiskCopyImage};
+///
+/// fn main() -> Result<(), DiskCopyError> {
+///     let mut image = DiskCopyImage::new(File::open("System Tools.image")?)?;
+///     println!("{}: {} bytes", image.header().name(), image.header().data_len());
+///
+///     // Read the disk's master directory block
+///     let mut mdb = [0; 512];
+///     image.seek(SeekFrom::Start(1024))?;
+///     image.read_exact(&mut mdb)?;
+///
+///     Ok(())
+/// }
+/// ```
+pub struct DiskCopyImage<R: Read + Seek> {
+    source: R,
+    base_offset: u64,
+    header: DiskCopyHeader,
+    position: u64,
+}
+
+impl<R: Read + Seek> DiskCopyImage<R> {
+    /// Creates a new DiskCopy image that will read data from the given source, starting at the
+    /// source's current position, and verifies the image's block and tag checksums.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the image's header could not be read or was
+    /// malformed, if the image is shorter than its header indicates, or if either of the image's
+    /// checksums doesn't match the image's content.
+    pub fn new(source: R) -> Result<Self, DiskCopyError> {
+        let mut image = DiskCopyImage::new_unchecked(source)?;
+        image.verify()?;
+
+        Ok(image)
+    }
+
+    /// Creates a new DiskCopy image that will read data from the given source, starting at the
+    /// source's current position, without verifying the image's checksums.
+    ///
+    /// Images that have been modified without updating their checksums (as some utilities did) can
+    /// still be read, but their content may be damaged.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the image's header could not be read or was
+    /// malformed.
+    pub fn new_unchecked(mut source: R) -> Result<Self, DiskCopyError> {
+        let base_offset = source.stream_position()?;
+
+        let mut header_bytes = [0; HEADER_LENGTH];
+        source.read_exact(&mut header_bytes)?;
+
+        Ok(DiskCopyImage {
+            source,
+            base_offset,
+            header: DiskCopyHeader::try_from(&header_bytes)?,
+            position: 0,
+        })
+    }
+
+    /// Returns this image's header.
+    pub fn header(&self) -> &DiskCopyHeader {
+        &self.header
+    }
+
+    /// Checks the image's block data and tag data against the checksums in its header.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if either section of the image could not be read or
+    /// doesn't match its checksum.
+    pub fn verify(&mut self) -> Result<(), DiskCopyError> {
+        let data_checksum = self.calculate_checksum(0, self.header.data_len())?;
+
+        if data_checksum != self.header.data_checksum() {
+            return Err(DiskCopyError::InvalidChecksum(
+                self.header.data_checksum(),
+                data_checksum,
+            ));
+        }
+
+        let tag_checksum = match self.header.tag_len() {
+            0 => 0,
+            tag_len => self.calculate_checksum(
+                self.header.data_len() + UNCHECKED_TAG_LENGTH as u64,
+                tag_len.saturating_sub(UNCHECKED_TAG_LENGTH as u64),
+            )?,
+        };
+
+        if tag_checksum != self.header.tag_checksum() {
+            return Err(DiskCopyError::InvalidChecksum(
+                self.header.tag_checksum(),
+                tag_checksum,
+            ));
+        }
+
+        self.seek(SeekFrom::Start(self.position))?;
+
+        Ok(())
+    }
+
+    /// Reads the image's tag data, which contains 12 bytes for each block on 400K and 800K disks,
+    /// and is empty for all other disks.
+    pub fn tag_data(&mut self) -> Result<Vec<u8>, DiskCopyError> {
+        let mut tag_data = vec![0; self.header.tag_len() as usize];
+
+        self.source.seek(SeekFrom::Start(
+            self.base_offset + HEADER_LENGTH as u64 + self.header.data_len(),
+        ))?;
+        self.source.read_exact(&mut tag_data)?;
+
+        self.seek(SeekFrom::Start(self.position))?;
+
+        Ok(tag_data)
+    }
+
+    /// Returns the source from which this image reads its data.
+    pub fn into_inner(self) -> R {
+        self.source
+    }
+
+    /// Calculates the DiskCopy checksum of the given range of the image, which is relative to the
+    /// start of the block data.
+    fn calculate_checksum(&mut self, start: u64, len: u64) -> Result<u32, DiskCopyError> {
+        self.source.seek(SeekFrom::Start(
+            self.base_offset + HEADER_LENGTH as u64 + start,
+        ))?;
+
+        let mut buffer = vec![0; CHECKSUM_BUFFER_LENGTH];
+        let mut remaining = len;
+        let mut checksum = 0;
+
+        while remaining > 0 {
+            let chunk = &mut buffer[..cmp::min(remaining, CHECKSUM_BUFFER_LENGTH as u64) as usize];
+            self.source.read_exact(chunk)?;
+
+            checksum = update_checksum(checksum, chunk);
+            remaining -= chunk.len() as u64;
+        }
+
+        Ok(checksum)
+    }
+}
+
+/// Adds the given bytes, which must have an even length, to a DiskCopy checksum: each big-endian
+/// 16-bit word is added to the checksum, which is then rotated right by one bit.
+fn update_checksum(mut checksum: u32, bytes: &[u8]) -> u32 {
+    for word in bytes.chunks_exact(2) {
+        checksum = checksum
+            .wrapping_add(u16::from_be_bytes([word[0], word[1]]) as u32)
+            .rotate_right(1);
+    }
+
+    checksum
+}
+
+impl<R: Read + Seek> Read for DiskCopyImage<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.header.data_len().saturating_sub(self.position);
+        let max_len = cmp::min(buf.len() as u64, remaining) as usize;
+
+        let bytes_read = self.source.read(&mut buf[..max_len])?;
+        self.position += bytes_read as u64;
+
+        Ok(bytes_read)
+    }
+}
+
+impl<R: Read + Seek> Seek for DiskCopyImage<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = seek_position(pos, self.position, self.header.data_len())?;
+
+        self.source.seek(SeekFrom::Start(
+            self.base_offset + HEADER_LENGTH as u64 + position,
+        ))?;
+        self.position = position;
+
+        Ok(position)
+    }
+}
+
+/// The error type for operations on DiskCopy images.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DiskCopyError {
+    /// An [`std::io::Error`] occurred while reading some part of the image.
+    ///
+    /// The kind of IO error is included.
+    IoError(io::ErrorKind),
+
+    /// The image's header (or, for NDIF images, the `bcem` resource that describes the image's
+    /// chunks) was malformed or missing.
+    InvalidHeader,
+
+    /// A checksum included in the image's header did not match the checksum calculated from the
+    /// image's block data or tag data.
+    ///
+    /// The provided checksum and the calculated checksum are included.
+    InvalidChecksum(u32, u32),
+
+    /// Some of the image's compressed data was malformed.
+    CorruptData,
+
+    /// The image uses a compression method that this crate can't expand.
+    ///
+    /// The image's identifier for the compression method is included.
+    UnsupportedCompression(u8),
+}
+
+impl Display for DiskCopyError {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DiskCopyError::IoError(kind) => write!(fmt, "IO error: {:?}", kind),
+            DiskCopyError::InvalidHeader => write!(fmt, "Invalid DiskCopy header"),
+            DiskCopyError::InvalidChecksum(provided, calculated) => write!(
+                fmt,
+                "Invalid checksum (provided: {:08x}, calculated: {:08x})",
+                provided, calculated
+            ),
+            DiskCopyError::CorruptData => write!(fmt, "Corrupt compressed data"),
+            DiskCopyError::UnsupportedCompression(method) => {
+                write!(fmt, "Unsupported compression method: {:#04x}", method)
+            }
+        }
+    }
+}
+
+impl From<io::Error> for DiskCopyError {
+    fn from(error: io::Error) -> Self {
+        DiskCopyError::IoError(error.kind())
+    }
+}
+
+impl error::Error for DiskCopyError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::diskcopy::DiskFormat;
+    use std::io::Cursor;
+
+    fn image_bytes(data: &[u8], tags: &[u8]) -> Vec<u8> {
+        let mut image = vec![0; HEADER_LENGTH];
+        image[0] = 5;
+        image[1..6].copy_from_slice(b"Disk1");
+        image[0x40..0x44].copy_from_slice(&(data.len() as u32).to_be_bytes());
+        image[0x44..0x48].copy_from_slice(&(tags.len() as u32).to_be_bytes());
+        image[0x48..0x4c].copy_from_slice(&update_checksum(0, data).to_be_bytes());
+        image[0x4c..0x50].copy_from_slice(
+            &update_checksum(0, tags.get(UNCHECKED_TAG_LENGTH..).unwrap_or(&[])).to_be_bytes(),
+        );
+        image[0x50] = 1;
+        image[0x51] = 0x22;
+        image[0x52..0x54].copy_from_slice(&[0x01, 0x00]);
+
+        image.extend_from_slice(data);
+        image.extend_from_slice(tags);
+
+        image
+    }
+
+    fn sample_data() -> (Vec<u8>, Vec<u8>) {
+        let data: Vec<u8> = (0..1600 * 512).map(|i| (i * 7 % 251) as u8).collect();
+        let tags: Vec<u8> = (0..1600 * 12).map(|i| (i % 13) as u8).collect();
+
+        (data, tags)
+    }
+
+    #[test]
+    fn checksum() {
+        assert_eq!(0, update_checksum(0, &[]));
+        assert_eq!(0x8000_0000, update_checksum(0, &[0x00, 0x01]));
+        assert_eq!(0x4000_0001, update_checksum(0x8000_0000, &[0x00, 0x02]));
+    }
+
+    #[test]
+    fn read_image() -> Result<(), DiskCopyError> {
+        let (data, tags) = sample_data();
+        let mut source = vec![0xff; 3];
+        source.extend(image_bytes(&data, &tags));
+
+        let mut cursor = Cursor::new(source);
+        cursor.seek(SeekFrom::Start(3))?;
+
+        let mut image = DiskCopyImage::new(cursor)?;
+
+        assert_eq!("Disk1", image.header().name());
+        assert_eq!(DiskFormat::Gcr800K, image.header().disk_format());
+
+        let mut block = [0; 512];
+        image.seek(SeekFrom::Start(1024))?;
+        image.read_exact(&mut block)?;
+        assert_eq!(&data[1024..1536], &block[..]);
+
+        assert_eq!(tags, image.tag_data()?);
+
+        // Reading the tags shouldn't disturb the image's position, and reads stop at the end of the
+        // block data
+        image.read_exact(&mut block)?;
+        assert_eq!(&data[1536..2048], &block[..]);
+
+        image.seek(SeekFrom::End(-100))?;
+        let mut tail = vec![];
+        image.read_to_end(&mut tail)?;
+        assert_eq!(&data[data.len() - 100..], &tail[..]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_checksums() -> Result<(), DiskCopyError> {
+        let (data, tags) = sample_data();
+
+        let mut bad_data = image_bytes(&data, &tags);
+        bad_data[HEADER_LENGTH + 1000] ^= 0x01;
+
+        assert!(matches!(
+            DiskCopyImage::new(Cursor::new(&bad_data)),
+            Err(DiskCopyError::InvalidChecksum(_, _))
+        ));
+
+        assert!(DiskCopyImage::new_unchecked(Cursor::new(&bad_data)).is_ok());
+
+        // The first 12 bytes of the tag data aren't covered by the tag checksum
+        let mut unchecked_tags = image_bytes(&data, &tags);
+        unchecked_tags[HEADER_LENGTH + data.len()] ^= 0x01;
+        assert!(DiskCopyImage::new(Cursor::new(&unchecked_tags)).is_ok());
+
+        let mut bad_tags = image_bytes(&data, &tags);
+        *bad_tags.last_mut().unwrap() ^= 0x01;
+
+        assert!(matches!(
+            DiskCopyImage::new(Cursor::new(&bad_tags)),
+            Err(DiskCopyError::InvalidChecksum(_, _))
+        ));
+
+        let mut truncated = image_bytes(&data, &[]);
+        truncated.truncate(truncated.len() - 2);
+
+        assert!(matches!(
+            DiskCopyImage::new(Cursor::new(&truncated)),
+            Err(DiskCopyError::IoError(io::ErrorKind::UnexpectedEof))
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,406 @@
+//! Decompression for DART's "best" compression method, which is Haruyasu Yoshizaki's LZHUF: LZSS
+//! over a 4 KiB window, with literals and match lengths encoded with an adaptive Huffman code, and
+//! the upper six bits of each match position encoded with a fixed prefix code.
+
+use super::image::DiskCopyError;
+use crate::stuffit::bits::BitReader;
+use crate::stuffit::StuffItError;
+use crate::util::output_capacity;
+
+const WINDOW_SIZE: usize = 4096;
+const MAX_MATCH_LENGTH: usize = 60;
+const THRESHOLD: usize = 2;
+
+/// The number of symbols in the adaptive code: one per literal byte, plus one per match length.
+pub(super) const SYMBOL_COUNT: usize = 256 - THRESHOLD + MAX_MATCH_LENGTH;
+
+/// The number of nodes in the adaptive code's tree.
+pub(super) const NODE_COUNT: usize = SYMBOL_COUNT * 2 - 1;
+
+/// The index of the root node of the adaptive code's tree.
+pub(super) const ROOT: usize = NODE_COUNT - 1;
+
+/// When the root's frequency reaches this value, all frequencies are halved.
+const MAX_FREQUENCY: u32 = 0x8000;
+
+/// The number of position codes of each length (in bits), in order of increasing length; the codes
+/// themselves are assigned sequentially.
+const POSITION_CODE_LENGTHS: [(usize, u32); 6] =
+    [(1, 3), (3, 4), (8, 5), (12, 6), (24, 7), (16, 8)];
+
+/// An adaptive Huffman code whose tree is rebalanced after each symbol. Nodes are kept in order of
+/// increasing frequency, and each internal node's children are adjacent.
+pub(super) struct AdaptiveCode {
+    frequencies: Vec<u32>,
+
+    /// The parent of each node, followed by the node that holds each symbol's leaf.
+    pub parents: Vec<usize>,
+
+    /// The first child of each internal node, or `NODE_COUNT` plus the symbol for leaves.
+    pub children: Vec<usize>,
+}
+
+impl AdaptiveCode {
+    pub fn new() -> Self {
+        let mut code = AdaptiveCode {
+            frequencies: vec![0; NODE_COUNT + 1],
+            parents: vec![0; NODE_COUNT + SYMBOL_COUNT],
+            children: vec![0; NODE_COUNT],
+        };
+
+        for symbol in 0..SYMBOL_COUNT {
+            code.frequencies[symbol] = 1;
+            code.children[symbol] = symbol + NODE_COUNT;
+            code.parents[symbol + NODE_COUNT] = symbol;
+        }
+
+        let mut child = 0;
+
+        for node in SYMBOL_COUNT..NODE_COUNT {
+            code.frequencies[node] = code.frequencies[child] + code.frequencies[child + 1];
+            code.children[node] = child;
+            code.parents[child] = node;
+            code.parents[child + 1] = node;
+            child += 2;
+        }
+
+        // A sentinel that's greater than any real frequency
+        code.frequencies[NODE_COUNT] = 0xffff;
+        code.parents[ROOT] = 0;
+
+        code
+    }
+
+    /// Increments the frequency of the given symbol and its ancestors, moving nodes as needed to
+    /// keep nodes in order of frequency.
+    pub fn update(&mut self, symbol: usize) {
+        if self.frequencies[ROOT] == MAX_FREQUENCY {
+            self.rebuild();
+        }
+
+        let mut node = self.parents[symbol + NODE_COUNT];
+
+        loop {
+            self.frequencies[node] += 1;
+            let frequency = self.frequencies[node];
+
+            if frequency > self.frequencies[node + 1] {
+                // Swap this node with the last node whose frequency is now lower
+                let mut other = node + 1;
+
+                while frequency > self.frequencies[other + 1] {
+                    other += 1;
+                }
+
+                self.frequencies[node] = self.frequencies[other];
+                self.frequencies[other] = frequency;
+
+                let child = self.children[node];
+                self.set_parent(child, other);
+
+                let other_child = self.children[other];
+                self.children[other] = child;
+                self.set_parent(other_child, node);
+                self.children[node] = other_child;
+
+                node = other;
+            }
+
+            node = self.parents[node];
+
+            if node == 0 {
+                break;
+            }
+        }
+    }
+
+    /// Sets the parent of the given child (and, for internal nodes, of its sibling).
+    fn set_parent(&mut self, child: usize, parent: usize) {
+        self.parents[child] = parent;
+
+        if child < NODE_COUNT {
+            self.parents[child + 1] = parent;
+        }
+    }
+
+    /// Halves every leaf's frequency and rebuilds the tree.
+    fn rebuild(&mut self) {
+        let mut leaf = 0;
+
+        for node in 0..NODE_COUNT {
+            if self.children[node] >= NODE_COUNT {
+                self.frequencies[leaf] = (self.frequencies[node] + 1) / 2;
+                self.children[leaf] = self.children[node];
+                leaf += 1;
+            }
+        }
+
+        let mut child = 0;
+
+        for node in SYMBOL_COUNT..NODE_COUNT {
+            let frequency = self.frequencies[child] + self.frequencies[child + 1];
+
+            let mut position = node;
+
+            while position > 0 && frequency < self.frequencies[position - 1] {
+                position -= 1;
+            }
+
+            self.frequencies.copy_within(position..node, position + 1);
+            self.frequencies[position] = frequency;
+            self.children.copy_within(position..node, position + 1);
+            self.children[position] = child;
+
+            child += 2;
+        }
+
+        for node in 0..NODE_COUNT {
+            self.set_parent(self.children[node], node);
+        }
+    }
+}
+
+/// Maps the first eight bits of an encoded match position to the upper six bits of the position
+/// and the length of the code that represents them.
+fn position_codes() -> ([usize; 256], [u32; 256]) {
+    let mut upper_bits = [0; 256];
+    let mut lengths = [0; 256];
+    let mut i = 0;
+    let mut code = 0;
+
+    for (count, length) in POSITION_CODE_LENGTHS.iter() {
+        for _ in 0..*count {
+            for _ in 0..1 << (8 - length) {
+                upper_bits[i] = code;
+                lengths[i] = *length;
+                i += 1;
+            }
+
+            code += 1;
+        }
+    }
+
+    (upper_bits, lengths)
+}
+
+fn corrupt(_: StuffItError) -> DiskCopyError {
+    DiskCopyError::CorruptData
+}
+
+/// Expands LZHUF-compressed data into exactly `len` bytes.
+pub(super) fn expand(compressed: &[u8], len: usize) -> Result<Vec<u8>, DiskCopyError> {
+    let mut bits = BitReader::msb_first(compressed);
+    let mut code = AdaptiveCode::new();
+    let (position_upper_bits, position_code_lengths) = position_codes();
+
+    // The window starts out full of spaces
+    let mut window = vec![b' '; WINDOW_SIZE];
+    let mut window_position = WINDOW_SIZE - MAX_MATCH_LENGTH;
+
+    let mut expanded = Vec::with_capacity(output_capacity(len, compressed.len()));
+
+    while expanded.len() < len {
+        let mut node = code.children[ROOT];
+
+        while node < NODE_COUNT {
+            node = code.children[node + bits.read_bit().map_err(corrupt)? as usize];
+        }
+
+        let symbol = node - NODE_COUNT;
+        code.update(symbol);
+
+        if symbol < 256 {
+            expanded.push(symbol as u8);
+            window[window_position] = symbol as u8;
+            window_position = (window_position + 1) % WINDOW_SIZE;
+        } else {
+            let first_bits = bits.read_bits(8).map_err(corrupt)? as usize;
+            let extra_bits = bits
+                .read_bits(position_code_lengths[first_bits] - 2)
+                .map_err(corrupt)? as usize;
+
+            let lower_bits =
+                ((first_bits << (position_code_lengths[first_bits] - 2)) | extra_bits) & 0x3f;
+            let distance = (position_upper_bits[first_bits] << 6) | lower_bits;
+
+            let start = (window_position + WINDOW_SIZE - distance - 1) % WINDOW_SIZE;
+
+            for i in 0..symbol - 255 + THRESHOLD {
+                let byte = window[(start + i) % WINDOW_SIZE];
+
+                expanded.push(byte);
+                window[window_position] = byte;
+                window_position = (window_position + 1) % WINDOW_SIZE;
+            }
+        }
+    }
+
+    expanded.truncate(len);
+
+    Ok(expanded)
+}
+
+#[cfg(test)]
+pub(super) mod test {
+    use super::*;
+
+    /// A simple bit writer for test data, which writes the most significant bit of each byte
+    /// first.
+    struct BitWriter {
+        bytes: Vec<u8>,
+        bit_count: usize,
+    }
+
+    impl BitWriter {
+        fn write(&mut self, value: usize, count: u32) {
+            for i in (0..count).rev() {
+                if self.bit_count % 8 == 0 {
+                    self.bytes.push(0);
+                }
+
+                if (value >> i) & 1 != 0 {
+                    *self.bytes.last_mut().unwrap() |= 0x80 >> (self.bit_count % 8);
+                }
+
+                self.bit_count += 1;
+            }
+        }
+    }
+
+    /// Compresses the given data, greedily choosing the longest match within the last 64 bytes of
+    /// data.
+    pub fn compress(data: &[u8]) -> Vec<u8> {
+        let mut writer = BitWriter {
+            bytes: vec![],
+            bit_count: 0,
+        };
+
+        let mut code = AdaptiveCode::new();
+        let (position_upper_bits, position_code_lengths) = position_codes();
+
+        let mut write_symbol = |writer: &mut BitWriter, symbol: usize| {
+            let mut path = vec![];
+            let mut node = code.parents[symbol + NODE_COUNT];
+
+            while node != ROOT {
+                path.push(node & 1);
+                node = code.parents[node];
+            }
+
+            for bit in path.iter().rev() {
+                writer.write(*bit, 1);
+            }
+
+            code.update(symbol);
+        };
+
+        let mut i = 0;
+
+        while i < data.len() {
+            let (mut best_start, mut best_len) = (0, 0);
+
+            for start in i.saturating_sub(64)..i {
+                let len = (0..MAX_MATCH_LENGTH.min(data.len() - i))
+                    .take_while(|k| data[start + k] == data[i + k])
+                    .count();
+
+                if len > best_len {
+                    best_start = start;
+                    best_len = len;
+                }
+            }
+
+            if best_len > THRESHOLD {
+                write_symbol(&mut writer, best_len + 255 - THRESHOLD);
+
+                let distance = i - best_start - 1;
+                let first_code = (0..256)
+                    .find(|code| position_upper_bits[*code] == distance >> 6)
+                    .unwrap();
+                let length = position_code_lengths[first_code];
+
+                writer.write(first_code >> (8 - length), length);
+                writer.write(distance & 0x3f, 6);
+
+                i += best_len;
+            } else {
+                write_symbol(&mut writer, data[i] as usize);
+                i += 1;
+            }
+        }
+
+        writer.bytes
+    }
+
+    #[test]
+    fn expand_compressed_data() -> Result<(), DiskCopyError> {
+        let mut data = b"Hello, Hello, Hello, world! ".repeat(100);
+        data.extend((0..20000u32).map(|i| (i * i / 7) as u8));
+        data.extend(vec![0; 5000]);
+
+        let compressed = compress(&data);
+        assert!(compressed.len() < data.len());
+        assert_eq!(data, expand(&compressed, data.len())?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn expand_after_rebuild() -> Result<(), DiskCopyError> {
+        // Enough symbols that the adaptive code's frequencies are halved at least once
+        let data: Vec<u8> = (0..80000u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8)
+            .collect();
+
+        assert_eq!(data, expand(&compress(&data), data.len())?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn expand_initial_window() -> Result<(), DiskCopyError> {
+        // A match that refers to the spaces that initially fill the window
+        let mut writer = BitWriter {
+            bytes: vec![],
+            bit_count: 0,
+        };
+
+        let code = AdaptiveCode::new();
+        let symbol = 10 + 255 - THRESHOLD;
+        let mut path = vec![];
+        let mut node = code.parents[symbol + NODE_COUNT];
+
+        while node != ROOT {
+            path.push(node & 1);
+            node = code.parents[node];
+        }
+
+        for bit in path.iter().rev() {
+            writer.write(*bit, 1);
+        }
+
+        // Distance 0, whose code is three zero bits, followed by six zero bits
+        writer.write(0, 9);
+
+        assert_eq!(vec![b' '; 10], expand(&writer.bytes, 10)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn expand_truncated_data() {
+        let data = b"Hello, Hello, Hello, world! ".repeat(10);
+        let compressed = compress(&data);
+
+        assert_eq!(
+            Err(DiskCopyError::CorruptData),
+            expand(&compressed[..compressed.len() / 2], data.len())
+        );
+
+        // An implausibly large expected length
+        assert_eq!(
+            Err(DiskCopyError::CorruptData),
+            expand(&compressed, usize::MAX)
+        );
+    }
+}
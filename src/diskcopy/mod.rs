@@ -0,0 +1,28 @@
+//! Tools for reading DiskCopy 4.2, NDIF, and DART disk images.
+//!
+//! DiskCopy 4.2 was Apple's standard format for distributing floppy disk images, and software
+//! archives often contain DiskCopy images (usually with a `.image` extension) wrapped in BinHex or
+//! MacBinary. A DiskCopy 4.2 image consists of an 84-byte header, followed by the disk's 512-byte
+//! blocks and, optionally, the 12-byte "tags" associated with each block on 400K and 800K disks.
+//!
+//! The header includes checksums of both the block data and the tag data, which are verified when
+//! an image is opened. Once opened, a [`DiskCopyImage`] acts as a `Read + Seek` view of the disk's
+//! blocks, and can be passed to a file system reader.
+//!
+//! Disk Copy 6 introduced NDIF, which stores a disk's blocks in the image's data fork as a series
+//! of (possibly compressed) chunks, and describes those chunks in a `bcem` resource in the image's
+//! resource fork. Apple's earlier DART images compress a floppy disk's blocks (and tags) in chunks
+//! of 40 blocks. [`NdifImage`] and [`DartImage`] both act as `Read + Seek` views of the disk's
+//! blocks, just like [`DiskCopyImage`].
+
+mod adc;
+mod dart;
+mod header;
+mod image;
+mod lzhuf;
+mod ndif;
+
+pub use dart::{DartCompression, DartImage};
+pub use header::{DiskCopyHeader, DiskFormat};
+pub use image::{DiskCopyError, DiskCopyImage};
+pub use ndif::NdifImage;
@@ -0,0 +1,447 @@
+use std::cmp;
+use std::io::{self, Read, Seek, SeekFrom};
+
+use super::adc;
+use super::image::DiskCopyError;
+use crate::rsrc::{ResourceError, ResourceFork, ResourceType};
+use crate::util::{seek_position, u32_at};
+
+/// The type and ID of the resource that describes an NDIF image's chunks.
+const BLOCK_MAP_TYPE: [u8; 4] = *b"bcem";
+const BLOCK_MAP_ID: u16 = 128;
+
+/// The offset of the chunk table within the `bcem` resource.
+const CHUNK_TABLE_OFFSET: usize = 128;
+const CHUNK_ENTRY_LENGTH: usize = 12;
+
+const SECTOR_SIZE: u64 = 512;
+
+/// The most sectors a single compressed chunk may span; Disk Copy itself writes much smaller
+/// chunks, and each compressed chunk is expanded into memory in its entirety.
+const MAX_COMPRESSED_CHUNK_SECTORS: u64 = 4096;
+
+/// The kinds of chunks that appear in an NDIF image's chunk table.
+const ZERO_CHUNK: u8 = 0x00;
+const RAW_CHUNK: u8 = 0x02;
+const ADC_CHUNK: u8 = 0x80;
+const END_CHUNK: u8 = 0xff;
+
+/// A run of sectors stored in the same way at the same place in an image's data fork.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+struct Chunk {
+    start_sector: u32,
+    kind: u8,
+    offset: u32,
+    len: u32,
+}
+
+/// An NDIF (New Disk Image Format) disk image, as written by Disk Copy 6.
+///
+/// An NDIF image's block data is stored in its data fork as a series of chunks, each of which may
+/// be compressed, and the image's `bcem` resource lists each chunk's kind and location. Images act
+/// as a `Read + Seek` view of the disk's block data; chunks are expanded on demand.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs::File;
+/// use clarus::diskcopy::NdifImage;
+/// use clarus::hfs::HfsVolume;
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let image = NdifImage::new(
+///         File::open("Utilities.img")?,
+///         File::open("Utilities.img/..namedfork/rsrc")?,
+///     )?;
+///
+///     println!("{} ({} bytes)", image.name(), image.data_len());
+///
+///     let volume = HfsVolume::new(image)?;
+///
+///     for entry in volume.entries() {
+///         println!("{}", entry.path().join(":"));
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+pub struct NdifImage<R: Read + Seek> {
+    source: R,
+    base_offset: u64,
+    name: String,
+    data_len: u64,
+    chunks: Vec<Chunk>,
+    position: u64,
+
+    /// The index and content of the most recently expanded compressed chunk.
+    expanded_chunk: Option<(usize, Vec<u8>)>,
+}
+
+impl<R: Read + Seek> NdifImage<R> {
+    /// Creates a new NDIF image that will read chunks from the given data fork, starting at the
+    /// data fork's current position, as described by the `bcem` resource in the given resource
+    /// fork.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the image's `bcem` resource could not be loaded or
+    /// was malformed, or if any of the image's chunks use a compression method that this crate
+    /// can't expand.
+    pub fn new<S: Read + Seek>(mut data_fork: R, resource_fork: S) -> Result<Self, DiskCopyError> {
+        let base_offset = data_fork.stream_position()?;
+
+        let mut block_map = vec![];
+        ResourceFork::new(resource_fork)
+            .map_err(resource_error)?
+            .load_by_id(
+                ResourceType::from(BLOCK_MAP_TYPE),
+                BLOCK_MAP_ID,
+                &mut block_map,
+            )
+            .map_err(resource_error)?;
+
+        if block_map.len() < CHUNK_TABLE_OFFSET {
+            return Err(DiskCopyError::InvalidHeader);
+        }
+
+        let name_len = (block_map[2] as usize).min(63);
+        let (name, _, _) = encoding_rs::MACINTOSH.decode(&block_map[3..3 + name_len]);
+
+        let sector_count = u32_at(&block_map, 68);
+        let chunk_count = u32_at(&block_map, 124) as usize;
+
+        let chunk_table = block_map
+            .get(CHUNK_TABLE_OFFSET..CHUNK_TABLE_OFFSET + chunk_count * CHUNK_ENTRY_LENGTH)
+            .ok_or(DiskCopyError::InvalidHeader)?;
+
+        let mut chunks = vec![];
+
+        for entry in chunk_table.chunks_exact(CHUNK_ENTRY_LENGTH) {
+            // The first three bytes of each entry hold the chunk's starting sector, and the fourth
+            // holds the chunk's kind
+            let chunk = Chunk {
+                start_sector: u32_at(entry, 0) >> 8,
+                kind: entry[3],
+                offset: u32_at(entry, 4),
+                len: u32_at(entry, 8),
+            };
+
+            if chunk.start_sector > sector_count
+                || chunks
+                    .last()
+                    .map_or(false, |last: &Chunk| last.start_sector > chunk.start_sector)
+            {
+                return Err(DiskCopyError::InvalidHeader);
+            }
+
+            match chunk.kind {
+                ZERO_CHUNK | RAW_CHUNK | ADC_CHUNK => chunks.push(chunk),
+                END_CHUNK => break,
+                kind => return Err(DiskCopyError::UnsupportedCompression(kind)),
+            }
+        }
+
+        for (i, chunk) in chunks.iter().enumerate() {
+            let end_sector = chunks
+                .get(i + 1)
+                .map(|next| next.start_sector)
+                .unwrap_or(sector_count);
+
+            if chunk.kind == ADC_CHUNK
+                && (end_sector - chunk.start_sector) as u64 > MAX_COMPRESSED_CHUNK_SECTORS
+            {
+                return Err(DiskCopyError::InvalidHeader);
+            }
+        }
+
+        Ok(NdifImage {
+            source: data_fork,
+            base_offset,
+            name: name.to_string(),
+            data_len: sector_count as u64 * SECTOR_SIZE,
+            chunks,
+            position: 0,
+            expanded_chunk: None,
+        })
+    }
+
+    /// Returns the name of the disk from which this image was made.
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    /// Returns the length, in bytes, of the disk's block data.
+    pub fn data_len(&self) -> u64 {
+        self.data_len
+    }
+
+    /// Returns the source from which this image reads its chunks.
+    pub fn into_inner(self) -> R {
+        self.source
+    }
+
+    /// Returns the offset (relative to the start of the block data) at which the given chunk ends.
+    fn chunk_end(&self, index: usize) -> u64 {
+        self.chunks
+            .get(index + 1)
+            .map(|next| next.start_sector as u64 * SECTOR_SIZE)
+            .unwrap_or(self.data_len)
+    }
+}
+
+fn resource_error(error: ResourceError) -> DiskCopyError {
+    match error {
+        ResourceError::IoError(kind) => DiskCopyError::IoError(kind),
+        _ => DiskCopyError::InvalidHeader,
+    }
+}
+
+impl<R: Read + Seek> Read for NdifImage<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position >= self.data_len || buf.is_empty() {
+            return Ok(0);
+        }
+
+        // Sectors before the first chunk (or missing from the chunk table) read as zeroes
+        let index = match self
+            .chunks
+            .partition_point(|chunk| chunk.start_sector as u64 * SECTOR_SIZE <= self.position)
+        {
+            0 => None,
+            i => Some(i - 1),
+        };
+
+        let (chunk_start, chunk_end) = match index {
+            Some(i) => (
+                self.chunks[i].start_sector as u64 * SECTOR_SIZE,
+                self.chunk_end(i),
+            ),
+            None => (
+                0,
+                self.chunks
+                    .first()
+                    .map(|chunk| chunk.start_sector as u64 * SECTOR_SIZE)
+                    .unwrap_or(self.data_len),
+            ),
+        };
+
+        let offset_in_chunk = self.position - chunk_start;
+        let len = cmp::min(buf.len() as u64, chunk_end - self.position) as usize;
+        let buf = &mut buf[..len];
+
+        let bytes_read = match index.map(|i| (i, self.chunks[i])) {
+            Some((_, chunk)) if chunk.kind == RAW_CHUNK => {
+                self.source.seek(SeekFrom::Start(
+                    self.base_offset + chunk.offset as u64 + offset_in_chunk,
+                ))?;
+
+                self.source.read(buf)?
+            }
+            Some((i, chunk)) if chunk.kind == ADC_CHUNK => {
+                if self.expanded_chunk.as_ref().map_or(true, |(j, _)| *j != i) {
+                    // Don't trust the chunk's length enough to allocate it all up front
+                    let mut compressed = vec![];
+                    self.source
+                        .seek(SeekFrom::Start(self.base_offset + chunk.offset as u64))?;
+                    (&mut self.source)
+                        .take(chunk.len as u64)
+                        .read_to_end(&mut compressed)?;
+
+                    if compressed.len() != chunk.len as usize {
+                        return Err(io::ErrorKind::UnexpectedEof.into());
+                    }
+
+                    let expanded = adc::expand(&compressed, (chunk_end - chunk_start) as usize)
+                        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+
+                    self.expanded_chunk = Some((i, expanded));
+                }
+
+                let (_, expanded) = self.expanded_chunk.as_ref().unwrap();
+                let start = offset_in_chunk as usize;
+                buf.copy_from_slice(&expanded[start..start + len]);
+
+                len
+            }
+            _ => {
+                buf.iter_mut().for_each(|byte| *byte = 0);
+                len
+            }
+        };
+
+        self.position += bytes_read as u64;
+
+        Ok(bytes_read)
+    }
+}
+
+impl<R: Read + Seek> Seek for NdifImage<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.position = seek_position(pos, self.position, self.data_len)?;
+
+        Ok(self.position)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rsrc::ResourceForkBuilder;
+    use std::io::Cursor;
+
+    /// Builds a resource fork that contains a single resource.
+    fn resource_fork_bytes(resource_type: &[u8; 4], id: u16, data: &[u8]) -> Vec<u8> {
+        let mut builder = ResourceForkBuilder::new();
+        builder
+            .add_resource(
+                ResourceType::from(*resource_type),
+                id,
+                None,
+                0,
+                data.to_vec(),
+            )
+            .unwrap();
+
+        builder.write(vec![]).unwrap()
+    }
+
+    fn block_map(name: &[u8], sector_count: u32, chunks: &[(u32, u8, u32, u32)]) -> Vec<u8> {
+        let mut block_map = vec![0; CHUNK_TABLE_OFFSET];
+        block_map[0..2].copy_from_slice(&10u16.to_be_bytes());
+        block_map[2] = name.len() as u8;
+        block_map[3..3 + name.len()].copy_from_slice(name);
+        block_map[68..72].copy_from_slice(&sector_count.to_be_bytes());
+        block_map[124..128].copy_from_slice(&(chunks.len() as u32).to_be_bytes());
+
+        for (start, kind, offset, len) in chunks {
+            block_map.extend_from_slice(&((start << 8) | *kind as u32).to_be_bytes());
+            block_map.extend_from_slice(&offset.to_be_bytes());
+            block_map.extend_from_slice(&len.to_be_bytes());
+        }
+
+        block_map
+    }
+
+    #[test]
+    fn read_image() -> Result<(), Box<dyn std::error::Error>> {
+        // Two raw sectors, then three zero sectors, then two ADC-compressed sectors that repeat a
+        // single literal byte
+        let mut data_fork = vec![0xee; 10];
+        data_fork.extend((0..1024u32).map(|i| (i % 251) as u8));
+        data_fork.extend_from_slice(&[0x80, 0x5a]);
+        data_fork.extend([0x7f, 0x00, 0x00].repeat(15));
+        data_fork.extend_from_slice(&[0x4e, 0x00, 0x00]);
+
+        let compressed_len = data_fork.len() as u32 - 1034;
+
+        let block_map = block_map(
+            b"Test Disk",
+            7,
+            &[
+                (0, RAW_CHUNK, 0, 1024),
+                (2, ZERO_CHUNK, 0, 0),
+                (5, ADC_CHUNK, 1024, compressed_len),
+                (7, END_CHUNK, 0, 0),
+            ],
+        );
+
+        let mut source = Cursor::new(data_fork);
+        source.seek(SeekFrom::Start(10))?;
+
+        let mut image = NdifImage::new(
+            source,
+            Cursor::new(resource_fork_bytes(b"bcem", 128, &block_map)),
+        )?;
+
+        assert_eq!("Test Disk", image.name());
+        assert_eq!(7 * 512, image.data_len());
+
+        let mut expected: Vec<u8> = (0..1024u32).map(|i| (i % 251) as u8).collect();
+        expected.extend(vec![0; 1536]);
+        expected.extend(vec![0x5a; 1024]);
+
+        let mut content = vec![];
+        image.read_to_end(&mut content)?;
+        assert_eq!(expected, content);
+
+        let mut buf = [0; 8];
+        image.seek(SeekFrom::Start(2556))?;
+        image.read_exact(&mut buf)?;
+        assert_eq!(expected[2556..2564], buf);
+
+        image.seek(SeekFrom::End(-4))?;
+        let mut buf = vec![];
+        image.read_to_end(&mut buf)?;
+        assert_eq!(vec![0x5a; 4], buf);
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_images() {
+        let fork = resource_fork_bytes(b"bcem", 129, &block_map(b"Test Disk", 0, &[]));
+
+        assert!(matches!(
+            NdifImage::new(Cursor::new(vec![]), Cursor::new(fork)),
+            Err(DiskCopyError::InvalidHeader)
+        ));
+
+        let fork = resource_fork_bytes(
+            b"bcem",
+            128,
+            &block_map(b"Test Disk", 4, &[(0, 0x83, 0, 100), (4, END_CHUNK, 0, 0)]),
+        );
+
+        assert!(matches!(
+            NdifImage::new(Cursor::new(vec![]), Cursor::new(fork)),
+            Err(DiskCopyError::UnsupportedCompression(0x83))
+        ));
+
+        // A compressed chunk that claims to span far more sectors than it could reasonably hold
+        let fork = resource_fork_bytes(
+            b"bcem",
+            128,
+            &block_map(b"Test Disk", 0x00ff_ffff, &[(0, ADC_CHUNK, 0, 2)]),
+        );
+
+        assert!(matches!(
+            NdifImage::new(Cursor::new(vec![0x80, 0x00]), Cursor::new(fork)),
+            Err(DiskCopyError::InvalidHeader)
+        ));
+
+        // A chunk that starts beyond the end of the disk
+        let fork = resource_fork_bytes(
+            b"bcem",
+            128,
+            &block_map(
+                b"Test Disk",
+                1,
+                &[(0, RAW_CHUNK, 0, 512), (2, END_CHUNK, 0, 0)],
+            ),
+        );
+
+        assert!(matches!(
+            NdifImage::new(Cursor::new(vec![0; 512]), Cursor::new(fork)),
+            Err(DiskCopyError::InvalidHeader)
+        ));
+
+        // A compressed chunk that expands to less than its sectors' worth of data
+        let fork = resource_fork_bytes(
+            b"bcem",
+            128,
+            &block_map(
+                b"Test Disk",
+                1,
+                &[(0, ADC_CHUNK, 0, 2), (1, END_CHUNK, 0, 0)],
+            ),
+        );
+
+        let mut image = NdifImage::new(Cursor::new(vec![0x80, 0x00]), Cursor::new(fork)).unwrap();
+        let mut content = vec![];
+
+        assert_eq!(
+            io::ErrorKind::InvalidData,
+            image.read_to_end(&mut content).unwrap_err().kind()
+        );
+    }
+}
@@ -0,0 +1,340 @@
+//! Finder metadata associated with "classic" Macintosh files.
+//!
+//! In addition to its forks, every classic Mac file carried a small amount of metadata used by the
+//! Finder: a file type, a creator, and a set of flags that controlled how the Finder displayed and
+//! treated the file. Archive formats like BinHex preserve some or all of this metadata.
+//!
+//! For details about Finder metadata, please see [the "Finder Interface" chapter of "Inside
+//! Macintosh: Macintosh Toolbox
+//! Essentials"](https://developer.apple.com/library/archive/documentation/mac/pdf/MacintoshToolboxEssentials.pdf#page=797).
+
+use crate::util::i16_at;
+
+const IS_ALIAS: u16 = 0x8000;
+const IS_INVISIBLE: u16 = 0x4000;
+const HAS_BUNDLE: u16 = 0x2000;
+const NAME_LOCKED: u16 = 0x1000;
+const IS_STATIONERY: u16 = 0x0800;
+const HAS_CUSTOM_ICON: u16 = 0x0400;
+const HAS_BEEN_INITED: u16 = 0x0100;
+const HAS_NO_INITS: u16 = 0x0080;
+const IS_SHARED: u16 = 0x0040;
+const COLOR: u16 = 0x000e;
+const IS_ON_DESK: u16 = 0x0001;
+
+/// The Finder flags for a file.
+///
+/// Finder flags are stored as a 16-bit field; this type provides typed access to the individual
+/// flags while preserving any reserved bits exactly as they were read. For a detailed description
+/// of the Finder flags, please see [the "File Information Record" section of "Inside Macintosh:
+/// Macintosh Toolbox
+/// Essentials"](https://developer.apple.com/library/archive/documentation/mac/pdf/MacintoshToolboxEssentials.pdf#page=845).
+///
+/// # Example
+///
+/// ```
+/// use clarus::finder::FinderFlags;
+///
+/// let mut flags = FinderFlags::from(0x0100);
+/// assert!(flags.has_been_inited());
+///
+/// flags.set_invisible(true);
+/// assert_eq!(0x4100, flags.bits());
+/// ```
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct FinderFlags(u16);
+
+impl FinderFlags {
+    /// Returns the raw 16-bit value of these flags.
+    pub fn bits(&self) -> u16 {
+        self.0
+    }
+
+    /// Returns `true` if the file is an alias file.
+    pub fn is_alias(&self) -> bool {
+        self.get(IS_ALIAS)
+    }
+
+    /// Sets whether the file is an alias file.
+    pub fn set_alias(&mut self, value: bool) {
+        self.set(IS_ALIAS, value)
+    }
+
+    /// Returns `true` if the file is invisible in the Finder.
+    pub fn is_invisible(&self) -> bool {
+        self.get(IS_INVISIBLE)
+    }
+
+    /// Sets whether the file is invisible in the Finder.
+    pub fn set_invisible(&mut self, value: bool) {
+        self.set(IS_INVISIBLE, value)
+    }
+
+    /// Returns `true` if the file contains a bundle resource (i.e. a `BNDL` resource that
+    /// associates icons with file types).
+    pub fn has_bundle(&self) -> bool {
+        self.get(HAS_BUNDLE)
+    }
+
+    /// Sets whether the file contains a bundle resource.
+    pub fn set_has_bundle(&mut self, value: bool) {
+        self.set(HAS_BUNDLE, value)
+    }
+
+    /// Returns `true` if the file's name and icon are locked.
+    pub fn is_name_locked(&self) -> bool {
+        self.get(NAME_LOCKED)
+    }
+
+    /// Sets whether the file's name and icon are locked.
+    pub fn set_name_locked(&mut self, value: bool) {
+        self.set(NAME_LOCKED, value)
+    }
+
+    /// Returns `true` if the file is a stationery pad.
+    pub fn is_stationery(&self) -> bool {
+        self.get(IS_STATIONERY)
+    }
+
+    /// Sets whether the file is a stationery pad.
+    pub fn set_stationery(&mut self, value: bool) {
+        self.set(IS_STATIONERY, value)
+    }
+
+    /// Returns `true` if the file has a custom icon (stored in its resource fork).
+    pub fn has_custom_icon(&self) -> bool {
+        self.get(HAS_CUSTOM_ICON)
+    }
+
+    /// Sets whether the file has a custom icon.
+    pub fn set_has_custom_icon(&mut self, value: bool) {
+        self.set(HAS_CUSTOM_ICON, value)
+    }
+
+    /// Returns `true` if the Finder has recorded the file's bundle information in the desktop
+    /// database.
+    pub fn has_been_inited(&self) -> bool {
+        self.get(HAS_BEEN_INITED)
+    }
+
+    /// Sets whether the Finder has recorded the file's bundle information.
+    pub fn set_has_been_inited(&mut self, value: bool) {
+        self.set(HAS_BEEN_INITED, value)
+    }
+
+    /// Returns `true` if the file contains no system extension (`INIT`) resources.
+    pub fn has_no_inits(&self) -> bool {
+        self.get(HAS_NO_INITS)
+    }
+
+    /// Sets whether the file contains no system extension resources.
+    pub fn set_has_no_inits(&mut self, value: bool) {
+        self.set(HAS_NO_INITS, value)
+    }
+
+    /// Returns `true` if the file (an application) can be launched by more than one user at a time
+    /// from a shared volume.
+    pub fn is_shared(&self) -> bool {
+        self.get(IS_SHARED)
+    }
+
+    /// Sets whether the file can be launched by more than one user at a time.
+    pub fn set_shared(&mut self, value: bool) {
+        self.set(IS_SHARED, value)
+    }
+
+    /// Returns the file's color label, between 0 (no label) and 7.
+    pub fn color(&self) -> u8 {
+        ((self.0 & COLOR) >> 1) as u8
+    }
+
+    /// Sets the file's color label; only the lowest three bits of the given value are used.
+    pub fn set_color(&mut self, color: u8) {
+        self.0 = (self.0 & !COLOR) | (((color as u16) << 1) & COLOR);
+    }
+
+    /// Returns `true` if the file is located on the desktop.
+    pub fn is_on_desk(&self) -> bool {
+        self.get(IS_ON_DESK)
+    }
+
+    /// Sets whether the file is located on the desktop.
+    pub fn set_on_desk(&mut self, value: bool) {
+        self.set(IS_ON_DESK, value)
+    }
+
+    fn get(&self, mask: u16) -> bool {
+        self.0 & mask != 0
+    }
+
+    fn set(&mut self, mask: u16, value: bool) {
+        if value {
+            self.0 |= mask;
+        } else {
+            self.0 &= !mask;
+        }
+    }
+}
+
+impl From<u16> for FinderFlags {
+    fn from(bits: u16) -> Self {
+        FinderFlags(bits)
+    }
+}
+
+impl From<FinderFlags> for u16 {
+    fn from(flags: FinderFlags) -> Self {
+        flags.0
+    }
+}
+
+/// The Finder information for a file (known as `FInfo` in "Inside Macintosh").
+///
+/// Finder information includes a file's type, creator, Finder flags, and location within its
+/// window. Many archive formats store Finder information as the 16-byte structure described in
+/// [the "File Information Record" section of "Inside Macintosh: Macintosh Toolbox
+/// Essentials"](https://developer.apple.com/library/archive/documentation/mac/pdf/MacintoshToolboxEssentials.pdf#page=845).
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct FinderInfo {
+    file_type: [u8; 4],
+    creator: [u8; 4],
+    flags: FinderFlags,
+    location: (i16, i16),
+    folder: i16,
+}
+
+impl FinderInfo {
+    /// Creates new Finder information with the given type, creator, and flags, located at the
+    /// origin of its window.
+    pub fn new(file_type: [u8; 4], creator: [u8; 4], flags: FinderFlags) -> Self {
+        FinderInfo {
+            file_type,
+            creator,
+            flags,
+            location: (0, 0),
+            folder: 0,
+        }
+    }
+
+    /// Returns the file's type identifier.
+    pub fn file_type(&self) -> [u8; 4] {
+        self.file_type
+    }
+
+    /// Returns the file's creator identifier.
+    pub fn creator(&self) -> [u8; 4] {
+        self.creator
+    }
+
+    /// Returns the file's Finder flags.
+    pub fn flags(&self) -> FinderFlags {
+        self.flags
+    }
+
+    /// Returns the vertical and horizontal position of the file's icon within its window.
+    pub fn location(&self) -> (i16, i16) {
+        self.location
+    }
+
+    /// Sets the vertical and horizontal position of the file's icon within its window.
+    pub fn set_location(&mut self, location: (i16, i16)) {
+        self.location = location;
+    }
+
+    /// Returns the ID of the window (or folder) that contains the file.
+    pub fn folder(&self) -> i16 {
+        self.folder
+    }
+
+    /// Sets the ID of the window (or folder) that contains the file.
+    pub fn set_folder(&mut self, folder: i16) {
+        self.folder = folder;
+    }
+}
+
+impl From<[u8; 16]> for FinderInfo {
+    fn from(bytes: [u8; 16]) -> Self {
+        FinderInfo {
+            file_type: [bytes[0], bytes[1], bytes[2], bytes[3]],
+            creator: [bytes[4], bytes[5], bytes[6], bytes[7]],
+            flags: FinderFlags::from(u16::from_be_bytes([bytes[8], bytes[9]])),
+            location: (i16_at(&bytes, 10), i16_at(&bytes, 12)),
+            folder: i16_at(&bytes, 14),
+        }
+    }
+}
+
+impl From<&FinderInfo> for [u8; 16] {
+    fn from(info: &FinderInfo) -> Self {
+        let mut bytes = [0; 16];
+
+        bytes[0..4].copy_from_slice(&info.file_type);
+        bytes[4..8].copy_from_slice(&info.creator);
+        bytes[8..10].copy_from_slice(&info.flags.bits().to_be_bytes());
+        bytes[10..12].copy_from_slice(&info.location.0.to_be_bytes());
+        bytes[12..14].copy_from_slice(&info.location.1.to_be_bytes());
+        bytes[14..16].copy_from_slice(&info.folder.to_be_bytes());
+
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn flags() {
+        let flags = FinderFlags::from(0xe501);
+
+        assert!(flags.is_alias());
+        assert!(flags.is_invisible());
+        assert!(flags.has_bundle());
+        assert!(!flags.is_name_locked());
+        assert!(!flags.is_stationery());
+        assert!(flags.has_custom_icon());
+        assert!(flags.has_been_inited());
+        assert!(!flags.has_no_inits());
+        assert!(!flags.is_shared());
+        assert_eq!(0, flags.color());
+        assert!(flags.is_on_desk());
+    }
+
+    #[test]
+    fn set_flags() {
+        let mut flags = FinderFlags::default();
+
+        flags.set_name_locked(true);
+        flags.set_shared(true);
+        flags.set_color(5);
+
+        assert_eq!(0x104a, flags.bits());
+
+        flags.set_name_locked(false);
+        flags.set_color(0x0f);
+
+        assert_eq!(0x004e, flags.bits());
+    }
+
+    #[test]
+    fn reserved_bits_preserved() {
+        let mut flags = FinderFlags::from(0x0230);
+        flags.set_stationery(true);
+
+        assert_eq!(0x0a30, u16::from(flags));
+    }
+
+    #[test]
+    fn finder_info() {
+        let bytes = *b"TEXTttxt\x01\x00\x00\x10\xff\xf0\xff\xfe";
+        let info = FinderInfo::from(bytes);
+
+        assert_eq!(*b"TEXT", info.file_type());
+        assert_eq!(*b"ttxt", info.creator());
+        assert!(info.flags().has_been_inited());
+        assert_eq!((16, -16), info.location());
+        assert_eq!(-2, info.folder());
+
+        assert_eq!(bytes, <[u8; 16]>::from(&info));
+    }
+}
@@ -0,0 +1,248 @@
+//! Reading for the B-trees that hold HFS and HFS+ catalogs and extents overflow records. Both
+//! file systems use the same node layout: a 14-byte node descriptor, followed by the node's
+//! records, followed by a list of record offsets that grows backward from the end of the node.
+
+use super::volume::HfsError;
+use crate::util::{u16_at, u32_at};
+
+const NODE_DESCRIPTOR_LENGTH: usize = 14;
+
+/// The minimum length of a B-tree node; the header node that appears at the start of every B-tree
+/// file may be longer, but its header record always fits within the first 512 bytes.
+pub(super) const MIN_NODE_SIZE: usize = 512;
+
+const LEAF_NODE: u8 = 0xff;
+const HEADER_NODE: u8 = 0x01;
+
+/// The B-tree's header record, which appears in the tree's first node.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+struct HeaderRecord {
+    first_leaf: u32,
+    node_size: usize,
+    node_count: u32,
+}
+
+/// A node's descriptor and records.
+struct Node<'a> {
+    kind: u8,
+    next: u32,
+    records: Vec<&'a [u8]>,
+}
+
+impl<'a> Node<'a> {
+    fn parse(bytes: &'a [u8]) -> Result<Self, HfsError> {
+        if bytes.len() < NODE_DESCRIPTOR_LENGTH {
+            return Err(HfsError::CorruptBTree);
+        }
+
+        let record_count = u16_at(bytes, 10) as usize;
+        let offsets_start = bytes
+            .len()
+            .checked_sub(2 * (record_count + 1))
+            .ok_or(HfsError::CorruptBTree)?;
+
+        // Offsets are stored in reverse order, and the last offset marks the start of the node's
+        // free space
+        let offset = |i: usize| u16_at(bytes, bytes.len() - 2 * (i + 1)) as usize;
+
+        let records = (0..record_count)
+            .map(|i| {
+                let (start, end) = (offset(i), offset(i + 1));
+
+                if start < NODE_DESCRIPTOR_LENGTH || start > end || end > offsets_start {
+                    Err(HfsError::CorruptBTree)
+                } else {
+                    Ok(&bytes[start..end])
+                }
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Node {
+            kind: bytes[8],
+            next: u32_at(bytes, 0),
+            records,
+        })
+    }
+}
+
+/// Reads every record in the leaf nodes of a B-tree, in key order.
+///
+/// `read` fills the given buffer with the content of the B-tree file at the given offset.
+pub(super) fn leaf_records<F>(mut read: F) -> Result<Vec<Vec<u8>>, HfsError>
+where
+    F: FnMut(u64, &mut [u8]) -> Result<(), HfsError>,
+{
+    let mut header_node = vec![0; MIN_NODE_SIZE];
+    read(0, &mut header_node)?;
+
+    // The header node's record offsets are at the end of the node, whose size we don't know yet,
+    // but the header record always immediately follows the node descriptor
+    if header_node[8] != HEADER_NODE {
+        return Err(HfsError::CorruptBTree);
+    }
+
+    let header = parse_header_record(&header_node[NODE_DESCRIPTOR_LENGTH..])?;
+
+    let mut records = vec![];
+    let mut node_buffer = vec![0; header.node_size];
+    let mut next = header.first_leaf;
+    let mut nodes_read = 0;
+
+    while next != 0 {
+        // Guard against cycles in the chain of leaf nodes
+        nodes_read += 1;
+
+        if nodes_read > header.node_count {
+            return Err(HfsError::CorruptBTree);
+        }
+
+        read(next as u64 * header.node_size as u64, &mut node_buffer)?;
+        let node = Node::parse(&node_buffer)?;
+
+        if node.kind != LEAF_NODE {
+            return Err(HfsError::CorruptBTree);
+        }
+
+        records.extend(node.records.iter().map(|record| record.to_vec()));
+        next = node.next;
+    }
+
+    Ok(records)
+}
+
+fn parse_header_record(record: &[u8]) -> Result<HeaderRecord, HfsError> {
+    if record.len() < 26 {
+        return Err(HfsError::CorruptBTree);
+    }
+
+    let node_size = u16_at(record, 18) as usize;
+
+    if node_size < MIN_NODE_SIZE || !node_size.is_power_of_two() {
+        return Err(HfsError::CorruptBTree);
+    }
+
+    Ok(HeaderRecord {
+        first_leaf: u32_at(record, 10),
+        node_size,
+        node_count: u32_at(record, 22),
+    })
+}
+
+#[cfg(test)]
+pub(super) mod test {
+    use super::*;
+
+    /// Builds a B-tree file with the given node size, whose leaf nodes contain the given records.
+    /// Index nodes are omitted, since they're never read.
+    pub fn btree(records: &[Vec<u8>], node_size: usize) -> Vec<u8> {
+        let mut leaves: Vec<Vec<&[u8]>> = vec![vec![]];
+
+        for record in records {
+            let leaf = leaves.last().unwrap();
+            let used: usize = leaf.iter().map(|record| record.len() + 2).sum();
+
+            if NODE_DESCRIPTOR_LENGTH + used + record.len() + 4 > node_size {
+                leaves.push(vec![]);
+            }
+
+            leaves.last_mut().unwrap().push(record);
+        }
+
+        if records.is_empty() {
+            leaves.clear();
+        }
+
+        let node_count = leaves.len() + 1;
+
+        let mut header_record = vec![0; 106];
+        header_record[0..2].copy_from_slice(&1u16.to_be_bytes());
+        header_record[2..6].copy_from_slice(&(leaves.len().min(1) as u32).to_be_bytes());
+        header_record[6..10].copy_from_slice(&(records.len() as u32).to_be_bytes());
+        header_record[10..14].copy_from_slice(&(leaves.len().min(1) as u32).to_be_bytes());
+        header_record[14..18].copy_from_slice(&(leaves.len() as u32).to_be_bytes());
+        header_record[18..20].copy_from_slice(&(node_size as u16).to_be_bytes());
+        header_record[22..26].copy_from_slice(&(node_count as u32).to_be_bytes());
+
+        let mut file = node(HEADER_NODE, 0, &[&header_record], node_size);
+
+        for (i, leaf) in leaves.iter().enumerate() {
+            let next = if i + 1 < leaves.len() { i + 2 } else { 0 };
+            file.extend(node(LEAF_NODE, next as u32, leaf, node_size));
+        }
+
+        file
+    }
+
+    fn node(kind: u8, next: u32, records: &[&[u8]], node_size: usize) -> Vec<u8> {
+        let mut node = vec![0; node_size];
+        node[0..4].copy_from_slice(&next.to_be_bytes());
+        node[8] = kind;
+        node[9] = if kind == LEAF_NODE { 1 } else { 0 };
+        node[10..12].copy_from_slice(&(records.len() as u16).to_be_bytes());
+
+        let mut offset = NODE_DESCRIPTOR_LENGTH;
+
+        for (i, record) in records.iter().enumerate() {
+            node[offset..offset + record.len()].copy_from_slice(record);
+            node[node_size - 2 * (i + 1)..node_size - 2 * i]
+                .copy_from_slice(&(offset as u16).to_be_bytes());
+
+            offset += record.len();
+        }
+
+        let i = records.len();
+        node[node_size - 2 * (i + 1)..node_size - 2 * i]
+            .copy_from_slice(&(offset as u16).to_be_bytes());
+
+        node
+    }
+
+    fn read_from(file: &[u8]) -> impl FnMut(u64, &mut [u8]) -> Result<(), HfsError> + '_ {
+        move |offset, buf| {
+            let offset = offset as usize;
+
+            buf.copy_from_slice(
+                file.get(offset..offset + buf.len())
+                    .ok_or(HfsError::CorruptBTree)?,
+            );
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn read_leaf_records() -> Result<(), HfsError> {
+        let records: Vec<Vec<u8>> = (0..100u8).map(|i| vec![i; 1 + i as usize % 30]).collect();
+
+        for node_size in [512, 4096].iter() {
+            let file = btree(&records, *node_size);
+            assert_eq!(records, leaf_records(read_from(&file))?);
+        }
+
+        assert!(leaf_records(read_from(&btree(&[], 512)))?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_corrupt_btree() {
+        let records: Vec<Vec<u8>> = (0..100u8).map(|i| vec![i; 20]).collect();
+        let file = btree(&records, 512);
+
+        // Point the last leaf back at the first
+        let mut cycle = file.clone();
+        let last_leaf = cycle.len() - 512;
+        cycle[last_leaf..last_leaf + 4].copy_from_slice(&1u32.to_be_bytes());
+
+        assert_eq!(Err(HfsError::CorruptBTree), leaf_records(read_from(&cycle)));
+
+        // Point the first record of the first leaf beyond the record offsets
+        let mut bad_offset = file;
+        bad_offset[1024 - 2..1024].copy_from_slice(&600u16.to_be_bytes());
+
+        assert_eq!(
+            Err(HfsError::CorruptBTree),
+            leaf_records(read_from(&bad_offset))
+        );
+    }
+}
@@ -0,0 +1,335 @@
+use std::collections::{HashMap, HashSet};
+use std::convert::TryInto;
+
+use super::entry::HfsEntry;
+use super::fork::{Extent, ForkData};
+use super::volume::HfsError;
+use crate::finder::FinderInfo;
+use crate::util::{u16_at, u32_at};
+
+const FOLDER_RECORD: u8 = 1;
+const FILE_RECORD: u8 = 2;
+
+const FOLDER_RECORD_LENGTH: usize = 70;
+const FILE_RECORD_LENGTH: usize = 102;
+
+const HFS_PLUS_FOLDER_RECORD: u16 = 1;
+const HFS_PLUS_FILE_RECORD: u16 = 2;
+
+const HFS_PLUS_FOLDER_RECORD_LENGTH: usize = 88;
+const HFS_PLUS_FILE_RECORD_LENGTH: usize = 248;
+
+/// The length of an HFS+ fork data structure, which includes the fork's first eight extents.
+pub(super) const HFS_PLUS_FORK_DATA_LENGTH: usize = 80;
+
+/// The fork types that appear in extents overflow keys.
+pub(super) const DATA_FORK: u8 = 0x00;
+pub(super) const RESOURCE_FORK: u8 = 0xff;
+
+/// Overflow extents for each fork, keyed by fork type and file ID, in order of their starting
+/// block within the fork.
+pub(super) type OverflowExtents = HashMap<(u8, u32), Vec<(u32, Vec<Extent>)>>;
+
+/// Parses a group of three HFS extent descriptors, omitting empty extents.
+pub(super) fn hfs_extents(bytes: &[u8]) -> Vec<Extent> {
+    bytes
+        .chunks_exact(4)
+        .take(3)
+        .map(|extent| Extent {
+            start: u16_at(extent, 0) as u32,
+            count: u16_at(extent, 2) as u32,
+        })
+        .filter(|extent| extent.count > 0)
+        .collect()
+}
+
+/// Parses the leaf records of an HFS extents overflow B-tree.
+pub(super) fn hfs_overflow_extents(records: &[Vec<u8>]) -> Result<OverflowExtents, HfsError> {
+    let mut overflow = OverflowExtents::new();
+
+    for record in records {
+        // Keys are always seven bytes long (plus the length byte itself), and are followed by a
+        // single extent record
+        if record.len() < 20 || record[0] != 7 {
+            return Err(HfsError::CorruptBTree);
+        }
+
+        overflow
+            .entry((record[1], u32_at(record, 2)))
+            .or_default()
+            .push((u16_at(record, 6) as u32, hfs_extents(&record[8..20])));
+    }
+
+    for extents in overflow.values_mut() {
+        extents.sort_by_key(|(start, _)| *start);
+    }
+
+    Ok(overflow)
+}
+
+/// Parses a group of eight HFS+ extent descriptors, omitting empty extents.
+pub(super) fn hfs_plus_extents(bytes: &[u8]) -> Vec<Extent> {
+    bytes
+        .chunks_exact(8)
+        .take(8)
+        .map(|extent| Extent {
+            start: u32_at(extent, 0),
+            count: u32_at(extent, 4),
+        })
+        .filter(|extent| extent.count > 0)
+        .collect()
+}
+
+/// Parses an HFS+ fork data structure, which holds a fork's length and its first eight extents.
+pub(super) fn hfs_plus_fork(bytes: &[u8]) -> ForkData {
+    ForkData {
+        len: u64::from_be_bytes(bytes[0..8].try_into().unwrap()),
+        extents: hfs_plus_extents(&bytes[16..HFS_PLUS_FORK_DATA_LENGTH]),
+    }
+}
+
+/// Parses the leaf records of an HFS+ extents overflow B-tree.
+pub(super) fn hfs_plus_overflow_extents(records: &[Vec<u8>]) -> Result<OverflowExtents, HfsError> {
+    let mut overflow = OverflowExtents::new();
+
+    for record in records {
+        // Keys are always ten bytes long (plus the two-byte length itself), and are followed by a
+        // single extent record
+        if record.len() < 76 || u16_at(record, 0) != 10 {
+            return Err(HfsError::CorruptBTree);
+        }
+
+        overflow
+            .entry((record[2], u32_at(record, 4)))
+            .or_default()
+            .push((u32_at(record, 8), hfs_plus_extents(&record[12..76])));
+    }
+
+    for extents in overflow.values_mut() {
+        extents.sort_by_key(|(start, _)| *start);
+    }
+
+    Ok(overflow)
+}
+
+/// Appends a fork's overflow extents (if any) to the extents recorded in its catalog record.
+pub(super) fn with_overflow(
+    mut fork: ForkData,
+    overflow: &OverflowExtents,
+    fork_type: u8,
+    id: u32,
+) -> ForkData {
+    if let Some(records) = overflow.get(&(fork_type, id)) {
+        for (_, extents) in records {
+            fork.extents.extend_from_slice(extents);
+        }
+    }
+
+    fork
+}
+
+/// Parses an HFS catalog leaf record, returning `None` for thread records.
+///
+/// The returned entry's path contains only the entry's own name.
+pub(super) fn hfs_catalog_entry(
+    record: &[u8],
+    overflow: &OverflowExtents,
+) -> Result<Option<HfsEntry>, HfsError> {
+    let key_len = *record.first().ok_or(HfsError::CorruptBTree)? as usize;
+    let name_len = *record.get(6).ok_or(HfsError::CorruptBTree)? as usize;
+
+    // The key length doesn't include the length byte itself
+    if key_len < 6 + name_len || record.len() < key_len + 1 {
+        return Err(HfsError::CorruptBTree);
+    }
+
+    let parent_id = u32_at(record, 2);
+    let (name, _, _) = encoding_rs::MACINTOSH.decode(&record[7..7 + name_len]);
+
+    // Record data always starts on an even offset
+    let data = record
+        .get((key_len + 2) & !1..)
+        .ok_or(HfsError::CorruptBTree)?;
+
+    let mut entry = HfsEntry {
+        path: vec![name.to_string()],
+        id: 0,
+        parent_id,
+        is_folder: false,
+        finder_info: FinderInfo::default(),
+        created: 0,
+        modified: 0,
+        backed_up: 0,
+        data_fork: ForkData::default(),
+        resource_fork: ForkData::default(),
+    };
+
+    match data.first() {
+        Some(&FOLDER_RECORD) if data.len() >= FOLDER_RECORD_LENGTH => {
+            entry.id = u32_at(data, 6);
+            entry.is_folder = true;
+            entry.finder_info = folder_info(&data[22..38]);
+            entry.created = u32_at(data, 10);
+            entry.modified = u32_at(data, 14);
+            entry.backed_up = u32_at(data, 18);
+        }
+        Some(&FILE_RECORD) if data.len() >= FILE_RECORD_LENGTH => {
+            let id = u32_at(data, 20);
+
+            let data_fork = ForkData {
+                len: u32_at(data, 26) as u64,
+                extents: hfs_extents(&data[74..86]),
+            };
+
+            let resource_fork = ForkData {
+                len: u32_at(data, 36) as u64,
+                extents: hfs_extents(&data[86..98]),
+            };
+
+            let finder_info: [u8; 16] = data[4..20].try_into().unwrap();
+
+            entry.id = id;
+            entry.finder_info = FinderInfo::from(finder_info);
+            entry.created = u32_at(data, 44);
+            entry.modified = u32_at(data, 48);
+            entry.backed_up = u32_at(data, 52);
+            entry.data_fork = with_overflow(data_fork, overflow, DATA_FORK, id);
+            entry.resource_fork = with_overflow(resource_fork, overflow, RESOURCE_FORK, id);
+        }
+        Some(&FOLDER_RECORD) | Some(&FILE_RECORD) => return Err(HfsError::CorruptBTree),
+        _ => return Ok(None),
+    }
+
+    Ok(Some(entry))
+}
+
+/// Parses an HFS+ catalog leaf record, returning `None` for thread records.
+///
+/// The returned entry's path contains only the entry's own name.
+pub(super) fn hfs_plus_catalog_entry(
+    record: &[u8],
+    overflow: &OverflowExtents,
+) -> Result<Option<HfsEntry>, HfsError> {
+    if record.len() < 8 {
+        return Err(HfsError::CorruptBTree);
+    }
+
+    let key_len = u16_at(record, 0) as usize;
+    let name_len = u16_at(record, 6) as usize;
+
+    if key_len < 6 + 2 * name_len || record.len() < key_len + 2 {
+        return Err(HfsError::CorruptBTree);
+    }
+
+    let parent_id = u32_at(record, 2);
+
+    // Names are stored as UTF-16 in Unicode's canonical decomposition
+    let name: Vec<u16> = (0..name_len).map(|i| u16_at(record, 8 + 2 * i)).collect();
+
+    let data = &record[key_len + 2..];
+
+    let mut entry = HfsEntry {
+        path: vec![String::from_utf16_lossy(&name)],
+        id: 0,
+        parent_id,
+        is_folder: false,
+        finder_info: FinderInfo::default(),
+        created: 0,
+        modified: 0,
+        backed_up: 0,
+        data_fork: ForkData::default(),
+        resource_fork: ForkData::default(),
+    };
+
+    match data.get(0..2).map(|record_type| u16_at(record_type, 0)) {
+        Some(HFS_PLUS_FOLDER_RECORD) if data.len() >= HFS_PLUS_FOLDER_RECORD_LENGTH => {
+            entry.id = u32_at(data, 8);
+            entry.is_folder = true;
+            entry.finder_info = folder_info(&data[48..64]);
+            entry.created = u32_at(data, 12);
+            entry.modified = u32_at(data, 16);
+            entry.backed_up = u32_at(data, 28);
+        }
+        Some(HFS_PLUS_FILE_RECORD) if data.len() >= HFS_PLUS_FILE_RECORD_LENGTH => {
+            let id = u32_at(data, 8);
+            let finder_info: [u8; 16] = data[48..64].try_into().unwrap();
+
+            entry.id = id;
+            entry.finder_info = FinderInfo::from(finder_info);
+            entry.created = u32_at(data, 12);
+            entry.modified = u32_at(data, 16);
+            entry.backed_up = u32_at(data, 28);
+            entry.data_fork = with_overflow(hfs_plus_fork(&data[88..168]), overflow, DATA_FORK, id);
+            entry.resource_fork =
+                with_overflow(hfs_plus_fork(&data[168..248]), overflow, RESOURCE_FORK, id);
+        }
+        Some(HFS_PLUS_FOLDER_RECORD) | Some(HFS_PLUS_FILE_RECORD) => {
+            return Err(HfsError::CorruptBTree)
+        }
+        _ => return Ok(None),
+    }
+
+    Ok(Some(entry))
+}
+
+/// Interprets a folder's 16-byte Finder information, which shares its flags and location fields
+/// with a file's Finder information, but has a window rectangle in place of a type and creator.
+pub(super) fn folder_info(bytes: &[u8]) -> FinderInfo {
+    let mut info: [u8; 16] = bytes[..16].try_into().unwrap();
+    info[0..8].copy_from_slice(&[0; 8]);
+    info[14..16].copy_from_slice(&[0; 2]);
+
+    FinderInfo::from(info)
+}
+
+/// Arranges catalog entries (whose paths contain only their own names) into a tree below the
+/// folder with the given ID, returning the entries in depth-first order with their full paths.
+/// Folders precede their contents, and entries that aren't reachable from the root are omitted.
+pub(super) fn arrange(entries: Vec<HfsEntry>, root_id: u32) -> Vec<HfsEntry> {
+    let mut children: HashMap<u32, Vec<usize>> = HashMap::new();
+
+    for (i, entry) in entries.iter().enumerate() {
+        children.entry(entry.parent_id).or_default().push(i);
+    }
+
+    let mut arranged = Vec::with_capacity(entries.len());
+    let mut visited = HashSet::new();
+    visited.insert(root_id);
+
+    let mut stack: Vec<(usize, Vec<String>)> = children
+        .get(&root_id)
+        .map(|indices| indices.iter().rev().map(|i| (*i, vec![])).collect())
+        .unwrap_or_default();
+
+    while let Some((i, mut path)) = stack.pop() {
+        let mut entry = entries[i].clone();
+        path.push(entry.name().clone());
+        entry.path = path.clone();
+
+        if entry.is_folder && visited.insert(entry.id) {
+            if let Some(indices) = children.get(&entry.id) {
+                stack.extend(indices.iter().rev().map(|i| (*i, path.clone())));
+            }
+        }
+
+        arranged.push(entry);
+    }
+
+    arranged
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn truncated_hfs_record() {
+        // A key that claims a five-character name, but ends after the first character
+        let record = [12, 0, 0, 0, 0, 2, 5, b'F'];
+
+        assert!(matches!(
+            hfs_catalog_entry(&record, &OverflowExtents::new()),
+            Err(HfsError::CorruptBTree)
+        ));
+    }
+}
@@ -0,0 +1,99 @@
+use super::fork::ForkData;
+use crate::finder::{FinderFlags, FinderInfo};
+
+/// A file or folder on an HFS or HFS+ volume.
+///
+/// Entries for files carry the file's Finder metadata and describe its forks, which may be read
+/// with the `data_fork` and `resource_fork` methods of the volume that produced the entry.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HfsEntry {
+    pub(super) path: Vec<String>,
+    pub(super) id: u32,
+    pub(super) parent_id: u32,
+    pub(super) is_folder: bool,
+    pub(super) finder_info: FinderInfo,
+    pub(super) created: u32,
+    pub(super) modified: u32,
+    pub(super) backed_up: u32,
+    pub(super) data_fork: ForkData,
+    pub(super) resource_fork: ForkData,
+}
+
+impl HfsEntry {
+    /// Returns the names of the folders that contain this entry, starting with the outermost folder
+    /// below the volume's root folder, followed by the name of this entry itself.
+    pub fn path(&self) -> &[String] {
+        &self.path
+    }
+
+    /// Returns the name of this entry.
+    pub fn name(&self) -> &String {
+        // Paths always contain at least the entry's own name
+        self.path.last().unwrap()
+    }
+
+    /// Returns this entry's catalog node ID, which uniquely identifies the entry within its volume.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Returns the catalog node ID of the folder that contains this entry.
+    pub fn parent_id(&self) -> u32 {
+        self.parent_id
+    }
+
+    /// Returns `true` if this entry is a folder rather than a file.
+    pub fn is_folder(&self) -> bool {
+        self.is_folder
+    }
+
+    /// Returns this entry's Finder information.
+    ///
+    /// Folders have no type or creator, and their Finder information includes only their flags and
+    /// location.
+    pub fn finder_info(&self) -> &FinderInfo {
+        &self.finder_info
+    }
+
+    /// Returns this entry's file type identifier.
+    pub fn file_type(&self) -> [u8; 4] {
+        self.finder_info.file_type()
+    }
+
+    /// Returns this entry's creator identifier.
+    pub fn creator(&self) -> [u8; 4] {
+        self.finder_info.creator()
+    }
+
+    /// Returns this entry's Finder flags.
+    pub fn flags(&self) -> FinderFlags {
+        self.finder_info.flags()
+    }
+
+    /// Returns this entry's creation date, expressed as seconds since midnight, January 1, 1904.
+    pub fn created(&self) -> u32 {
+        self.created
+    }
+
+    /// Returns this entry's modification date, expressed as seconds since midnight, January 1,
+    /// 1904.
+    pub fn modified(&self) -> u32 {
+        self.modified
+    }
+
+    /// Returns the date on which this entry was last backed up, expressed as seconds since
+    /// midnight, January 1, 1904, or zero if the entry has never been backed up.
+    pub fn backed_up(&self) -> u32 {
+        self.backed_up
+    }
+
+    /// Returns the length, in bytes, of this entry's data fork.
+    pub fn data_fork_len(&self) -> u64 {
+        self.data_fork.len
+    }
+
+    /// Returns the length, in bytes, of this entry's resource fork.
+    pub fn resource_fork_len(&self) -> u64 {
+        self.resource_fork.len
+    }
+}
@@ -0,0 +1,173 @@
+use std::cmp;
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::util::seek_position;
+
+/// A contiguous run of allocation blocks.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(super) struct Extent {
+    pub start: u32,
+    pub count: u32,
+}
+
+/// The length of a fork and the allocation blocks that hold its content, in order.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(super) struct ForkData {
+    pub len: u64,
+    pub extents: Vec<Extent>,
+}
+
+impl ForkData {
+    /// Returns the location of the given offset within the fork, expressed as an allocation block
+    /// number and an offset within that block, along with the number of bytes that may be read
+    /// contiguously from that location.
+    fn locate(&self, offset: u64, block_size: u64) -> Option<(u64, u64, u64)> {
+        let mut extent_start = 0;
+
+        for extent in &self.extents {
+            let extent_len = extent.count as u64 * block_size;
+
+            if offset < extent_start + extent_len {
+                let offset_in_extent = offset - extent_start;
+
+                return Some((
+                    extent.start as u64 + offset_in_extent / block_size,
+                    offset_in_extent % block_size,
+                    extent_len - offset_in_extent,
+                ));
+            }
+
+            extent_start += extent_len;
+        }
+
+        None
+    }
+}
+
+/// The information needed to find an allocation block within a volume's source.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(super) struct Geometry {
+    /// The offset of allocation block 0 within the source.
+    pub first_block_offset: u64,
+    pub block_size: u64,
+}
+
+/// A reader for the content of one fork of a file on an HFS or HFS+ volume.
+///
+/// Fork readers read directly from the underlying volume's source. Seeking is relative to the
+/// start of the fork, and reads never extend beyond the end of the fork.
+pub struct HfsForkReader<'a, R: Read + Seek> {
+    source: &'a mut R,
+    geometry: Geometry,
+    fork: ForkData,
+    position: u64,
+}
+
+impl<'a, R: Read + Seek> HfsForkReader<'a, R> {
+    pub(super) fn new(source: &'a mut R, geometry: Geometry, fork: ForkData) -> Self {
+        HfsForkReader {
+            source,
+            geometry,
+            fork,
+            position: 0,
+        }
+    }
+
+    /// Returns the length, in bytes, of the fork.
+    pub fn len(&self) -> u64 {
+        self.fork.len
+    }
+
+    /// Returns `true` if the fork is empty.
+    pub fn is_empty(&self) -> bool {
+        self.fork.len == 0
+    }
+}
+
+impl<'a, R: Read + Seek> Read for HfsForkReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position >= self.fork.len || buf.is_empty() {
+            return Ok(0);
+        }
+
+        let (block, offset_in_block, contiguous_len) = self
+            .fork
+            .locate(self.position, self.geometry.block_size)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+
+        let max_len = cmp::min(
+            buf.len() as u64,
+            cmp::min(self.fork.len - self.position, contiguous_len),
+        ) as usize;
+
+        self.source.seek(SeekFrom::Start(
+            self.geometry.first_block_offset + block * self.geometry.block_size + offset_in_block,
+        ))?;
+
+        let bytes_read = self.source.read(&mut buf[..max_len])?;
+        self.position += bytes_read as u64;
+
+        Ok(bytes_read)
+    }
+}
+
+impl<'a, R: Read + Seek> Seek for HfsForkReader<'a, R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.position = seek_position(pos, self.position, self.fork.len)?;
+
+        Ok(self.position)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn read_fragmented_fork() -> io::Result<()> {
+        // Four-byte blocks starting at offset 2, holding "abcd", "efgh", ...
+        let mut source = vec![0xff, 0xff];
+        for block in 0..8u8 {
+            source.extend((0..4).map(|i| b'a' + block * 4 + i));
+        }
+
+        let fork = ForkData {
+            len: 10,
+            extents: vec![Extent { start: 5, count: 1 }, Extent { start: 1, count: 2 }],
+        };
+
+        let geometry = Geometry {
+            first_block_offset: 2,
+            block_size: 4,
+        };
+
+        let mut source = Cursor::new(source);
+        let mut reader = HfsForkReader::new(&mut source, geometry, fork.clone());
+
+        let mut content = vec![];
+        reader.read_to_end(&mut content)?;
+        assert_eq!(b"uvwxefghij".to_vec(), content);
+
+        reader.seek(SeekFrom::End(-3))?;
+        content.clear();
+        reader.read_to_end(&mut content)?;
+        assert_eq!(b"hij".to_vec(), content);
+
+        // Forks whose extents don't cover their length are truncated
+        let short_fork = ForkData {
+            len: 20,
+            extents: fork.extents,
+        };
+
+        let mut reader = HfsForkReader::new(&mut source, geometry, short_fork);
+        let mut content = vec![];
+
+        assert_eq!(
+            io::ErrorKind::UnexpectedEof,
+            reader.read_to_end(&mut content).unwrap_err().kind()
+        );
+
+        Ok(())
+    }
+}
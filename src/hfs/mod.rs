@@ -0,0 +1,23 @@
+//! HFS ("Mac OS Standard") is the file system used by classic Mac OS volumes from the Macintosh
+//! Plus onward, including floppy disks, hard disks, and CD-ROMs. An HFS volume keeps the
+//! hierarchy of its files and folders in a catalog B-tree, and the location of each fork's content
+//! in a list of extents (with extents beyond the first three kept in a separate extents overflow
+//! B-tree).
+//!
+//! HFS+ ("Mac OS Extended") arrived with Mac OS 8.1, and keeps the same overall structure with
+//! smaller allocation blocks, more extents per fork, and Unicode names. Many HFS+ volumes are
+//! wrapped in an HFS volume whose only purpose is to tell older systems that they can't read the
+//! embedded HFS+ volume.
+//!
+//! This module provides read-only access to HFS and HFS+ volumes (including wrapped HFS+ volumes),
+//! including each file's data and resource forks and Finder information.
+
+mod btree;
+mod catalog;
+mod entry;
+mod fork;
+mod volume;
+
+pub use entry::HfsEntry;
+pub use fork::HfsForkReader;
+pub use volume::{HfsError, HfsVolume};
@@ -0,0 +1,876 @@
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Read, Seek, SeekFrom};
+
+use super::btree;
+use super::catalog::{self, DATA_FORK};
+use super::entry::HfsEntry;
+use super::fork::{ForkData, Geometry, HfsForkReader};
+use crate::util::{u16_at, u32_at};
+
+/// The offset of the master directory block (or HFS+ volume header) within a volume.
+pub(super) const MDB_OFFSET: u64 = 1024;
+
+/// The length of the sector that holds the master directory block or HFS+ volume header.
+const HEADER_LENGTH: usize = 512;
+
+/// The signature at the start of an HFS master directory block ("BD").
+pub(super) const HFS_SIGNATURE: u16 = 0x4244;
+
+/// The signature at the start of an HFS+ volume header ("H+"), which also identifies an HFS+
+/// volume embedded in an HFS wrapper volume.
+pub(super) const HFS_PLUS_SIGNATURE: u16 = 0x482b;
+
+/// The signature at the start of an HFSX (case-sensitive HFS+) volume header ("HX").
+pub(super) const HFSX_SIGNATURE: u16 = 0x4858;
+
+/// The length of the sectors in which the start of a volume's allocation blocks is measured.
+const SECTOR_SIZE: u64 = 512;
+
+/// The catalog node ID of every volume's root folder.
+pub(super) const ROOT_FOLDER_ID: u32 = 2;
+
+/// The catalog node ID of the catalog file, whose overflow extents are stored in the extents
+/// overflow file.
+pub(super) const CATALOG_FILE_ID: u32 = 4;
+
+/// An HFS ("Mac OS Standard") or HFS+ ("Mac OS Extended") volume.
+///
+/// The volume's catalog is read when the volume is opened, and each file's forks are read on
+/// demand. Volumes are read-only. HFS wrapper volumes that contain an embedded HFS+ volume are
+/// opened as the embedded HFS+ volume.
+///
+/// HFS stores dates in local time, while HFS+ stores all dates except the volume's creation date
+/// in UTC.
+pub struct HfsVolume<R: Read + Seek> {
+    source: R,
+    geometry: Geometry,
+    is_hfs_plus: bool,
+    name: String,
+    created: u32,
+    modified: u32,
+    entries: Vec<HfsEntry>,
+}
+
+impl<R: Read + Seek> HfsVolume<R> {
+    /// Opens the HFS or HFS+ volume that starts at the given source's current position, and reads
+    /// the volume's catalog.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the volume's master directory block or volume header
+    /// could not be read or doesn't describe an HFS or HFS+ volume, or if the volume's catalog or
+    /// extents overflow B-tree is malformed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use clarus::diskcopy::DiskCopyImage;
+    /// use clarus::hfs::HfsVolume;
+    /// use clarus::rsrc::ResourceFork;
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let image = DiskCopyImage::new(File::open("System Tools.image")?)?;
+    ///     let mut volume = HfsVolume::new(image)?;
+    ///
+    ///     for entry in volume.entries().to_vec() {
+    ///         if !entry.is_folder() && entry.resource_fork_len() > 0 {
+    ///             if let Ok(resource_fork) = ResourceFork::new(volume.resource_fork(&entry)) {
+    ///                 println!("{}: {} resources", entry.path().join(":"), resource_fork.resources().count());
+    ///             }
+    ///         }
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn new(mut source: R) -> Result<Self, HfsError> {
+        let base_offset = source.stream_position()?;
+        let header = read_header(&mut source, base_offset)?;
+
+        match u16_at(&header, 0) {
+            HFS_SIGNATURE if u16_at(&header, 124) == HFS_PLUS_SIGNATURE => {
+                // The embedded volume occupies a single extent within the wrapper volume
+                let geometry = hfs_geometry(&header, base_offset)?;
+                let embedded_offset =
+                    geometry.first_block_offset + u16_at(&header, 126) as u64 * geometry.block_size;
+
+                let header = read_header(&mut source, embedded_offset)?;
+
+                match u16_at(&header, 0) {
+                    HFS_PLUS_SIGNATURE | HFSX_SIGNATURE => {
+                        Self::open_hfs_plus(source, embedded_offset, &header)
+                    }
+                    _ => Err(HfsError::InvalidHeader),
+                }
+            }
+            HFS_SIGNATURE => Self::open_hfs(source, base_offset, &header),
+            HFS_PLUS_SIGNATURE | HFSX_SIGNATURE => {
+                Self::open_hfs_plus(source, base_offset, &header)
+            }
+            _ => Err(HfsError::InvalidHeader),
+        }
+    }
+
+    fn open_hfs(mut source: R, base_offset: u64, mdb: &[u8]) -> Result<Self, HfsError> {
+        let geometry = hfs_geometry(mdb, base_offset)?;
+
+        let name_len = (mdb[36] as usize).min(27);
+        let (name, _, _) = encoding_rs::MACINTOSH.decode(&mdb[37..37 + name_len]);
+
+        let extents_file = ForkData {
+            len: u32_at(mdb, 130) as u64,
+            extents: catalog::hfs_extents(&mdb[134..146]),
+        };
+
+        let overflow =
+            catalog::hfs_overflow_extents(&read_btree(&mut source, geometry, &extents_file)?)?;
+
+        let catalog_file = catalog::with_overflow(
+            ForkData {
+                len: u32_at(mdb, 146) as u64,
+                extents: catalog::hfs_extents(&mdb[150..162]),
+            },
+            &overflow,
+            DATA_FORK,
+            CATALOG_FILE_ID,
+        );
+
+        let mut entries = vec![];
+
+        for record in read_btree(&mut source, geometry, &catalog_file)? {
+            if let Some(entry) = catalog::hfs_catalog_entry(&record, &overflow)? {
+                entries.push(entry);
+            }
+        }
+
+        Ok(HfsVolume {
+            source,
+            geometry,
+            is_hfs_plus: false,
+            name: name.to_string(),
+            created: u32_at(mdb, 2),
+            modified: u32_at(mdb, 6),
+            entries: catalog::arrange(entries, ROOT_FOLDER_ID),
+        })
+    }
+
+    fn open_hfs_plus(mut source: R, base_offset: u64, header: &[u8]) -> Result<Self, HfsError> {
+        let block_size = u32_at(header, 40) as u64;
+
+        if block_size < SECTOR_SIZE || !block_size.is_power_of_two() {
+            return Err(HfsError::InvalidHeader);
+        }
+
+        // Unlike HFS, HFS+ allocation blocks start at the very beginning of the volume
+        let geometry = Geometry {
+            first_block_offset: base_offset,
+            block_size,
+        };
+
+        let extents_file = catalog::hfs_plus_fork(&header[192..272]);
+
+        let overflow =
+            catalog::hfs_plus_overflow_extents(&read_btree(&mut source, geometry, &extents_file)?)?;
+
+        let catalog_file = catalog::with_overflow(
+            catalog::hfs_plus_fork(&header[272..352]),
+            &overflow,
+            DATA_FORK,
+            CATALOG_FILE_ID,
+        );
+
+        let mut entries = vec![];
+
+        for record in read_btree(&mut source, geometry, &catalog_file)? {
+            if let Some(entry) = catalog::hfs_plus_catalog_entry(&record, &overflow)? {
+                entries.push(entry);
+            }
+        }
+
+        // The volume header doesn't include the volume's name, which is the root folder's name
+        let name = entries
+            .iter()
+            .find(|entry| entry.is_folder && entry.id == ROOT_FOLDER_ID)
+            .map(|root| root.name().clone())
+            .unwrap_or_default();
+
+        Ok(HfsVolume {
+            source,
+            geometry,
+            is_hfs_plus: true,
+            name,
+            created: u32_at(header, 16),
+            modified: u32_at(header, 20),
+            entries: catalog::arrange(entries, ROOT_FOLDER_ID),
+        })
+    }
+
+    /// Returns the name of this volume.
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    /// Returns `true` if this is an HFS+ (or HFSX) volume rather than an HFS volume.
+    pub fn is_hfs_plus(&self) -> bool {
+        self.is_hfs_plus
+    }
+
+    /// Returns this volume's creation date, expressed as seconds since midnight, January 1, 1904.
+    pub fn created(&self) -> u32 {
+        self.created
+    }
+
+    /// Returns the date this volume was last modified, expressed as seconds since midnight,
+    /// January 1, 1904.
+    pub fn modified(&self) -> u32 {
+        self.modified
+    }
+
+    /// Returns the size, in bytes, of this volume's allocation blocks.
+    pub fn block_size(&self) -> u64 {
+        self.geometry.block_size
+    }
+
+    /// Returns all of the files and folders on this volume (excluding the root folder), in
+    /// depth-first order. Folders precede their contents.
+    pub fn entries(&self) -> &[HfsEntry] {
+        &self.entries
+    }
+
+    /// Returns the file or folder with the given path, relative to the volume's root folder.
+    ///
+    /// Names are compared exactly, even though HFS and HFS+ generally ignore case. HFS+ names are
+    /// stored in Unicode's canonical decomposition, and so must be given in decomposed form.
+    pub fn entry(&self, path: &[&str]) -> Option<&HfsEntry> {
+        self.entries.iter().find(|entry| entry.path() == path)
+    }
+
+    /// Returns a reader for the given entry's data fork; folders have empty data forks.
+    pub fn data_fork(&mut self, entry: &HfsEntry) -> HfsForkReader<'_, R> {
+        HfsForkReader::new(&mut self.source, self.geometry, entry.data_fork.clone())
+    }
+
+    /// Returns a reader for the given entry's resource fork, which may be opened directly with
+    /// [`crate::rsrc::ResourceFork`]; folders have empty resource forks.
+    pub fn resource_fork(&mut self, entry: &HfsEntry) -> HfsForkReader<'_, R> {
+        HfsForkReader::new(&mut self.source, self.geometry, entry.resource_fork.clone())
+    }
+
+    /// Returns the source from which this volume reads its data.
+    pub fn into_inner(self) -> R {
+        self.source
+    }
+}
+
+/// Reads the sector that holds the master directory block or volume header of the volume that
+/// starts at the given offset.
+fn read_header<R: Read + Seek>(
+    source: &mut R,
+    base_offset: u64,
+) -> Result<[u8; HEADER_LENGTH], HfsError> {
+    let mut header = [0; HEADER_LENGTH];
+    source.seek(SeekFrom::Start(base_offset + MDB_OFFSET))?;
+    source.read_exact(&mut header)?;
+
+    Ok(header)
+}
+
+/// Finds the allocation blocks of the HFS volume with the given master directory block.
+fn hfs_geometry(mdb: &[u8], base_offset: u64) -> Result<Geometry, HfsError> {
+    let block_size = u32_at(mdb, 20) as u64;
+
+    if block_size == 0 || block_size % SECTOR_SIZE != 0 {
+        return Err(HfsError::InvalidHeader);
+    }
+
+    Ok(Geometry {
+        first_block_offset: base_offset + u16_at(mdb, 28) as u64 * SECTOR_SIZE,
+        block_size,
+    })
+}
+
+/// Reads the leaf records of the B-tree stored in the given fork.
+pub(super) fn read_btree<R: Read + Seek>(
+    source: &mut R,
+    geometry: Geometry,
+    fork: &ForkData,
+) -> Result<Vec<Vec<u8>>, HfsError> {
+    let mut reader = HfsForkReader::new(source, geometry, fork.clone());
+
+    btree::leaf_records(|offset, buf| {
+        reader.seek(SeekFrom::Start(offset))?;
+        reader.read_exact(buf)?;
+
+        Ok(())
+    })
+}
+
+/// The error type for operations on HFS and HFS+ volumes.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum HfsError {
+    /// An [`std::io::Error`] occurred while reading some part of the volume.
+    ///
+    /// The kind of IO error is included.
+    IoError(io::ErrorKind),
+
+    /// The volume's header was malformed or didn't describe a supported volume.
+    InvalidHeader,
+
+    /// A node or record in one of the volume's B-trees was malformed.
+    CorruptBTree,
+}
+
+impl Display for HfsError {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            HfsError::IoError(kind) => write!(fmt, "IO error: {:?}", kind),
+            HfsError::InvalidHeader => write!(fmt, "Invalid volume header"),
+            HfsError::CorruptBTree => write!(fmt, "Corrupt B-tree"),
+        }
+    }
+}
+
+impl From<io::Error> for HfsError {
+    fn from(error: io::Error) -> Self {
+        HfsError::IoError(error.kind())
+    }
+}
+
+impl error::Error for HfsError {}
+
+#[cfg(test)]
+mod test {
+    use super::super::btree::test::btree;
+    use super::super::catalog::{HFS_PLUS_FORK_DATA_LENGTH, RESOURCE_FORK};
+    use super::super::fork::Extent;
+    use super::*;
+    use crate::rsrc::ResourceFork;
+    use std::io::Cursor;
+
+    const RESOURCE_FORK_CONTENT: &[u8] = include_bytes!("../rsrc/string-table.rsrc");
+
+    const BLOCK_SIZE: usize = 512;
+    const FIRST_BLOCK_SECTOR: usize = 4;
+
+    struct VolumeBuilder {
+        image: Vec<u8>,
+        next_block: u32,
+    }
+
+    impl VolumeBuilder {
+        fn new() -> Self {
+            VolumeBuilder {
+                image: vec![0; FIRST_BLOCK_SECTOR * SECTOR_SIZE as usize],
+                next_block: 0,
+            }
+        }
+
+        /// Creates a builder for an HFS+ volume, whose allocation blocks start at the beginning of
+        /// the volume, and whose first few blocks are reserved for the volume header.
+        fn hfs_plus() -> Self {
+            VolumeBuilder {
+                image: vec![0; FIRST_BLOCK_SECTOR * BLOCK_SIZE],
+                next_block: FIRST_BLOCK_SECTOR as u32,
+            }
+        }
+
+        /// Writes the given data to contiguous blocks, optionally followed by an unused block.
+        fn allocate(&mut self, data: &[u8], gap: bool) -> Extent {
+            let count = ((data.len() + BLOCK_SIZE - 1) / BLOCK_SIZE) as u32;
+            let extent = Extent {
+                start: self.next_block,
+                count,
+            };
+
+            self.image.extend_from_slice(data);
+            self.image.resize(
+                self.image.len() + (count as usize * BLOCK_SIZE - data.len()),
+                0,
+            );
+
+            self.next_block += count;
+
+            if gap {
+                self.image.extend_from_slice(&[0xee; BLOCK_SIZE]);
+                self.next_block += 1;
+            }
+
+            extent
+        }
+    }
+
+    fn extent_record(extents: &[Extent]) -> Vec<u8> {
+        let mut record = vec![0; 12];
+
+        for (i, extent) in extents.iter().enumerate() {
+            record[i * 4..i * 4 + 2].copy_from_slice(&(extent.start as u16).to_be_bytes());
+            record[i * 4 + 2..i * 4 + 4].copy_from_slice(&(extent.count as u16).to_be_bytes());
+        }
+
+        record
+    }
+
+    fn catalog_key(parent_id: u32, name: &[u8]) -> Vec<u8> {
+        let mut key = vec![(6 + name.len()) as u8, 0];
+        key.extend_from_slice(&parent_id.to_be_bytes());
+        key.push(name.len() as u8);
+        key.extend_from_slice(name);
+
+        if key.len() % 2 != 0 {
+            key.push(0);
+        }
+
+        key
+    }
+
+    fn folder_record(parent_id: u32, name: &[u8], id: u32, flags: u16) -> Vec<u8> {
+        let mut data = vec![0; 70];
+        data[0] = 1;
+        data[6..10].copy_from_slice(&id.to_be_bytes());
+        data[10..14].copy_from_slice(&0xa000_0000u32.to_be_bytes());
+        data[14..18].copy_from_slice(&0xa000_0001u32.to_be_bytes());
+        data[22..30].copy_from_slice(&[0x11; 8]);
+        data[30..32].copy_from_slice(&flags.to_be_bytes());
+        data[32..34].copy_from_slice(&12i16.to_be_bytes());
+        data[34..36].copy_from_slice(&34i16.to_be_bytes());
+
+        let mut record = catalog_key(parent_id, name);
+        record.extend(data);
+        record
+    }
+
+    fn file_record(
+        parent_id: u32,
+        name: &[u8],
+        id: u32,
+        data_fork: (usize, &[Extent]),
+        resource_fork: (usize, &[Extent]),
+    ) -> Vec<u8> {
+        let mut data = vec![0; 102];
+        data[0] = 2;
+        data[4..12].copy_from_slice(b"TEXTttxt");
+        data[12..14].copy_from_slice(&0x4000u16.to_be_bytes());
+        data[20..24].copy_from_slice(&id.to_be_bytes());
+        data[26..30].copy_from_slice(&(data_fork.0 as u32).to_be_bytes());
+        data[36..40].copy_from_slice(&(resource_fork.0 as u32).to_be_bytes());
+        data[44..48].copy_from_slice(&0xb000_0000u32.to_be_bytes());
+        data[48..52].copy_from_slice(&0xb000_0001u32.to_be_bytes());
+        data[52..56].copy_from_slice(&0xb000_0002u32.to_be_bytes());
+        data[74..86].copy_from_slice(&extent_record(data_fork.1));
+        data[86..98].copy_from_slice(&extent_record(resource_fork.1));
+
+        let mut record = catalog_key(parent_id, name);
+        record.extend(data);
+        record
+    }
+
+    fn thread_record(id: u32, parent_id: u32, name: &[u8]) -> Vec<u8> {
+        let mut data = vec![0; 46];
+        data[0] = 3;
+        data[10..14].copy_from_slice(&parent_id.to_be_bytes());
+        data[14] = name.len() as u8;
+        data[15..15 + name.len()].copy_from_slice(name);
+
+        let mut record = catalog_key(id, b"");
+        record.extend(data);
+        record
+    }
+
+    fn overflow_record(fork_type: u8, id: u32, start: u16, extents: &[Extent]) -> Vec<u8> {
+        let mut record = vec![7, fork_type];
+        record.extend_from_slice(&id.to_be_bytes());
+        record.extend_from_slice(&start.to_be_bytes());
+        record.extend(extent_record(extents));
+        record
+    }
+
+    fn fragmented_data() -> Vec<u8> {
+        (0..BLOCK_SIZE * 5 - 100).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn volume_bytes() -> Vec<u8> {
+        let mut builder = VolumeBuilder::new();
+
+        let read_me = builder.allocate(b"Hello, HFS!", false);
+        let resource_fork = builder.allocate(RESOURCE_FORK_CONTENT, true);
+
+        let fragmented = fragmented_data();
+        let fragments: Vec<Extent> = fragmented
+            .chunks(BLOCK_SIZE)
+            .map(|chunk| builder.allocate(chunk, true))
+            .collect();
+
+        let catalog = btree(
+            &[
+                folder_record(1, b"Test Volume", ROOT_FOLDER_ID, 0),
+                thread_record(ROOT_FOLDER_ID, 1, b"Test Volume"),
+                folder_record(ROOT_FOLDER_ID, b"Folder", 17, 0x4000),
+                file_record(ROOT_FOLDER_ID, b"Read Me", 16, (11, &[read_me]), (0, &[])),
+                file_record(
+                    17,
+                    b"Fragmented",
+                    18,
+                    (fragmented.len(), &fragments[..3]),
+                    (RESOURCE_FORK_CONTENT.len(), &[resource_fork]),
+                ),
+                folder_record(17, b"Nested \xa5", 19, 0),
+                thread_record(19, 17, b"Nested \xa5"),
+                file_record(99, b"Orphan", 20, (0, &[]), (0, &[])),
+            ],
+            BLOCK_SIZE,
+        );
+
+        let extents = btree(
+            &[
+                overflow_record(DATA_FORK, 18, 3, &fragments[3..]),
+                overflow_record(RESOURCE_FORK, 99, 0, &fragments[..1]),
+            ],
+            BLOCK_SIZE,
+        );
+
+        let catalog_extent = builder.allocate(&catalog, false);
+        let extents_extent = builder.allocate(&extents, false);
+
+        let mut image = builder.image;
+        let mdb = &mut image[MDB_OFFSET as usize..MDB_OFFSET as usize + HEADER_LENGTH];
+        mdb[0..2].copy_from_slice(&HFS_SIGNATURE.to_be_bytes());
+        mdb[2..6].copy_from_slice(&0xc000_0000u32.to_be_bytes());
+        mdb[6..10].copy_from_slice(&0xc000_0001u32.to_be_bytes());
+        mdb[18..20].copy_from_slice(&(builder.next_block as u16).to_be_bytes());
+        mdb[20..24].copy_from_slice(&(BLOCK_SIZE as u32).to_be_bytes());
+        mdb[28..30].copy_from_slice(&(FIRST_BLOCK_SECTOR as u16).to_be_bytes());
+        mdb[36] = 11;
+        mdb[37..48].copy_from_slice(b"Test Volume");
+        mdb[130..134].copy_from_slice(&(extents.len() as u32).to_be_bytes());
+        mdb[134..146].copy_from_slice(&extent_record(&[extents_extent]));
+        mdb[146..150].copy_from_slice(&(catalog.len() as u32).to_be_bytes());
+        mdb[150..162].copy_from_slice(&extent_record(&[catalog_extent]));
+
+        image
+    }
+
+    #[test]
+    fn read_volume() -> Result<(), Box<dyn std::error::Error>> {
+        let mut volume = HfsVolume::new(Cursor::new(volume_bytes()))?;
+
+        assert_eq!("Test Volume", volume.name());
+        assert!(!volume.is_hfs_plus());
+        assert_eq!(0xc000_0000, volume.created());
+        assert_eq!(0xc000_0001, volume.modified());
+        assert_eq!(BLOCK_SIZE as u64, volume.block_size());
+
+        let paths: Vec<String> = volume
+            .entries()
+            .iter()
+            .map(|entry| entry.path().join(":"))
+            .collect();
+
+        assert_eq!(
+            vec!["Folder", "Folder:Fragmented", "Folder:Nested •", "Read Me"],
+            paths
+        );
+
+        let folder = volume.entry(&["Folder"]).unwrap().clone();
+        assert!(folder.is_folder());
+        assert_eq!(17, folder.id());
+        assert_eq!(ROOT_FOLDER_ID, folder.parent_id());
+        assert!(folder.flags().is_invisible());
+        assert_eq!((12, 34), folder.finder_info().location());
+        assert_eq!([0; 4], folder.file_type());
+        assert_eq!(0xa000_0000, folder.created());
+
+        let read_me = volume.entry(&["Read Me"]).unwrap().clone();
+        assert!(!read_me.is_folder());
+        assert_eq!(*b"TEXT", read_me.file_type());
+        assert_eq!(*b"ttxt", read_me.creator());
+        assert!(read_me.flags().is_invisible());
+        assert_eq!(0xb000_0000, read_me.created());
+        assert_eq!(0xb000_0001, read_me.modified());
+        assert_eq!(0xb000_0002, read_me.backed_up());
+
+        let mut content = vec![];
+        volume.data_fork(&read_me).read_to_end(&mut content)?;
+        assert_eq!(b"Hello, HFS!".to_vec(), content);
+        assert!(volume.resource_fork(&read_me).is_empty());
+
+        let fragmented = volume.entry(&["Folder", "Fragmented"]).unwrap().clone();
+        assert_eq!(fragmented_data().len() as u64, fragmented.data_fork_len());
+
+        let mut content = vec![];
+        volume.data_fork(&fragmented).read_to_end(&mut content)?;
+        assert_eq!(fragmented_data(), content);
+
+        let resource_fork = ResourceFork::new(volume.resource_fork(&fragmented)).unwrap();
+        assert!(resource_fork.resources().count() > 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_volumes() {
+        let mut bytes = volume_bytes();
+        bytes[MDB_OFFSET as usize] = b'H';
+
+        assert!(matches!(
+            HfsVolume::new(Cursor::new(&bytes)),
+            Err(HfsError::InvalidHeader)
+        ));
+
+        // Make the catalog file's header node claim to be a leaf node
+        let mut bytes = volume_bytes();
+        let catalog_start = (FIRST_BLOCK_SECTOR as u64 * SECTOR_SIZE) as usize
+            + u16_at(&bytes, MDB_OFFSET as usize + 150) as usize * BLOCK_SIZE;
+        bytes[catalog_start + 8] = 0xff;
+
+        assert!(matches!(
+            HfsVolume::new(Cursor::new(&bytes)),
+            Err(HfsError::CorruptBTree)
+        ));
+    }
+
+    fn hfs_plus_fork(len: usize, extents: &[Extent]) -> Vec<u8> {
+        let mut fork = vec![0; HFS_PLUS_FORK_DATA_LENGTH];
+        fork[0..8].copy_from_slice(&(len as u64).to_be_bytes());
+        fork[16..].copy_from_slice(&hfs_plus_extent_record(extents));
+        fork
+    }
+
+    fn hfs_plus_extent_record(extents: &[Extent]) -> Vec<u8> {
+        let mut record = vec![0; 64];
+
+        for (i, extent) in extents.iter().enumerate() {
+            record[i * 8..i * 8 + 4].copy_from_slice(&extent.start.to_be_bytes());
+            record[i * 8 + 4..i * 8 + 8].copy_from_slice(&extent.count.to_be_bytes());
+        }
+
+        record
+    }
+
+    fn hfs_plus_catalog_key(parent_id: u32, name: &str) -> Vec<u8> {
+        let name: Vec<u16> = name.encode_utf16().collect();
+
+        let mut key = ((6 + 2 * name.len()) as u16).to_be_bytes().to_vec();
+        key.extend_from_slice(&parent_id.to_be_bytes());
+        key.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        key.extend(name.iter().flat_map(|c| c.to_be_bytes().to_vec()));
+        key
+    }
+
+    fn hfs_plus_folder_record(parent_id: u32, name: &str, id: u32, flags: u16) -> Vec<u8> {
+        let mut data = vec![0; 88];
+        data[0..2].copy_from_slice(&1u16.to_be_bytes());
+        data[8..12].copy_from_slice(&id.to_be_bytes());
+        data[12..16].copy_from_slice(&0xa000_0000u32.to_be_bytes());
+        data[16..20].copy_from_slice(&0xa000_0001u32.to_be_bytes());
+        data[48..56].copy_from_slice(&[0x11; 8]);
+        data[56..58].copy_from_slice(&flags.to_be_bytes());
+        data[58..60].copy_from_slice(&12i16.to_be_bytes());
+        data[60..62].copy_from_slice(&34i16.to_be_bytes());
+
+        let mut record = hfs_plus_catalog_key(parent_id, name);
+        record.extend(data);
+        record
+    }
+
+    fn hfs_plus_file_record(
+        parent_id: u32,
+        name: &str,
+        id: u32,
+        data_fork: (usize, &[Extent]),
+        resource_fork: (usize, &[Extent]),
+    ) -> Vec<u8> {
+        let mut data = vec![0; 248];
+        data[0..2].copy_from_slice(&2u16.to_be_bytes());
+        data[8..12].copy_from_slice(&id.to_be_bytes());
+        data[12..16].copy_from_slice(&0xb000_0000u32.to_be_bytes());
+        data[16..20].copy_from_slice(&0xb000_0001u32.to_be_bytes());
+        data[28..32].copy_from_slice(&0xb000_0002u32.to_be_bytes());
+        data[48..56].copy_from_slice(b"TEXTttxt");
+        data[56..58].copy_from_slice(&0x4000u16.to_be_bytes());
+        data[88..168].copy_from_slice(&hfs_plus_fork(data_fork.0, data_fork.1));
+        data[168..248].copy_from_slice(&hfs_plus_fork(resource_fork.0, resource_fork.1));
+
+        let mut record = hfs_plus_catalog_key(parent_id, name);
+        record.extend(data);
+        record
+    }
+
+    fn hfs_plus_thread_record(id: u32, parent_id: u32, name: &str) -> Vec<u8> {
+        let mut data = vec![0; 8];
+        data[0..2].copy_from_slice(&3u16.to_be_bytes());
+        data[4..8].copy_from_slice(&parent_id.to_be_bytes());
+        data.extend(&hfs_plus_catalog_key(0, name)[6..]);
+
+        let mut record = hfs_plus_catalog_key(id, "");
+        record.extend(data);
+        record
+    }
+
+    fn hfs_plus_overflow_record(fork_type: u8, id: u32, start: u32, extents: &[Extent]) -> Vec<u8> {
+        let mut record = vec![0, 10, fork_type, 0];
+        record.extend_from_slice(&id.to_be_bytes());
+        record.extend_from_slice(&start.to_be_bytes());
+        record.extend(hfs_plus_extent_record(extents));
+        record
+    }
+
+    fn hfs_plus_fragmented_data() -> Vec<u8> {
+        (0..BLOCK_SIZE * 10 - 100)
+            .map(|i| (i % 251) as u8)
+            .collect()
+    }
+
+    fn hfs_plus_volume_bytes() -> Vec<u8> {
+        let mut builder = VolumeBuilder::hfs_plus();
+
+        let read_me = builder.allocate(b"Hello, HFS+!", false);
+        let resource_fork = builder.allocate(RESOURCE_FORK_CONTENT, true);
+
+        let fragmented = hfs_plus_fragmented_data();
+        let fragments: Vec<Extent> = fragmented
+            .chunks(BLOCK_SIZE)
+            .map(|chunk| builder.allocate(chunk, true))
+            .collect();
+
+        let catalog = btree(
+            &[
+                hfs_plus_folder_record(1, "Test Volume", ROOT_FOLDER_ID, 0),
+                hfs_plus_thread_record(ROOT_FOLDER_ID, 1, "Test Volume"),
+                hfs_plus_folder_record(ROOT_FOLDER_ID, "Cafe\u{301}", 17, 0x4000),
+                hfs_plus_file_record(ROOT_FOLDER_ID, "Read Me", 16, (12, &[read_me]), (0, &[])),
+                hfs_plus_file_record(
+                    17,
+                    "Fragmented",
+                    18,
+                    (fragmented.len(), &fragments[..8]),
+                    (RESOURCE_FORK_CONTENT.len(), &[resource_fork]),
+                ),
+                hfs_plus_thread_record(17, ROOT_FOLDER_ID, "Cafe\u{301}"),
+            ],
+            BLOCK_SIZE,
+        );
+
+        let extents = btree(
+            &[hfs_plus_overflow_record(DATA_FORK, 18, 8, &fragments[8..])],
+            BLOCK_SIZE,
+        );
+
+        let catalog_extent = builder.allocate(&catalog, false);
+        let extents_extent = builder.allocate(&extents, false);
+
+        let mut image = builder.image;
+        let header = &mut image[MDB_OFFSET as usize..MDB_OFFSET as usize + HEADER_LENGTH];
+        header[0..2].copy_from_slice(&HFS_PLUS_SIGNATURE.to_be_bytes());
+        header[2..4].copy_from_slice(&4u16.to_be_bytes());
+        header[16..20].copy_from_slice(&0xc000_0000u32.to_be_bytes());
+        header[20..24].copy_from_slice(&0xc000_0001u32.to_be_bytes());
+        header[40..44].copy_from_slice(&(BLOCK_SIZE as u32).to_be_bytes());
+        header[44..48].copy_from_slice(&builder.next_block.to_be_bytes());
+        header[192..272].copy_from_slice(&hfs_plus_fork(extents.len(), &[extents_extent]));
+        header[272..352].copy_from_slice(&hfs_plus_fork(catalog.len(), &[catalog_extent]));
+
+        image
+    }
+
+    fn check_hfs_plus_volume<R: Read + Seek>(
+        volume: &mut HfsVolume<R>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!("Test Volume", volume.name());
+        assert!(volume.is_hfs_plus());
+        assert_eq!(0xc000_0000, volume.created());
+        assert_eq!(0xc000_0001, volume.modified());
+
+        let paths: Vec<String> = volume
+            .entries()
+            .iter()
+            .map(|entry| entry.path().join(":"))
+            .collect();
+
+        assert_eq!(
+            vec!["Cafe\u{301}", "Cafe\u{301}:Fragmented", "Read Me"],
+            paths
+        );
+
+        let folder = volume.entry(&["Cafe\u{301}"]).unwrap().clone();
+        assert!(folder.is_folder());
+        assert_eq!(17, folder.id());
+        assert!(folder.flags().is_invisible());
+        assert_eq!((12, 34), folder.finder_info().location());
+        assert_eq!(0xa000_0001, folder.modified());
+
+        let read_me = volume.entry(&["Read Me"]).unwrap().clone();
+        assert_eq!(*b"TEXT", read_me.file_type());
+        assert_eq!(*b"ttxt", read_me.creator());
+        assert!(read_me.flags().is_invisible());
+        assert_eq!(0xb000_0002, read_me.backed_up());
+
+        let mut content = vec![];
+        volume.data_fork(&read_me).read_to_end(&mut content)?;
+        assert_eq!(b"Hello, HFS+!".to_vec(), content);
+
+        let fragmented = volume
+            .entry(&["Cafe\u{301}", "Fragmented"])
+            .unwrap()
+            .clone();
+
+        let mut content = vec![];
+        volume.data_fork(&fragmented).read_to_end(&mut content)?;
+        assert_eq!(hfs_plus_fragmented_data(), content);
+
+        let resource_fork = ResourceFork::new(volume.resource_fork(&fragmented)).unwrap();
+        assert!(resource_fork.resources().count() > 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_hfs_plus_volume() -> Result<(), Box<dyn std::error::Error>> {
+        check_hfs_plus_volume(&mut HfsVolume::new(Cursor::new(hfs_plus_volume_bytes()))?)
+    }
+
+    #[test]
+    fn read_wrapped_hfs_plus_volume() -> Result<(), Box<dyn std::error::Error>> {
+        let embedded = hfs_plus_volume_bytes();
+
+        // A wrapper volume whose only allocation block before the embedded volume is unused
+        let mut image = vec![0; FIRST_BLOCK_SECTOR * SECTOR_SIZE as usize + BLOCK_SIZE];
+        image.extend_from_slice(&embedded);
+
+        let mdb = &mut image[MDB_OFFSET as usize..MDB_OFFSET as usize + HEADER_LENGTH];
+        mdb[0..2].copy_from_slice(&HFS_SIGNATURE.to_be_bytes());
+        mdb[20..24].copy_from_slice(&(BLOCK_SIZE as u32).to_be_bytes());
+        mdb[28..30].copy_from_slice(&(FIRST_BLOCK_SECTOR as u16).to_be_bytes());
+        mdb[124..126].copy_from_slice(&HFS_PLUS_SIGNATURE.to_be_bytes());
+        mdb[126..128].copy_from_slice(&1u16.to_be_bytes());
+        mdb[128..130].copy_from_slice(&((embedded.len() / BLOCK_SIZE) as u16).to_be_bytes());
+
+        check_hfs_plus_volume(&mut HfsVolume::new(Cursor::new(image))?)
+    }
+
+    #[test]
+    fn invalid_hfs_plus_volumes() {
+        // HFS+ block sizes must be powers of two
+        let mut bytes = hfs_plus_volume_bytes();
+        bytes[MDB_OFFSET as usize + 40..MDB_OFFSET as usize + 44]
+            .copy_from_slice(&1000u32.to_be_bytes());
+
+        assert!(matches!(
+            HfsVolume::new(Cursor::new(&bytes)),
+            Err(HfsError::InvalidHeader)
+        ));
+
+        // Truncate the catalog's first leaf node so its records overlap the record offsets
+        let mut bytes = hfs_plus_volume_bytes();
+        let catalog_start =
+            u32_at(&bytes, MDB_OFFSET as usize + 272 + 16) as usize * BLOCK_SIZE + BLOCK_SIZE;
+        bytes[catalog_start + BLOCK_SIZE - 2..catalog_start + BLOCK_SIZE]
+            .copy_from_slice(&600u16.to_be_bytes());
+
+        assert!(matches!(
+            HfsVolume::new(Cursor::new(&bytes)),
+            Err(HfsError::CorruptBTree)
+        ));
+    }
+}
@@ -1,2 +1,16 @@
+pub mod applesingle;
 pub mod binhex;
+pub mod compactpro;
+pub mod detect;
+pub mod diskcopy;
+pub mod finder;
+pub mod hfs;
+pub mod macbinary;
+pub mod macfile;
+pub mod mail;
+pub mod mfs;
+pub mod partition;
 pub mod rsrc;
+pub mod stuffit;
+
+mod util;
@@ -0,0 +1,660 @@
+use std::cmp;
+use std::convert::TryFrom;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Read, Write};
+
+use super::header::{MacBinaryHeader, MacBinaryVersion, HEADER_LENGTH};
+use crate::finder::FinderFlags;
+use crate::util::copy_exact;
+
+/// Every section of a MacBinary file (except the header itself) is padded to a multiple of this
+/// many bytes.
+const BLOCK_SIZE: usize = 128;
+
+/// A MacBinary file.
+///
+/// MacBinary files combine the data fork, resource fork, and metadata associated with a "classic"
+/// Macintosh file into a single binary file. Headers written in any version of the MacBinary format
+/// (I, II, or III) can be read.
+pub struct MacBinaryArchive<R: Read> {
+    source: R,
+    header: MacBinaryHeader,
+    position: Position,
+}
+
+impl<R: Read> MacBinaryArchive<R> {
+    /// Creates a new MacBinary archive that will extract data from the given reader.
+    ///
+    /// Any secondary header that follows the archive's header is read and discarded.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a valid MacBinary header could not be read from the
+    /// given source. For MacBinary II and III headers, this includes headers whose checksum doesn't
+    /// match their content.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use clarus::macbinary::{MacBinaryArchive, MacBinaryError};
+    ///
+    /// fn main() -> Result<(), MacBinaryError> {
+    ///     let archive = MacBinaryArchive::new(File::open("example.bin")?)?;
+    ///
+    ///     println!("{} ({:?})", archive.filename(), archive.version());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn new(mut source: R) -> Result<Self, MacBinaryError> {
+        let mut header_bytes = [0; HEADER_LENGTH];
+        source.read_exact(&mut header_bytes)?;
+
+        let header = MacBinaryHeader::try_from(&header_bytes)?;
+
+        skip(&mut source, padded_len(header.secondary_header_len()))?;
+
+        Ok(MacBinaryArchive {
+            source,
+            header,
+            position: Position::Fork(Fork::Data, 0),
+        })
+    }
+
+    /// Returns the version of the MacBinary format in which this archive was written.
+    pub fn version(&self) -> MacBinaryVersion {
+        self.header.version()
+    }
+
+    /// Returns the original filename of the file contained in this archive.
+    pub fn filename(&self) -> &String {
+        self.header.filename()
+    }
+
+    /// Returns the file type identifier for the file contained in this archive.
+    ///
+    /// For a detailed description of file signatures (including file type identifiers), please see
+    /// the ["Giving a Signature to Your Application and a Creator and a File Type to Your
+    /// Documents" section of "Inside Macintosh: Macintosh Toolbox
+    /// Essentials"](https://developer.apple.com/library/archive/documentation/mac/pdf/MacintoshToolboxEssentials.pdf#page=806).
+    pub fn file_type(&self) -> [u8; 4] {
+        self.header.file_type()
+    }
+
+    /// Returns the creator identifier for the file contained in this archive.
+    ///
+    /// For a detailed description of file signatures (including creator identifiers), please see
+    /// the ["Giving a Signature to Your Application and a Creator and a File Type to Your
+    /// Documents" section of "Inside Macintosh: Macintosh Toolbox
+    /// Essentials"](https://developer.apple.com/library/archive/documentation/mac/pdf/MacintoshToolboxEssentials.pdf#page=806).
+    pub fn creator(&self) -> [u8; 4] {
+        self.header.creator()
+    }
+
+    /// Returns the Finder flags for the file contained in this archive.
+    ///
+    /// For a typed view of the flags, please see [`MacBinaryHeader::flags`].
+    pub fn flags(&self) -> u16 {
+        self.header.flags().bits()
+    }
+
+    /// Returns the length, in bytes, of the data fork of the file contained in this archive.
+    pub fn data_fork_len(&self) -> usize {
+        self.header.data_fork_len()
+    }
+
+    /// Returns the length, in bytes, of the resource fork of the file contained in this archive.
+    pub fn resource_fork_len(&self) -> usize {
+        self.header.resource_fork_len()
+    }
+
+    /// Returns this archive's header, which includes all of the metadata stored in the archive.
+    pub fn header(&self) -> &MacBinaryHeader {
+        &self.header
+    }
+
+    /// Returns a reader for this archive's data fork.
+    ///
+    /// The returned reader produces exactly [`MacBinaryArchive::data_fork_len`] bytes.
+    ///
+    /// MacBinary archives are read sequentially, and the data fork precedes the resource fork. A
+    /// reader for the data fork may be created any number of times until a reader for the resource
+    /// fork has been created; each data fork reader resumes reading where the previous reader
+    /// stopped.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if a reader for the resource fork has already been created.
+    pub fn data_fork(&mut self) -> Result<MacBinaryForkReader<'_, R>, MacBinaryError> {
+        match self.position {
+            Position::Fork(Fork::Data, _) => Ok(MacBinaryForkReader {
+                archive: self,
+                fork: Fork::Data,
+            }),
+            _ => Err(MacBinaryError::ForkUnavailable(Fork::Data)),
+        }
+    }
+
+    /// Returns a reader for this archive's resource fork.
+    ///
+    /// The returned reader produces exactly [`MacBinaryArchive::resource_fork_len`] bytes. Any
+    /// unread portion of the data fork is read and discarded before the reader is returned, after
+    /// which the data fork is no longer available.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if an IO error occurs while skipping the remainder of the
+    /// data fork or if the resource fork has already been read in its entirety.
+    pub fn resource_fork(&mut self) -> Result<MacBinaryForkReader<'_, R>, MacBinaryError> {
+        if let Position::Fork(Fork::Data, _) = self.position {
+            io::copy(
+                &mut MacBinaryForkReader {
+                    archive: self,
+                    fork: Fork::Data,
+                },
+                &mut io::sink(),
+            )?;
+        }
+
+        match self.position {
+            Position::Fork(Fork::Resource, _) => Ok(MacBinaryForkReader {
+                archive: self,
+                fork: Fork::Resource,
+            }),
+            _ => Err(MacBinaryError::ForkUnavailable(Fork::Resource)),
+        }
+    }
+
+    /// Extracts this archive's content to the given writers.
+    ///
+    /// This method may return an error after some or all of the archive's content has been written
+    /// to the given writers.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error immediately if it encounters an IO error while extracting data,
+    /// including if the archive ends before both forks have been read.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use clarus::macbinary::{MacBinaryArchive, MacBinaryError};
+    ///
+    /// fn main() -> Result<(), MacBinaryError> {
+    ///     let archive = MacBinaryArchive::new(File::open("example.bin")?)?;
+    ///
+    ///     let mut data_fork_content = Vec::with_capacity(archive.data_fork_len());
+    ///     let mut rsrc_fork_content = Vec::with_capacity(archive.resource_fork_len());
+    ///
+    ///     archive.extract(&mut data_fork_content, &mut rsrc_fork_content)
+    /// }
+    /// ```
+    pub fn extract(
+        mut self,
+        data_writer: &mut impl Write,
+        resource_writer: &mut impl Write,
+    ) -> Result<(), MacBinaryError> {
+        io::copy(&mut self.data_fork()?, data_writer)?;
+        io::copy(&mut self.resource_fork()?, resource_writer)?;
+
+        Ok(())
+    }
+
+    /// Reads the enclosed file's Get Info comment, which follows the resource fork, discarding any
+    /// unread portion of either fork.
+    ///
+    /// Comments are stored as raw bytes in the Macintosh character encoding; the returned comment
+    /// is empty if the archive doesn't include a comment.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if an IO error occurs while skipping the remainder of
+    /// either fork or reading the comment.
+    pub fn comment(mut self) -> Result<Vec<u8>, MacBinaryError> {
+        if self.position != Position::End {
+            io::copy(&mut self.resource_fork()?, &mut io::sink())?;
+        }
+
+        let mut comment = vec![0; self.header.comment_len()];
+        self.source.read_exact(&mut comment)?;
+
+        Ok(comment)
+    }
+
+    fn read_fork(&mut self, fork: Fork, buf: &mut [u8]) -> io::Result<usize> {
+        let offset = match self.position {
+            Position::Fork(current_fork, offset) if current_fork == fork => offset,
+            _ => return Ok(0),
+        };
+
+        let len = match fork {
+            Fork::Data => self.header.data_fork_len(),
+            Fork::Resource => self.header.resource_fork_len(),
+        };
+
+        if offset == len {
+            // Skip the padding at the end of the fork and move on to the next section
+            skip(&mut self.source, padded_len(len) - len)?;
+
+            self.position = match fork {
+                Fork::Data => Position::Fork(Fork::Resource, 0),
+                Fork::Resource => Position::End,
+            };
+
+            return Ok(0);
+        }
+
+        let max_len = cmp::min(buf.len(), len - offset);
+        let bytes_read = self.source.read(&mut buf[..max_len])?;
+
+        if bytes_read == 0 {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+        }
+
+        self.position = Position::Fork(fork, offset + bytes_read);
+
+        Ok(bytes_read)
+    }
+}
+
+/// Returns the given length rounded up to the next multiple of the MacBinary block size.
+fn padded_len(len: usize) -> usize {
+    (len + BLOCK_SIZE - 1) / BLOCK_SIZE * BLOCK_SIZE
+}
+
+/// Reads and discards up to `len` bytes from the given source.
+///
+/// Some MacBinary writers don't pad the last section of a file, and so reaching the end of the
+/// source while skipping padding isn't an error.
+fn skip(source: &mut impl Read, len: usize) -> io::Result<()> {
+    io::copy(&mut source.take(len as u64), &mut io::sink())?;
+    Ok(())
+}
+
+/// A reader for one of the forks in a MacBinary archive.
+pub struct MacBinaryForkReader<'a, R: Read> {
+    archive: &'a mut MacBinaryArchive<R>,
+    fork: Fork,
+}
+
+impl<'a, R: Read> Read for MacBinaryForkReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.archive.read_fork(self.fork, buf)
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Position {
+    /// Reading the content of the given fork; the number of bytes of the fork read so far is
+    /// included.
+    Fork(Fork, usize),
+
+    /// Both forks have been read.
+    End,
+}
+
+/// Writes MacBinary files.
+///
+/// A `MacBinaryWriter` combines the data fork, resource fork, and metadata associated with a
+/// "classic" Macintosh file into a single MacBinary file that can be read with
+/// [`MacBinaryArchive`].
+pub struct MacBinaryWriter<W: Write> {
+    dest: W,
+    header: MacBinaryHeader,
+    comment: Vec<u8>,
+}
+
+impl<W: Write> MacBinaryWriter<W> {
+    /// Creates a new MacBinary writer that will write a MacBinary III file with the given metadata
+    /// to the given destination.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the given filename is empty, is longer than 63 bytes
+    /// when encoded, or contains characters that can't be represented in the Macintosh character
+    /// encoding.
+    pub fn new(
+        dest: W,
+        filename: &str,
+        file_type: [u8; 4],
+        creator: [u8; 4],
+        flags: u16,
+    ) -> Result<Self, MacBinaryError> {
+        let header = MacBinaryHeader::new(filename, file_type, creator, FinderFlags::from(flags))?;
+
+        Ok(MacBinaryWriter::with_header(dest, header))
+    }
+
+    /// Creates a new MacBinary writer that will write a file with the given header to the given
+    /// destination.
+    ///
+    /// The header's metadata (including its version) is written unchanged, except that the fork
+    /// lengths are replaced with the lengths given to [`MacBinaryWriter::encode`], the written file
+    /// has no secondary header, and the written file only has a comment if one is set with
+    /// [`MacBinaryWriter::set_comment`].
+    pub fn with_header(dest: W, header: MacBinaryHeader) -> Self {
+        MacBinaryWriter {
+            dest,
+            header,
+            comment: vec![],
+        }
+    }
+
+    /// Sets the file's Get Info comment, which should be encoded in the Macintosh character
+    /// encoding.
+    ///
+    /// Comments are written after the resource fork; MacBinary I files can't include comments.
+    pub fn set_comment(&mut self, comment: &[u8]) {
+        self.comment = comment.to_vec();
+    }
+
+    /// Writes a complete MacBinary file containing the given forks to this writer's destination,
+    /// returning the destination when the file has been written.
+    ///
+    /// Exactly `data_fork_len` bytes are read from `data_fork`, and exactly `resource_fork_len`
+    /// bytes are read from `resource_fork`.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error immediately if it encounters an IO error while reading from
+    /// either fork or writing to the destination, if either fork ends before the given number of
+    /// bytes could be read, if either fork length is too large to be represented in a MacBinary
+    /// header, or if this writer has a comment that can't be represented in its header's version of
+    /// the format (MacBinary I files can't include comments, and comments in later versions must
+    /// be shorter than 64 KiB).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use std::io;
+    /// use clarus::macbinary::{MacBinaryError, MacBinaryWriter};
+    ///
+    /// fn main() -> Result<(), MacBinaryError> {
+    ///     let mut data_file = File::open("example.txt")?;
+    ///     let data_fork_len = data_file.metadata()?.len() as usize;
+    ///
+    ///     let macbinary_file = File::create("example.bin")?;
+    ///     let writer = MacBinaryWriter::new(macbinary_file, "example.txt", *b"TEXT", *b"ttxt", 0)?;
+    ///
+    ///     writer.encode(&mut data_file, data_fork_len, &mut io::empty(), 0)?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn encode(
+        mut self,
+        data_fork: &mut impl Read,
+        data_fork_len: usize,
+        resource_fork: &mut impl Read,
+        resource_fork_len: usize,
+    ) -> Result<W, MacBinaryError> {
+        if data_fork_len > u32::MAX as usize || resource_fork_len > u32::MAX as usize {
+            return Err(MacBinaryError::InvalidHeader);
+        }
+
+        if !self.comment.is_empty()
+            && (self.header.version() == MacBinaryVersion::MacBinaryI
+                || self.comment.len() > u16::MAX as usize)
+        {
+            return Err(MacBinaryError::InvalidHeader);
+        }
+
+        let header = self
+            .header
+            .for_writing(data_fork_len, resource_fork_len, self.comment.len());
+
+        self.dest.write_all(&<[u8; HEADER_LENGTH]>::from(&header))?;
+
+        copy_fork(data_fork, &mut self.dest, data_fork_len)?;
+        copy_fork(resource_fork, &mut self.dest, resource_fork_len)?;
+        copy_fork(
+            &mut self.comment.as_slice(),
+            &mut self.dest,
+            self.comment.len(),
+        )?;
+
+        Ok(self.dest)
+    }
+}
+
+/// Copies exactly `len` bytes of a fork (or comment) from the given source to the given
+/// destination, then pads it to a multiple of the MacBinary block size.
+fn copy_fork(source: &mut impl Read, dest: &mut impl Write, len: usize) -> io::Result<()> {
+    copy_exact(source, dest, len as u64)?;
+    dest.write_all(&[0; BLOCK_SIZE][..padded_len(len) - len])
+}
+
+/// A fork of a file in a MacBinary archive.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Fork {
+    /// The data fork.
+    Data,
+
+    /// The resource fork.
+    Resource,
+}
+
+/// The error type for operations on MacBinary files.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MacBinaryError {
+    /// An [`std::io::Error`] occurred while reading or writing some part of the file.
+    ///
+    /// The kind of IO error is included.
+    IoError(io::ErrorKind),
+
+    /// The MacBinary header was malformed and could not be read, or the given metadata could not be
+    /// represented in a MacBinary header.
+    InvalidHeader,
+
+    /// The given filename could not be represented in a MacBinary header.
+    ///
+    /// Filenames in MacBinary headers must be between 1 and 63 bytes long and must be representable
+    /// in the Macintosh character encoding.
+    InvalidFilename,
+
+    /// The checksum included in a MacBinary II or III header did not match the checksum calculated
+    /// from the header's content.
+    ///
+    /// The checksum provided in the header and the checksum calculated from the header's content are
+    /// included.
+    InvalidChecksum(u16, u16),
+
+    /// A reader for the given fork could not be created because the archive has already been read
+    /// past the start of that fork.
+    ForkUnavailable(Fork),
+}
+
+impl Display for MacBinaryError {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MacBinaryError::IoError(kind) => write!(fmt, "IO error: {:?}", kind),
+            MacBinaryError::InvalidHeader => write!(fmt, "Invalid MacBinary header"),
+            MacBinaryError::InvalidFilename => write!(fmt, "Invalid filename"),
+            MacBinaryError::InvalidChecksum(provided, calculated) => write!(
+                fmt,
+                "Invalid header checksum; provided: {:04x}, calculated: {:04x}",
+                provided, calculated
+            ),
+            MacBinaryError::ForkUnavailable(fork) => write!(fmt, "{:?} fork unavailable", fork),
+        }
+    }
+}
+
+impl From<io::Error> for MacBinaryError {
+    fn from(error: io::Error) -> Self {
+        MacBinaryError::IoError(error.kind())
+    }
+}
+
+impl error::Error for MacBinaryError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    const DATA_FORK: &[u8] = b"===== Hello from the data fork! =====";
+    const RESOURCE_FORK: &[u8] = b"----- Hello from the resource fork! -----";
+
+    fn encode(header: MacBinaryHeader) -> Vec<u8> {
+        MacBinaryWriter::with_header(vec![], header)
+            .encode(
+                &mut Cursor::new(DATA_FORK),
+                DATA_FORK.len(),
+                &mut Cursor::new(RESOURCE_FORK),
+                RESOURCE_FORK.len(),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn encode_and_extract() -> Result<(), MacBinaryError> {
+        let writer =
+            MacBinaryWriter::new(vec![], "SimpleText™ Document", *b"TEXT", *b"ttxt", 0x0100)?;
+        let encoded = writer.encode(
+            &mut Cursor::new(DATA_FORK),
+            DATA_FORK.len(),
+            &mut Cursor::new(RESOURCE_FORK),
+            RESOURCE_FORK.len(),
+        )?;
+
+        assert_eq!(128 * 3, encoded.len());
+
+        let archive = MacBinaryArchive::new(Cursor::new(encoded))?;
+
+        assert_eq!(MacBinaryVersion::MacBinaryIII, archive.version());
+        assert_eq!(&String::from("SimpleText™ Document"), archive.filename());
+        assert_eq!(*b"TEXT", archive.file_type());
+        assert_eq!(*b"ttxt", archive.creator());
+        assert_eq!(0x0100, archive.flags());
+        assert_eq!(DATA_FORK.len(), archive.data_fork_len());
+        assert_eq!(RESOURCE_FORK.len(), archive.resource_fork_len());
+
+        let mut data_fork = vec![];
+        let mut resource_fork = vec![];
+
+        archive.extract(&mut data_fork, &mut resource_fork)?;
+
+        assert_eq!(DATA_FORK, data_fork.as_slice());
+        assert_eq!(RESOURCE_FORK, resource_fork.as_slice());
+
+        Ok(())
+    }
+
+    #[test]
+    fn resource_fork_only() -> Result<(), MacBinaryError> {
+        let header = MacBinaryHeader::new("test", *b"TEXT", *b"ttxt", FinderFlags::default())?;
+        let mut archive = MacBinaryArchive::new(Cursor::new(encode(header)))?;
+
+        let mut resource_fork = vec![];
+        archive.resource_fork()?.read_to_end(&mut resource_fork)?;
+
+        assert_eq!(RESOURCE_FORK, resource_fork.as_slice());
+        assert!(matches!(
+            archive.data_fork(),
+            Err(MacBinaryError::ForkUnavailable(Fork::Data))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn secondary_header_and_comment() -> Result<(), MacBinaryError> {
+        let header = MacBinaryHeader::new("test", *b"TEXT", *b"ttxt", FinderFlags::default())?;
+        let mut encoded = encode(header);
+
+        // Insert a 10-byte secondary header (padded to 128 bytes) and append a comment
+        encoded[121] = 10;
+        encoded[100] = 7;
+
+        let checksum = crc16::State::<crc16::XMODEM>::calculate(&encoded[..124]);
+        encoded[124..126].copy_from_slice(&checksum.to_be_bytes());
+
+        encoded.splice(128..128, vec![0xff; 128]);
+        encoded.extend_from_slice(b"Comment");
+
+        let mut archive = MacBinaryArchive::new(Cursor::new(encoded.clone()))?;
+
+        let mut data_fork = vec![];
+        archive.data_fork()?.read_to_end(&mut data_fork)?;
+
+        assert_eq!(DATA_FORK, data_fork.as_slice());
+        assert_eq!(b"Comment".to_vec(), archive.comment()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn encode_comment() -> Result<(), MacBinaryError> {
+        let mut writer = MacBinaryWriter::new(vec![], "test", *b"TEXT", *b"ttxt", 0)?;
+        writer.set_comment(b"Comment");
+
+        let encoded = writer.encode(
+            &mut Cursor::new(DATA_FORK),
+            DATA_FORK.len(),
+            &mut Cursor::new(RESOURCE_FORK),
+            RESOURCE_FORK.len(),
+        )?;
+
+        assert_eq!(128 * 4, encoded.len());
+
+        let archive = MacBinaryArchive::new(Cursor::new(encoded))?;
+
+        assert_eq!(7, archive.header().comment_len());
+        assert_eq!(b"Comment".to_vec(), archive.comment()?);
+
+        let mut writer = MacBinaryWriter::new(vec![], "test", *b"TEXT", *b"ttxt", 0)?;
+        writer.set_comment(&[b'x'; 0x10000]);
+
+        assert!(matches!(
+            writer.encode(&mut io::empty(), 0, &mut io::empty(), 0),
+            Err(MacBinaryError::InvalidHeader)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn unpadded_resource_fork() -> Result<(), MacBinaryError> {
+        let header = MacBinaryHeader::new("test", *b"TEXT", *b"ttxt", FinderFlags::default())?;
+        let mut encoded = encode(header);
+        encoded.truncate(256 + RESOURCE_FORK.len());
+
+        let mut data_fork = vec![];
+        let mut resource_fork = vec![];
+
+        MacBinaryArchive::new(Cursor::new(encoded))?.extract(&mut data_fork, &mut resource_fork)?;
+
+        assert_eq!(RESOURCE_FORK, resource_fork.as_slice());
+
+        Ok(())
+    }
+
+    #[test]
+    fn truncated_fork() -> Result<(), MacBinaryError> {
+        let header = MacBinaryHeader::new("test", *b"TEXT", *b"ttxt", FinderFlags::default())?;
+        let mut encoded = encode(header);
+        encoded.truncate(140);
+
+        let archive = MacBinaryArchive::new(Cursor::new(encoded))?;
+
+        assert_eq!(
+            Err(MacBinaryError::IoError(io::ErrorKind::UnexpectedEof)),
+            archive.extract(&mut io::sink(), &mut io::sink())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn truncated_header() {
+        assert!(matches!(
+            MacBinaryArchive::new(Cursor::new(vec![0; 100])),
+            Err(MacBinaryError::IoError(io::ErrorKind::UnexpectedEof))
+        ));
+    }
+}
@@ -0,0 +1,451 @@
+use std::convert::{TryFrom, TryInto};
+
+use super::archive::MacBinaryError;
+use crate::finder::FinderFlags;
+use crate::util::{encode_filename, MAX_FILENAME_LENGTH};
+use crate::util::{u16_at, u32_at};
+
+pub(super) const HEADER_LENGTH: usize = 128;
+
+const SIGNATURE: &[u8; 4] = b"mBIN";
+const MACBINARY_II_VERSION: u8 = 129;
+const MACBINARY_III_VERSION: u8 = 130;
+
+/// The largest fork length allowed in a MacBinary I header.
+const MAX_MACBINARY_I_FORK_LENGTH: u32 = 0x7f_ffff;
+
+/// A version of the MacBinary format.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum MacBinaryVersion {
+    /// The original MacBinary format, which has no checksum and stores only the high byte of the
+    /// Finder flags.
+    MacBinaryI,
+
+    /// MacBinary II, which adds a header checksum, the low byte of the Finder flags, and support for
+    /// secondary headers.
+    MacBinaryII,
+
+    /// MacBinary III, which adds a signature, the script of the file's name, and extended Finder
+    /// flags.
+    MacBinaryIII,
+}
+
+/// The header of a MacBinary file, which contains the metadata for the enclosed file.
+///
+/// MacBinary headers are always 128 bytes long. For details about the fields in a MacBinary header,
+/// please see the [MacBinary III
+/// specification](https://web.archive.org/web/20050305044255/http://www.lazerware.com/formats/macbinary/macbinary_iii.html).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MacBinaryHeader {
+    version: MacBinaryVersion,
+    name: String,
+    name_bytes: Vec<u8>,
+    file_type: [u8; 4],
+    creator: [u8; 4],
+    flags: FinderFlags,
+    location: (i16, i16),
+    folder_id: u16,
+    protected: bool,
+    data_fork_length: usize,
+    resource_fork_length: usize,
+    created: u32,
+    modified: u32,
+    comment_length: usize,
+    secondary_header_length: usize,
+    script: u8,
+    extended_flags: u8,
+}
+
+impl MacBinaryHeader {
+    /// Creates a new MacBinary III header with the given metadata and empty forks; all other
+    /// metadata (like the file's location and dates) is zeroed.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the given filename is empty, is longer than 63 bytes
+    /// when encoded, or contains characters that can't be represented in the Macintosh character
+    /// encoding.
+    pub fn new(
+        filename: &str,
+        file_type: [u8; 4],
+        creator: [u8; 4],
+        flags: FinderFlags,
+    ) -> Result<Self, MacBinaryError> {
+        let name_bytes = encode_filename(filename).ok_or(MacBinaryError::InvalidFilename)?;
+
+        Ok(MacBinaryHeader {
+            version: MacBinaryVersion::MacBinaryIII,
+            name: String::from(filename),
+            name_bytes: name_bytes.to_vec(),
+            file_type,
+            creator,
+            flags,
+            location: (0, 0),
+            folder_id: 0,
+            protected: false,
+            data_fork_length: 0,
+            resource_fork_length: 0,
+            created: 0,
+            modified: 0,
+            comment_length: 0,
+            secondary_header_length: 0,
+            script: 0,
+            extended_flags: 0,
+        })
+    }
+
+    /// Returns the version of the MacBinary format in which this header was written.
+    pub fn version(&self) -> MacBinaryVersion {
+        self.version
+    }
+
+    /// Returns the original filename of the enclosed file, decoded from the Macintosh character
+    /// encoding.
+    pub fn filename(&self) -> &String {
+        &self.name
+    }
+
+    /// Returns the raw bytes of the enclosed file's name, before decoding.
+    pub fn filename_bytes(&self) -> &[u8] {
+        &self.name_bytes
+    }
+
+    /// Returns the file type identifier for the enclosed file.
+    pub fn file_type(&self) -> [u8; 4] {
+        self.file_type
+    }
+
+    /// Returns the creator identifier for the enclosed file.
+    pub fn creator(&self) -> [u8; 4] {
+        self.creator
+    }
+
+    /// Returns the Finder flags for the enclosed file.
+    ///
+    /// MacBinary I headers only store the high byte of the Finder flags; the low byte is always
+    /// zero for MacBinary I files.
+    pub fn flags(&self) -> FinderFlags {
+        self.flags
+    }
+
+    /// Returns the vertical and horizontal position of the file's icon within its window.
+    pub fn location(&self) -> (i16, i16) {
+        self.location
+    }
+
+    /// Sets the vertical and horizontal position of the file's icon within its window.
+    pub fn set_location(&mut self, location: (i16, i16)) {
+        self.location = location;
+    }
+
+    /// Returns the ID of the window or folder that contained the file.
+    pub fn folder_id(&self) -> u16 {
+        self.folder_id
+    }
+
+    /// Sets the ID of the window or folder that contained the file.
+    pub fn set_folder_id(&mut self, folder_id: u16) {
+        self.folder_id = folder_id;
+    }
+
+    /// Returns `true` if the file was marked as protected (i.e. copy-protected).
+    pub fn is_protected(&self) -> bool {
+        self.protected
+    }
+
+    /// Returns the length, in bytes, of the enclosed file's data fork.
+    pub fn data_fork_len(&self) -> usize {
+        self.data_fork_length
+    }
+
+    /// Returns the length, in bytes, of the enclosed file's resource fork.
+    pub fn resource_fork_len(&self) -> usize {
+        self.resource_fork_length
+    }
+
+    /// Returns the file's creation date, in seconds since midnight, January 1, 1904 (local time).
+    pub fn created(&self) -> u32 {
+        self.created
+    }
+
+    /// Returns the file's modification date, in seconds since midnight, January 1, 1904 (local
+    /// time).
+    pub fn modified(&self) -> u32 {
+        self.modified
+    }
+
+    /// Sets the file's creation and modification dates, in seconds since midnight, January 1, 1904
+    /// (local time).
+    pub fn set_dates(&mut self, created: u32, modified: u32) {
+        self.created = created;
+        self.modified = modified;
+    }
+
+    /// Returns the length, in bytes, of the file's Get Info comment, which follows the resource
+    /// fork.
+    pub fn comment_len(&self) -> usize {
+        self.comment_length
+    }
+
+    /// Returns the length, in bytes, of the secondary header that follows this header.
+    pub fn secondary_header_len(&self) -> usize {
+        self.secondary_header_length
+    }
+
+    /// Returns the script code of the file's name (MacBinary III only).
+    pub fn script(&self) -> u8 {
+        self.script
+    }
+
+    /// Returns the file's extended Finder flags (MacBinary III only).
+    pub fn extended_flags(&self) -> u8 {
+        self.extended_flags
+    }
+
+    /// Returns a copy of this header with the given fork and comment lengths and no secondary
+    /// header.
+    pub(super) fn for_writing(
+        self,
+        data_fork_length: usize,
+        resource_fork_length: usize,
+        comment_length: usize,
+    ) -> Self {
+        MacBinaryHeader {
+            data_fork_length,
+            resource_fork_length,
+            comment_length,
+            secondary_header_length: 0,
+            ..self
+        }
+    }
+}
+
+impl TryFrom<&[u8; HEADER_LENGTH]> for MacBinaryHeader {
+    type Error = MacBinaryError;
+
+    fn try_from(bytes: &[u8; HEADER_LENGTH]) -> Result<Self, Self::Error> {
+        // The "old version" byte and the zero fill bytes must be zero in all versions
+        if bytes[0] != 0 || bytes[74] != 0 || bytes[82] != 0 {
+            return Err(MacBinaryError::InvalidHeader);
+        }
+
+        let name_length = bytes[1] as usize;
+
+        if name_length == 0 || name_length > MAX_FILENAME_LENGTH {
+            return Err(MacBinaryError::InvalidHeader);
+        }
+
+        let provided_checksum = u16_at(bytes, 124);
+        let calculated_checksum = crc16::State::<crc16::XMODEM>::calculate(&bytes[..124]);
+
+        let version = if provided_checksum == calculated_checksum {
+            if &bytes[102..106] == SIGNATURE {
+                MacBinaryVersion::MacBinaryIII
+            } else {
+                MacBinaryVersion::MacBinaryII
+            }
+        } else if bytes[122] >= MACBINARY_II_VERSION || &bytes[102..106] == SIGNATURE {
+            // The header claims to be a MacBinary II or III header, but its checksum doesn't match
+            return Err(MacBinaryError::InvalidChecksum(
+                provided_checksum,
+                calculated_checksum,
+            ));
+        } else {
+            MacBinaryVersion::MacBinaryI
+        };
+
+        let data_fork_length = u32_at(bytes, 83);
+        let resource_fork_length = u32_at(bytes, 87);
+
+        if version == MacBinaryVersion::MacBinaryI
+            && (data_fork_length > MAX_MACBINARY_I_FORK_LENGTH
+                || resource_fork_length > MAX_MACBINARY_I_FORK_LENGTH)
+        {
+            return Err(MacBinaryError::InvalidHeader);
+        }
+
+        let name_bytes = &bytes[2..2 + name_length];
+        let (name, _, _) = encoding_rs::MACINTOSH.decode(name_bytes);
+
+        // MacBinary I headers don't include any fields after the modification date
+        let is_macbinary_i = version == MacBinaryVersion::MacBinaryI;
+        let extended = |value| if is_macbinary_i { 0 } else { value };
+
+        Ok(MacBinaryHeader {
+            version,
+            name: name.to_string(),
+            name_bytes: name_bytes.to_vec(),
+            file_type: bytes[65..69].try_into().unwrap(),
+            creator: bytes[69..73].try_into().unwrap(),
+            flags: FinderFlags::from(u16::from_be_bytes([bytes[73], extended(bytes[101])])),
+            location: (u16_at(bytes, 75) as i16, u16_at(bytes, 77) as i16),
+            folder_id: u16_at(bytes, 79),
+            protected: bytes[81] & 0x01 != 0,
+            data_fork_length: data_fork_length as usize,
+            resource_fork_length: resource_fork_length as usize,
+            created: u32_at(bytes, 91),
+            modified: u32_at(bytes, 95),
+            comment_length: if is_macbinary_i {
+                0
+            } else {
+                u16_at(bytes, 99) as usize
+            },
+            secondary_header_length: if is_macbinary_i {
+                0
+            } else {
+                u16_at(bytes, 120) as usize
+            },
+            script: if version == MacBinaryVersion::MacBinaryIII {
+                bytes[106]
+            } else {
+                0
+            },
+            extended_flags: if version == MacBinaryVersion::MacBinaryIII {
+                bytes[107]
+            } else {
+                0
+            },
+        })
+    }
+}
+
+impl From<&MacBinaryHeader> for [u8; HEADER_LENGTH] {
+    fn from(header: &MacBinaryHeader) -> Self {
+        let mut bytes = [0; HEADER_LENGTH];
+        let flags = header.flags.bits().to_be_bytes();
+
+        bytes[1] = header.name_bytes.len() as u8;
+        bytes[2..2 + header.name_bytes.len()].copy_from_slice(&header.name_bytes);
+        bytes[65..69].copy_from_slice(&header.file_type);
+        bytes[69..73].copy_from_slice(&header.creator);
+        bytes[73] = flags[0];
+        bytes[75..77].copy_from_slice(&header.location.0.to_be_bytes());
+        bytes[77..79].copy_from_slice(&header.location.1.to_be_bytes());
+        bytes[79..81].copy_from_slice(&header.folder_id.to_be_bytes());
+        bytes[81] = header.protected as u8;
+        bytes[83..87].copy_from_slice(&(header.data_fork_length as u32).to_be_bytes());
+        bytes[87..91].copy_from_slice(&(header.resource_fork_length as u32).to_be_bytes());
+        bytes[91..95].copy_from_slice(&header.created.to_be_bytes());
+        bytes[95..99].copy_from_slice(&header.modified.to_be_bytes());
+
+        if header.version == MacBinaryVersion::MacBinaryI {
+            return bytes;
+        }
+
+        bytes[99..101].copy_from_slice(&(header.comment_length as u16).to_be_bytes());
+        bytes[101] = flags[1];
+
+        if header.version == MacBinaryVersion::MacBinaryIII {
+            bytes[102..106].copy_from_slice(SIGNATURE);
+            bytes[106] = header.script;
+            bytes[107] = header.extended_flags;
+            bytes[122] = MACBINARY_III_VERSION;
+        } else {
+            bytes[122] = MACBINARY_II_VERSION;
+        }
+
+        bytes[120..122].copy_from_slice(&(header.secondary_header_length as u16).to_be_bytes());
+        bytes[123] = MACBINARY_II_VERSION;
+
+        let checksum = crc16::State::<crc16::XMODEM>::calculate(&bytes[..124]);
+        bytes[124..126].copy_from_slice(&checksum.to_be_bytes());
+
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn header_bytes(version: MacBinaryVersion) -> [u8; HEADER_LENGTH] {
+        let mut header =
+            MacBinaryHeader::new("Read Me", *b"TEXT", *b"ttxt", FinderFlags::from(0x2140)).unwrap();
+
+        header.version = version;
+        header.data_fork_length = 5;
+        header.resource_fork_length = 300;
+        header.created = 0xa000_0000;
+        header.script = 1;
+
+        <[u8; HEADER_LENGTH]>::from(&header)
+    }
+
+    #[test]
+    fn round_trip() -> Result<(), MacBinaryError> {
+        for &version in &[
+            MacBinaryVersion::MacBinaryI,
+            MacBinaryVersion::MacBinaryII,
+            MacBinaryVersion::MacBinaryIII,
+        ] {
+            let bytes = header_bytes(version);
+            let header = MacBinaryHeader::try_from(&bytes)?;
+
+            assert_eq!(version, header.version());
+            assert_eq!("Read Me", header.filename());
+            assert_eq!(*b"TEXT", header.file_type());
+            assert_eq!(*b"ttxt", header.creator());
+            assert_eq!(5, header.data_fork_len());
+            assert_eq!(300, header.resource_fork_len());
+            assert_eq!(0xa000_0000, header.created());
+            assert_eq!(bytes, <[u8; HEADER_LENGTH]>::from(&header));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn version_specific_fields() -> Result<(), MacBinaryError> {
+        let header = MacBinaryHeader::try_from(&header_bytes(MacBinaryVersion::MacBinaryI))?;
+        assert_eq!(0x2100, header.flags().bits());
+        assert_eq!(0, header.script());
+
+        let header = MacBinaryHeader::try_from(&header_bytes(MacBinaryVersion::MacBinaryII))?;
+        assert_eq!(0x2140, header.flags().bits());
+        assert_eq!(0, header.script());
+
+        let header = MacBinaryHeader::try_from(&header_bytes(MacBinaryVersion::MacBinaryIII))?;
+        assert_eq!(0x2140, header.flags().bits());
+        assert_eq!(1, header.script());
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_checksum() {
+        let mut bytes = header_bytes(MacBinaryVersion::MacBinaryII);
+        bytes[66] = b'X';
+
+        assert!(matches!(
+            MacBinaryHeader::try_from(&bytes),
+            Err(MacBinaryError::InvalidChecksum(_, _))
+        ));
+    }
+
+    #[test]
+    fn invalid_header() {
+        let mut bytes = header_bytes(MacBinaryVersion::MacBinaryI);
+        bytes[1] = 64;
+
+        assert_eq!(
+            Err(MacBinaryError::InvalidHeader),
+            MacBinaryHeader::try_from(&bytes)
+        );
+
+        let mut bytes = header_bytes(MacBinaryVersion::MacBinaryI);
+        bytes[74] = 1;
+
+        assert_eq!(
+            Err(MacBinaryError::InvalidHeader),
+            MacBinaryHeader::try_from(&bytes)
+        );
+
+        let mut bytes = header_bytes(MacBinaryVersion::MacBinaryI);
+        bytes[83] = 0x01;
+
+        assert_eq!(
+            Err(MacBinaryError::InvalidHeader),
+            MacBinaryHeader::try_from(&bytes)
+        );
+    }
+}
@@ -0,0 +1,18 @@
+//! Tools for extracting data from and writing MacBinary files.
+//!
+//! Like BinHex, MacBinary combines the data fork, resource fork, and metadata associated with a
+//! "classic" Mac file into a single file. Unlike BinHex, MacBinary files are binary files: a
+//! 128-byte header is followed by each fork, padded to a multiple of 128 bytes. MacBinary files
+//! usually have a `.bin` extension.
+//!
+//! For details about the MacBinary format, please see:
+//!
+//! - [MacBinary I](https://web.archive.org/web/20050307030202/http://www.lazerware.com/formats/macbinary/macbinary.html)
+//! - [MacBinary II](https://web.archive.org/web/20050305042909/http://www.lazerware.com/formats/macbinary/macbinary_ii.html)
+//! - [MacBinary III](https://web.archive.org/web/20050305044255/http://www.lazerware.com/formats/macbinary/macbinary_iii.html)
+
+mod archive;
+mod header;
+
+pub use archive::{Fork, MacBinaryArchive, MacBinaryError, MacBinaryForkReader, MacBinaryWriter};
+pub use header::{MacBinaryHeader, MacBinaryVersion};
@@ -0,0 +1,767 @@
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Read, Seek, Write};
+
+use super::report::{LossReport, MacFileField};
+use crate::applesingle::{
+    AppleSingleArchive, AppleSingleError, AppleSingleKind, AppleSingleVersion, AppleSingleWriter,
+    EntryId, FileDates, MacFileInfo,
+};
+use crate::binhex::{BinHexArchive, BinHexError, BinHexHeader, BinHexWriter};
+use crate::finder::{FinderFlags, FinderInfo};
+use crate::macbinary::{MacBinaryArchive, MacBinaryError, MacBinaryHeader, MacBinaryWriter};
+
+/// A "classic" Macintosh file, independent of the container in which it was stored.
+///
+/// A `MacFile` holds a file's name, Finder information (including its type and creator), extended
+/// Finder information, attributes, dates, comment, and both of its forks in memory. Files can be
+/// loaded from any supported container format and written to any other, preserving as much
+/// metadata as the destination format allows; every method that writes a file returns a
+/// [`LossReport`] describing the metadata that the destination format couldn't represent.
+///
+/// Dates are represented as classic Mac OS dates (seconds since midnight, January 1, 1904, in local
+/// time), and are `None` if unknown.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs::File;
+/// use clarus::binhex::BinHexArchive;
+/// use clarus::macfile::{MacFile, MacFileError};
+///
+/// fn main() -> Result<(), MacFileError> {
+///     let file = MacFile::from_binhex(BinHexArchive::new(File::open("example.hqx")?)?)?;
+///
+///     let report = file.to_apple_double(
+///         &mut File::create(file.name())?,
+///         &mut File::create(format!("._{}", file.name()))?,
+///     )?;
+///
+///     assert!(report.is_lossless());
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MacFile {
+    name: String,
+    finder_info: FinderInfo,
+    extended_finder_info: [u8; 16],
+    mac_file_info: MacFileInfo,
+    created: Option<u32>,
+    modified: Option<u32>,
+    backed_up: Option<u32>,
+    comment: Vec<u8>,
+    data_fork: Vec<u8>,
+    resource_fork: Vec<u8>,
+}
+
+impl MacFile {
+    /// Creates a new file with the given name, type, and creator, empty forks, and no other
+    /// metadata.
+    pub fn new(name: &str, file_type: [u8; 4], creator: [u8; 4]) -> Self {
+        MacFile {
+            name: String::from(name),
+            finder_info: FinderInfo::new(file_type, creator, FinderFlags::default()),
+            ..Default::default()
+        }
+    }
+
+    /// Loads a file from a BinHex 4.0 archive.
+    ///
+    /// BinHex archives don't include dates, comments, extended Finder information, attributes, or
+    /// the file's location within its window.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if either fork could not be extracted from the archive.
+    pub fn from_binhex<R: Read>(archive: BinHexArchive<R>) -> Result<Self, MacFileError> {
+        let header = archive.header().clone();
+
+        let mut file = MacFile {
+            name: header.filename().clone(),
+            finder_info: FinderInfo::new(header.file_type(), header.creator(), header.flags()),
+            ..Default::default()
+        };
+
+        archive.extract(&mut file.data_fork, &mut file.resource_fork)?;
+
+        Ok(file)
+    }
+
+    /// Loads a file from a MacBinary archive.
+    ///
+    /// MacBinary archives don't include backup dates, extended Finder information, or attributes.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if either fork or the file's comment could not be read
+    /// from the archive.
+    pub fn from_macbinary<R: Read>(mut archive: MacBinaryArchive<R>) -> Result<Self, MacFileError> {
+        let header = archive.header().clone();
+
+        let mut finder_info = FinderInfo::new(header.file_type(), header.creator(), header.flags());
+        finder_info.set_location(header.location());
+        finder_info.set_folder(header.folder_id() as i16);
+
+        let mut file = MacFile {
+            name: header.filename().clone(),
+            finder_info,
+            created: mac_date(header.created()),
+            modified: mac_date(header.modified()),
+            ..Default::default()
+        };
+
+        io::copy(&mut archive.data_fork()?, &mut file.data_fork)?;
+        io::copy(&mut archive.resource_fork()?, &mut file.resource_fork)?;
+        file.comment = archive.comment()?;
+
+        Ok(file)
+    }
+
+    /// Loads a file from an AppleSingle archive.
+    ///
+    /// If the archive doesn't include the file's real name, the loaded file's name is empty.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any entry could not be read from the archive.
+    pub fn from_apple_single<R: Read + Seek>(
+        mut archive: AppleSingleArchive<R>,
+    ) -> Result<Self, MacFileError> {
+        let mut file = MacFile::default();
+        file.load_apple_single_entries(&mut archive, "")?;
+
+        if archive.entry(EntryId::DataFork).is_some() {
+            io::copy(&mut archive.data_fork()?, &mut file.data_fork)?;
+        }
+
+        Ok(file)
+    }
+
+    /// Loads a file from a plain data file and its AppleDouble header file.
+    ///
+    /// AppleDouble header files don't usually include the file's real name, and so the given name
+    /// (usually the name of the data file) is used unless the header file includes a real name.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the data file could not be read or if any entry could
+    /// not be read from the header file.
+    pub fn from_apple_double<R: Read + Seek>(
+        name: &str,
+        data_file: &mut impl Read,
+        mut header: AppleSingleArchive<R>,
+    ) -> Result<Self, MacFileError> {
+        let mut file = MacFile::default();
+        file.load_apple_single_entries(&mut header, name)?;
+
+        io::copy(data_file, &mut file.data_fork)?;
+
+        Ok(file)
+    }
+
+    fn load_apple_single_entries<R: Read + Seek>(
+        &mut self,
+        archive: &mut AppleSingleArchive<R>,
+        default_name: &str,
+    ) -> Result<(), MacFileError> {
+        self.name = archive
+            .real_name()?
+            .unwrap_or_else(|| String::from(default_name));
+
+        if let Some(finder_info) = archive.finder_info()? {
+            self.finder_info = finder_info;
+        }
+
+        if let Some(extended_finder_info) = archive.extended_finder_info()? {
+            self.extended_finder_info = extended_finder_info;
+        }
+
+        if let Some(mac_file_info) = archive.mac_file_info()? {
+            self.mac_file_info = mac_file_info;
+        }
+
+        if let Some(dates) = archive.file_dates()? {
+            self.created = mac_date(FileDates::to_mac_date(dates.created));
+            self.modified = mac_date(FileDates::to_mac_date(dates.modified));
+            self.backed_up = mac_date(FileDates::to_mac_date(dates.backed_up));
+        }
+
+        self.comment = archive.comment()?.unwrap_or_default();
+
+        if archive.entry(EntryId::ResourceFork).is_some() {
+            io::copy(&mut archive.resource_fork()?, &mut self.resource_fork)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the file's name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Sets the file's name.
+    pub fn set_name(&mut self, name: &str) {
+        self.name = String::from(name);
+    }
+
+    /// Returns the file's type identifier.
+    pub fn file_type(&self) -> [u8; 4] {
+        self.finder_info.file_type()
+    }
+
+    /// Returns the file's creator identifier.
+    pub fn creator(&self) -> [u8; 4] {
+        self.finder_info.creator()
+    }
+
+    /// Returns the file's Finder information, which includes its type, creator, and Finder flags.
+    pub fn finder_info(&self) -> &FinderInfo {
+        &self.finder_info
+    }
+
+    /// Sets the file's Finder information.
+    pub fn set_finder_info(&mut self, finder_info: FinderInfo) {
+        self.finder_info = finder_info;
+    }
+
+    /// Returns the file's extended Finder information (known as `FXInfo` in "Inside Macintosh") as
+    /// raw bytes; the extended Finder information is all zeroes if unknown.
+    pub fn extended_finder_info(&self) -> [u8; 16] {
+        self.extended_finder_info
+    }
+
+    /// Sets the file's extended Finder information.
+    pub fn set_extended_finder_info(&mut self, extended_finder_info: [u8; 16]) {
+        self.extended_finder_info = extended_finder_info;
+    }
+
+    /// Returns the file's Macintosh attributes (i.e. whether the file is locked or protected).
+    pub fn mac_file_info(&self) -> MacFileInfo {
+        self.mac_file_info
+    }
+
+    /// Sets the file's Macintosh attributes.
+    pub fn set_mac_file_info(&mut self, mac_file_info: MacFileInfo) {
+        self.mac_file_info = mac_file_info;
+    }
+
+    /// Returns the file's creation date, if known.
+    pub fn created(&self) -> Option<u32> {
+        self.created
+    }
+
+    /// Sets the file's creation date.
+    pub fn set_created(&mut self, created: Option<u32>) {
+        self.created = created;
+    }
+
+    /// Returns the file's modification date, if known.
+    pub fn modified(&self) -> Option<u32> {
+        self.modified
+    }
+
+    /// Sets the file's modification date.
+    pub fn set_modified(&mut self, modified: Option<u32>) {
+        self.modified = modified;
+    }
+
+    /// Returns the file's backup date, if known.
+    pub fn backed_up(&self) -> Option<u32> {
+        self.backed_up
+    }
+
+    /// Sets the file's backup date.
+    pub fn set_backed_up(&mut self, backed_up: Option<u32>) {
+        self.backed_up = backed_up;
+    }
+
+    /// Returns the file's Get Info comment as raw bytes in the Macintosh character encoding; the
+    /// comment is empty if the file has no comment.
+    pub fn comment(&self) -> &[u8] {
+        &self.comment
+    }
+
+    /// Sets the file's Get Info comment.
+    pub fn set_comment(&mut self, comment: Vec<u8>) {
+        self.comment = comment;
+    }
+
+    /// Returns the content of the file's data fork.
+    pub fn data_fork(&self) -> &[u8] {
+        &self.data_fork
+    }
+
+    /// Sets the content of the file's data fork.
+    pub fn set_data_fork(&mut self, data_fork: Vec<u8>) {
+        self.data_fork = data_fork;
+    }
+
+    /// Returns the content of the file's resource fork.
+    pub fn resource_fork(&self) -> &[u8] {
+        &self.resource_fork
+    }
+
+    /// Sets the content of the file's resource fork.
+    pub fn set_resource_fork(&mut self, resource_fork: Vec<u8>) {
+        self.resource_fork = resource_fork;
+    }
+
+    /// Writes this file to the given destination as a BinHex 4.0 archive.
+    ///
+    /// BinHex archives can't represent dates, comments, extended Finder information, attributes, or
+    /// the file's location within its window.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if this file's name can't be represented in a BinHex
+    /// header or if an IO error occurs while writing the archive.
+    pub fn to_binhex(&self, dest: &mut impl Write) -> Result<LossReport, MacFileError> {
+        let header = BinHexHeader::new(
+            &self.name,
+            self.file_type(),
+            self.creator(),
+            self.finder_info.flags(),
+        )?;
+
+        BinHexWriter::with_header(dest, header).encode(
+            &mut self.data_fork.as_slice(),
+            self.data_fork.len(),
+            &mut self.resource_fork.as_slice(),
+            self.resource_fork.len(),
+        )?;
+
+        let mut report = LossReport::default();
+        self.report_location(&mut report);
+        self.report_dates(
+            &mut report,
+            &[
+                MacFileField::CreationDate,
+                MacFileField::ModificationDate,
+                MacFileField::BackupDate,
+            ],
+        );
+        self.report_comment(&mut report);
+        self.report_extended_info(&mut report);
+
+        Ok(report)
+    }
+
+    /// Writes this file to the given destination as a MacBinary III archive.
+    ///
+    /// MacBinary archives written by this method can't represent backup dates, extended Finder
+    /// information, attributes, or comments of 64 KiB or more.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if this file's name can't be represented in a MacBinary
+    /// header or if an IO error occurs while writing the archive.
+    pub fn to_macbinary(&self, dest: &mut impl Write) -> Result<LossReport, MacFileError> {
+        let mut header = MacBinaryHeader::new(
+            &self.name,
+            self.file_type(),
+            self.creator(),
+            self.finder_info.flags(),
+        )?;
+
+        header.set_location(self.finder_info.location());
+        header.set_folder_id(self.finder_info.folder() as u16);
+        header.set_dates(self.created.unwrap_or(0), self.modified.unwrap_or(0));
+
+        let mut writer = MacBinaryWriter::with_header(dest, header);
+        let mut report = LossReport::default();
+
+        if self.comment.len() <= u16::MAX as usize {
+            writer.set_comment(&self.comment);
+        } else {
+            self.report_comment(&mut report);
+        }
+
+        writer.encode(
+            &mut self.data_fork.as_slice(),
+            self.data_fork.len(),
+            &mut self.resource_fork.as_slice(),
+            self.resource_fork.len(),
+        )?;
+
+        self.report_dates(&mut report, &[MacFileField::BackupDate]);
+        self.report_extended_info(&mut report);
+
+        Ok(report)
+    }
+
+    /// Writes this file to the given destination as an AppleSingle file in the given version of the
+    /// format.
+    ///
+    /// AppleSingle files can represent all of the metadata held by a `MacFile`, and so the returned
+    /// report is always lossless.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if an IO error occurs while writing the file.
+    pub fn to_apple_single(
+        &self,
+        dest: &mut impl Write,
+        version: AppleSingleVersion,
+    ) -> Result<LossReport, MacFileError> {
+        self.apple_single_writer(dest, AppleSingleKind::AppleSingle, version)?
+            .encode(
+                &mut self.data_fork.as_slice(),
+                self.data_fork.len(),
+                &mut self.resource_fork.as_slice(),
+                self.resource_fork.len(),
+            )?;
+
+        Ok(LossReport::default())
+    }
+
+    /// Writes this file's data fork to `data_dest` as a plain file and everything else to
+    /// `header_dest` as a version 2 AppleDouble header file.
+    ///
+    /// AppleDouble files can represent all of the metadata held by a `MacFile`, and so the returned
+    /// report is always lossless.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if an IO error occurs while writing either file.
+    pub fn to_apple_double(
+        &self,
+        data_dest: &mut impl Write,
+        header_dest: &mut impl Write,
+    ) -> Result<LossReport, MacFileError> {
+        data_dest.write_all(&self.data_fork)?;
+
+        self.apple_single_writer(
+            header_dest,
+            AppleSingleKind::AppleDouble,
+            AppleSingleVersion::V2,
+        )?
+        .encode(
+            &mut io::empty(),
+            0,
+            &mut self.resource_fork.as_slice(),
+            self.resource_fork.len(),
+        )?;
+
+        Ok(LossReport::default())
+    }
+
+    fn apple_single_writer<W: Write>(
+        &self,
+        dest: W,
+        kind: AppleSingleKind,
+        version: AppleSingleVersion,
+    ) -> Result<AppleSingleWriter<W>, MacFileError> {
+        let mut writer = AppleSingleWriter::new(dest, kind, version);
+
+        if !self.name.is_empty() {
+            writer.set_real_name(&self.name)?;
+        }
+
+        if !self.comment.is_empty() {
+            writer.set_comment(&self.comment);
+        }
+
+        writer.set_finder_info(self.finder_info);
+
+        if self.extended_finder_info != [0; 16] {
+            writer.set_extended_finder_info(self.extended_finder_info);
+        }
+
+        if self.mac_file_info != MacFileInfo::default() {
+            writer.set_mac_file_info(self.mac_file_info);
+        }
+
+        if self.created.is_some() || self.modified.is_some() || self.backed_up.is_some() {
+            let apple_single_date =
+                |date: Option<u32>| date.map_or(FileDates::UNKNOWN, FileDates::from_mac_date);
+
+            writer.set_file_dates(FileDates {
+                created: apple_single_date(self.created),
+                modified: apple_single_date(self.modified),
+                backed_up: apple_single_date(self.backed_up),
+                accessed: FileDates::UNKNOWN,
+            });
+        }
+
+        Ok(writer)
+    }
+
+    fn report_location(&self, report: &mut LossReport) {
+        if self.finder_info.location() != (0, 0) {
+            report.add(MacFileField::Location);
+        }
+
+        if self.finder_info.folder() != 0 {
+            report.add(MacFileField::Folder);
+        }
+    }
+
+    fn report_dates(&self, report: &mut LossReport, fields: &[MacFileField]) {
+        for field in fields {
+            let date = match field {
+                MacFileField::CreationDate => self.created,
+                MacFileField::ModificationDate => self.modified,
+                MacFileField::BackupDate => self.backed_up,
+                _ => None,
+            };
+
+            if date.is_some() {
+                report.add(*field);
+            }
+        }
+    }
+
+    fn report_comment(&self, report: &mut LossReport) {
+        if !self.comment.is_empty() {
+            report.add(MacFileField::Comment);
+        }
+    }
+
+    fn report_extended_info(&self, report: &mut LossReport) {
+        if self.extended_finder_info != [0; 16] {
+            report.add(MacFileField::ExtendedFinderInfo);
+        }
+
+        if self.mac_file_info != MacFileInfo::default() {
+            report.add(MacFileField::Attributes);
+        }
+    }
+}
+
+/// Converts a classic Mac OS date, which is zero if unknown, to an optional date.
+fn mac_date(date: u32) -> Option<u32> {
+    match date {
+        0 => None,
+        _ => Some(date),
+    }
+}
+
+/// The error type for loading and writing [`MacFile`]s.
+///
+/// Errors from the underlying container formats are passed through unchanged.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MacFileError {
+    /// An [`std::io::Error`] occurred while reading or writing a file outside of any container.
+    ///
+    /// The kind of IO error is included.
+    IoError(io::ErrorKind),
+
+    /// An error occurred while reading or writing a BinHex archive.
+    BinHex(BinHexError),
+
+    /// An error occurred while reading or writing a MacBinary archive.
+    MacBinary(MacBinaryError),
+
+    /// An error occurred while reading or writing an AppleSingle or AppleDouble file.
+    AppleSingle(AppleSingleError),
+}
+
+impl Display for MacFileError {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MacFileError::IoError(kind) => write!(fmt, "IO error: {:?}", kind),
+            MacFileError::BinHex(error) => write!(fmt, "BinHex error: {}", error),
+            MacFileError::MacBinary(error) => write!(fmt, "MacBinary error: {}", error),
+            MacFileError::AppleSingle(error) => write!(fmt, "AppleSingle error: {}", error),
+        }
+    }
+}
+
+impl From<io::Error> for MacFileError {
+    fn from(error: io::Error) -> Self {
+        MacFileError::IoError(error.kind())
+    }
+}
+
+impl From<BinHexError> for MacFileError {
+    fn from(error: BinHexError) -> Self {
+        MacFileError::BinHex(error)
+    }
+}
+
+impl From<MacBinaryError> for MacFileError {
+    fn from(error: MacBinaryError) -> Self {
+        MacFileError::MacBinary(error)
+    }
+}
+
+impl From<AppleSingleError> for MacFileError {
+    fn from(error: AppleSingleError) -> Self {
+        MacFileError::AppleSingle(error)
+    }
+}
+
+impl error::Error for MacFileError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    const DATA_FORK: &[u8] = b"===== Hello from the data fork! =====";
+    const RESOURCE_FORK: &[u8] = include_bytes!("../rsrc/string-table.rsrc");
+
+    fn mac_file() -> MacFile {
+        let mut finder_info = FinderInfo::new(*b"TEXT", *b"ttxt", FinderFlags::from(0x0100));
+        finder_info.set_location((16, 32));
+
+        let mut file = MacFile::new("SimpleText™ Document", *b"TEXT", *b"ttxt");
+        file.set_finder_info(finder_info);
+        file.set_extended_finder_info([0, 0, 0, 0, 0, 0, 0, 0, 0, 0x80, 0, 0, 0, 0, 0, 0]);
+        file.set_mac_file_info(MacFileInfo {
+            locked: true,
+            protected: false,
+        });
+        file.set_created(Some(0xb492_f400));
+        file.set_modified(Some(0xb492_f401));
+        file.set_backed_up(Some(0xb492_f402));
+        file.set_comment(b"A comment".to_vec());
+        file.set_data_fork(DATA_FORK.to_vec());
+        file.set_resource_fork(RESOURCE_FORK.to_vec());
+
+        file
+    }
+
+    #[test]
+    fn binhex_round_trip() -> Result<(), MacFileError> {
+        let original = mac_file();
+
+        let mut encoded = vec![];
+        let report = original.to_binhex(&mut encoded)?;
+
+        assert!(!report.is_lossless());
+        assert!(report.has_lost(MacFileField::Location));
+        assert!(!report.has_lost(MacFileField::Folder));
+        assert!(report.has_lost(MacFileField::CreationDate));
+        assert!(report.has_lost(MacFileField::BackupDate));
+        assert!(report.has_lost(MacFileField::Comment));
+        assert!(report.has_lost(MacFileField::ExtendedFinderInfo));
+        assert!(report.has_lost(MacFileField::Attributes));
+
+        let decoded = MacFile::from_binhex(BinHexArchive::new(encoded.as_slice())?)?;
+
+        assert_eq!(original.name(), decoded.name());
+        assert_eq!(original.file_type(), decoded.file_type());
+        assert_eq!(original.creator(), decoded.creator());
+        assert_eq!(
+            original.finder_info().flags(),
+            decoded.finder_info().flags()
+        );
+        assert_eq!(None, decoded.created());
+        assert_eq!(DATA_FORK, decoded.data_fork());
+        assert_eq!(RESOURCE_FORK, decoded.resource_fork());
+
+        Ok(())
+    }
+
+    #[test]
+    fn macbinary_round_trip() -> Result<(), MacFileError> {
+        let original = mac_file();
+
+        let mut encoded = vec![];
+        let report = original.to_macbinary(&mut encoded)?;
+
+        assert_eq!(
+            &[
+                MacFileField::BackupDate,
+                MacFileField::ExtendedFinderInfo,
+                MacFileField::Attributes
+            ],
+            report.lost()
+        );
+
+        let decoded = MacFile::from_macbinary(MacBinaryArchive::new(encoded.as_slice())?)?;
+
+        assert_eq!(original.finder_info(), decoded.finder_info());
+        assert_eq!(original.created(), decoded.created());
+        assert_eq!(original.modified(), decoded.modified());
+        assert_eq!(None, decoded.backed_up());
+        assert_eq!(original.comment(), decoded.comment());
+        assert_eq!(DATA_FORK, decoded.data_fork());
+        assert_eq!(RESOURCE_FORK, decoded.resource_fork());
+
+        Ok(())
+    }
+
+    #[test]
+    fn apple_single_round_trip() -> Result<(), MacFileError> {
+        let original = mac_file();
+
+        for version in &[AppleSingleVersion::V1, AppleSingleVersion::V2] {
+            let mut encoded = vec![];
+            assert!(original
+                .to_apple_single(&mut encoded, *version)?
+                .is_lossless());
+
+            let decoded =
+                MacFile::from_apple_single(AppleSingleArchive::new(Cursor::new(encoded))?)?;
+
+            assert_eq!(original, decoded);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn apple_double_round_trip() -> Result<(), MacFileError> {
+        let original = mac_file();
+
+        let mut data_file = vec![];
+        let mut header_file = vec![];
+
+        assert!(original
+            .to_apple_double(&mut data_file, &mut header_file)?
+            .is_lossless());
+
+        assert_eq!(DATA_FORK, data_file.as_slice());
+
+        let decoded = MacFile::from_apple_double(
+            "Ignored",
+            &mut data_file.as_slice(),
+            AppleSingleArchive::new(Cursor::new(header_file))?,
+        )?;
+
+        assert_eq!(original, decoded);
+
+        Ok(())
+    }
+
+    #[test]
+    fn binhex_to_apple_double() -> Result<(), MacFileError> {
+        let mut encoded = vec![];
+        MacFile::new("Plain", *b"TEXT", *b"ttxt").to_binhex(&mut encoded)?;
+
+        let file = MacFile::from_binhex(BinHexArchive::new(encoded.as_slice())?)?;
+
+        let mut data_file = vec![];
+        let mut header_file = vec![];
+
+        assert!(file
+            .to_apple_double(&mut data_file, &mut header_file)?
+            .is_lossless());
+
+        let decoded = MacFile::from_apple_double(
+            "Plain",
+            &mut data_file.as_slice(),
+            AppleSingleArchive::new(Cursor::new(header_file))?,
+        )?;
+
+        assert_eq!(file, decoded);
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_name() {
+        assert_eq!(
+            MacFileError::BinHex(BinHexError::InvalidFilename),
+            MacFile::new("", *b"TEXT", *b"ttxt")
+                .to_binhex(&mut vec![])
+                .err()
+                .unwrap()
+        );
+    }
+}
@@ -0,0 +1,14 @@
+//! A container-independent model of "classic" Mac files.
+//!
+//! Each container format supported by this crate (BinHex, MacBinary, AppleSingle, and AppleDouble)
+//! stores a different subset of a file's metadata in its own way. [`MacFile`] represents a file's
+//! name, Finder information, attributes, dates, comment, and forks independently of any container,
+//! and can be loaded from and written to any supported format. Because not every format can
+//! represent every piece of metadata, writing a file produces a [`LossReport`] that lists anything
+//! that was discarded.
+
+mod file;
+mod report;
+
+pub use file::{MacFile, MacFileError};
+pub use report::{LossReport, MacFileField};
@@ -0,0 +1,61 @@
+/// A piece of metadata that a [`MacFile`](super::MacFile) may carry, but that some container
+/// formats can't represent.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum MacFileField {
+    /// The position of the file's icon within its window.
+    Location,
+
+    /// The ID of the window (or folder) that contained the file.
+    Folder,
+
+    /// The file's creation date.
+    CreationDate,
+
+    /// The file's modification date.
+    ModificationDate,
+
+    /// The file's backup date.
+    BackupDate,
+
+    /// The file's Get Info comment.
+    Comment,
+
+    /// The file's extended Finder information.
+    ExtendedFinderInfo,
+
+    /// The file's Macintosh attributes (i.e. whether the file is locked or protected).
+    Attributes,
+}
+
+/// A report of the metadata that was discarded when a [`MacFile`](super::MacFile) was written in
+/// a format that couldn't represent all of it.
+///
+/// Only metadata that was actually present is reported; for example, writing a file with no
+/// comment to a format that can't store comments doesn't report a lost comment.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LossReport {
+    lost: Vec<MacFileField>,
+}
+
+impl LossReport {
+    pub(super) fn add(&mut self, field: MacFileField) {
+        if !self.lost.contains(&field) {
+            self.lost.push(field);
+        }
+    }
+
+    /// Returns `true` if all of the file's metadata was preserved.
+    pub fn is_lossless(&self) -> bool {
+        self.lost.is_empty()
+    }
+
+    /// Returns the metadata that was discarded, in no particular order.
+    pub fn lost(&self) -> &[MacFileField] {
+        &self.lost
+    }
+
+    /// Returns `true` if the given piece of metadata was discarded.
+    pub fn has_lost(&self, field: MacFileField) -> bool {
+        self.lost.contains(&field)
+    }
+}
@@ -0,0 +1,112 @@
+use crate::binhex::hex_value;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Decodes base64-encoded content.
+///
+/// Decoding is lenient: line breaks and any other characters outside of the base64 alphabet are
+/// ignored, padding is optional, and a dangling character at the end of the content (which can't
+/// represent a whole byte) is ignored.
+pub(super) fn decode_base64(encoded: &[u8]) -> Vec<u8> {
+    let mut chars: Vec<u8> = encoded
+        .iter()
+        .copied()
+        .filter(|&c| c.is_ascii_alphanumeric() || c == b'+' || c == b'/')
+        .collect();
+
+    if chars.len() % 4 == 1 {
+        chars.pop();
+    }
+
+    // The last character of content that doesn't fill a whole group may carry bits that don't
+    // belong to any byte; those bits should be zero, but lenient decoders ignore them
+    let unused_bits = match chars.len() % 4 {
+        2 => 0b1111,
+        3 => 0b11,
+        _ => 0,
+    };
+
+    if unused_bits != 0 {
+        if let Some(last) = chars.last_mut() {
+            let value = BASE64_ALPHABET.iter().position(|c| c == last).unwrap();
+            *last = BASE64_ALPHABET[value & !unused_bits];
+        }
+    }
+
+    radix64::STD_NO_PAD
+        .decode(&chars)
+        .expect("Filtered base64 content should always decode")
+}
+
+/// Decodes quoted-printable content as described in [RFC 2045, section
+/// 6.7](https://tools.ietf.org/html/rfc2045#section-6.7).
+///
+/// Decoding is lenient: an equals sign that isn't followed by two hexadecimal digits or a line
+/// break is kept as-is.
+pub(super) fn decode_quoted_printable(encoded: &[u8]) -> Vec<u8> {
+    let mut decoded = Vec::with_capacity(encoded.len());
+    let mut i = 0;
+
+    while i < encoded.len() {
+        if encoded[i] != b'=' {
+            decoded.push(encoded[i]);
+            i += 1;
+            continue;
+        }
+
+        let escape = &encoded[i + 1..];
+
+        if escape.starts_with(b"\r\n") {
+            // Soft line break
+            i += 3;
+        } else if escape.starts_with(b"\n") {
+            i += 2;
+        } else if let [high, low, ..] = escape {
+            match (hex_value(*high), hex_value(*low)) {
+                (Some(high), Some(low)) => {
+                    decoded.push((high << 4) | low);
+                    i += 3;
+                }
+                _ => {
+                    decoded.push(b'=');
+                    i += 1;
+                }
+            }
+        } else {
+            decoded.push(b'=');
+            i += 1;
+        }
+    }
+
+    decoded
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn base64() {
+        assert_eq!(b"Hello!".to_vec(), decode_base64(b"SGVs\r\nbG8h"));
+        assert_eq!(b"Hello".to_vec(), decode_base64(b"SGVsbG8="));
+        assert_eq!(b"Hello".to_vec(), decode_base64(b"SGVsbG8"));
+        assert_eq!(b"Hel".to_vec(), decode_base64(b"SGVsb"));
+
+        // Stray bits in the last character are ignored
+        assert_eq!(b"Hi".to_vec(), decode_base64(b"SGl"));
+        assert_eq!(b"H".to_vec(), decode_base64(b"SH"));
+    }
+
+    #[test]
+    fn quoted_printable() {
+        assert_eq!(
+            b"(This file must be converted with BinHex 4.0)\n:abc=def".to_vec(),
+            decode_quoted_printable(
+                b"(This file must be =\r\nconverted with BinHex 4.0)\n:abc=3Ddef"
+            )
+        );
+
+        assert_eq!(b"a=zz=".to_vec(), decode_quoted_printable(b"a=zz="));
+    }
+}
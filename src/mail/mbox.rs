@@ -0,0 +1,157 @@
+use std::io::{BufRead, Result};
+
+const FROM_LINE: &[u8] = b"From ";
+
+/// An iterator over the messages in an mbox file.
+///
+/// Messages in an mbox file are separated by "From " lines, each of which must follow a blank line
+/// (or appear at the start of the file). The "From " line itself is not included in the messages
+/// produced by this iterator. Lines within a message that begin with "From " are escaped with a
+/// leading `>` when written to an mbox file; this iterator removes one leading `>` from any line
+/// that consists of one or more `>` characters followed by "From ", which reverses the escaping
+/// used by both the "mboxo" and "mboxrd" variants of the format.
+///
+/// For details about the mbox format, please see [RFC 4155 - The application/mbox Media
+/// Type](https://tools.ietf.org/html/rfc4155).
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs::File;
+/// use std::io::BufReader;
+/// use clarus::binhex::BinHexError;
+/// use clarus::mail::{self, Mbox};
+///
+/// fn main() -> Result<(), BinHexError> {
+///     let mbox = Mbox::new(BufReader::new(File::open("info-mac.mbox")?));
+///
+///     for message in mbox {
+///         for part in mail::binhex_parts(&message?) {
+///             println!("Found {}", part.archive()?.filename());
+///         }
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+pub struct Mbox<R: BufRead> {
+    source: R,
+    line: Vec<u8>,
+    done: bool,
+}
+
+impl<R: BufRead> Mbox<R> {
+    /// Creates a new iterator over the messages in the given source.
+    pub fn new(source: R) -> Self {
+        Mbox {
+            source,
+            line: vec![],
+            done: false,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Mbox<R> {
+    type Item = Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let mut message = vec![];
+
+        // The previous call (if any) stopped just after a "From " line, which can't be followed by
+        // another "From " line; at the start of the file, a "From " line is expected.
+        let mut previous_line_blank = true;
+
+        loop {
+            self.line.clear();
+
+            match self.source.read_until(b'\n', &mut self.line) {
+                Ok(0) => {
+                    self.done = true;
+
+                    return if message.is_empty() {
+                        None
+                    } else {
+                        Some(Ok(without_separator(message)))
+                    };
+                }
+                Ok(_) => {}
+                Err(error) => {
+                    self.done = true;
+                    return Some(Err(error));
+                }
+            }
+
+            if previous_line_blank && self.line.starts_with(FROM_LINE) {
+                if message.is_empty() {
+                    continue;
+                }
+
+                return Some(Ok(without_separator(message)));
+            }
+
+            previous_line_blank = self.line == b"\n" || self.line == b"\r\n";
+
+            let quoted_from = self.line.iter().take_while(|&&b| b == b'>').count();
+
+            if quoted_from > 0 && self.line[quoted_from..].starts_with(FROM_LINE) {
+                message.extend_from_slice(&self.line[1..]);
+            } else {
+                message.extend_from_slice(&self.line);
+            }
+        }
+    }
+}
+
+/// Removes the blank line that separates a message from the next "From " line.
+fn without_separator(mut message: Vec<u8>) -> Vec<u8> {
+    if message.ends_with(b"\r\n\r\n") {
+        message.truncate(message.len() - 2);
+    } else if message.ends_with(b"\n\n") {
+        message.truncate(message.len() - 1);
+    }
+
+    message
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn messages() {
+        let mbox = indoc! {b"
+            From someone@example.com Sat Jan  1 00:00:00 1994
+            Subject: First
+
+            Hello.
+            >From the desk of someone.
+            >>From here.
+
+            From someone-else@example.com Sun Jan  2 00:00:00 1994
+            Subject: Second
+
+            >From now on,
+            hello.
+        "};
+
+        let messages: Vec<Vec<u8>> = Mbox::new(&mbox[..]).map(|m| m.unwrap()).collect();
+
+        assert_eq!(
+            vec![
+                b"Subject: First\n\nHello.\nFrom the desk of someone.\n>From here.\n".to_vec(),
+                b"Subject: Second\n\nFrom now on,\nhello.\n".to_vec(),
+            ],
+            messages
+        );
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(0, Mbox::new(&b""[..]).count());
+    }
+}
@@ -0,0 +1,499 @@
+use super::encoding::{decode_base64, decode_quoted_printable};
+use crate::binhex::{find, BinHexArchive, BinHexError, BinHexStream, BANNER};
+
+/// The content types that identify BinHex parts. RFC 1741 registers `application/mac-binhex40`;
+/// the others were used by various mail clients before (and after) registration.
+const BINHEX_CONTENT_TYPES: &[&str] = &[
+    "application/mac-binhex40",
+    "application/mac-binhex",
+    "application/x-mac-binhex40",
+    "application/x-binhex40",
+    "application/binhex",
+    "application/binhex4",
+];
+
+/// Multipart entities nested more deeply than this are ignored.
+const MAX_DEPTH: usize = 32;
+
+/// A part of an email message that contains BinHex-encoded data.
+///
+/// The part's content has already been decoded from its transfer encoding (e.g. base64 or
+/// quoted-printable), and so can be read directly as a BinHex archive.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BinHexPart {
+    content_type: String,
+    filename: Option<String>,
+    content: Vec<u8>,
+}
+
+impl BinHexPart {
+    /// Returns the part's MIME content type (e.g. `application/mac-binhex40`), in lowercase.
+    ///
+    /// Parts with a `text/*` content type are included if their content contains a BinHex banner,
+    /// as was common before RFC 1741.
+    pub fn content_type(&self) -> &str {
+        &self.content_type
+    }
+
+    /// Returns the filename suggested by the part's `Content-Type` or `Content-Disposition`
+    /// header, if any.
+    pub fn filename(&self) -> Option<&String> {
+        self.filename.as_ref()
+    }
+
+    /// Returns the part's content, decoded from its transfer encoding.
+    pub fn content(&self) -> &[u8] {
+        &self.content
+    }
+
+    /// Opens the part's content as a BinHex archive.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error if the part's content doesn't contain a valid BinHex header.
+    pub fn archive(&self) -> Result<BinHexArchive<&[u8]>, BinHexError> {
+        BinHexArchive::new(self.content.as_slice())
+    }
+
+    /// Returns a stream over all of the BinHex archives in the part's content.
+    ///
+    /// Text parts (like mailing list digests) may contain more than one archive.
+    pub fn archives(&self) -> BinHexStream<&[u8]> {
+        BinHexStream::new(self.content.as_slice())
+    }
+}
+
+/// Finds all of the BinHex parts in an RFC 822/MIME message.
+///
+/// Parts nested in `multipart/*` entities (including `multipart/mixed` and `multipart/appledouble`)
+/// and in attached `message/rfc822` messages are included. A part is considered a BinHex part if
+/// its content type is `application/mac-binhex40` (or one of a handful of unregistered variants),
+/// if its suggested filename ends with `.hqx`, or if it's a text part that contains a BinHex
+/// banner.
+///
+/// Parsing is deliberately lenient: old mailing list archives are full of messages that don't
+/// quite follow the standards, and so malformed headers and multipart bodies are handled as
+/// gracefully as possible rather than treated as errors.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs;
+/// use clarus::binhex::BinHexError;
+/// use clarus::mail;
+///
+/// fn main() -> Result<(), BinHexError> {
+///     let message = fs::read("message.eml")?;
+///
+///     for part in mail::binhex_parts(&message) {
+///         println!("Found {}", part.archive()?.filename());
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+pub fn binhex_parts(message: &[u8]) -> Vec<BinHexPart> {
+    let mut parts = vec![];
+    collect_binhex_parts(message, 0, &mut parts);
+
+    parts
+}
+
+fn collect_binhex_parts(entity: &[u8], depth: usize, parts: &mut Vec<BinHexPart>) {
+    if depth > MAX_DEPTH {
+        return;
+    }
+
+    let (headers, body) = split_entity(entity);
+
+    let content_type = header(&headers, "content-type")
+        .map(HeaderValue::parse)
+        .unwrap_or_else(|| HeaderValue {
+            value: String::from("text/plain"),
+            parameters: vec![],
+        });
+
+    if content_type.value.starts_with("multipart/") {
+        if let Some(boundary) = content_type.parameter("boundary") {
+            for part in split_multipart(body, boundary.as_bytes()) {
+                collect_binhex_parts(part, depth + 1, parts);
+            }
+
+            return;
+        }
+    }
+
+    let content = match header(&headers, "content-transfer-encoding")
+        .map(|value| HeaderValue::parse(value).value)
+        .as_deref()
+    {
+        Some("base64") => decode_base64(body),
+        Some("quoted-printable") => decode_quoted_printable(body),
+        _ => body.to_vec(),
+    };
+
+    if content_type.value == "message/rfc822" {
+        collect_binhex_parts(&content, depth + 1, parts);
+        return;
+    }
+
+    let filename = content_type.parameter("name").cloned().or_else(|| {
+        header(&headers, "content-disposition")
+            .and_then(|value| HeaderValue::parse(value).parameter("filename").cloned())
+    });
+
+    let is_binhex = BINHEX_CONTENT_TYPES.contains(&content_type.value.as_str())
+        || filename
+            .as_ref()
+            .map_or(false, |filename| filename.to_lowercase().ends_with(".hqx"))
+        || (content_type.value.starts_with("text/") && find(&content, BANNER).is_some());
+
+    if is_binhex {
+        parts.push(BinHexPart {
+            content_type: content_type.value,
+            filename,
+            content,
+        });
+    }
+}
+
+/// Splits an entity into its (unfolded) header fields and its body.
+///
+/// Header names are converted to lowercase. Lines in the header section that aren't header fields
+/// (like the "From " line at the start of a message in an mbox file) are ignored.
+fn split_entity(entity: &[u8]) -> (Vec<(String, String)>, &[u8]) {
+    let mut headers: Vec<(String, String)> = vec![];
+    let mut start = 0;
+
+    while start < entity.len() {
+        let end =
+            memchr::memchr(b'\n', &entity[start..]).map_or(entity.len(), |pos| start + pos + 1);
+        let line = trim_line_ending(&entity[start..end]);
+
+        start = end;
+
+        if line.is_empty() {
+            return (headers, &entity[start..]);
+        }
+
+        if line[0] == b' ' || line[0] == b'\t' {
+            // Continuation of a folded header field
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(String::from_utf8_lossy(line).trim());
+            }
+        } else if let Some(colon) = memchr::memchr(b':', line) {
+            headers.push((
+                String::from_utf8_lossy(&line[..colon])
+                    .trim()
+                    .to_lowercase(),
+                String::from_utf8_lossy(&line[colon + 1..])
+                    .trim()
+                    .to_string(),
+            ));
+        }
+    }
+
+    (headers, &entity[entity.len()..])
+}
+
+/// Returns the value of the first header field with the given (lowercase) name.
+fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(header_name, _)| header_name == name)
+        .map(|(_, value)| value.as_str())
+}
+
+/// Splits the body of a multipart entity into its parts as described in [RFC 2046, section
+/// 5.1.1](https://tools.ietf.org/html/rfc2046#section-5.1.1).
+///
+/// If the closing delimiter is missing, the last part extends to the end of the body.
+fn split_multipart<'a>(body: &'a [u8], boundary: &[u8]) -> Vec<&'a [u8]> {
+    let mut parts = vec![];
+    let mut part_start = None;
+    let mut start = 0;
+
+    while start < body.len() {
+        let end = memchr::memchr(b'\n', &body[start..]).map_or(body.len(), |pos| start + pos + 1);
+        let line = trim_line_ending(&body[start..end]);
+
+        if line.starts_with(b"--") && line[2..].starts_with(boundary) {
+            let rest = trim_whitespace(&line[2 + boundary.len()..]);
+
+            if rest.is_empty() || rest == b"--" {
+                if let Some(part_start) = part_start {
+                    // The line break before a delimiter belongs to the delimiter
+                    parts.push(trim_line_ending(&body[part_start..start]));
+                }
+
+                if rest == b"--" {
+                    return parts;
+                }
+
+                part_start = Some(end);
+            }
+        }
+
+        start = end;
+    }
+
+    if let Some(part_start) = part_start {
+        parts.push(&body[part_start..]);
+    }
+
+    parts
+}
+
+/// A structured header field value (like a `Content-Type` value) with optional parameters.
+struct HeaderValue {
+    value: String,
+    parameters: Vec<(String, String)>,
+}
+
+impl HeaderValue {
+    /// Parses a value like `multipart/mixed; boundary="abc"`. The value and parameter names are
+    /// converted to lowercase; parameter values may be quoted.
+    fn parse(text: &str) -> Self {
+        let mut segments = split_parameters(text).into_iter();
+        let value = segments.next().unwrap_or_default().trim().to_lowercase();
+
+        let parameters = segments
+            .filter_map(|segment| {
+                let (name, value) = segment.split_once('=')?;
+                let value = value.trim();
+
+                let value = if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+                    value[1..value.len() - 1].replace("\\\"", "\"")
+                } else {
+                    value.to_string()
+                };
+
+                Some((name.trim().to_lowercase(), value))
+            })
+            .collect();
+
+        HeaderValue { value, parameters }
+    }
+
+    fn parameter(&self, name: &str) -> Option<&String> {
+        self.parameters
+            .iter()
+            .find(|(parameter_name, _)| parameter_name == name)
+            .map(|(_, value)| value)
+    }
+}
+
+/// Splits a header field value at semicolons that aren't inside quoted strings.
+fn split_parameters(text: &str) -> Vec<&str> {
+    let mut segments = vec![];
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+
+    for (i, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ';' if !quoted => {
+                segments.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    segments.push(&text[start..]);
+    segments
+}
+
+fn trim_line_ending(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+fn trim_whitespace(text: &[u8]) -> &[u8] {
+    let end = text
+        .iter()
+        .rposition(|b| !b.is_ascii_whitespace())
+        .map_or(0, |pos| pos + 1);
+
+    &text[..end]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use indoc::indoc;
+
+    const MESSAGE: &[u8] = indoc! {br#"
+        From: someone@example.com
+        To: info-mac@example.com
+        Subject: [*] binhex-test.txt
+        MIME-Version: 1.0
+        Content-Type: multipart/mixed;
+          boundary="outer boundary"
+
+        This is a multi-part message in MIME format.
+
+        --outer boundary
+        Content-Type: text/plain; charset=us-ascii
+
+        Here's the file you asked for.
+
+        --outer boundary
+        Content-Type: application/mac-binhex40; name="binhex-test.hqx"
+        Content-Transfer-Encoding: 7bit
+
+        (This file must be converted with BinHex 4.0)
+        :$f*TEQKPH#edCA0d,R4iG!#3$L8!N!-TR@dpN!8J5'9XE'mJCR*[E5"dD'8JC'&
+        dB5"QEh*V)5!pN!9Bm5f3"5")C@aXEb"QFQpY)(4SC5"bCA0[GA*MC5"QEh*V)5!
+        YN!8SI!:
+
+        --outer boundary
+        Content-Type: multipart/appledouble; boundary=inner
+
+        --inner
+        Content-Type: application/applefile
+        Content-Transfer-Encoding: base64
+
+        AAUWBwACAAA=
+        --inner
+        Content-Type: application/octet-stream
+        Content-Disposition: attachment; filename="BINHEX-TEST.HQX"
+        Content-Transfer-Encoding: quoted-printable
+
+        (This file must be converted with BinHex 4.0)
+        :$f*TEQKPH#edCA0d,R4iG!#3$L8!N!-TR@dpN!8J5'9XE'mJCR*[E5"dD'8JC'&
+        dB5"QEh*V)5!pN!9Bm5f3"5")C@aXEb"QFQpY)(4SC5"bCA0[GA*MC5"QEh*V)5!
+        YN!8SI!:
+        --inner--
+
+        --outer boundary--
+    "#};
+
+    #[test]
+    fn find_parts() -> Result<(), BinHexError> {
+        let parts = binhex_parts(MESSAGE);
+
+        assert_eq!(2, parts.len());
+
+        assert_eq!("application/mac-binhex40", parts[0].content_type());
+        assert_eq!(Some(&String::from("binhex-test.hqx")), parts[0].filename());
+        assert_eq!("application/octet-stream", parts[1].content_type());
+        assert_eq!(Some(&String::from("BINHEX-TEST.HQX")), parts[1].filename());
+
+        for part in parts {
+            let mut data_fork = vec![];
+            let mut resource_fork = vec![];
+
+            part.archive()?
+                .extract(&mut data_fork, &mut resource_fork)?;
+
+            assert_eq!(
+                b"===== Hello from the data fork! =====",
+                data_fork.as_slice()
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn find_base64_part() -> Result<(), BinHexError> {
+        let message = indoc! {b"
+            Content-Type: application/mac-binhex40
+            Content-Transfer-Encoding: base64
+
+            KFRoaXMgZmlsZSBtdXN0IGJlIGNvbnZlcnRlZCB3aXRoIEJpbkhleCA0LjApCjokZipURVFL
+            UEgjZWRDQTBkLFI0aUchIzMkTDghTiEtVFJAZHBOIThKNSc5WEUnbUpDUipbRTUiZEQnOEpD
+            JyYKZEI1IlFFaCpWKTUhcE4hOUJtNWYzIjUiKUNAYVhFYiJRRlFwWSkoNFNDNSJiQ0EwW0dB
+            Kk1DNSJRRWgqVik1IQpZTiE4U0khOgo=
+        "};
+
+        let parts = binhex_parts(message);
+
+        assert_eq!(1, parts.len());
+        assert_eq!(
+            &String::from("binhex-test.txt"),
+            parts[0].archive()?.filename()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn find_inline_part() {
+        let message = indoc! {b"
+            From: someone@example.com
+            Subject: Digest
+
+            Today's files:
+
+            (This file must be converted with BinHex 4.0)
+            :$f*TEQKPH#edCA0d,R4iG!#3$L8!N!-TR@dpN!8J5'9XE'mJCR*[E5\"dD'8JC'&
+            dB5\"QEh*V)5!pN!9Bm5f3\"5\")C@aXEb\"QFQpY)(4SC5\"bCA0[GA*MC5\"QEh*V)5!
+            YN!8SI!:
+        "};
+
+        let parts = binhex_parts(message);
+
+        assert_eq!(1, parts.len());
+        assert_eq!("text/plain", parts[0].content_type());
+        assert_eq!(1, parts[0].archives().count());
+    }
+
+    #[test]
+    fn find_nested_message() {
+        let message = indoc! {b"
+            Content-Type: message/rfc822
+
+            Content-Type: application/mac-binhex40
+
+            (This file must be converted with BinHex 4.0)
+        "};
+
+        assert_eq!(1, binhex_parts(message).len());
+    }
+
+    #[test]
+    fn no_parts() {
+        let message = indoc! {b"
+            Content-Type: multipart/mixed; boundary=abc
+
+            --abc
+            Content-Type: text/plain
+
+            Nothing to see here.
+            --abc--
+        "};
+
+        assert!(binhex_parts(message).is_empty());
+    }
+
+    #[test]
+    fn split_multipart() {
+        let body = b"preamble\r\n--abc\r\none\r\n--abc \r\ntwo\r\n--abcd\r\n--abc--\r\nepilogue";
+
+        assert_eq!(
+            vec![&b"one"[..], &b"two\r\n--abcd"[..]],
+            super::split_multipart(body, b"abc")
+        );
+
+        assert_eq!(
+            vec![&b"one"[..], &b"two"[..]],
+            super::split_multipart(b"--abc\none\n--abc\ntwo", b"abc")
+        );
+    }
+
+    #[test]
+    fn parse_header_value() {
+        let value = HeaderValue::parse(r#"Multipart/Mixed; Boundary="a;b\"c"; charset=us-ascii"#);
+
+        assert_eq!("multipart/mixed", value.value);
+        assert_eq!(Some(&String::from("a;b\"c")), value.parameter("boundary"));
+        assert_eq!(Some(&String::from("us-ascii")), value.parameter("charset"));
+        assert_eq!(None, value.parameter("name"));
+    }
+}
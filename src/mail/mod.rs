@@ -0,0 +1,23 @@
+//! Tools for finding BinHex archives in email messages and mbox files.
+//!
+//! Before the web, BinHex archives were most often distributed by email, whether sent directly or
+//! posted to mailing lists (like Info-Mac) that were later preserved as mbox files. This module
+//! parses messages just enough to locate BinHex-encoded parts (including parts nested in
+//! `multipart/mixed` and `multipart/appledouble` entities), decode their transfer encodings, and
+//! hand them to [`crate::binhex::BinHexArchive`].
+//!
+//! For details about the formats involved, please see:
+//!
+//! - [RFC 822 - Standard for the Format of ARPA Internet Text Messages](https://tools.ietf.org/html/rfc822)
+//! - [RFC 2045 - MIME Part One: Format of Internet Message Bodies](https://tools.ietf.org/html/rfc2045)
+//! - [RFC 2046 - MIME Part Two: Media Types](https://tools.ietf.org/html/rfc2046)
+//! - [RFC 1740 - MIME Encapsulation of Macintosh Files - MacMIME](https://tools.ietf.org/html/rfc1740)
+//! - [RFC 1741 - MIME Content Type for BinHex Encoded Files](https://tools.ietf.org/html/rfc1741)
+//! - [RFC 4155 - The application/mbox Media Type](https://tools.ietf.org/html/rfc4155)
+
+mod encoding;
+mod mbox;
+mod message;
+
+pub use mbox::Mbox;
+pub use message::{binhex_parts, BinHexPart};
@@ -0,0 +1,85 @@
+use super::fork::ForkData;
+use crate::finder::{FinderFlags, FinderInfo};
+
+/// A file on an MFS volume.
+///
+/// Entries carry the file's Finder metadata and describe its forks, which may be read with the
+/// `data_fork` and `resource_fork` methods of the volume that produced the entry.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MfsEntry {
+    pub(super) name: String,
+    pub(super) name_bytes: Vec<u8>,
+    pub(super) file_number: u32,
+    pub(super) is_locked: bool,
+    pub(super) finder_info: FinderInfo,
+    pub(super) created: u32,
+    pub(super) modified: u32,
+    pub(super) data_fork: ForkData,
+    pub(super) resource_fork: ForkData,
+}
+
+impl MfsEntry {
+    /// Returns the name of this file.
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    /// Returns the raw bytes of this file's name, which is encoded in Mac OS Roman.
+    pub fn name_bytes(&self) -> &[u8] {
+        &self.name_bytes
+    }
+
+    /// Returns this file's file number, which uniquely identifies the file within its volume.
+    pub fn file_number(&self) -> u32 {
+        self.file_number
+    }
+
+    /// Returns `true` if this file is software-locked.
+    pub fn is_locked(&self) -> bool {
+        self.is_locked
+    }
+
+    /// Returns this file's Finder information.
+    ///
+    /// Because MFS has no real folders, the Finder records the folder in which a file appears in
+    /// the file's Finder information; see [`FinderInfo::folder`].
+    pub fn finder_info(&self) -> &FinderInfo {
+        &self.finder_info
+    }
+
+    /// Returns this file's file type identifier.
+    pub fn file_type(&self) -> [u8; 4] {
+        self.finder_info.file_type()
+    }
+
+    /// Returns this file's creator identifier.
+    pub fn creator(&self) -> [u8; 4] {
+        self.finder_info.creator()
+    }
+
+    /// Returns this file's Finder flags.
+    pub fn flags(&self) -> FinderFlags {
+        self.finder_info.flags()
+    }
+
+    /// Returns this file's creation date, expressed as seconds since midnight, January 1, 1904.
+    pub fn created(&self) -> u32 {
+        self.created
+    }
+
+    /// Returns this file's modification date, expressed as seconds since midnight, January 1,
+    /// 1904.
+    pub fn modified(&self) -> u32 {
+        self.modified
+    }
+
+    /// Returns the length, in bytes, of this file's data fork.
+    pub fn data_fork_len(&self) -> u64 {
+        self.data_fork.len
+    }
+
+    /// Returns the length, in bytes, of this file's resource fork.
+    pub fn resource_fork_len(&self) -> u64 {
+        self.resource_fork.len
+    }
+}
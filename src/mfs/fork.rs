@@ -0,0 +1,91 @@
+use std::cmp;
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::util::seek_position;
+
+/// The length of a fork and the allocation blocks that hold its content, in order.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(super) struct ForkData {
+    pub len: u64,
+    pub blocks: Vec<u16>,
+}
+
+/// The information needed to find an allocation block within a volume's source.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(super) struct Geometry {
+    /// The offset of the first allocation block (which is numbered 2) within the source.
+    pub first_block_offset: u64,
+    pub block_size: u64,
+}
+
+/// A reader for the content of one fork of a file on an MFS volume.
+///
+/// Fork readers read directly from the underlying volume's source. Seeking is relative to the
+/// start of the fork, and reads never extend beyond the end of the fork.
+pub struct MfsForkReader<'a, R: Read + Seek> {
+    source: &'a mut R,
+    geometry: Geometry,
+    fork: ForkData,
+    position: u64,
+}
+
+impl<'a, R: Read + Seek> MfsForkReader<'a, R> {
+    pub(super) fn new(source: &'a mut R, geometry: Geometry, fork: ForkData) -> Self {
+        MfsForkReader {
+            source,
+            geometry,
+            fork,
+            position: 0,
+        }
+    }
+
+    /// Returns the length, in bytes, of the fork.
+    pub fn len(&self) -> u64 {
+        self.fork.len
+    }
+
+    /// Returns `true` if the fork is empty.
+    pub fn is_empty(&self) -> bool {
+        self.fork.len == 0
+    }
+}
+
+impl<'a, R: Read + Seek> Read for MfsForkReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position >= self.fork.len || buf.is_empty() {
+            return Ok(0);
+        }
+
+        let block_size = self.geometry.block_size;
+
+        let block = *self
+            .fork
+            .blocks
+            .get((self.position / block_size) as usize)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+
+        let offset_in_block = self.position % block_size;
+
+        let max_len = cmp::min(
+            buf.len() as u64,
+            cmp::min(self.fork.len - self.position, block_size - offset_in_block),
+        ) as usize;
+
+        self.source.seek(SeekFrom::Start(
+            self.geometry.first_block_offset + (block as u64 - 2) * block_size + offset_in_block,
+        ))?;
+
+        let bytes_read = self.source.read(&mut buf[..max_len])?;
+        self.position += bytes_read as u64;
+
+        Ok(bytes_read)
+    }
+}
+
+impl<'a, R: Read + Seek> Seek for MfsForkReader<'a, R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.position = seek_position(pos, self.position, self.fork.len)?;
+
+        Ok(self.position)
+    }
+}
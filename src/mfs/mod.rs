@@ -0,0 +1,18 @@
+//! Tools for reading MFS volumes.
+//!
+//! MFS (the Macintosh File System) was the file system of the original Macintosh, and appears on
+//! 400K floppy disks from 1984 and 1985. Unlike HFS, MFS has no real folders: every file on a
+//! volume is listed in a single flat directory, and the folders shown by the Finder exist only in
+//! each file's Finder information. Each fork's content is stored in a chain of allocation blocks
+//! recorded in the volume's allocation block map.
+//!
+//! This module provides read-only access to MFS volumes, including each file's data and resource
+//! forks and Finder information.
+
+mod entry;
+mod fork;
+mod volume;
+
+pub use entry::MfsEntry;
+pub use fork::MfsForkReader;
+pub use volume::{MfsError, MfsVolume};
@@ -0,0 +1,548 @@
+use std::convert::TryInto;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Read, Seek, SeekFrom};
+
+use super::entry::MfsEntry;
+use super::fork::{ForkData, Geometry, MfsForkReader};
+use crate::finder::FinderInfo;
+use crate::util::{u16_at, u32_at};
+
+/// The offset of the master directory block within a volume.
+pub(super) const MDB_OFFSET: u64 = 1024;
+
+/// The length of the master directory block's fixed fields; the allocation block map immediately
+/// follows them.
+const MDB_LENGTH: usize = 64;
+
+/// The signature at the start of an MFS master directory block.
+pub(super) const MFS_SIGNATURE: u16 = 0xd2d7;
+
+/// The length of the sectors in which directory and allocation block locations are measured.
+const SECTOR_SIZE: u64 = 512;
+
+/// The length of a directory entry's fixed fields, which are followed by the file's name.
+const DIRECTORY_ENTRY_LENGTH: usize = 51;
+
+/// The number of the first allocation block; block numbers 0 and 1 have special meanings in the
+/// allocation block map.
+const FIRST_BLOCK: u16 = 2;
+
+/// Marks the last block of a fork in the allocation block map.
+const LAST_BLOCK: u16 = 1;
+
+/// An MFS volume.
+///
+/// The volume's directory and allocation block map are read when the volume is opened, and each
+/// file's forks are read on demand. Volumes are read-only.
+pub struct MfsVolume<R: Read + Seek> {
+    source: R,
+    geometry: Geometry,
+    name: String,
+    created: u32,
+    backed_up: u32,
+    entries: Vec<MfsEntry>,
+}
+
+impl<R: Read + Seek> MfsVolume<R> {
+    /// Opens the MFS volume that starts at the given source's current position, and reads the
+    /// volume's directory.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the volume's master directory block could not be
+    /// read or doesn't describe an MFS volume, or if the volume's directory or allocation block
+    /// map is malformed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use clarus::diskcopy::DiskCopyImage;
+    /// use clarus::mfs::MfsVolume;
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let image = DiskCopyImage::new(File::open("MacPaint.image")?)?;
+    ///     let volume = MfsVolume::new(image)?;
+    ///
+    ///     for entry in volume.entries() {
+    ///         println!("{} ({} bytes)", entry.name(), entry.data_fork_len());
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn new(mut source: R) -> Result<Self, MfsError> {
+        let base_offset = source.stream_position()?;
+
+        let mut mdb = [0; MDB_LENGTH];
+        source.seek(SeekFrom::Start(base_offset + MDB_OFFSET))?;
+        source.read_exact(&mut mdb)?;
+
+        if u16_at(&mdb, 0) != MFS_SIGNATURE {
+            return Err(MfsError::InvalidHeader);
+        }
+
+        let block_size = u32_at(&mdb, 20) as u64;
+        let block_count = u16_at(&mdb, 18);
+
+        if block_size == 0 || block_size % SECTOR_SIZE != 0 {
+            return Err(MfsError::InvalidHeader);
+        }
+
+        // Each block has a 12-bit entry in the allocation block map
+        let mut block_map = vec![0; (block_count as usize * 3 + 1) / 2];
+        source.read_exact(&mut block_map)?;
+
+        let block_map = BlockMap {
+            map: block_map,
+            block_count,
+        };
+
+        let geometry = Geometry {
+            first_block_offset: base_offset + u16_at(&mdb, 28) as u64 * SECTOR_SIZE,
+            block_size,
+        };
+
+        let name_len = (mdb[36] as usize).min(27);
+        let (name, _, _) = encoding_rs::MACINTOSH.decode(&mdb[37..37 + name_len]);
+
+        let mut directory = vec![0; u16_at(&mdb, 16) as usize * SECTOR_SIZE as usize];
+        source.seek(SeekFrom::Start(
+            base_offset + u16_at(&mdb, 14) as u64 * SECTOR_SIZE,
+        ))?;
+        source.read_exact(&mut directory)?;
+
+        let mut entries = vec![];
+
+        for sector in directory.chunks_exact(SECTOR_SIZE as usize) {
+            let mut offset = 0;
+
+            // Entries never cross sector boundaries, and the first unused entry marks the end of
+            // the sector's entries
+            while sector.get(offset).map_or(false, |flags| flags & 0x80 != 0) {
+                let entry = sector
+                    .get(offset..offset + DIRECTORY_ENTRY_LENGTH)
+                    .ok_or(MfsError::CorruptDirectory)?;
+
+                let name_len = entry[50] as usize;
+                let name_bytes = sector
+                    .get(
+                        offset + DIRECTORY_ENTRY_LENGTH..offset + DIRECTORY_ENTRY_LENGTH + name_len,
+                    )
+                    .ok_or(MfsError::CorruptDirectory)?;
+
+                let (name, _, _) = encoding_rs::MACINTOSH.decode(name_bytes);
+                let finder_info: [u8; 16] = entry[2..18].try_into().unwrap();
+
+                entries.push(MfsEntry {
+                    name: name.to_string(),
+                    name_bytes: name_bytes.to_vec(),
+                    file_number: u32_at(entry, 18),
+                    is_locked: entry[0] & 0x01 != 0,
+                    finder_info: FinderInfo::from(finder_info),
+                    created: u32_at(entry, 42),
+                    modified: u32_at(entry, 46),
+                    data_fork: ForkData {
+                        len: u32_at(entry, 24) as u64,
+                        blocks: block_map.chain(u16_at(entry, 22))?,
+                    },
+                    resource_fork: ForkData {
+                        len: u32_at(entry, 34) as u64,
+                        blocks: block_map.chain(u16_at(entry, 32))?,
+                    },
+                });
+
+                // Entries always start on an even offset
+                offset += (DIRECTORY_ENTRY_LENGTH + name_len + 1) & !1;
+            }
+        }
+
+        Ok(MfsVolume {
+            source,
+            geometry,
+            name: name.to_string(),
+            created: u32_at(&mdb, 2),
+            backed_up: u32_at(&mdb, 6),
+            entries,
+        })
+    }
+
+    /// Returns the name of this volume.
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    /// Returns this volume's creation date, expressed as seconds since midnight, January 1, 1904.
+    pub fn created(&self) -> u32 {
+        self.created
+    }
+
+    /// Returns the date on which this volume was last backed up, expressed as seconds since
+    /// midnight, January 1, 1904, or zero if the volume has never been backed up.
+    pub fn backed_up(&self) -> u32 {
+        self.backed_up
+    }
+
+    /// Returns the size, in bytes, of this volume's allocation blocks.
+    pub fn block_size(&self) -> u64 {
+        self.geometry.block_size
+    }
+
+    /// Returns all of the files on this volume, in the order in which they appear in the volume's
+    /// directory.
+    pub fn entries(&self) -> &[MfsEntry] {
+        &self.entries
+    }
+
+    /// Returns the file with the given name.
+    ///
+    /// Names are compared exactly, even though MFS itself ignores case.
+    pub fn entry(&self, name: &str) -> Option<&MfsEntry> {
+        self.entries.iter().find(|entry| entry.name() == name)
+    }
+
+    /// Returns a reader for the given file's data fork.
+    pub fn data_fork(&mut self, entry: &MfsEntry) -> MfsForkReader<'_, R> {
+        MfsForkReader::new(&mut self.source, self.geometry, entry.data_fork.clone())
+    }
+
+    /// Returns a reader for the given file's resource fork, which may be opened directly with
+    /// [`crate::rsrc::ResourceFork`].
+    pub fn resource_fork(&mut self, entry: &MfsEntry) -> MfsForkReader<'_, R> {
+        MfsForkReader::new(&mut self.source, self.geometry, entry.resource_fork.clone())
+    }
+
+    /// Returns the source from which this volume reads its data.
+    pub fn into_inner(self) -> R {
+        self.source
+    }
+}
+
+/// A volume's allocation block map, which records the next block in each fork's chain of blocks.
+struct BlockMap {
+    map: Vec<u8>,
+    block_count: u16,
+}
+
+impl BlockMap {
+    /// Returns the map entry for the given block, which must be a valid block number.
+    fn next(&self, block: u16) -> u16 {
+        let i = (block - FIRST_BLOCK) as usize;
+        let offset = i * 3 / 2;
+
+        if i % 2 == 0 {
+            ((self.map[offset] as u16) << 4) | (self.map[offset + 1] as u16 >> 4)
+        } else {
+            (((self.map[offset] & 0x0f) as u16) << 8) | self.map[offset + 1] as u16
+        }
+    }
+
+    /// Returns the chain of blocks that starts with the given block, or an empty chain if the
+    /// starting block is 0.
+    fn chain(&self, start: u16) -> Result<Vec<u16>, MfsError> {
+        let mut blocks = vec![];
+        let mut block = start;
+
+        if start == 0 {
+            return Ok(blocks);
+        }
+
+        loop {
+            // Guard against cycles, free blocks, and references beyond the end of the volume
+            if block < FIRST_BLOCK
+                || block - FIRST_BLOCK >= self.block_count
+                || blocks.len() >= self.block_count as usize
+            {
+                return Err(MfsError::CorruptAllocationMap);
+            }
+
+            blocks.push(block);
+
+            match self.next(block) {
+                LAST_BLOCK => return Ok(blocks),
+                next => block = next,
+            }
+        }
+    }
+}
+
+/// The error type for operations on MFS volumes.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MfsError {
+    /// An [`std::io::Error`] occurred while reading some part of the volume.
+    ///
+    /// The kind of IO error is included.
+    IoError(io::ErrorKind),
+
+    /// The volume's master directory block was malformed or didn't describe an MFS volume.
+    InvalidHeader,
+
+    /// An entry in the volume's directory was malformed.
+    CorruptDirectory,
+
+    /// The volume's allocation block map referred to a nonexistent or free block, or contained a
+    /// cycle.
+    CorruptAllocationMap,
+}
+
+impl Display for MfsError {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MfsError::IoError(kind) => write!(fmt, "IO error: {:?}", kind),
+            MfsError::InvalidHeader => write!(fmt, "Invalid master directory block"),
+            MfsError::CorruptDirectory => write!(fmt, "Corrupt directory"),
+            MfsError::CorruptAllocationMap => write!(fmt, "Corrupt allocation block map"),
+        }
+    }
+}
+
+impl From<io::Error> for MfsError {
+    fn from(error: io::Error) -> Self {
+        MfsError::IoError(error.kind())
+    }
+}
+
+impl error::Error for MfsError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rsrc::ResourceFork;
+    use std::io::Cursor;
+
+    const RESOURCE_FORK_CONTENT: &[u8] = include_bytes!("../rsrc/string-table.rsrc");
+
+    const BLOCK_SIZE: usize = 1024;
+    const BLOCK_COUNT: u16 = 20;
+    const DIRECTORY_SECTOR: usize = 4;
+    const DIRECTORY_SECTORS: usize = 2;
+    const FIRST_BLOCK_SECTOR: usize = DIRECTORY_SECTOR + DIRECTORY_SECTORS;
+
+    struct VolumeBuilder {
+        image: Vec<u8>,
+        block_map: Vec<u16>,
+        directory: Vec<Vec<u8>>,
+    }
+
+    impl VolumeBuilder {
+        fn new() -> Self {
+            VolumeBuilder {
+                image: vec![
+                    0;
+                    FIRST_BLOCK_SECTOR * SECTOR_SIZE as usize
+                        + BLOCK_COUNT as usize * BLOCK_SIZE
+                ],
+                block_map: vec![0; BLOCK_COUNT as usize],
+                directory: vec![vec![]; DIRECTORY_SECTORS],
+            }
+        }
+
+        /// Writes the given data to the given blocks and chains them together.
+        fn allocate(&mut self, data: &[u8], blocks: &[u16]) -> u16 {
+            for (chunk, block) in data.chunks(BLOCK_SIZE).zip(blocks) {
+                let offset = FIRST_BLOCK_SECTOR * SECTOR_SIZE as usize
+                    + (*block - FIRST_BLOCK) as usize * BLOCK_SIZE;
+
+                self.image[offset..offset + chunk.len()].copy_from_slice(chunk);
+            }
+
+            for pair in blocks.windows(2) {
+                self.block_map[(pair[0] - FIRST_BLOCK) as usize] = pair[1];
+            }
+
+            if let Some(last) = blocks.last() {
+                self.block_map[(*last - FIRST_BLOCK) as usize] = LAST_BLOCK;
+            }
+
+            blocks.first().copied().unwrap_or(0)
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        fn add_file(
+            &mut self,
+            sector: usize,
+            name: &[u8],
+            file_number: u32,
+            data_fork: &[u8],
+            data_blocks: &[u16],
+            resource_fork: &[u8],
+            resource_blocks: &[u16],
+        ) {
+            let data_start = self.allocate(data_fork, data_blocks);
+            let resource_start = self.allocate(resource_fork, resource_blocks);
+
+            let mut entry = vec![0; DIRECTORY_ENTRY_LENGTH];
+            entry[0] = 0x81;
+            entry[2..10].copy_from_slice(b"TEXTttxt");
+            entry[10..12].copy_from_slice(&0x4000u16.to_be_bytes());
+            entry[16..18].copy_from_slice(&(-2i16).to_be_bytes());
+            entry[18..22].copy_from_slice(&file_number.to_be_bytes());
+            entry[22..24].copy_from_slice(&data_start.to_be_bytes());
+            entry[24..28].copy_from_slice(&(data_fork.len() as u32).to_be_bytes());
+            entry[28..32].copy_from_slice(&((data_blocks.len() * BLOCK_SIZE) as u32).to_be_bytes());
+            entry[32..34].copy_from_slice(&resource_start.to_be_bytes());
+            entry[34..38].copy_from_slice(&(resource_fork.len() as u32).to_be_bytes());
+            entry[38..42]
+                .copy_from_slice(&((resource_blocks.len() * BLOCK_SIZE) as u32).to_be_bytes());
+            entry[42..46].copy_from_slice(&0xa000_0000u32.to_be_bytes());
+            entry[46..50].copy_from_slice(&0xa000_0001u32.to_be_bytes());
+            entry[50] = name.len() as u8;
+            entry.extend_from_slice(name);
+
+            if entry.len() % 2 != 0 {
+                entry.push(0);
+            }
+
+            self.directory[sector].extend(entry);
+        }
+
+        fn build(self) -> Vec<u8> {
+            let mut image = self.image;
+
+            let mdb = &mut image[MDB_OFFSET as usize..MDB_OFFSET as usize + MDB_LENGTH];
+            mdb[0..2].copy_from_slice(&MFS_SIGNATURE.to_be_bytes());
+            mdb[2..6].copy_from_slice(&0xc000_0000u32.to_be_bytes());
+            mdb[6..10].copy_from_slice(&0xc000_0001u32.to_be_bytes());
+            mdb[14..16].copy_from_slice(&(DIRECTORY_SECTOR as u16).to_be_bytes());
+            mdb[16..18].copy_from_slice(&(DIRECTORY_SECTORS as u16).to_be_bytes());
+            mdb[18..20].copy_from_slice(&BLOCK_COUNT.to_be_bytes());
+            mdb[20..24].copy_from_slice(&(BLOCK_SIZE as u32).to_be_bytes());
+            mdb[28..30].copy_from_slice(&(FIRST_BLOCK_SECTOR as u16).to_be_bytes());
+            mdb[36] = 11;
+            mdb[37..48].copy_from_slice(b"Test Volume");
+
+            let map_offset = MDB_OFFSET as usize + MDB_LENGTH;
+
+            for (i, pair) in self.block_map.chunks(2).enumerate() {
+                let (a, b) = (pair[0], pair.get(1).copied().unwrap_or(0));
+                image[map_offset + i * 3] = (a >> 4) as u8;
+                image[map_offset + i * 3 + 1] = ((a & 0x0f) << 4) as u8 | (b >> 8) as u8;
+                image[map_offset + i * 3 + 2] = b as u8;
+            }
+
+            for (i, sector) in self.directory.iter().enumerate() {
+                let offset = (DIRECTORY_SECTOR + i) * SECTOR_SIZE as usize;
+                image[offset..offset + sector.len()].copy_from_slice(sector);
+            }
+
+            image
+        }
+    }
+
+    fn fragmented_data() -> Vec<u8> {
+        (0..BLOCK_SIZE * 3 - 100).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn volume_bytes() -> Vec<u8> {
+        let mut builder = VolumeBuilder::new();
+
+        builder.add_file(0, b"Read Me", 1, b"Hello, MFS!", &[2], &[], &[]);
+        builder.add_file(
+            0,
+            b"Fragmented \xa5",
+            2,
+            &fragmented_data(),
+            &[9, 3, 12],
+            RESOURCE_FORK_CONTENT,
+            &[4, 5, 6, 7, 8][..(RESOURCE_FORK_CONTENT.len() + BLOCK_SIZE - 1) / BLOCK_SIZE],
+        );
+        builder.add_file(1, b"Empty", 3, &[], &[], &[], &[]);
+
+        builder.build()
+    }
+
+    #[test]
+    fn read_volume() -> Result<(), Box<dyn std::error::Error>> {
+        let mut volume = MfsVolume::new(Cursor::new(volume_bytes()))?;
+
+        assert_eq!("Test Volume", volume.name());
+        assert_eq!(0xc000_0000, volume.created());
+        assert_eq!(0xc000_0001, volume.backed_up());
+        assert_eq!(BLOCK_SIZE as u64, volume.block_size());
+
+        let names: Vec<&String> = volume.entries().iter().map(|entry| entry.name()).collect();
+        assert_eq!(vec!["Read Me", "Fragmented •", "Empty"], names);
+
+        let read_me = volume.entry("Read Me").unwrap().clone();
+        assert_eq!(1, read_me.file_number());
+        assert!(read_me.is_locked());
+        assert_eq!(*b"TEXT", read_me.file_type());
+        assert_eq!(*b"ttxt", read_me.creator());
+        assert!(read_me.flags().is_invisible());
+        assert_eq!(-2, read_me.finder_info().folder());
+        assert_eq!(0xa000_0000, read_me.created());
+        assert_eq!(0xa000_0001, read_me.modified());
+
+        let mut content = vec![];
+        volume.data_fork(&read_me).read_to_end(&mut content)?;
+        assert_eq!(b"Hello, MFS!".to_vec(), content);
+        assert!(volume.resource_fork(&read_me).is_empty());
+
+        let fragmented = volume.entry("Fragmented •").unwrap().clone();
+        assert_eq!(b"Fragmented \xa5", fragmented.name_bytes());
+
+        let mut content = vec![];
+        let mut data_fork = volume.data_fork(&fragmented);
+        data_fork.read_to_end(&mut content)?;
+        assert_eq!(fragmented_data(), content);
+
+        data_fork.seek(SeekFrom::Start(BLOCK_SIZE as u64 - 2))?;
+        let mut buf = [0; 4];
+        data_fork.read_exact(&mut buf)?;
+        assert_eq!(fragmented_data()[BLOCK_SIZE - 2..BLOCK_SIZE + 2], buf);
+
+        let resource_fork = ResourceFork::new(volume.resource_fork(&fragmented)).unwrap();
+        assert!(resource_fork.resources().count() > 0);
+
+        let empty = volume.entry("Empty").unwrap().clone();
+        assert_eq!(0, empty.data_fork_len());
+        assert!(volume.data_fork(&empty).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_volumes() {
+        let mut bytes = volume_bytes();
+        bytes[MDB_OFFSET as usize] = 0;
+
+        assert!(matches!(
+            MfsVolume::new(Cursor::new(&bytes)),
+            Err(MfsError::InvalidHeader)
+        ));
+
+        // Point the "Read Me" file's only block back at itself
+        let mut builder = VolumeBuilder::new();
+        builder.add_file(0, b"Read Me", 1, b"Hello, MFS!", &[2], &[], &[]);
+        builder.block_map[0] = 2;
+
+        assert!(matches!(
+            MfsVolume::new(Cursor::new(builder.build())),
+            Err(MfsError::CorruptAllocationMap)
+        ));
+
+        // Start a fork at a free block
+        let mut builder = VolumeBuilder::new();
+        builder.add_file(0, b"Read Me", 1, b"Hello, MFS!", &[2], &[], &[]);
+        builder.block_map[0] = 0;
+
+        assert!(matches!(
+            MfsVolume::new(Cursor::new(builder.build())),
+            Err(MfsError::CorruptAllocationMap)
+        ));
+
+        // Make the last entry in a sector run past the end of the sector
+        let mut builder = VolumeBuilder::new();
+        builder.directory[0] = vec![0; 357];
+        builder.directory[0][0] = 0x80;
+        builder.directory[0][50] = 255;
+        builder.directory[0][306] = 0x80;
+        builder.directory[0][356] = 255;
+
+        assert!(matches!(
+            MfsVolume::new(Cursor::new(builder.build())),
+            Err(MfsError::CorruptDirectory)
+        ));
+    }
+}
@@ -0,0 +1,48 @@
+/// A single partition in an Apple Partition Map.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Partition {
+    pub(super) name: String,
+    pub(super) partition_type: String,
+    pub(super) start: u64,
+    pub(super) len: u64,
+    pub(super) status: u32,
+}
+
+impl Partition {
+    /// Returns the partition's name, which may be empty.
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    /// Returns the partition's type (for example, `Apple_HFS` or `Apple_Driver43`).
+    pub fn partition_type(&self) -> &String {
+        &self.partition_type
+    }
+
+    /// Returns `true` if this partition holds an HFS or HFS+ volume.
+    pub fn is_hfs(&self) -> bool {
+        self.partition_type == "Apple_HFS"
+    }
+
+    /// Returns the offset, in bytes, of the start of the partition, relative to the start of the
+    /// partition map's device.
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// Returns the length, in bytes, of the partition.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if the partition is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the partition's status flags, which indicate (among other things) whether the
+    /// partition is valid, allocated, and readable.
+    pub fn status(&self) -> u32 {
+        self.status
+    }
+}
@@ -0,0 +1,350 @@
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Read, Seek, SeekFrom};
+
+use super::entry::Partition;
+use super::reader::PartitionReader;
+use crate::util::{u16_at, u32_at};
+
+/// The signature at the start of a driver descriptor map.
+const DDM_SIGNATURE: u16 = 0x4552;
+
+/// The signature at the start of each partition map entry.
+const PARTITION_SIGNATURE: u16 = 0x504d;
+
+/// The length of the sectors in which partition locations are measured when a device's block size
+/// doesn't apply.
+const SECTOR_SIZE: u64 = 512;
+
+/// The length of a partition map entry's fields.
+const ENTRY_LENGTH: usize = 136;
+
+/// The length of the name and type fields of a partition map entry.
+const NAME_LENGTH: usize = 32;
+
+/// An Apple Partition Map.
+///
+/// The driver descriptor map and every entry in the partition map are read when the map is
+/// opened, and each partition's content is read on demand.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs::File;
+/// use clarus::hfs::HfsVolume;
+/// use clarus::partition::PartitionMap;
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let mut map = PartitionMap::new(File::open("Developer CD.iso")?)?;
+///
+///     let partition = map.partitions().iter().find(|partition| partition.is_hfs()).cloned();
+///
+///     if let Some(partition) = partition {
+///         let volume = HfsVolume::new(map.partition(&partition))?;
+///         println!("{}", volume.name());
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+pub struct PartitionMap<R: Read + Seek> {
+    source: R,
+    base_offset: u64,
+    block_size: u64,
+    block_count: u32,
+    partitions: Vec<Partition>,
+}
+
+impl<R: Read + Seek> PartitionMap<R> {
+    /// Opens the partition map of the device that starts at the given source's current position,
+    /// and reads the map's entries.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the device's driver descriptor map could not be read
+    /// or is malformed, or if any of the partition map's entries could not be read or are
+    /// malformed.
+    pub fn new(mut source: R) -> Result<Self, PartitionError> {
+        let base_offset = source.stream_position()?;
+
+        let mut ddm = [0; SECTOR_SIZE as usize];
+        source.read_exact(&mut ddm)?;
+
+        if u16_at(&ddm, 0) != DDM_SIGNATURE {
+            return Err(PartitionError::InvalidHeader);
+        }
+
+        let block_size = u16_at(&ddm, 2) as u64;
+
+        if block_size == 0 || block_size % SECTOR_SIZE != 0 {
+            return Err(PartitionError::InvalidHeader);
+        }
+
+        let mut entry = [0; ENTRY_LENGTH];
+
+        // Partition maps on devices with large blocks (CD-ROMs, in particular) are usually laid
+        // out in the device's blocks, but some are laid out in 512-byte sectors regardless
+        let mut map_block_size = block_size;
+        source.seek(SeekFrom::Start(base_offset + block_size))?;
+        source.read_exact(&mut entry)?;
+
+        if u16_at(&entry, 0) != PARTITION_SIGNATURE && block_size != SECTOR_SIZE {
+            map_block_size = SECTOR_SIZE;
+            source.seek(SeekFrom::Start(base_offset + SECTOR_SIZE))?;
+            source.read_exact(&mut entry)?;
+        }
+
+        if u16_at(&entry, 0) != PARTITION_SIGNATURE {
+            return Err(PartitionError::CorruptPartitionMap);
+        }
+
+        // Every entry records the number of entries in the map, and the first entry is at block 1
+        let entry_count = u32_at(&entry, 4);
+
+        if entry_count == 0 || entry_count as u64 * map_block_size > 0x10_0000 {
+            return Err(PartitionError::CorruptPartitionMap);
+        }
+
+        let mut partitions = Vec::with_capacity(entry_count as usize);
+
+        for i in 1..=entry_count as u64 {
+            if i > 1 {
+                source.seek(SeekFrom::Start(base_offset + i * map_block_size))?;
+                source.read_exact(&mut entry)?;
+
+                if u16_at(&entry, 0) != PARTITION_SIGNATURE {
+                    return Err(PartitionError::CorruptPartitionMap);
+                }
+            }
+
+            partitions.push(Partition {
+                name: c_string(&entry[16..16 + NAME_LENGTH]),
+                partition_type: c_string(&entry[48..48 + NAME_LENGTH]),
+                start: u32_at(&entry, 8) as u64 * map_block_size,
+                len: u32_at(&entry, 12) as u64 * map_block_size,
+                status: u32_at(&entry, 88),
+            });
+        }
+
+        Ok(PartitionMap {
+            source,
+            base_offset,
+            block_size,
+            block_count: u32_at(&ddm, 4),
+            partitions,
+        })
+    }
+
+    /// Returns the device's block size, in bytes, as recorded in its driver descriptor map.
+    pub fn block_size(&self) -> u64 {
+        self.block_size
+    }
+
+    /// Returns the number of blocks on the device, as recorded in its driver descriptor map.
+    pub fn block_count(&self) -> u32 {
+        self.block_count
+    }
+
+    /// Returns every partition in the partition map, in the order in which they appear in the map
+    /// (which is usually, but not necessarily, the order in which they appear on the device).
+    pub fn partitions(&self) -> &[Partition] {
+        &self.partitions
+    }
+
+    /// Returns a reader for the given partition's content, which may be passed directly to (for
+    /// example) [`crate::hfs::HfsVolume::new`].
+    pub fn partition(&mut self, partition: &Partition) -> PartitionReader<'_, R> {
+        PartitionReader::new(
+            &mut self.source,
+            self.base_offset + partition.start,
+            partition.len,
+        )
+    }
+
+    /// Returns the source from which this partition map reads its data.
+    pub fn into_inner(self) -> R {
+        self.source
+    }
+}
+
+/// Decodes a null-terminated string from a fixed-length field.
+fn c_string(bytes: &[u8]) -> String {
+    let len = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    let (string, _, _) = encoding_rs::MACINTOSH.decode(&bytes[..len]);
+
+    string.to_string()
+}
+
+/// The error type for operations on Apple Partition Maps.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PartitionError {
+    /// An [`std::io::Error`] occurred while reading some part of the device.
+    ///
+    /// The kind of IO error is included.
+    IoError(io::ErrorKind),
+
+    /// The device's driver descriptor map was missing or malformed.
+    InvalidHeader,
+
+    /// An entry in the partition map was missing or malformed.
+    CorruptPartitionMap,
+}
+
+impl Display for PartitionError {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PartitionError::IoError(kind) => write!(fmt, "IO error: {:?}", kind),
+            PartitionError::InvalidHeader => write!(fmt, "Invalid driver descriptor map"),
+            PartitionError::CorruptPartitionMap => write!(fmt, "Corrupt partition map"),
+        }
+    }
+}
+
+impl From<io::Error> for PartitionError {
+    fn from(error: io::Error) -> Self {
+        PartitionError::IoError(error.kind())
+    }
+}
+
+impl error::Error for PartitionError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Builds a device with the given block size, whose partition map (laid out in blocks of
+    /// `map_block_size` bytes) describes the given partitions; each partition is a name, type,
+    /// start block, and block count.
+    fn device_bytes(
+        block_size: u16,
+        map_block_size: usize,
+        partitions: &[(&str, &str, u32, u32)],
+    ) -> Vec<u8> {
+        let device_blocks = partitions
+            .iter()
+            .map(|(_, _, start, count)| (start + count) as usize)
+            .max()
+            .unwrap_or(1);
+
+        let mut device = vec![0; device_blocks * map_block_size];
+        device[0..2].copy_from_slice(&DDM_SIGNATURE.to_be_bytes());
+        device[2..4].copy_from_slice(&block_size.to_be_bytes());
+        device[4..8].copy_from_slice(&(device_blocks as u32).to_be_bytes());
+
+        for (i, (name, partition_type, start, count)) in partitions.iter().enumerate() {
+            let entry = &mut device[(i + 1) * map_block_size..];
+
+            entry[0..2].copy_from_slice(&PARTITION_SIGNATURE.to_be_bytes());
+            entry[4..8].copy_from_slice(&(partitions.len() as u32).to_be_bytes());
+            entry[8..12].copy_from_slice(&start.to_be_bytes());
+            entry[12..16].copy_from_slice(&count.to_be_bytes());
+            entry[16..16 + name.len()].copy_from_slice(name.as_bytes());
+            entry[48..48 + partition_type.len()].copy_from_slice(partition_type.as_bytes());
+            entry[88..92].copy_from_slice(&0x37u32.to_be_bytes());
+        }
+
+        device
+    }
+
+    #[test]
+    fn read_partition_map() -> Result<(), PartitionError> {
+        let mut device = device_bytes(
+            512,
+            512,
+            &[
+                ("Apple", "Apple_partition_map", 1, 63),
+                ("Macintosh", "Apple_Driver43", 64, 4),
+                ("Untitled", "Apple_HFS", 68, 8),
+            ],
+        );
+
+        for (i, byte) in device[68 * 512..].iter_mut().enumerate() {
+            *byte = (i % 251) as u8;
+        }
+
+        let mut map = PartitionMap::new(Cursor::new(device))?;
+
+        assert_eq!(512, map.block_size());
+        assert_eq!(76, map.block_count());
+        assert_eq!(3, map.partitions().len());
+
+        let partition = map.partitions()[2].clone();
+        assert_eq!("Untitled", partition.name());
+        assert_eq!("Apple_HFS", partition.partition_type());
+        assert!(partition.is_hfs());
+        assert!(!map.partitions()[1].is_hfs());
+        assert_eq!(68 * 512, partition.start());
+        assert_eq!(8 * 512, partition.len());
+        assert_eq!(0x37, partition.status());
+
+        let mut reader = map.partition(&partition);
+        assert_eq!(4096, reader.len());
+
+        let mut content = vec![];
+        reader.read_to_end(&mut content)?;
+        assert_eq!(
+            (0..4096).map(|i| (i % 251) as u8).collect::<Vec<u8>>(),
+            content
+        );
+
+        let mut bytes = [0; 4];
+        reader.seek(SeekFrom::End(-2))?;
+        assert_eq!(2, reader.read(&mut bytes)?);
+        assert_eq!([78, 79], bytes[..2]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_cd_partition_maps() -> Result<(), PartitionError> {
+        let partitions = [
+            ("Apple", "Apple_partition_map", 1, 3),
+            ("CD", "Apple_HFS", 4, 4),
+        ];
+
+        // A map laid out in the device's 2048-byte blocks
+        let map = PartitionMap::new(Cursor::new(device_bytes(2048, 2048, &partitions)))?;
+        assert_eq!(2048, map.block_size());
+        assert_eq!(4 * 2048, map.partitions()[1].start());
+
+        // A map laid out in 512-byte sectors, even though the device's blocks are larger
+        let map = PartitionMap::new(Cursor::new(device_bytes(2048, 512, &partitions)))?;
+        assert_eq!(2048, map.block_size());
+        assert_eq!(4 * 512, map.partitions()[1].start());
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_partition_maps() {
+        let partitions = [
+            ("Apple", "Apple_partition_map", 1, 2),
+            ("Untitled", "Apple_HFS", 3, 4),
+        ];
+
+        let mut device = device_bytes(512, 512, &partitions);
+        device[0] = 0;
+
+        assert!(matches!(
+            PartitionMap::new(Cursor::new(&device)),
+            Err(PartitionError::InvalidHeader)
+        ));
+
+        let mut device = device_bytes(512, 512, &partitions);
+        device[1024] = 0;
+
+        assert!(matches!(
+            PartitionMap::new(Cursor::new(&device)),
+            Err(PartitionError::CorruptPartitionMap)
+        ));
+
+        let mut device = device_bytes(512, 512, &partitions);
+        device[2..4].copy_from_slice(&1000u16.to_be_bytes());
+
+        assert!(matches!(
+            PartitionMap::new(Cursor::new(&device)),
+            Err(PartitionError::InvalidHeader)
+        ));
+    }
+}
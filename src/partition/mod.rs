@@ -0,0 +1,18 @@
+//! Tools for reading Apple Partition Maps.
+//!
+//! Hard disks, removable cartridges, and CD-ROMs formatted by classic Mac OS begin with a driver
+//! descriptor map, which records the device's block size and the location of its drivers, followed
+//! by a partition map with one entry per block. Each entry names a partition and gives its type
+//! (`Apple_HFS` for HFS and HFS+ volumes, `Apple_Driver43` for SCSI drivers, `Apple_partition_map`
+//! for the map itself, and so on), along with its location on the device.
+//!
+//! This module lists the partitions in a partition map and provides a bounded reader for each
+//! partition's content, from which the volume inside a partition may be opened directly.
+
+mod entry;
+mod map;
+mod reader;
+
+pub use entry::Partition;
+pub use map::{PartitionError, PartitionMap};
+pub use reader::PartitionReader;
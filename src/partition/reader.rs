@@ -0,0 +1,41 @@
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::util::BoundedReader;
+
+/// A reader for the content of a single partition.
+///
+/// Partition readers read directly from the underlying partition map's source. Seeking is relative
+/// to the start of the partition, and reads never extend beyond the end of the partition.
+pub struct PartitionReader<'a, R: Read + Seek> {
+    reader: BoundedReader<'a, R>,
+}
+
+impl<'a, R: Read + Seek> PartitionReader<'a, R> {
+    pub(super) fn new(source: &'a mut R, start: u64, len: u64) -> Self {
+        PartitionReader {
+            reader: BoundedReader::new(source, start, len),
+        }
+    }
+
+    /// Returns the length, in bytes, of the partition.
+    pub fn len(&self) -> u64 {
+        self.reader.len()
+    }
+
+    /// Returns `true` if the partition is empty.
+    pub fn is_empty(&self) -> bool {
+        self.reader.len() == 0
+    }
+}
+
+impl<'a, R: Read + Seek> Read for PartitionReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+impl<'a, R: Read + Seek> Seek for PartitionReader<'a, R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.reader.seek(pos)
+    }
+}
@@ -0,0 +1,435 @@
+use std::io::{Read, Seek, Write};
+
+use super::write::{self, MapEntry};
+use super::{ResourceError, ResourceFork, ResourceMetadata, ResourceType};
+
+/// The data for a resource in a fork that's being edited.
+enum ResourceData {
+    /// The resource's data is unchanged, and is still in the original fork (at the offset in the
+    /// resource's metadata); only its length has been read.
+    Original(u32),
+
+    /// The resource's data has been replaced or the resource has been added.
+    Replaced(Vec<u8>),
+}
+
+/// Edits the resources in an existing resource fork, much as ResEdit did.
+///
+/// Resources may be added, replaced, removed, or renamed, and the attributes of resources and of
+/// the fork itself may be changed. Edits are kept in memory until [`ResourceForkEditor::save`]
+/// writes a new fork to a destination; the data of resources that weren't replaced is copied
+/// directly from the original fork when the new fork is written, and is never loaded into memory
+/// all at once. Saved forks are compacted: space left behind by removed or replaced resources is
+/// not carried over into the new fork.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::convert::TryFrom;
+/// use std::fs::File;
+/// use clarus::rsrc::{ResourceError, ResourceForkEditor, ResourceType};
+///
+/// fn main() -> Result<(), ResourceError> {
+///     let mut editor = ResourceForkEditor::new(File::open("example.rsrc")?)?;
+///     let str_type = ResourceType::try_from("STR ").unwrap();
+///
+///     editor.replace_data(str_type, 128, b"\x07Bonjour".to_vec())?;
+///     editor.rename_resource(str_type, 128, Some("Greeting (French)"))?;
+///     editor.remove_resource(str_type, 129);
+///
+///     editor.save(File::create("example-fr.rsrc")?)?;
+///
+///     Ok(())
+/// }
+/// ```
+pub struct ResourceForkEditor<R: Read + Seek> {
+    fork: ResourceFork<R>,
+    attributes: u16,
+    resources: Vec<(ResourceMetadata, ResourceData)>,
+}
+
+impl<R: Read + Seek> ResourceForkEditor<R> {
+    /// Opens the resource fork in the given source for editing.
+    ///
+    /// The fork's resource map and the length of each resource are read immediately; resources'
+    /// data are not.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error if a valid resource map could not be loaded from the given
+    /// source, or if the length of any resource's data could not be read.
+    pub fn new(source: R) -> Result<Self, ResourceError> {
+        let mut fork = ResourceFork::new(source)?;
+
+        // Keep resources in the order in which their data appear in the original fork
+        let mut resources: Vec<ResourceMetadata> = fork.resources().cloned().collect();
+        resources.sort_by_key(|metadata| metadata.data_offset);
+
+        let resources = resources
+            .into_iter()
+            .map(|metadata| {
+                let len = fork.resource_len(metadata.data_offset)?;
+                Ok((metadata, ResourceData::Original(len)))
+            })
+            .collect::<Result<_, ResourceError>>()?;
+
+        Ok(ResourceForkEditor {
+            attributes: fork.attributes(),
+            fork,
+            resources,
+        })
+    }
+
+    /// Returns an iterator over the metadata of all of the resources in the edited fork, in the
+    /// order in which they'll be written.
+    pub fn resources(&self) -> impl Iterator<Item = &ResourceMetadata> {
+        self.resources.iter().map(|(metadata, _)| metadata)
+    }
+
+    /// Loads the data and metadata of the resource with the given type and ID, reflecting any
+    /// edits. The provided `dest` is resized to the size of the loaded resource, and resource data
+    /// is copied into `dest`.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error if no resource could be found for the given type/ID, or if
+    /// the resource's original data could not be read.
+    pub fn load_by_id(
+        &mut self,
+        resource_type: ResourceType,
+        id: u16,
+        dest: &mut Vec<u8>,
+    ) -> Result<&ResourceMetadata, ResourceError> {
+        let index = self.index(resource_type, id)?;
+
+        match &self.resources[index].1 {
+            ResourceData::Original(_) => {
+                self.fork.load_by_id(resource_type, id, dest)?;
+            }
+            ResourceData::Replaced(data) => {
+                dest.clear();
+                dest.extend_from_slice(data);
+            }
+        }
+
+        Ok(&self.resources[index].0)
+    }
+
+    /// Returns the "attributes" bitfield for the edited fork; see [`ResourceFork::attributes`].
+    pub fn attributes(&self) -> u16 {
+        self.attributes
+    }
+
+    /// Sets the "attributes" bitfield for the edited fork.
+    pub fn set_attributes(&mut self, attributes: u16) {
+        self.attributes = attributes;
+    }
+
+    /// Adds a resource with the given type, ID, name, attributes, and data, replacing any existing
+    /// resource with the same type and ID.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error if the given name can't be represented in the Macintosh
+    /// character encoding or is longer than 255 bytes when encoded, or if the given data is too
+    /// large to fit in a resource fork.
+    pub fn add_resource(
+        &mut self,
+        resource_type: ResourceType,
+        id: u16,
+        name: Option<&str>,
+        attributes: u8,
+        data: Vec<u8>,
+    ) -> Result<(), ResourceError> {
+        check_name(name)?;
+        write::data_len(data.len())?;
+
+        let resource = (
+            ResourceMetadata {
+                resource_type,
+                id,
+                name: name.map(String::from),
+                attributes,
+                data_offset: 0,
+            },
+            ResourceData::Replaced(data),
+        );
+
+        match self.index(resource_type, id) {
+            Ok(index) => self.resources[index] = resource,
+            Err(_) => self.resources.push(resource),
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the data of the resource with the given type and ID, keeping its name and
+    /// attributes.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error if no resource could be found for the given type/ID, or if the
+    /// given data is too large to fit in a resource fork.
+    pub fn replace_data(
+        &mut self,
+        resource_type: ResourceType,
+        id: u16,
+        data: Vec<u8>,
+    ) -> Result<(), ResourceError> {
+        let index = self.index(resource_type, id)?;
+        write::data_len(data.len())?;
+
+        self.resources[index].1 = ResourceData::Replaced(data);
+
+        Ok(())
+    }
+
+    /// Sets or removes the name of the resource with the given type and ID.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error if no resource could be found for the given type/ID, or if the
+    /// given name can't be represented in the Macintosh character encoding or is longer than 255
+    /// bytes when encoded.
+    pub fn rename_resource(
+        &mut self,
+        resource_type: ResourceType,
+        id: u16,
+        name: Option<&str>,
+    ) -> Result<(), ResourceError> {
+        let index = self.index(resource_type, id)?;
+        check_name(name)?;
+
+        self.resources[index].0.name = name.map(String::from);
+
+        Ok(())
+    }
+
+    /// Sets the "attributes" bitfield of the resource with the given type and ID; see
+    /// [`ResourceMetadata::attributes`].
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error if no resource could be found for the given type/ID.
+    pub fn set_resource_attributes(
+        &mut self,
+        resource_type: ResourceType,
+        id: u16,
+        attributes: u8,
+    ) -> Result<(), ResourceError> {
+        let index = self.index(resource_type, id)?;
+        self.resources[index].0.attributes = attributes;
+
+        Ok(())
+    }
+
+    /// Removes the resource with the given type and ID, returning `true` if a resource was
+    /// removed.
+    pub fn remove_resource(&mut self, resource_type: ResourceType, id: u16) -> bool {
+        match self.index(resource_type, id) {
+            Ok(index) => {
+                self.resources.remove(index);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Writes the edited resource fork to the given destination, returning the destination when
+    /// the fork has been written. The original fork is left unchanged.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error if the resources' data or map would be too large to be
+    /// represented in a resource fork, if any resource's original data could not be read, or if an
+    /// IO error occurs while writing to the destination.
+    pub fn save<W: Write>(&mut self, dest: W) -> Result<W, ResourceError> {
+        let entries = self
+            .resources
+            .iter()
+            .map(|(metadata, data)| {
+                Ok(MapEntry {
+                    resource_type: metadata.resource_type,
+                    id: metadata.id,
+                    name: metadata
+                        .name
+                        .as_deref()
+                        .map(write::encode_name)
+                        .transpose()?,
+                    attributes: metadata.attributes,
+                    data_len: match data {
+                        ResourceData::Original(len) => *len,
+                        ResourceData::Replaced(data) => data.len() as u32,
+                    },
+                })
+            })
+            .collect::<Result<Vec<MapEntry>, ResourceError>>()?;
+
+        let resources = &self.resources;
+        let fork = &mut self.fork;
+
+        write::write_fork(
+            dest,
+            self.attributes,
+            &entries.iter().collect::<Vec<_>>(),
+            |i, dest| {
+                match &resources[i] {
+                    (metadata, ResourceData::Original(len)) => {
+                        fork.copy_resource_data(metadata.data_offset, *len, dest)?
+                    }
+                    (_, ResourceData::Replaced(data)) => dest.write_all(data)?,
+                }
+
+                Ok(())
+            },
+        )
+    }
+
+    fn index(&self, resource_type: ResourceType, id: u16) -> Result<usize, ResourceError> {
+        self.resources
+            .iter()
+            .position(|(metadata, _)| metadata.resource_type == resource_type && metadata.id == id)
+            .ok_or(ResourceError::NotFound)
+    }
+}
+
+fn check_name(name: Option<&str>) -> Result<(), ResourceError> {
+    name.map(write::encode_name).transpose().map(|_| ())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rsrc::ResourceForkBuilder;
+    use std::io::Cursor;
+
+    const STR_TYPE: [u8; 4] = *b"STR ";
+    const ICON_TYPE: [u8; 4] = *b"ICON";
+
+    fn fork_bytes() -> Vec<u8> {
+        let mut builder = ResourceForkBuilder::new();
+        builder.set_attributes(0x0020);
+
+        builder
+            .add_resource(
+                STR_TYPE.into(),
+                128,
+                Some("Hello"),
+                0,
+                b"\x05Hello".to_vec(),
+            )
+            .unwrap();
+        builder
+            .add_resource(ICON_TYPE.into(), 128, None, 0x20, vec![0x55; 128])
+            .unwrap();
+        builder
+            .add_resource(STR_TYPE.into(), 129, None, 0, b"\x07Goodbye".to_vec())
+            .unwrap();
+
+        builder.write(vec![]).unwrap()
+    }
+
+    #[test]
+    fn save_unedited_fork() -> Result<(), ResourceError> {
+        let original = fork_bytes();
+        let mut editor = ResourceForkEditor::new(Cursor::new(original.clone()))?;
+
+        assert_eq!(0x0020, editor.attributes());
+        assert_eq!(3, editor.resources().count());
+        assert_eq!(original, editor.save(vec![])?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn save_edited_fork() -> Result<(), ResourceError> {
+        let original = fork_bytes();
+        let mut editor = ResourceForkEditor::new(Cursor::new(original.clone()))?;
+
+        editor.set_attributes(0x0080);
+        editor.replace_data(STR_TYPE.into(), 128, b"\x07Bonjour".to_vec())?;
+        editor.rename_resource(STR_TYPE.into(), 128, Some("Bonjour"))?;
+        editor.rename_resource(STR_TYPE.into(), 129, Some("Au revoir"))?;
+        editor.set_resource_attributes(STR_TYPE.into(), 129, 0x08)?;
+        editor.add_resource(STR_TYPE.into(), 130, None, 0, b"\x03Oui".to_vec())?;
+
+        assert!(editor.remove_resource(ICON_TYPE.into(), 128));
+        assert!(!editor.remove_resource(ICON_TYPE.into(), 128));
+
+        let mut data = vec![];
+        let metadata = editor.load_by_id(STR_TYPE.into(), 129, &mut data)?;
+        assert_eq!(Some(&String::from("Au revoir")), metadata.name());
+        assert_eq!(b"\x07Goodbye".to_vec(), data);
+
+        let saved = editor.save(vec![])?;
+
+        // The removed icon's data isn't carried over into the saved fork
+        assert!(saved.len() < original.len());
+
+        let mut fork = ResourceFork::new(Cursor::new(saved))?;
+        assert_eq!(0x0080, fork.attributes());
+        assert_eq!(3, fork.resources().count());
+
+        let metadata = fork.load_by_name(STR_TYPE.into(), String::from("Bonjour"), &mut data)?;
+        assert_eq!(128, metadata.id());
+        assert_eq!(b"\x07Bonjour".to_vec(), data);
+
+        let metadata = fork.load_by_id(STR_TYPE.into(), 129, &mut data)?;
+        assert_eq!(Some(&String::from("Au revoir")), metadata.name());
+        assert_eq!(0x08, metadata.attributes());
+        assert_eq!(b"\x07Goodbye".to_vec(), data);
+
+        fork.load_by_id(STR_TYPE.into(), 130, &mut data)?;
+        assert_eq!(b"\x03Oui".to_vec(), data);
+
+        assert!(matches!(
+            fork.load_by_id(ICON_TYPE.into(), 128, &mut data),
+            Err(ResourceError::NotFound)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_edits() -> Result<(), ResourceError> {
+        let mut editor = ResourceForkEditor::new(Cursor::new(fork_bytes()))?;
+
+        assert!(matches!(
+            editor.replace_data(STR_TYPE.into(), 200, vec![]),
+            Err(ResourceError::NotFound)
+        ));
+
+        assert!(matches!(
+            editor.rename_resource(STR_TYPE.into(), 128, Some("🤘")),
+            Err(ResourceError::InvalidName)
+        ));
+
+        assert!(matches!(
+            editor.set_resource_attributes(ICON_TYPE.into(), 129, 0),
+            Err(ResourceError::NotFound)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn corrupt_resource_data() {
+        let mut bytes = fork_bytes();
+
+        // Claim that the first resource is longer than the data section
+        bytes[256..260].copy_from_slice(&0x1000u32.to_be_bytes());
+
+        assert!(matches!(
+            ResourceForkEditor::new(Cursor::new(bytes.clone())),
+            Err(ResourceError::CorruptResourceData)
+        ));
+
+        // Claim a length that would overflow if added to the resource's offset
+        bytes[256..260].copy_from_slice(&0xffff_fffeu32.to_be_bytes());
+
+        assert!(matches!(
+            ResourceForkEditor::new(Cursor::new(bytes)),
+            Err(ResourceError::CorruptResourceData)
+        ));
+    }
+}
@@ -9,13 +9,23 @@
 //! represented as separate files, as filesystem-level metadata, or sometimes in archives (like
 //! BinHex) that encode both forks of a file.
 //!
+//! Resource forks may be read with [`ResourceFork`], new resource forks may be written with
+//! [`ResourceForkBuilder`], and existing resource forks may be edited with
+//! [`ResourceForkEditor`].
+//!
 //! For a complete overview of resources, please see the ["Resource Manager" chapter of "Inside
 //! Macintosh: More Macintosh
 //! Toolbox"](https://developer.apple.com/library/archive/documentation/mac/pdf/MoreMacintoshToolbox.pdf)
 
 use std::collections::HashMap;
 use std::convert::{TryFrom, TryInto};
-use std::io::{self, Error, Read, Seek, SeekFrom};
+use std::io::{self, Error, Read, Seek, SeekFrom, Write};
+
+mod edit;
+mod write;
+
+pub use edit::ResourceForkEditor;
+pub use write::ResourceForkBuilder;
 
 const NO_NAME: u16 = 0xffff;
 
@@ -84,8 +94,9 @@ impl<R: Read + Seek> ResourceFork<R> {
         let type_list_offset = u16::from_be_bytes(type_list_offset_bytes.try_into().unwrap());
         let name_list_offset = u16::from_be_bytes(name_list_offset_bytes.try_into().unwrap());
 
-        // The type count in the resource fork is "number of types in the map minus 1"
-        let type_count = u16::from_be_bytes(type_count_bytes.try_into().unwrap()) + 1;
+        // The type count in the resource fork is "number of types in the map minus 1," and so an
+        // empty map has a type count of -1 (0xffff)
+        let type_count = u16::from_be_bytes(type_count_bytes.try_into().unwrap()).wrapping_add(1);
 
         // The map length must be at least 30 bytes (for the header, including the type count), then
         // 8 bytes for each item in the type list
@@ -98,19 +109,24 @@ impl<R: Read + Seek> ResourceFork<R> {
 
         for t in 0..type_count {
             // Plus 2 because the type count is technically part of the type list
-            let type_offset = (type_list_offset + 2 + (t * 8)) as usize;
+            let type_offset = type_list_offset as usize + 2 + (t as usize * 8);
+
+            if map_bytes.len() < type_offset + 8 {
+                return Err(ResourceError::CorruptResourceMap);
+            }
 
             let type_entry_bytes: [u8; 8] =
                 map_bytes[type_offset..type_offset + 8].try_into().unwrap();
             let type_entry = TypeListEntry::from(type_entry_bytes);
 
             for r in 0..type_entry.count {
-                let reference_offset =
-                    (type_list_offset + type_entry.reference_list_offset + (r * 12)) as usize;
+                let reference_offset = type_list_offset as usize
+                    + type_entry.reference_list_offset as usize
+                    + (r as usize * 12);
 
                 // The reference list entry is 12 bytes; we expect to have at least that many still
                 // to read at the given offset
-                if header.map_len < reference_offset as u32 + 12 {
+                if map_bytes.len() < reference_offset + 12 {
                     return Err(ResourceError::CorruptResourceMap);
                 }
 
@@ -124,27 +140,25 @@ impl<R: Read + Seek> ResourceFork<R> {
                 let maybe_name = if reference_entry.name_list_offset == NO_NAME {
                     None
                 } else {
+                    let name_offset =
+                        name_list_offset as usize + reference_entry.name_list_offset as usize;
+
                     // Make sure we have at least one byte to read at the start of the name entry
-                    if header.map_len < (name_list_offset + reference_entry.name_list_offset) as u32
-                    {
+                    if map_bytes.len() <= name_offset {
                         return Err(ResourceError::CorruptResourceMap);
                     }
 
-                    let name_len =
-                        map_bytes[(name_list_offset + reference_entry.name_list_offset) as usize];
+                    let name_len = map_bytes[name_offset] as usize;
+                    let name_start = name_offset + 1;
 
                     // ...and that we have enough bytes left to read the whole name
-                    if header.map_len
-                        < (name_list_offset + reference_entry.name_list_offset + 1) as u32
-                    {
+                    if map_bytes.len() < name_start + name_len {
                         return Err(ResourceError::CorruptResourceMap);
                     }
 
-                    let name_start =
-                        (name_list_offset + reference_entry.name_list_offset + 1) as usize;
-                    let name_bytes = &map_bytes[name_start..name_start + name_len as usize];
+                    let name_bytes = &map_bytes[name_start..name_start + name_len];
 
-                    Some(encoding_rs::MACINTOSH.decode(&name_bytes).0.to_string())
+                    Some(encoding_rs::MACINTOSH.decode(name_bytes).0.to_string())
                 };
 
                 if let Some(ref name) = maybe_name {
@@ -240,33 +254,18 @@ impl<R: Read + Seek> ResourceFork<R> {
         id: u16,
         dest: &mut Vec<u8>,
     ) -> Result<&ResourceMetadata, ResourceError> {
-        if let Some(entry) = self.resources_by_id.get(&(resource_type, id)) {
-            // Make sure we can at least load the data length bytes
-            if self.header._data_len < entry.data_offset + 4 {
-                return Err(ResourceError::CorruptResourceData);
-            }
-
-            let mut len_bytes = [0; std::mem::size_of::<u32>()];
-
-            self.source.seek(SeekFrom::Start(
-                (self.header.data_offset + entry.data_offset) as u64,
-            ))?;
+        let data_offset = self
+            .resources_by_id
+            .get(&(resource_type, id))
+            .ok_or(ResourceError::NotFound)?
+            .data_offset;
 
-            self.source.read_exact(&mut len_bytes)?;
-            let resource_len = u32::from_be_bytes(len_bytes);
+        let resource_len = self.resource_len(data_offset)?;
 
-            // ...and make sure we can load the rest of the resource data, too
-            if self.header._data_len < entry.data_offset + 4 + resource_len {
-                return Err(ResourceError::CorruptResourceData);
-            }
-
-            dest.resize(resource_len as usize, 0);
-            self.source.read_exact(dest)?;
+        dest.resize(resource_len as usize, 0);
+        self.source.read_exact(dest)?;
 
-            Ok(entry)
-        } else {
-            Err(ResourceError::NotFound)
-        }
+        Ok(&self.resources_by_id[&(resource_type, id)])
     }
 
     /// Loads data and metadata for the resource with the given type and name. The provided `dest`
@@ -328,6 +327,54 @@ impl<R: Read + Seek> ResourceFork<R> {
     pub fn attributes(&self) -> u16 {
         self.attributes
     }
+
+    /// Reads the length of the resource data at the given offset (relative to the start of the
+    /// resource data), leaving the source positioned at the start of the resource data.
+    fn resource_len(&mut self, data_offset: u32) -> Result<u32, ResourceError> {
+        let data_offset = data_offset as u64;
+        let data_len = self.header.data_len as u64;
+
+        // Make sure we can at least load the data length bytes...
+        if data_len < data_offset + 4 {
+            return Err(ResourceError::CorruptResourceData);
+        }
+
+        let mut len_bytes = [0; std::mem::size_of::<u32>()];
+
+        self.source.seek(SeekFrom::Start(
+            self.header.data_offset as u64 + data_offset,
+        ))?;
+
+        self.source.read_exact(&mut len_bytes)?;
+        let resource_len = u32::from_be_bytes(len_bytes);
+
+        // ...and make sure we can load the rest of the resource data, too
+        if data_len < data_offset + 4 + resource_len as u64 {
+            return Err(ResourceError::CorruptResourceData);
+        }
+
+        Ok(resource_len)
+    }
+
+    /// Copies the resource data at the given offset (relative to the start of the resource data),
+    /// which is `len` bytes long, to the given destination without reading the whole resource into
+    /// memory.
+    fn copy_resource_data(
+        &mut self,
+        data_offset: u32,
+        len: u32,
+        dest: &mut impl Write,
+    ) -> Result<(), ResourceError> {
+        self.source.seek(SeekFrom::Start(
+            self.header.data_offset as u64 + data_offset as u64 + 4,
+        ))?;
+
+        if io::copy(&mut (&mut self.source).take(len as u64), dest)? != len as u64 {
+            return Err(ResourceError::CorruptResourceData);
+        }
+
+        Ok(())
+    }
 }
 
 /// A resource type identifier.
@@ -396,7 +443,7 @@ impl PartialEq<ResourceType> for &str {
     fn eq(&self, other: &ResourceType) -> bool {
         let (cow, _, _) = encoding_rs::MACINTOSH.encode(self);
 
-        return cow.as_ref() == other.bytes;
+        cow.as_ref() == other.bytes
     }
 }
 
@@ -484,6 +531,14 @@ pub enum ResourceError {
 
     /// The data for a specific resource was corrupt and could not be loaded.
     CorruptResourceData,
+
+    /// A resource's name could not be represented in the Macintosh character encoding, or was
+    /// longer than 255 bytes.
+    InvalidName,
+
+    /// The resources' data or resource map would be too large to be represented in a resource
+    /// fork.
+    ResourceForkTooLarge,
 }
 
 impl From<std::io::Error> for ResourceError {
@@ -495,7 +550,7 @@ impl From<std::io::Error> for ResourceError {
 struct ResourceForkHeader {
     data_offset: u32,
     map_offset: u32,
-    _data_len: u32,
+    data_len: u32,
     map_len: u32,
 }
 
@@ -508,7 +563,7 @@ impl From<[u8; 16]> for ResourceForkHeader {
         ResourceForkHeader {
             data_offset: u32::from_be_bytes(data_offset_bytes.try_into().unwrap()),
             map_offset: u32::from_be_bytes(map_offset_bytes.try_into().unwrap()),
-            _data_len: u32::from_be_bytes(data_len_bytes.try_into().unwrap()),
+            data_len: u32::from_be_bytes(data_len_bytes.try_into().unwrap()),
             map_len: u32::from_be_bytes(map_len_bytes.try_into().unwrap()),
         }
     }
@@ -516,7 +571,7 @@ impl From<[u8; 16]> for ResourceForkHeader {
 
 struct TypeListEntry {
     resource_type: ResourceType,
-    count: u16,
+    count: u32,
     reference_list_offset: u16,
 }
 
@@ -524,7 +579,7 @@ impl From<[u8; 8]> for TypeListEntry {
     fn from(bytes: [u8; 8]) -> Self {
         TypeListEntry {
             resource_type: bytes[0..4].try_into().unwrap(),
-            count: u16::from_be_bytes(bytes[4..6].try_into().unwrap()) + 1,
+            count: u16::from_be_bytes(bytes[4..6].try_into().unwrap()) as u32 + 1,
             reference_list_offset: u16::from_be_bytes(bytes[6..8].try_into().unwrap()),
         }
     }
@@ -573,7 +628,7 @@ mod test {
             &mut resource_data,
         )?;
 
-        assert!(resource_data.len() > 0);
+        assert!(!resource_data.is_empty());
         assert_eq!("STR#", metadata.resource_type);
         assert_eq!(777, metadata.id);
         assert_eq!(Some(String::from("Example")), metadata.name);
@@ -585,7 +640,7 @@ mod test {
             String::from("Example"),
             &mut resource_data,
         )?;
-        assert!(resource_data.len() > 0);
+        assert!(!resource_data.is_empty());
         assert_eq!("STR#", metadata.resource_type);
         assert_eq!(777, metadata.id);
         assert_eq!(Some(String::from("Example")), metadata.name);
@@ -593,6 +648,21 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn load_corrupt_resource() {
+        // Claim a length for the only resource that would overflow if added to its offset
+        let mut bytes = RSRC_DATA.to_vec();
+        let data_offset = u32::from_be_bytes(bytes[0..4].try_into().unwrap()) as usize;
+        bytes[data_offset..data_offset + 4].copy_from_slice(&0xffff_fffeu32.to_be_bytes());
+
+        let mut resource_fork = ResourceFork::new(Cursor::new(bytes)).unwrap();
+
+        assert!(matches!(
+            resource_fork.load_by_id(ResourceType::try_from("STR#").unwrap(), 777, &mut vec![]),
+            Err(ResourceError::CorruptResourceData)
+        ));
+    }
+
     #[test]
     fn resource_type_from_slice() {
         let bytes = b"__snd __";
@@ -0,0 +1,392 @@
+use std::convert::TryFrom;
+use std::io::Write;
+
+use super::{ResourceError, ResourceType, NO_NAME};
+
+/// The offset of the first resource's data. Like Rez and the Resource Manager, we leave the space
+/// between the fork header and the data (reserved for system and application use) empty.
+const DATA_OFFSET: u32 = 256;
+
+/// The length of the resource map's header, including the reserved copy of the fork header; the
+/// type list immediately follows it.
+const MAP_HEADER_LENGTH: usize = 28;
+
+/// The largest offset that fits in the three bytes a reference list entry has for a resource's data
+/// offset.
+const MAX_DATA_OFFSET: u64 = 0x00ff_ffff;
+
+/// The layout-relevant parts of a single resource to be written to a resource fork.
+pub(super) struct MapEntry {
+    pub resource_type: ResourceType,
+    pub id: u16,
+    pub name: Option<Vec<u8>>,
+    pub attributes: u8,
+    pub data_len: u32,
+}
+
+/// Builds a resource fork from scratch.
+///
+/// Resources are written grouped by type, with types in the order in which they were first added
+/// and resources of each type in the order in which they were added. The written fork follows the
+/// same layout as forks written by Rez: a 16-byte header, 240 reserved bytes, the resource data
+/// (each resource preceded by its length), and finally the resource map.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::convert::TryFrom;
+/// use std::fs::File;
+/// use clarus::rsrc::{ResourceError, ResourceForkBuilder, ResourceType};
+///
+/// fn main() -> Result<(), ResourceError> {
+///     let mut builder = ResourceForkBuilder::new();
+///
+///     builder.add_resource(
+///         ResourceType::try_from("STR ").unwrap(),
+///         128,
+///         Some("Greeting"),
+///         0,
+///         b"\x0dHello, world!".to_vec(),
+///     )?;
+///
+///     builder.write(File::create("example.rsrc")?)?;
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Default)]
+pub struct ResourceForkBuilder {
+    attributes: u16,
+    resources: Vec<(MapEntry, Vec<u8>)>,
+}
+
+impl ResourceForkBuilder {
+    /// Creates a new builder for an empty resource fork with no attributes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the "attributes" bitfield for the resource fork; see
+    /// [`crate::rsrc::ResourceFork::attributes`].
+    pub fn set_attributes(&mut self, attributes: u16) {
+        self.attributes = attributes;
+    }
+
+    /// Adds a resource with the given type, ID, name, attributes, and data, replacing any resource
+    /// previously added with the same type and ID.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error if the given name can't be represented in the Macintosh
+    /// character encoding or is longer than 255 bytes when encoded, or if the given data is too
+    /// large to fit in a resource fork.
+    pub fn add_resource(
+        &mut self,
+        resource_type: ResourceType,
+        id: u16,
+        name: Option<&str>,
+        attributes: u8,
+        data: Vec<u8>,
+    ) -> Result<(), ResourceError> {
+        let entry = MapEntry {
+            resource_type,
+            id,
+            name: name.map(encode_name).transpose()?,
+            attributes,
+            data_len: data_len(data.len())?,
+        };
+
+        match self
+            .resources
+            .iter_mut()
+            .find(|(existing, _)| existing.resource_type == resource_type && existing.id == id)
+        {
+            Some(resource) => *resource = (entry, data),
+            None => self.resources.push((entry, data)),
+        }
+
+        Ok(())
+    }
+
+    /// Removes the resource with the given type and ID, returning `true` if a resource was
+    /// removed.
+    pub fn remove_resource(&mut self, resource_type: ResourceType, id: u16) -> bool {
+        let len = self.resources.len();
+
+        self.resources
+            .retain(|(entry, _)| entry.resource_type != resource_type || entry.id != id);
+
+        self.resources.len() != len
+    }
+
+    /// Writes the resource fork to the given destination, returning the destination when the fork
+    /// has been written.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error if the resources' data or map would be too large to be
+    /// represented in a resource fork, or if an IO error occurs while writing to the destination.
+    pub fn write<W: Write>(&self, dest: W) -> Result<W, ResourceError> {
+        let entries: Vec<&MapEntry> = self.resources.iter().map(|(entry, _)| entry).collect();
+
+        write_fork(dest, self.attributes, &entries, |i, dest| {
+            dest.write_all(&self.resources[i].1)?;
+            Ok(())
+        })
+    }
+}
+
+/// Encodes a resource name in the Macintosh character encoding.
+pub(super) fn encode_name(name: &str) -> Result<Vec<u8>, ResourceError> {
+    let (bytes, _, had_errors) = encoding_rs::MACINTOSH.encode(name);
+
+    if had_errors || bytes.len() > u8::MAX as usize {
+        return Err(ResourceError::InvalidName);
+    }
+
+    Ok(bytes.into_owned())
+}
+
+/// Checks that a resource's data isn't too large for its length to be written in a resource fork.
+pub(super) fn data_len(len: usize) -> Result<u32, ResourceError> {
+    u32::try_from(len).map_err(|_| ResourceError::ResourceForkTooLarge)
+}
+
+/// Writes a complete resource fork with the given attributes and resources to the given
+/// destination. Resources' data are written by `write_data`, which is called with the index of
+/// each resource (in `entries`) in the order in which resources' data appear in the fork, and must
+/// write exactly as many bytes as the resource's `data_len`.
+pub(super) fn write_fork<W: Write>(
+    mut dest: W,
+    attributes: u16,
+    entries: &[&MapEntry],
+    mut write_data: impl FnMut(usize, &mut W) -> Result<(), ResourceError>,
+) -> Result<W, ResourceError> {
+    // Group resources by type, keeping types in the order in which they first appear
+    let mut types: Vec<(ResourceType, Vec<usize>)> = vec![];
+
+    for (i, entry) in entries.iter().enumerate() {
+        match types
+            .iter_mut()
+            .find(|(resource_type, _)| *resource_type == entry.resource_type)
+        {
+            Some((_, indices)) => indices.push(i),
+            None => types.push((entry.resource_type, vec![i])),
+        }
+    }
+
+    let too_large = |value: usize| value > u16::MAX as usize;
+
+    // The type count is part of the type list, and reference list offsets are relative to the
+    // start of the type list; keeping the name list's offset within 16 bits also keeps the type
+    // and resource counts within 16 bits
+    let type_list_len = 2 + types.len() * 8;
+    let name_list_offset = MAP_HEADER_LENGTH + type_list_len + entries.len() * 12;
+
+    if too_large(name_list_offset) {
+        return Err(ResourceError::ResourceForkTooLarge);
+    }
+
+    let mut type_list = Vec::with_capacity(type_list_len);
+    let mut reference_lists = Vec::with_capacity(entries.len() * 12);
+    let mut name_list = vec![];
+    let mut data_offset = 0u64;
+
+    // The type count and each type's resource count are stored as "count minus one"
+    type_list.extend_from_slice(&(types.len() as u16).wrapping_sub(1).to_be_bytes());
+
+    for (resource_type, indices) in &types {
+        type_list.extend_from_slice(&resource_type.bytes);
+        type_list.extend_from_slice(&(indices.len() as u16).wrapping_sub(1).to_be_bytes());
+        type_list
+            .extend_from_slice(&((type_list_len + reference_lists.len()) as u16).to_be_bytes());
+
+        for &i in indices {
+            let entry = entries[i];
+
+            if data_offset > MAX_DATA_OFFSET {
+                return Err(ResourceError::ResourceForkTooLarge);
+            }
+
+            let name_offset = match &entry.name {
+                Some(name) => {
+                    let offset = name_list.len();
+
+                    if too_large(offset) {
+                        return Err(ResourceError::ResourceForkTooLarge);
+                    }
+
+                    name_list.push(name.len() as u8);
+                    name_list.extend_from_slice(name);
+
+                    offset as u16
+                }
+                None => NO_NAME,
+            };
+
+            reference_lists.extend_from_slice(&entry.id.to_be_bytes());
+            reference_lists.extend_from_slice(&name_offset.to_be_bytes());
+            reference_lists.push(entry.attributes);
+            reference_lists.extend_from_slice(&(data_offset as u32).to_be_bytes()[1..]);
+
+            // Reserved for a handle to the resource's data in memory
+            reference_lists.extend_from_slice(&[0; 4]);
+
+            data_offset += 4 + entry.data_len as u64;
+        }
+    }
+
+    let map_len = name_list_offset + name_list.len();
+    let map_offset = DATA_OFFSET as u64 + data_offset;
+
+    if map_offset + map_len as u64 > u32::MAX as u64 {
+        return Err(ResourceError::ResourceForkTooLarge);
+    }
+
+    let mut header = Vec::with_capacity(16);
+    header.extend_from_slice(&DATA_OFFSET.to_be_bytes());
+    header.extend_from_slice(&(map_offset as u32).to_be_bytes());
+    header.extend_from_slice(&(data_offset as u32).to_be_bytes());
+    header.extend_from_slice(&(map_len as u32).to_be_bytes());
+
+    dest.write_all(&header)?;
+    dest.write_all(&[0; DATA_OFFSET as usize - 16])?;
+
+    for (_, indices) in &types {
+        for &i in indices {
+            dest.write_all(&entries[i].data_len.to_be_bytes())?;
+            write_data(i, &mut dest)?;
+        }
+    }
+
+    // The map starts with a copy of the fork header, followed by space for a handle to the next
+    // resource map and a file reference number
+    let mut map = Vec::with_capacity(map_len);
+    map.extend_from_slice(&header);
+    map.extend_from_slice(&[0; 6]);
+    map.extend_from_slice(&attributes.to_be_bytes());
+    map.extend_from_slice(&(MAP_HEADER_LENGTH as u16).to_be_bytes());
+    map.extend_from_slice(&(name_list_offset as u16).to_be_bytes());
+    map.extend(type_list);
+    map.extend(reference_lists);
+    map.extend(name_list);
+
+    dest.write_all(&map)?;
+
+    Ok(dest)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rsrc::ResourceFork;
+    use std::io::Cursor;
+
+    const RSRC_DATA: &[u8] = include_bytes!("string-table.rsrc");
+
+    #[test]
+    fn write_rez_layout() -> Result<(), ResourceError> {
+        let mut builder = ResourceForkBuilder::new();
+
+        builder.add_resource(
+            ResourceType::try_from("STR#").unwrap(),
+            777,
+            Some("Example"),
+            0,
+            b"\x00\x01\x0bPreferences".to_vec(),
+        )?;
+
+        // Rez leaves leftovers from memory in the map's reserved file reference number and
+        // resource handle fields, which we always write as zeroes
+        let mut expected = RSRC_DATA.to_vec();
+        expected[0x126] = 0;
+        expected[0x140] = 0;
+
+        assert_eq!(expected, builder.write(vec![])?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_resources() -> Result<(), ResourceError> {
+        let str_type = ResourceType::from(*b"STR ");
+        let icon_type = ResourceType::from(*b"ICON");
+
+        let mut builder = ResourceForkBuilder::new();
+        builder.set_attributes(0x0080);
+        builder.add_resource(str_type, 128, Some("First"), 0x20, b"one".to_vec())?;
+        builder.add_resource(icon_type, 128, None, 0, vec![0xaa; 128])?;
+        builder.add_resource(str_type, 129, None, 0, vec![])?;
+        builder.add_resource(str_type, 130, Some("Replaced"), 0, b"three".to_vec())?;
+        builder.add_resource(str_type, 130, Some("Third"), 0x08, b"3".to_vec())?;
+        builder.add_resource(str_type, 131, None, 0, b"removed".to_vec())?;
+
+        assert!(builder.remove_resource(str_type, 131));
+        assert!(!builder.remove_resource(str_type, 131));
+
+        let mut fork = ResourceFork::new(Cursor::new(builder.write(vec![])?))?;
+
+        assert_eq!(0x0080, fork.attributes());
+        assert_eq!(4, fork.resources().count());
+
+        let mut data = vec![];
+
+        let metadata = fork.load_by_id(str_type, 128, &mut data)?;
+        assert_eq!(Some(&String::from("First")), metadata.name());
+        assert_eq!(0x20, metadata.attributes());
+        assert_eq!(b"one".to_vec(), data);
+
+        let metadata = fork.load_by_name(str_type, String::from("Third"), &mut data)?;
+        assert_eq!(130, metadata.id());
+        assert_eq!(0x08, metadata.attributes());
+        assert_eq!(b"3".to_vec(), data);
+
+        let metadata = fork.load_by_id(str_type, 129, &mut data)?;
+        assert_eq!(None, metadata.name());
+        assert!(data.is_empty());
+
+        fork.load_by_id(icon_type, 128, &mut data)?;
+        assert_eq!(vec![0xaa; 128], data);
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_empty_fork() -> Result<(), ResourceError> {
+        let bytes = ResourceForkBuilder::new().write(vec![])?;
+
+        assert_eq!(256 + 30, bytes.len());
+        assert_eq!(
+            0,
+            ResourceFork::new(Cursor::new(bytes))?.resources().count()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_resources() {
+        let mut builder = ResourceForkBuilder::new();
+
+        assert!(matches!(
+            builder.add_resource(ResourceType::from(*b"STR "), 128, Some("🤘"), 0, vec![]),
+            Err(ResourceError::InvalidName)
+        ));
+
+        assert!(matches!(
+            builder.add_resource(
+                ResourceType::from(*b"STR "),
+                128,
+                Some(&"a".repeat(256)),
+                0,
+                vec![]
+            ),
+            Err(ResourceError::InvalidName)
+        ));
+
+        assert!(matches!(
+            data_len(u32::MAX as usize + 1),
+            Err(ResourceError::ResourceForkTooLarge)
+        ));
+    }
+}
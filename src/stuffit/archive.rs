@@ -0,0 +1,619 @@
+use std::cmp;
+use std::convert::TryInto;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crc16::{State, ARC};
+
+use super::entry::{ForkInfo, StuffItEntry};
+use super::method::CompressionMethod;
+use crate::finder::FinderFlags;
+use crate::util::{u16_at, u32_at};
+
+/// The signatures that may appear at the start of a classic StuffIt archive.
+pub(crate) const SIGNATURES: [&[u8; 4]; 9] = [
+    b"SIT!", b"ST46", b"ST50", b"ST60", b"ST65", b"STin", b"STi2", b"STi3", b"STi4",
+];
+
+/// The second signature, which appears after the archive's file count and length.
+pub(crate) const SECOND_SIGNATURE: &[u8; 4] = b"rLau";
+
+/// The length, in bytes, of an archive's header.
+const ARCHIVE_HEADER_LENGTH: usize = 22;
+
+/// The length, in bytes, of each entry's header.
+pub(super) const ENTRY_HEADER_LENGTH: usize = 112;
+
+/// The "compression method" of an entry that marks the start of a folder.
+const FOLDER_START: u8 = 32;
+
+/// The "compression method" of an entry that marks the end of a folder.
+const FOLDER_END: u8 = 33;
+
+const METHOD_MASK: u8 = 0x0f;
+const ENCRYPTED_FLAG: u8 = 0x10;
+
+/// A classic StuffIt archive, written by StuffIt 1.5 through 4.5.
+///
+/// Archives contain a hierarchy of files and folders. Files carry Finder metadata and both forks,
+/// each of which is compressed separately; forks compressed with any method for which
+/// [`CompressionMethod::is_supported`] returns `true` (and method 13 forks that store their own
+/// codes) can be decompressed.
+///
+/// Entries are listed when the archive is opened, and forks are read from a seekable source on
+/// demand and in any order.
+pub struct StuffItArchive<R: Read + Seek> {
+    source: R,
+    base_offset: u64,
+    entries: Vec<StuffItEntry>,
+}
+
+impl<R: Read + Seek> StuffItArchive<R> {
+    /// Creates a new StuffIt archive that will extract data from the given source, starting at the
+    /// source's current position, and reads the archive's list of entries.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the archive's header or any of its entries' headers
+    /// could not be read, or if any entry's header doesn't match its checksum.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use clarus::stuffit::{StuffItArchive, StuffItError};
+    ///
+    /// fn main() -> Result<(), StuffItError> {
+    ///     let archive = StuffItArchive::new(File::open("example.sit")?)?;
+    ///
+    ///     for entry in archive.entries() {
+    ///         println!("{}", entry.path().join("/"));
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn new(mut source: R) -> Result<Self, StuffItError> {
+        let base_offset = source.stream_position()?;
+        let source_len = source.seek(SeekFrom::End(0))? - base_offset;
+        source.seek(SeekFrom::Start(base_offset))?;
+
+        let mut header = [0; ARCHIVE_HEADER_LENGTH];
+        source.read_exact(&mut header)?;
+
+        if !SIGNATURES
+            .iter()
+            .any(|signature| header[0..4] == signature[..])
+            || header[10..14] != SECOND_SIGNATURE[..]
+        {
+            return Err(StuffItError::InvalidHeader);
+        }
+
+        let archive_len = u32::from_be_bytes(header[6..10].try_into().unwrap()) as u64;
+        let end = cmp::min(archive_len, source_len);
+
+        let mut entries = Vec::new();
+        let mut folders: Vec<String> = Vec::new();
+        let mut offset = ARCHIVE_HEADER_LENGTH as u64;
+
+        while offset + ENTRY_HEADER_LENGTH as u64 <= end {
+            let mut entry_header = [0; ENTRY_HEADER_LENGTH];
+            source.seek(SeekFrom::Start(base_offset + offset))?;
+            source.read_exact(&mut entry_header)?;
+
+            offset += ENTRY_HEADER_LENGTH as u64;
+
+            let provided_checksum = u16_at(&entry_header, 110);
+            let calculated_checksum = State::<ARC>::calculate(&entry_header[..110]);
+
+            if provided_checksum != calculated_checksum {
+                return Err(StuffItError::InvalidChecksum(
+                    provided_checksum as u32,
+                    calculated_checksum as u32,
+                ));
+            }
+
+            let (resource_method, data_method) = (entry_header[0], entry_header[1]);
+
+            if resource_method == FOLDER_END || data_method == FOLDER_END {
+                folders.pop().ok_or(StuffItError::InvalidHeader)?;
+                continue;
+            }
+
+            let name_len = cmp::min(entry_header[2] as usize, 63);
+            let (name, _, _) = encoding_rs::MACINTOSH.decode(&entry_header[3..3 + name_len]);
+
+            let mut path = folders.clone();
+            path.push(name.to_string());
+
+            let is_folder = resource_method == FOLDER_START || data_method == FOLDER_START;
+
+            let (resource_fork, data_fork) = if is_folder {
+                folders.push(name.to_string());
+
+                (ForkInfo::empty(), ForkInfo::empty())
+            } else {
+                let resource_fork = ForkInfo {
+                    method: CompressionMethod::from(resource_method & METHOD_MASK),
+                    encrypted: resource_method & ENCRYPTED_FLAG != 0,
+                    offset,
+                    len: u32_at(&entry_header, 84),
+                    compressed_len: u32_at(&entry_header, 92),
+                    crc: u16_at(&entry_header, 100),
+                };
+
+                offset += resource_fork.compressed_len as u64;
+
+                let data_fork = ForkInfo {
+                    method: CompressionMethod::from(data_method & METHOD_MASK),
+                    encrypted: data_method & ENCRYPTED_FLAG != 0,
+                    offset,
+                    len: u32_at(&entry_header, 88),
+                    compressed_len: u32_at(&entry_header, 96),
+                    crc: u16_at(&entry_header, 102),
+                };
+
+                offset += data_fork.compressed_len as u64;
+
+                if offset > end {
+                    return Err(StuffItError::InvalidHeader);
+                }
+
+                (resource_fork, data_fork)
+            };
+
+            entries.push(StuffItEntry::new(
+                path,
+                is_folder,
+                entry_header[66..70].try_into().unwrap(),
+                entry_header[70..74].try_into().unwrap(),
+                FinderFlags::from(u16_at(&entry_header, 74)),
+                u32_at(&entry_header, 76),
+                u32_at(&entry_header, 80),
+                data_fork,
+                resource_fork,
+            ));
+        }
+
+        Ok(StuffItArchive {
+            source,
+            base_offset,
+            entries,
+        })
+    }
+
+    /// Returns all of the files and folders in this archive, in the order in which they appear in
+    /// the archive. Folders precede their contents.
+    pub fn entries(&self) -> &[StuffItEntry] {
+        &self.entries
+    }
+
+    /// Reads and decompresses the given entry's data fork.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if the fork is encrypted or compressed with an unsupported
+    /// method, if its compressed data is malformed, or if its decompressed content doesn't match
+    /// its checksum.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use clarus::stuffit::{StuffItArchive, StuffItError};
+    ///
+    /// fn main() -> Result<(), StuffItError> {
+    ///     let mut archive = StuffItArchive::new(File::open("example.sit")?)?;
+    ///
+    ///     for entry in archive.entries().to_vec() {
+    ///         if !entry.is_folder() {
+    ///             let data_fork = archive.data_fork(&entry)?;
+    ///             println!("{}: {} bytes", entry.name(), data_fork.len());
+    ///         }
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn data_fork(&mut self, entry: &StuffItEntry) -> Result<Vec<u8>, StuffItError> {
+        self.read_fork(entry.data_fork)
+    }
+
+    /// Reads and decompresses the given entry's resource fork.
+    ///
+    /// The returned fork may be opened with [`crate::rsrc::ResourceFork`].
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if the fork is encrypted or compressed with an unsupported
+    /// method, if its compressed data is malformed, or if its decompressed content doesn't match
+    /// its checksum.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use std::io::Cursor;
+    /// use clarus::rsrc::ResourceFork;
+    /// use clarus::stuffit::{StuffItArchive, StuffItError};
+    ///
+    /// fn main() -> Result<(), StuffItError> {
+    ///     let mut archive = StuffItArchive::new(File::open("example.sit")?)?;
+    ///     let entry = archive.entries()[0].clone();
+    ///
+    ///     let resource_fork = archive.resource_fork(&entry)?;
+    ///
+    ///     if let Ok(resource_fork) = ResourceFork::new(Cursor::new(resource_fork)) {
+    ///         println!("{} resources", resource_fork.resources().count());
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn resource_fork(&mut self, entry: &StuffItEntry) -> Result<Vec<u8>, StuffItError> {
+        self.read_fork(entry.resource_fork)
+    }
+
+    fn read_fork(&mut self, fork: ForkInfo) -> Result<Vec<u8>, StuffItError> {
+        read_fork(&mut self.source, self.base_offset, fork)
+    }
+}
+
+/// Reads and decompresses the given fork from an archive that starts at the given offset within
+/// the given source, verifying the fork's checksum if its compression method calls for it.
+pub(super) fn read_fork<R: Read + Seek>(
+    source: &mut R,
+    base_offset: u64,
+    fork: ForkInfo,
+) -> Result<Vec<u8>, StuffItError> {
+    if fork.encrypted {
+        return Err(StuffItError::Encrypted);
+    }
+
+    source.seek(SeekFrom::Start(base_offset + fork.offset))?;
+
+    // The compressed length comes from the archive and can't be trusted, so the buffer only grows
+    // as data actually arrives from the source
+    let mut compressed = vec![];
+    source
+        .take(fork.compressed_len as u64)
+        .read_to_end(&mut compressed)?;
+
+    if compressed.len() < fork.compressed_len as usize {
+        return Err(StuffItError::IoError(io::ErrorKind::UnexpectedEof));
+    }
+
+    let decompressed = fork.method.decompress(&compressed, fork.len as usize)?;
+
+    if fork.method.has_header_crc() {
+        let calculated_checksum = State::<ARC>::calculate(&decompressed);
+
+        if calculated_checksum != fork.crc {
+            return Err(StuffItError::InvalidChecksum(
+                fork.crc as u32,
+                calculated_checksum as u32,
+            ));
+        }
+    }
+
+    Ok(decompressed)
+}
+
+/// The error type for operations on StuffIt archives.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum StuffItError {
+    /// An [`std::io::Error`] occurred while reading some part of the archive.
+    ///
+    /// The kind of IO error is included.
+    IoError(io::ErrorKind),
+
+    /// The archive's header or one of its entries' headers was malformed.
+    InvalidHeader,
+
+    /// The checksum included in an entry's header (or at the end of a fork's compressed data) did
+    /// not match the checksum calculated from the header's content or from the content of one of
+    /// the entry's forks.
+    ///
+    /// The provided checksum and the calculated checksum are included.
+    InvalidChecksum(u32, u32),
+
+    /// A fork was compressed with a method (or a variant of a method) this library can't
+    /// decompress.
+    ///
+    /// The compression method is included.
+    UnsupportedMethod(CompressionMethod),
+
+    /// A fork was encrypted.
+    Encrypted,
+
+    /// A fork's compressed data was malformed or didn't expand to the expected length.
+    CorruptData,
+}
+
+impl Display for StuffItError {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            StuffItError::IoError(kind) => write!(fmt, "IO error: {:?}", kind),
+            StuffItError::InvalidHeader => write!(fmt, "Invalid StuffIt header"),
+            StuffItError::InvalidChecksum(provided, calculated) => write!(
+                fmt,
+                "Invalid checksum (provided: {:04x}, calculated: {:04x})",
+                provided, calculated
+            ),
+            StuffItError::UnsupportedMethod(method) => {
+                write!(fmt, "Unsupported compression method: {:?}", method)
+            }
+            StuffItError::Encrypted => write!(fmt, "Encrypted fork"),
+            StuffItError::CorruptData => write!(fmt, "Corrupt compressed data"),
+        }
+    }
+}
+
+impl From<io::Error> for StuffItError {
+    fn from(error: io::Error) -> Self {
+        StuffItError::IoError(error.kind())
+    }
+}
+
+impl error::Error for StuffItError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::binhex::BinHexArchive;
+    use crate::rsrc::ResourceFork;
+    use std::io::Cursor;
+
+    const RESOURCE_FORK: &[u8] = include_bytes!("../rsrc/string-table.rsrc");
+
+    /// Builds an entry header (and any fork content that follows it).
+    fn entry(
+        methods: (u8, u8),
+        name: &str,
+        forks: (&[u8], &[u8]),
+        compressed: (&[u8], &[u8]),
+    ) -> Vec<u8> {
+        let (resource_fork, data_fork) = forks;
+        let (compressed_resource_fork, compressed_data_fork) = compressed;
+
+        let mut header = vec![0; ENTRY_HEADER_LENGTH];
+        header[0] = methods.0;
+        header[1] = methods.1;
+        header[2] = name.len() as u8;
+        header[3..3 + name.len()].copy_from_slice(name.as_bytes());
+        header[66..70].copy_from_slice(b"TEXT");
+        header[70..74].copy_from_slice(b"ttxt");
+        header[74..76].copy_from_slice(&0x2000u16.to_be_bytes());
+        header[76..80].copy_from_slice(&0xa000_0000u32.to_be_bytes());
+        header[80..84].copy_from_slice(&0xa000_0001u32.to_be_bytes());
+        header[84..88].copy_from_slice(&(resource_fork.len() as u32).to_be_bytes());
+        header[88..92].copy_from_slice(&(data_fork.len() as u32).to_be_bytes());
+        header[92..96].copy_from_slice(&(compressed_resource_fork.len() as u32).to_be_bytes());
+        header[96..100].copy_from_slice(&(compressed_data_fork.len() as u32).to_be_bytes());
+        header[100..102].copy_from_slice(&State::<ARC>::calculate(resource_fork).to_be_bytes());
+        header[102..104].copy_from_slice(&State::<ARC>::calculate(data_fork).to_be_bytes());
+
+        let checksum = State::<ARC>::calculate(&header[..110]);
+        header[110..112].copy_from_slice(&checksum.to_be_bytes());
+
+        header.extend_from_slice(compressed_resource_fork);
+        header.extend_from_slice(compressed_data_fork);
+
+        header
+    }
+
+    fn archive(entries: &[Vec<u8>]) -> Vec<u8> {
+        let mut archive = b"SIT!".to_vec();
+        archive.extend_from_slice(&(entries.len() as u16).to_be_bytes());
+
+        let len = ARCHIVE_HEADER_LENGTH + entries.iter().map(Vec::len).sum::<usize>();
+        archive.extend_from_slice(&(len as u32).to_be_bytes());
+        archive.extend_from_slice(SECOND_SIGNATURE);
+        archive.push(1);
+        archive.resize(ARCHIVE_HEADER_LENGTH, 0);
+
+        for entry in entries {
+            archive.extend_from_slice(entry);
+        }
+
+        archive
+    }
+
+    #[test]
+    fn read_archive() -> Result<(), StuffItError> {
+        let data_fork = b"Hello from the data fork!";
+
+        let bytes = archive(&[
+            entry(
+                (FOLDER_START, FOLDER_START),
+                "Folder",
+                (&[], &[]),
+                (&[], &[]),
+            ),
+            entry(
+                (0, 0),
+                "Inner",
+                (RESOURCE_FORK, data_fork),
+                (RESOURCE_FORK, data_fork),
+            ),
+            entry((FOLDER_END, FOLDER_END), "", (&[], &[]), (&[], &[])),
+            entry((0, 0), "Outer", (&[], data_fork), (&[], data_fork)),
+        ]);
+
+        // Archives needn't start at the beginning of their source
+        let mut source = Cursor::new([b"padding".to_vec(), bytes].concat());
+        source.set_position(7);
+
+        let mut archive = StuffItArchive::new(source)?;
+
+        let paths: Vec<String> = archive
+            .entries()
+            .iter()
+            .map(|entry| entry.path().join("/"))
+            .collect();
+
+        assert_eq!(vec!["Folder", "Folder/Inner", "Outer"], paths);
+        assert!(archive.entries()[0].is_folder());
+
+        let inner = archive.entries()[1].clone();
+
+        assert!(!inner.is_folder());
+        assert_eq!("Inner", inner.name());
+        assert_eq!(*b"TEXT", inner.file_type());
+        assert_eq!(*b"ttxt", inner.creator());
+        assert!(inner.flags().has_bundle());
+        assert_eq!(0xa000_0000, inner.created());
+        assert_eq!(0xa000_0001, inner.modified());
+        assert_eq!(data_fork.len(), inner.data_fork_len());
+        assert_eq!(RESOURCE_FORK.len(), inner.resource_fork_len());
+        assert_eq!(CompressionMethod::None, inner.data_fork_method());
+
+        assert_eq!(data_fork.to_vec(), archive.data_fork(&inner)?);
+
+        let resource_fork = ResourceFork::new(Cursor::new(archive.resource_fork(&inner)?)).unwrap();
+        assert!(resource_fork.resources().count() > 0);
+
+        let outer = archive.entries()[2].clone();
+        assert_eq!(data_fork.to_vec(), archive.data_fork(&outer)?);
+        assert!(archive.resource_fork(&outer)?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_compressed_fork() -> Result<(), StuffItError> {
+        let original = b"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaab";
+
+        let bytes = archive(&[entry(
+            (0, 1),
+            "Compressed",
+            (&[], original),
+            (&[], &[b'a', 0x90, 32, b'b']),
+        )]);
+
+        let mut archive = StuffItArchive::new(Cursor::new(bytes))?;
+        let entry = archive.entries()[0].clone();
+
+        assert_eq!(CompressionMethod::Rle90, entry.data_fork_method());
+        assert_eq!(original.to_vec(), archive.data_fork(&entry)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_fork_checksum() -> Result<(), StuffItError> {
+        let mut bytes = archive(&[entry((0, 0), "File", (&[], b"Hello"), (&[], b"Hello"))]);
+        let len = bytes.len();
+        bytes[len - 1] = b'!';
+
+        let mut archive = StuffItArchive::new(Cursor::new(bytes))?;
+        let entry = archive.entries()[0].clone();
+
+        assert!(matches!(
+            archive.data_fork(&entry),
+            Err(StuffItError::InvalidChecksum(_, _))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_header_checksum() {
+        let mut bytes = archive(&[entry((0, 0), "File", (&[], b"Hello"), (&[], b"Hello"))]);
+        bytes[ARCHIVE_HEADER_LENGTH + 3] = b'f';
+
+        assert!(matches!(
+            StuffItArchive::new(Cursor::new(bytes)),
+            Err(StuffItError::InvalidChecksum(_, _))
+        ));
+    }
+
+    #[test]
+    fn unbalanced_folders() {
+        let bytes = archive(&[entry((FOLDER_END, FOLDER_END), "", (&[], &[]), (&[], &[]))]);
+
+        assert!(matches!(
+            StuffItArchive::new(Cursor::new(bytes)),
+            Err(StuffItError::InvalidHeader)
+        ));
+    }
+
+    #[test]
+    fn unsupported_and_encrypted_forks() -> Result<(), StuffItError> {
+        let bytes = archive(&[entry(
+            (ENCRYPTED_FLAG, 14),
+            "File",
+            (b"Hello", b"Hello"),
+            (b"?????", b"?????"),
+        )]);
+
+        let mut archive = StuffItArchive::new(Cursor::new(bytes))?;
+        let entry = archive.entries()[0].clone();
+
+        assert!(entry.is_encrypted());
+        assert_eq!(Err(StuffItError::Encrypted), archive.resource_fork(&entry));
+        assert_eq!(
+            Err(StuffItError::UnsupportedMethod(
+                CompressionMethod::Installer
+            )),
+            archive.data_fork(&entry)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn not_an_archive() {
+        assert!(matches!(
+            StuffItArchive::new(Cursor::new(vec![0; 64])),
+            Err(StuffItError::InvalidHeader)
+        ));
+    }
+
+    #[test]
+    fn stuffit_expander_installer() -> Result<(), StuffItError> {
+        // The data fork of this BinHex file is an archive made with StuffIt InstallerMaker.
+        let mut binhex = BinHexArchive::new(Cursor::new(
+            &include_bytes!("../../benches/stuffit-expander-4.hqx")[..],
+        ))
+        .unwrap();
+
+        let mut data_fork = vec![];
+        binhex.data_fork().unwrap().read_to_end(&mut data_fork)?;
+
+        let mut archive = StuffItArchive::new(Cursor::new(data_fork))?;
+
+        let expander = archive
+            .entries()
+            .iter()
+            .find(|entry| entry.name() == "StuffIt Expander™")
+            .unwrap()
+            .clone();
+
+        assert_eq!(*b"APPL", expander.file_type());
+        assert_eq!(*b"SITx", expander.creator());
+        assert_eq!(
+            CompressionMethod::Installer,
+            expander.resource_fork_method()
+        );
+        assert_eq!(
+            Err(StuffItError::UnsupportedMethod(
+                CompressionMethod::Installer
+            )),
+            archive.resource_fork(&expander)
+        );
+
+        assert!(archive
+            .entries()
+            .iter()
+            .any(|entry| entry.is_folder() && entry.name() == "Read Us First!"));
+
+        assert!(archive
+            .entries()
+            .iter()
+            .any(|entry| entry.path().len() == 2 && entry.path()[0] == "Read Us First!"));
+
+        Ok(())
+    }
+}
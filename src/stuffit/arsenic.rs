@@ -0,0 +1,712 @@
+//! Decompression for StuffIt's "Arsenic" method (method 15), which was StuffIt 4's default.
+//!
+//! Arsenic is a block-sorting compressor much like bzip2: the input is run-length encoded, split
+//! into blocks, and each block is transformed with the Burrows-Wheeler transform and a move-to-front
+//! transform. Instead of Huffman coding, though, the result is encoded with an adaptive arithmetic
+//! coder.
+//!
+//! Like bzip2, Arsenic can "randomize" blocks that would otherwise sort slowly, and it uses bzip2's
+//! table of pseudo-random intervals to do so. Streams end with a CRC of the uncompressed data.
+
+use super::archive::StuffItError;
+use super::bits::BitReader;
+use crate::util::output_capacity;
+
+const NUM_BITS: u32 = 26;
+const ONE: u32 = 1 << (NUM_BITS - 1);
+const HALF: u32 = 1 << (NUM_BITS - 2);
+
+const SELECTOR_END_OF_BLOCK: u32 = 10;
+
+/// The intervals (in bytes) between the bits flipped in randomized blocks, from bzip2.
+const RANDOMIZATION_TABLE: [u16; 512] = [
+    619, 720, 127, 481, 931, 816, 813, 233, 566, 247, 985, 724, 205, 454, 863, 491, 741, 242, 949,
+    214, 733, 859, 335, 708, 621, 574, 73, 654, 730, 472, 419, 436, 278, 496, 867, 210, 399, 680,
+    480, 51, 878, 465, 811, 169, 869, 675, 611, 697, 867, 561, 862, 687, 507, 283, 482, 129, 807,
+    591, 733, 623, 150, 238, 59, 379, 684, 877, 625, 169, 643, 105, 170, 607, 520, 932, 727, 476,
+    693, 425, 174, 647, 73, 122, 335, 530, 442, 853, 695, 249, 445, 515, 909, 545, 703, 919, 874,
+    474, 882, 500, 594, 612, 641, 801, 220, 162, 819, 984, 589, 513, 495, 799, 161, 604, 958, 533,
+    221, 400, 386, 867, 600, 782, 382, 596, 414, 171, 516, 375, 682, 485, 911, 276, 98, 553, 163,
+    354, 666, 933, 424, 341, 533, 870, 227, 730, 475, 186, 263, 647, 537, 686, 600, 224, 469, 68,
+    770, 919, 190, 373, 294, 822, 808, 206, 184, 943, 795, 384, 383, 461, 404, 758, 839, 887, 715,
+    67, 618, 276, 204, 918, 873, 777, 604, 560, 951, 160, 578, 722, 79, 804, 96, 409, 713, 940,
+    652, 934, 970, 447, 318, 353, 859, 672, 112, 785, 645, 863, 803, 350, 139, 93, 354, 99, 820,
+    908, 609, 772, 154, 274, 580, 184, 79, 626, 630, 742, 653, 282, 762, 623, 680, 81, 927, 626,
+    789, 125, 411, 521, 938, 300, 821, 78, 343, 175, 128, 250, 170, 774, 972, 275, 999, 639, 495,
+    78, 352, 126, 857, 956, 358, 619, 580, 124, 737, 594, 701, 612, 669, 112, 134, 694, 363, 992,
+    809, 743, 168, 974, 944, 375, 748, 52, 600, 747, 642, 182, 862, 81, 344, 805, 988, 739, 511,
+    655, 814, 334, 249, 515, 897, 955, 664, 981, 649, 113, 974, 459, 893, 228, 433, 837, 553, 268,
+    926, 240, 102, 654, 459, 51, 686, 754, 806, 760, 493, 403, 415, 394, 687, 700, 946, 670, 656,
+    610, 738, 392, 760, 799, 887, 653, 978, 321, 576, 617, 626, 502, 894, 679, 243, 440, 680, 879,
+    194, 572, 640, 724, 926, 56, 204, 700, 707, 151, 457, 449, 797, 195, 791, 558, 945, 679, 297,
+    59, 87, 824, 713, 663, 412, 693, 342, 606, 134, 108, 571, 364, 631, 212, 174, 643, 304, 329,
+    343, 97, 430, 751, 497, 314, 983, 374, 822, 928, 140, 206, 73, 263, 980, 736, 876, 478, 430,
+    305, 170, 514, 364, 692, 829, 82, 855, 953, 676, 246, 369, 970, 294, 750, 807, 827, 150, 790,
+    288, 923, 804, 378, 215, 828, 592, 281, 565, 555, 710, 82, 896, 831, 547, 261, 524, 462, 293,
+    465, 502, 56, 661, 821, 976, 991, 658, 869, 905, 758, 745, 193, 768, 550, 608, 933, 378, 286,
+    215, 979, 792, 961, 61, 688, 793, 644, 986, 403, 106, 366, 905, 644, 372, 567, 466, 434, 645,
+    210, 389, 550, 919, 135, 780, 773, 635, 389, 707, 100, 626, 958, 165, 504, 920, 176, 193, 713,
+    857, 265, 203, 50, 668, 108, 645, 990, 626, 197, 510, 357, 358, 850, 858, 364, 936, 638,
+];
+
+/// Produces the sequence of bit masks applied to the bytes of a randomized block.
+struct Randomizer {
+    table_index: usize,
+    countdown: u16,
+}
+
+impl Randomizer {
+    fn new() -> Self {
+        Randomizer {
+            table_index: 0,
+            countdown: 0,
+        }
+    }
+
+    fn next_mask(&mut self) -> u8 {
+        if self.countdown == 0 {
+            self.countdown = RANDOMIZATION_TABLE[self.table_index];
+            self.table_index = (self.table_index + 1) % RANDOMIZATION_TABLE.len();
+        }
+
+        self.countdown -= 1;
+
+        (self.countdown == 1) as u8
+    }
+}
+
+/// An adaptive frequency model for a contiguous range of symbols.
+struct Model {
+    first_symbol: u32,
+    frequencies: Vec<u32>,
+    total_frequency: u32,
+    increment: u32,
+    limit: u32,
+}
+
+impl Model {
+    fn new(first_symbol: u32, last_symbol: u32, increment: u32, limit: u32) -> Self {
+        let mut model = Model {
+            first_symbol,
+            frequencies: vec![0; (last_symbol - first_symbol + 1) as usize],
+            total_frequency: 0,
+            increment,
+            limit,
+        };
+
+        model.reset();
+        model
+    }
+
+    fn reset(&mut self) {
+        for frequency in self.frequencies.iter_mut() {
+            *frequency = self.increment;
+        }
+
+        self.total_frequency = self.increment * self.frequencies.len() as u32;
+    }
+
+    fn increase_frequency(&mut self, index: usize) {
+        self.frequencies[index] += self.increment;
+        self.total_frequency += self.increment;
+
+        if self.total_frequency > self.limit {
+            self.total_frequency = 0;
+
+            for frequency in self.frequencies.iter_mut() {
+                *frequency = (*frequency + 1) >> 1;
+                self.total_frequency += *frequency;
+            }
+        }
+    }
+
+    /// Returns the index of the symbol whose cumulative frequency range contains the given value,
+    /// along with the symbol's cumulative frequency.
+    fn find(&self, value: u32) -> (usize, u32) {
+        let mut cumulative_frequency = 0;
+
+        for (index, frequency) in self.frequencies.iter().enumerate() {
+            if index == self.frequencies.len() - 1 || cumulative_frequency + frequency > value {
+                return (index, cumulative_frequency);
+            }
+
+            cumulative_frequency += frequency;
+        }
+
+        unreachable!("Models always have at least one symbol")
+    }
+}
+
+/// The models used to decode (or encode) an Arsenic stream.
+struct Models {
+    /// Used for the stream header, block headers, and end-of-stream flags.
+    initial: Model,
+
+    /// Used to select how the next move-to-front index is encoded.
+    selector: Model,
+
+    /// Used for move-to-front indices in the ranges 2-3, 4-7, ..., 128-255.
+    mtf: Vec<Model>,
+}
+
+impl Models {
+    fn new() -> Self {
+        Models {
+            initial: Model::new(0, 1, 1, 256),
+            selector: Model::new(0, 10, 8, 1024),
+            mtf: vec![
+                Model::new(2, 3, 8, 1024),
+                Model::new(4, 7, 4, 1024),
+                Model::new(8, 15, 4, 1024),
+                Model::new(16, 31, 4, 1024),
+                Model::new(32, 63, 2, 1024),
+                Model::new(64, 127, 2, 1024),
+                Model::new(128, 255, 1, 1024),
+            ],
+        }
+    }
+
+    fn reset_block_models(&mut self) {
+        self.selector.reset();
+        self.mtf.iter_mut().for_each(Model::reset);
+    }
+}
+
+struct ArithmeticDecoder<'a> {
+    bits: BitReader<'a>,
+
+    /// The decoder reads ahead, but never this far past the end of the data.
+    bit_limit: usize,
+
+    range: u32,
+    code: u32,
+}
+
+impl<'a> ArithmeticDecoder<'a> {
+    fn new(compressed: &'a [u8]) -> Self {
+        let mut bits = BitReader::msb_first(compressed);
+        let code = bits.read_bits_or_zero(NUM_BITS);
+
+        ArithmeticDecoder {
+            bits,
+            bit_limit: (compressed.len() * 8) + (NUM_BITS as usize * 2),
+            range: ONE,
+            code,
+        }
+    }
+
+    fn next_symbol(&mut self, model: &mut Model) -> Result<u32, StuffItError> {
+        if self.bits.position() > self.bit_limit {
+            return Err(StuffItError::CorruptData);
+        }
+
+        let scale = self.range / model.total_frequency;
+        let (index, cumulative_frequency) = model.find(self.code / scale);
+        let frequency = model.frequencies[index];
+
+        let low_increment = scale * cumulative_frequency;
+        self.code = self
+            .code
+            .checked_sub(low_increment)
+            .ok_or(StuffItError::CorruptData)?;
+
+        if cumulative_frequency + frequency == model.total_frequency {
+            self.range -= low_increment;
+        } else {
+            self.range = frequency * scale;
+        }
+
+        while self.range <= HALF {
+            self.range <<= 1;
+            self.code = (self.code << 1) | self.bits.read_bit_or_zero();
+        }
+
+        model.increase_frequency(index);
+
+        Ok(model.first_symbol + index as u32)
+    }
+
+    /// Reads a value of the given number of bits, least significant bit first.
+    fn next_bits(&mut self, model: &mut Model, count: u32) -> Result<u32, StuffItError> {
+        let mut value = 0;
+
+        for i in 0..count {
+            value |= self.next_symbol(model)? << i;
+        }
+
+        Ok(value)
+    }
+}
+
+pub(super) fn decompress(compressed: &[u8], len: usize) -> Result<Vec<u8>, StuffItError> {
+    let mut decoder = ArithmeticDecoder::new(compressed);
+    let mut models = Models::new();
+
+    if decoder.next_bits(&mut models.initial, 8)? != b'A' as u32
+        || decoder.next_bits(&mut models.initial, 8)? != b's' as u32
+    {
+        return Err(StuffItError::CorruptData);
+    }
+
+    let block_bits = decoder.next_bits(&mut models.initial, 4)? + 9;
+    let block_size = 1 << block_bits;
+
+    let mut end_of_blocks = decoder.next_symbol(&mut models.initial)? == 1;
+
+    let mut output = Vec::with_capacity(output_capacity(len, compressed.len()));
+    let mut block = Vec::new();
+
+    let mut provided_crc = None;
+
+    while !end_of_blocks {
+        let randomized = decoder.next_symbol(&mut models.initial)? == 1;
+        let primary_index = decoder.next_bits(&mut models.initial, block_bits)? as usize;
+
+        read_block(&mut decoder, &mut models, &mut block, block_size)?;
+
+        models.reset_block_models();
+
+        if decoder.next_symbol(&mut models.initial)? == 1 {
+            provided_crc = Some(decoder.next_bits(&mut models.initial, 32)?);
+            end_of_blocks = true;
+        }
+
+        if primary_index >= block.len() {
+            return Err(StuffItError::CorruptData);
+        }
+
+        unsort_block(&block, primary_index, randomized, &mut output, len)?;
+    }
+
+    if let Some(provided_crc) = provided_crc {
+        let calculated_crc = crc(&output);
+
+        if provided_crc != calculated_crc {
+            return Err(StuffItError::InvalidChecksum(provided_crc, calculated_crc));
+        }
+    }
+
+    Ok(output)
+}
+
+/// Calculates the CRC that ends an Arsenic stream, which uses bzip2's variant of CRC-32 (with bits
+/// processed most significant first).
+fn crc(data: &[u8]) -> u32 {
+    !data.iter().fold(!0, |crc, byte| {
+        (0..8).fold(crc ^ ((*byte as u32) << 24), |crc, _| {
+            if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ 0x04c1_1db7
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
+/// Reads a block of move-to-front-encoded bytes, undoing the move-to-front transform as it goes.
+fn read_block(
+    decoder: &mut ArithmeticDecoder,
+    models: &mut Models,
+    block: &mut Vec<u8>,
+    block_size: usize,
+) -> Result<(), StuffItError> {
+    let mut mtf: Vec<u8> = (0..=255).collect();
+    block.clear();
+
+    let mut selector = decoder.next_symbol(&mut models.selector)?;
+
+    loop {
+        if selector < 2 {
+            // Runs of zeroes are written as a sequence of "digits" in bijective base 2.
+            let mut run_length = 0;
+            let mut digit_value = 1;
+
+            while selector < 2 {
+                run_length += (selector as usize + 1) * digit_value;
+                digit_value *= 2;
+
+                if run_length > block_size {
+                    return Err(StuffItError::CorruptData);
+                }
+
+                selector = decoder.next_symbol(&mut models.selector)?;
+            }
+
+            if block.len() + run_length > block_size {
+                return Err(StuffItError::CorruptData);
+            }
+
+            block.resize(block.len() + run_length, mtf[0]);
+        }
+
+        let index = match selector {
+            SELECTOR_END_OF_BLOCK => return Ok(()),
+            2 => 1,
+            _ => decoder.next_symbol(&mut models.mtf[selector as usize - 3])? as usize,
+        };
+
+        if block.len() >= block_size {
+            return Err(StuffItError::CorruptData);
+        }
+
+        let byte = mtf.remove(index);
+        mtf.insert(0, byte);
+        block.push(byte);
+
+        selector = decoder.next_symbol(&mut models.selector)?;
+    }
+}
+
+/// Reverses the Burrows-Wheeler transform of the given block, undoes randomization if the block was
+/// randomized, and expands runs, appending the result to the given output buffer.
+fn unsort_block(
+    block: &[u8],
+    primary_index: usize,
+    randomized: bool,
+    output: &mut Vec<u8>,
+    len: usize,
+) -> Result<(), StuffItError> {
+    let mut counts = [0usize; 256];
+
+    for byte in block {
+        counts[*byte as usize] += 1;
+    }
+
+    let mut starts = [0usize; 256];
+    let mut total = 0;
+
+    for (start, count) in starts.iter_mut().zip(counts.iter()) {
+        *start = total;
+        total += count;
+    }
+
+    let mut transform = vec![0; block.len()];
+
+    for (i, byte) in block.iter().enumerate() {
+        transform[starts[*byte as usize]] = i;
+        starts[*byte as usize] += 1;
+    }
+
+    // After four identical bytes, the next byte is a count of additional repetitions.
+    let mut index = primary_index;
+    let mut last = None;
+    let mut run_length = 0;
+    let mut randomizer = Randomizer::new();
+
+    for _ in 0..block.len() {
+        index = transform[index];
+        let mut byte = block[index];
+
+        if randomized {
+            byte ^= randomizer.next_mask();
+        }
+
+        if run_length == 4 {
+            output.resize(output.len() + byte as usize, last.unwrap_or(0));
+            run_length = 0;
+        } else {
+            if last == Some(byte) {
+                run_length += 1;
+            } else {
+                last = Some(byte);
+                run_length = 1;
+            }
+
+            output.push(byte);
+        }
+
+        if output.len() > len {
+            return Err(StuffItError::CorruptData);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct ArithmeticEncoder {
+        low: u32,
+        range: u32,
+        bits: Vec<u8>,
+    }
+
+    impl ArithmeticEncoder {
+        fn new() -> Self {
+            ArithmeticEncoder {
+                low: 0,
+                range: ONE,
+                bits: vec![],
+            }
+        }
+
+        fn encode_symbol(&mut self, model: &mut Model, symbol: u32) {
+            let index = (symbol - model.first_symbol) as usize;
+            let cumulative_frequency: u32 = model.frequencies[..index].iter().sum();
+            let frequency = model.frequencies[index];
+
+            let scale = self.range / model.total_frequency;
+            let low_increment = scale * cumulative_frequency;
+
+            self.low += low_increment;
+
+            if cumulative_frequency + frequency == model.total_frequency {
+                self.range -= low_increment;
+            } else {
+                self.range = frequency * scale;
+            }
+
+            if self.low >= 1 << NUM_BITS {
+                self.low -= 1 << NUM_BITS;
+
+                let carry = self.bits.iter().rposition(|bit| *bit == 0).unwrap();
+                self.bits[carry] = 1;
+                self.bits[carry + 1..].iter_mut().for_each(|bit| *bit = 0);
+            }
+
+            while self.range <= HALF {
+                self.bits.push(((self.low >> (NUM_BITS - 1)) & 1) as u8);
+                self.low = (self.low << 1) & ((1 << NUM_BITS) - 1);
+                self.range <<= 1;
+            }
+
+            model.increase_frequency(index);
+        }
+
+        fn encode_bits(&mut self, model: &mut Model, value: u32, count: u32) {
+            for i in 0..count {
+                self.encode_symbol(model, (value >> i) & 1);
+            }
+        }
+
+        fn finish(mut self) -> Vec<u8> {
+            for i in (0..NUM_BITS).rev() {
+                self.bits.push(((self.low >> i) & 1) as u8);
+            }
+
+            self.bits
+                .chunks(8)
+                .map(|chunk| {
+                    chunk
+                        .iter()
+                        .enumerate()
+                        .fold(0, |byte, (i, bit)| byte | (bit << (7 - i)))
+                })
+                .collect()
+        }
+    }
+
+    /// Run-length encodes the given data, then splits it into blocks without splitting any runs.
+    fn run_length_encode(data: &[u8], block_size: usize) -> Vec<Vec<u8>> {
+        let mut blocks: Vec<Vec<u8>> = vec![];
+        let mut i = 0;
+
+        while i < data.len() {
+            let run = data[i..]
+                .iter()
+                .take_while(|byte| **byte == data[i])
+                .take(4 + 255)
+                .count();
+
+            let unit = if run >= 4 {
+                let mut unit = vec![data[i]; 4];
+                unit.push((run - 4) as u8);
+                unit
+            } else {
+                vec![data[i]; run]
+            };
+
+            match blocks.last_mut() {
+                Some(block) if block.len() + unit.len() <= block_size => block.extend(unit),
+                _ => blocks.push(unit),
+            }
+
+            i += run;
+        }
+
+        blocks
+    }
+
+    fn flush_zero_run(encoder: &mut ArithmeticEncoder, models: &mut Models, zero_run: &mut usize) {
+        while *zero_run > 0 {
+            if *zero_run % 2 == 1 {
+                encoder.encode_symbol(&mut models.selector, 0);
+                *zero_run = (*zero_run - 1) / 2;
+            } else {
+                encoder.encode_symbol(&mut models.selector, 1);
+                *zero_run = (*zero_run - 2) / 2;
+            }
+        }
+    }
+
+    fn encode_block(
+        encoder: &mut ArithmeticEncoder,
+        models: &mut Models,
+        block: &[u8],
+        block_bits: u32,
+        randomized: bool,
+    ) {
+        let mut block = block.to_vec();
+
+        if randomized {
+            let mut randomizer = Randomizer::new();
+            block
+                .iter_mut()
+                .for_each(|byte| *byte ^= randomizer.next_mask());
+        }
+
+        let n = block.len();
+        let mut rotations: Vec<usize> = (0..n).collect();
+        rotations.sort_by(|a, b| {
+            (0..n)
+                .map(|i| block[(a + i) % n])
+                .cmp((0..n).map(|i| block[(b + i) % n]))
+        });
+
+        let primary_index = rotations.iter().position(|r| *r == 0).unwrap();
+
+        encoder.encode_symbol(&mut models.initial, randomized as u32);
+        encoder.encode_bits(&mut models.initial, primary_index as u32, block_bits);
+
+        let mut mtf: Vec<u8> = (0..=255).collect();
+        let mut zero_run = 0;
+
+        for rotation in rotations {
+            let byte = block[(rotation + n - 1) % n];
+            let index = mtf.iter().position(|b| *b == byte).unwrap();
+            mtf.remove(index);
+            mtf.insert(0, byte);
+
+            if index == 0 {
+                zero_run += 1;
+                continue;
+            }
+
+            flush_zero_run(encoder, models, &mut zero_run);
+
+            if index == 1 {
+                encoder.encode_symbol(&mut models.selector, 2);
+            } else {
+                let model = (usize::BITS - index.leading_zeros()) as usize - 2;
+
+                encoder.encode_symbol(&mut models.selector, model as u32 + 3);
+                encoder.encode_symbol(&mut models.mtf[model], index as u32);
+            }
+        }
+
+        flush_zero_run(encoder, models, &mut zero_run);
+        encoder.encode_symbol(&mut models.selector, SELECTOR_END_OF_BLOCK);
+        models.reset_block_models();
+    }
+
+    fn compress(data: &[u8], block_bits: u32, randomized: bool) -> Vec<u8> {
+        let mut encoder = ArithmeticEncoder::new();
+        let mut models = Models::new();
+
+        encoder.encode_bits(&mut models.initial, b'A' as u32, 8);
+        encoder.encode_bits(&mut models.initial, b's' as u32, 8);
+        encoder.encode_bits(&mut models.initial, block_bits - 9, 4);
+
+        let blocks = run_length_encode(data, 1 << block_bits);
+
+        encoder.encode_symbol(&mut models.initial, blocks.is_empty() as u32);
+
+        for (i, block) in blocks.iter().enumerate() {
+            encode_block(&mut encoder, &mut models, block, block_bits, randomized);
+
+            if i == blocks.len() - 1 {
+                encoder.encode_symbol(&mut models.initial, 1);
+                encoder.encode_bits(&mut models.initial, crc(data), 32);
+            } else {
+                encoder.encode_symbol(&mut models.initial, 0);
+            }
+        }
+
+        encoder.finish()
+    }
+
+    #[test]
+    fn decompress_arsenic() -> Result<(), StuffItError> {
+        let mut original = b"Arsenic and old lace; ".repeat(20);
+        original.extend(vec![0; 700]);
+
+        let mut state = 3u32;
+        for _ in 0..1500 {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            original.push((state >> 16) as u8);
+        }
+
+        original.extend(b"xxxxyyyyy".iter());
+
+        assert_eq!(
+            original,
+            decompress(&compress(&original, 9, false), original.len())?
+        );
+        assert_eq!(
+            original,
+            decompress(&compress(&original, 12, false), original.len())?
+        );
+
+        // Randomization only changes blocks longer than the first interval in the table
+        assert!(original.len() > RANDOMIZATION_TABLE[0] as usize);
+        assert_ne!(
+            compress(&original, 12, false),
+            compress(&original, 12, true)
+        );
+        assert_eq!(
+            original,
+            decompress(&compress(&original, 12, true), original.len())?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn crc_check_value() {
+        assert_eq!(0xfc89_1918, crc(b"123456789"));
+    }
+
+    #[test]
+    fn decompress_empty() -> Result<(), StuffItError> {
+        assert_eq!(Vec::<u8>::new(), decompress(&compress(&[], 9, false), 0)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn decompress_corrupt() {
+        assert_eq!(
+            Err(StuffItError::CorruptData),
+            decompress(b"Not an arsenic stream", 16)
+        );
+
+        // A stream whose CRC doesn't match its content
+        let original = b"Arsenic and old lace".repeat(4);
+
+        let mut models = Models::new();
+        let mut encoder = ArithmeticEncoder::new();
+        encoder.encode_bits(&mut models.initial, b'A' as u32, 8);
+        encoder.encode_bits(&mut models.initial, b's' as u32, 8);
+        encoder.encode_bits(&mut models.initial, 0, 4);
+        encoder.encode_symbol(&mut models.initial, 0);
+        encode_block(
+            &mut encoder,
+            &mut models,
+            &run_length_encode(&original, 512)[0],
+            9,
+            false,
+        );
+        encoder.encode_symbol(&mut models.initial, 1);
+        encoder.encode_bits(&mut models.initial, !crc(&original), 32);
+
+        assert_eq!(
+            Err(StuffItError::InvalidChecksum(
+                !crc(&original),
+                crc(&original)
+            )),
+            decompress(&encoder.finish(), original.len())
+        );
+    }
+}
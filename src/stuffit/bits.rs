@@ -0,0 +1,129 @@
+use super::archive::StuffItError;
+
+/// Reads individual bits and multi-bit values from a byte slice.
+///
+/// StuffIt's compression methods disagree about bit order: some read the most significant bit of
+/// each byte first, and others read the least significant bit first. Multi-bit values are assembled
+/// in the same order in which their bits are read (i.e. the first bit read is the most significant
+/// bit of an MSB-first value and the least significant bit of an LSB-first value).
+pub(crate) struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    lsb_first: bool,
+}
+
+impl<'a> BitReader<'a> {
+    /// Creates a reader that reads the most significant bit of each byte first.
+    pub fn msb_first(data: &'a [u8]) -> Self {
+        BitReader {
+            data,
+            position: 0,
+            lsb_first: false,
+        }
+    }
+
+    /// Creates a reader that reads the least significant bit of each byte first.
+    pub fn lsb_first(data: &'a [u8]) -> Self {
+        BitReader {
+            data,
+            position: 0,
+            lsb_first: true,
+        }
+    }
+
+    /// Returns the number of bits read so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Moves to the given bit position.
+    pub fn set_position(&mut self, position: usize) {
+        self.position = position;
+    }
+
+    /// Returns the number of bits remaining in the underlying data.
+    pub fn remaining(&self) -> usize {
+        (self.data.len() * 8).saturating_sub(self.position)
+    }
+
+    /// Reads a single bit, returning an error if no bits remain.
+    pub fn read_bit(&mut self) -> Result<u32, StuffItError> {
+        if self.remaining() == 0 {
+            return Err(StuffItError::CorruptData);
+        }
+
+        Ok(self.read_bit_or_zero())
+    }
+
+    /// Reads a single bit, treating any bits past the end of the data as zeroes.
+    pub fn read_bit_or_zero(&mut self) -> u32 {
+        let byte = match self.data.get(self.position / 8) {
+            Some(byte) => *byte,
+            None => {
+                self.position += 1;
+                return 0;
+            }
+        };
+
+        let shift = match self.lsb_first {
+            true => self.position % 8,
+            false => 7 - (self.position % 8),
+        };
+
+        self.position += 1;
+
+        ((byte >> shift) & 1) as u32
+    }
+
+    /// Reads a value of up to 32 bits, returning an error if not enough bits remain.
+    pub fn read_bits(&mut self, count: u32) -> Result<u32, StuffItError> {
+        if self.remaining() < count as usize {
+            return Err(StuffItError::CorruptData);
+        }
+
+        Ok(self.read_bits_or_zero(count))
+    }
+
+    /// Reads a value of up to 32 bits, treating any bits past the end of the data as zeroes.
+    pub fn read_bits_or_zero(&mut self, count: u32) -> u32 {
+        let mut value = 0;
+
+        for i in 0..count {
+            let bit = self.read_bit_or_zero();
+
+            if self.lsb_first {
+                value |= bit << i;
+            } else {
+                value = (value << 1) | bit;
+            }
+        }
+
+        value
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bit_order() -> Result<(), StuffItError> {
+        let data = [0b1011_0001, 0b0100_0000];
+
+        let mut msb = BitReader::msb_first(&data);
+        assert_eq!(1, msb.read_bit()?);
+        assert_eq!(0b011, msb.read_bits(3)?);
+        assert_eq!(0b00_0101, msb.read_bits(6)?);
+
+        let mut lsb = BitReader::lsb_first(&data);
+        assert_eq!(1, lsb.read_bit()?);
+        assert_eq!(0b000, lsb.read_bits(3)?);
+        assert_eq!(0b00_1011, lsb.read_bits(6)?);
+
+        assert_eq!(6, lsb.remaining());
+        assert!(lsb.read_bits(7).is_err());
+        assert_eq!(0b01_0000, lsb.read_bits_or_zero(7));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,140 @@
+use super::method::CompressionMethod;
+use crate::finder::FinderFlags;
+
+/// The location and encoding of one fork of an entry in a StuffIt archive.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(super) struct ForkInfo {
+    pub method: CompressionMethod,
+    pub encrypted: bool,
+    pub offset: u64,
+    pub len: u32,
+    pub compressed_len: u32,
+    pub crc: u16,
+}
+
+impl ForkInfo {
+    /// Returns the description of an empty, uncompressed fork.
+    pub fn empty() -> Self {
+        ForkInfo {
+            method: CompressionMethod::None,
+            encrypted: false,
+            offset: 0,
+            len: 0,
+            compressed_len: 0,
+            crc: 0,
+        }
+    }
+}
+
+/// A file or folder in a classic StuffIt or StuffIt 5 archive.
+///
+/// Entries for files carry the file's Finder metadata and describe its forks, which may be read
+/// with the `data_fork` and `resource_fork` methods of the archive that produced the entry (i.e.
+/// [`super::StuffItArchive`] or [`super::StuffIt5Archive`]).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StuffItEntry {
+    path: Vec<String>,
+    is_folder: bool,
+    file_type: [u8; 4],
+    creator: [u8; 4],
+    flags: FinderFlags,
+    created: u32,
+    modified: u32,
+    pub(super) data_fork: ForkInfo,
+    pub(super) resource_fork: ForkInfo,
+}
+
+impl StuffItEntry {
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn new(
+        path: Vec<String>,
+        is_folder: bool,
+        file_type: [u8; 4],
+        creator: [u8; 4],
+        flags: FinderFlags,
+        created: u32,
+        modified: u32,
+        data_fork: ForkInfo,
+        resource_fork: ForkInfo,
+    ) -> Self {
+        StuffItEntry {
+            path,
+            is_folder,
+            file_type,
+            creator,
+            flags,
+            created,
+            modified,
+            data_fork,
+            resource_fork,
+        }
+    }
+
+    /// Returns the names of the folders that contain this entry, starting with the outermost
+    /// folder, followed by the name of this entry itself.
+    pub fn path(&self) -> &[String] {
+        &self.path
+    }
+
+    /// Returns the name of this entry.
+    pub fn name(&self) -> &String {
+        // Paths always contain at least the entry's own name
+        self.path.last().unwrap()
+    }
+
+    /// Returns `true` if this entry is a folder rather than a file.
+    pub fn is_folder(&self) -> bool {
+        self.is_folder
+    }
+
+    /// Returns this entry's file type identifier.
+    pub fn file_type(&self) -> [u8; 4] {
+        self.file_type
+    }
+
+    /// Returns this entry's creator identifier.
+    pub fn creator(&self) -> [u8; 4] {
+        self.creator
+    }
+
+    /// Returns this entry's Finder flags.
+    pub fn flags(&self) -> FinderFlags {
+        self.flags
+    }
+
+    /// Returns this entry's creation date, expressed as seconds since midnight, January 1, 1904.
+    pub fn created(&self) -> u32 {
+        self.created
+    }
+
+    /// Returns this entry's modification date, expressed as seconds since midnight, January 1,
+    /// 1904.
+    pub fn modified(&self) -> u32 {
+        self.modified
+    }
+
+    /// Returns the length, in bytes, of this entry's uncompressed data fork.
+    pub fn data_fork_len(&self) -> usize {
+        self.data_fork.len as usize
+    }
+
+    /// Returns the length, in bytes, of this entry's uncompressed resource fork.
+    pub fn resource_fork_len(&self) -> usize {
+        self.resource_fork.len as usize
+    }
+
+    /// Returns the method used to compress this entry's data fork.
+    pub fn data_fork_method(&self) -> CompressionMethod {
+        self.data_fork.method
+    }
+
+    /// Returns the method used to compress this entry's resource fork.
+    pub fn resource_fork_method(&self) -> CompressionMethod {
+        self.resource_fork.method
+    }
+
+    /// Returns `true` if either of this entry's forks is encrypted.
+    pub fn is_encrypted(&self) -> bool {
+        self.data_fork.encrypted || self.resource_fork.encrypted
+    }
+}
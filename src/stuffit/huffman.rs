@@ -0,0 +1,110 @@
+//! Decompression for StuffIt's Huffman method, which stores a static Huffman tree at the start of
+//! the compressed fork.
+
+use super::archive::StuffItError;
+use super::bits::BitReader;
+use crate::util::output_capacity;
+
+/// The deepest tree that can be built from 256 leaves.
+const MAX_DEPTH: usize = 256;
+
+enum Node {
+    Leaf(u8),
+    Branch(usize, usize),
+}
+
+pub(super) fn decompress(compressed: &[u8], len: usize) -> Result<Vec<u8>, StuffItError> {
+    let mut bits = BitReader::msb_first(compressed);
+    let mut nodes = Vec::new();
+    let root = read_node(&mut bits, &mut nodes, 0)?;
+
+    let mut output = Vec::with_capacity(output_capacity(len, compressed.len()));
+
+    while output.len() < len {
+        let mut node = root;
+
+        loop {
+            match nodes[node] {
+                Node::Leaf(byte) => {
+                    output.push(byte);
+                    break;
+                }
+                Node::Branch(zero, one) => {
+                    node = match bits.read_bit()? {
+                        0 => zero,
+                        _ => one,
+                    };
+                }
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+/// Reads a (sub)tree, which is stored as a `1` bit followed by an eight-bit byte for leaves, or
+/// as a `0` bit followed by the "zero" and "one" subtrees for branches.
+fn read_node(
+    bits: &mut BitReader,
+    nodes: &mut Vec<Node>,
+    depth: usize,
+) -> Result<usize, StuffItError> {
+    if depth > MAX_DEPTH {
+        return Err(StuffItError::CorruptData);
+    }
+
+    let node = match bits.read_bit()? {
+        1 => Node::Leaf(bits.read_bits(8)? as u8),
+        _ => {
+            let zero = read_node(bits, nodes, depth + 1)?;
+            let one = read_node(bits, nodes, depth + 1)?;
+
+            Node::Branch(zero, one)
+        }
+    };
+
+    nodes.push(node);
+
+    Ok(nodes.len() - 1)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decompress_huffman() -> Result<(), StuffItError> {
+        // A tree with "a" => 0, "b" => 10, and "c" => 11, followed by "abacab"
+        let tree = "0 1 01100001 0 1 01100010 1 01100011";
+        let data = "0 10 0 11 0 10";
+
+        let bits: Vec<u8> = format!("{}{}", tree, data)
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| (c == '1') as u8)
+            .collect();
+
+        let compressed: Vec<u8> = bits
+            .chunks(8)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .fold(0, |byte, (i, bit)| byte | (bit << (7 - i)))
+            })
+            .collect();
+
+        assert_eq!(b"abacab".to_vec(), decompress(&compressed, 6)?);
+        assert_eq!(Err(StuffItError::CorruptData), decompress(&compressed, 16));
+
+        Ok(())
+    }
+
+    #[test]
+    fn decompress_single_leaf() -> Result<(), StuffItError> {
+        // A degenerate tree with a single leaf consumes no bits per byte.
+        assert_eq!(vec![0xa5; 4], decompress(&[0b1101_0010, 0b1000_0000], 4)?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,378 @@
+//! Decompression for StuffIt's LZAH method, which is Haruyasu Yoshizaki's LZHUF: LZ77 with a
+//! 4 KiB sliding window, an adaptive Huffman code for literals and match lengths, and a static
+//! code for the upper bits of match positions.
+
+use super::archive::StuffItError;
+use super::bits::BitReader;
+use crate::util::output_capacity;
+
+const WINDOW_SIZE: usize = 4096;
+const MAX_MATCH: usize = 60;
+const THRESHOLD: usize = 2;
+
+/// The number of symbols in the adaptive code: 256 literals plus one for each match length.
+const SYMBOL_COUNT: usize = 256 - THRESHOLD + MAX_MATCH;
+const TABLE_SIZE: usize = SYMBOL_COUNT * 2 - 1;
+const ROOT: usize = TABLE_SIZE - 1;
+const MAX_FREQUENCY: u16 = 0x8000;
+
+/// An adaptive Huffman code whose nodes are kept sorted by frequency so that the tree can be
+/// rebalanced by swapping nodes as frequencies change.
+pub(super) struct AdaptiveHuffman {
+    frequencies: [u16; TABLE_SIZE + 1],
+
+    /// The parent of each node; entries from `TABLE_SIZE` onward hold the parents of leaves.
+    parents: [usize; TABLE_SIZE + SYMBOL_COUNT],
+
+    /// The first child of each node (the second child is always the next node), or the leaf's
+    /// symbol plus `TABLE_SIZE` for leaves.
+    children: [usize; TABLE_SIZE],
+}
+
+impl AdaptiveHuffman {
+    pub fn new() -> Self {
+        let mut tree = AdaptiveHuffman {
+            frequencies: [0; TABLE_SIZE + 1],
+            parents: [0; TABLE_SIZE + SYMBOL_COUNT],
+            children: [0; TABLE_SIZE],
+        };
+
+        for i in 0..SYMBOL_COUNT {
+            tree.frequencies[i] = 1;
+            tree.children[i] = i + TABLE_SIZE;
+            tree.parents[i + TABLE_SIZE] = i;
+        }
+
+        let mut i = 0;
+
+        for j in SYMBOL_COUNT..TABLE_SIZE {
+            tree.frequencies[j] = tree.frequencies[i] + tree.frequencies[i + 1];
+            tree.children[j] = i;
+            tree.parents[i] = j;
+            tree.parents[i + 1] = j;
+
+            i += 2;
+        }
+
+        tree.frequencies[TABLE_SIZE] = 0xffff;
+        tree.parents[ROOT] = 0;
+
+        tree
+    }
+
+    fn decode(&mut self, bits: &mut BitReader) -> usize {
+        let mut node = self.children[ROOT];
+
+        while node < TABLE_SIZE {
+            node = self.children[node + bits.read_bit_or_zero() as usize];
+        }
+
+        let symbol = node - TABLE_SIZE;
+        self.update(symbol);
+
+        symbol
+    }
+
+    /// Returns the code for the given symbol as a sequence of bits, starting from the root.
+    #[cfg(test)]
+    pub fn code(&self, symbol: usize) -> Vec<u8> {
+        let mut code = vec![];
+        let mut node = self.parents[symbol + TABLE_SIZE];
+
+        while node != ROOT {
+            code.push((node & 1) as u8);
+            node = self.parents[node];
+        }
+
+        code.reverse();
+        code
+    }
+
+    /// Increments the frequency of the given symbol, rebuilding the tree if frequencies have grown
+    /// too large and reordering nodes as needed to keep the tree sorted.
+    pub fn update(&mut self, symbol: usize) {
+        if self.frequencies[ROOT] == MAX_FREQUENCY {
+            self.rebuild();
+        }
+
+        let mut node = self.parents[symbol + TABLE_SIZE];
+
+        loop {
+            self.frequencies[node] += 1;
+            let frequency = self.frequencies[node];
+
+            let mut swap = node + 1;
+
+            if frequency > self.frequencies[swap] {
+                while frequency > self.frequencies[swap + 1] {
+                    swap += 1;
+                }
+
+                self.frequencies[node] = self.frequencies[swap];
+                self.frequencies[swap] = frequency;
+
+                let child = self.children[node];
+                self.parents[child] = swap;
+
+                if child < TABLE_SIZE {
+                    self.parents[child + 1] = swap;
+                }
+
+                let other_child = self.children[swap];
+                self.children[swap] = child;
+                self.parents[other_child] = node;
+
+                if other_child < TABLE_SIZE {
+                    self.parents[other_child + 1] = node;
+                }
+
+                self.children[node] = other_child;
+                node = swap;
+            }
+
+            node = self.parents[node];
+
+            if node == 0 {
+                break;
+            }
+        }
+    }
+
+    fn rebuild(&mut self) {
+        // Collect the leaves, halving their frequencies...
+        let mut j = 0;
+
+        for i in 0..TABLE_SIZE {
+            if self.children[i] >= TABLE_SIZE {
+                self.frequencies[j] = (self.frequencies[i] + 1) / 2;
+                self.children[j] = self.children[i];
+                j += 1;
+            }
+        }
+
+        // ...then rebuild the branches, inserting each into its sorted position.
+        let mut i = 0;
+
+        for j in SYMBOL_COUNT..TABLE_SIZE {
+            let frequency = self.frequencies[i] + self.frequencies[i + 1];
+
+            let mut k = j;
+
+            while k > 0 && frequency < self.frequencies[k - 1] {
+                k -= 1;
+            }
+
+            self.frequencies.copy_within(k..j, k + 1);
+            self.frequencies[k] = frequency;
+            self.children.copy_within(k..j, k + 1);
+            self.children[k] = i;
+
+            i += 2;
+        }
+
+        for i in 0..TABLE_SIZE {
+            let child = self.children[i];
+            self.parents[child] = i;
+
+            if child < TABLE_SIZE {
+                self.parents[child + 1] = i;
+            }
+        }
+    }
+}
+
+/// Returns the number of bits (including the six low-order bits) used to encode a match position
+/// whose code starts with the given byte.
+fn position_code_len(byte: u32) -> u32 {
+    match byte {
+        0x00..=0x1f => 3,
+        0x20..=0x4f => 4,
+        0x50..=0x8f => 5,
+        0x90..=0xbf => 6,
+        0xc0..=0xef => 7,
+        _ => 8,
+    }
+}
+
+/// Returns the upper six bits of a match position whose code starts with the given byte.
+fn position_upper_bits(byte: u32) -> u32 {
+    match byte {
+        0x00..=0x1f => 0,
+        0x20..=0x4f => (byte - 0x20) / 16 + 0x01,
+        0x50..=0x8f => (byte - 0x50) / 8 + 0x04,
+        0x90..=0xbf => (byte - 0x90) / 4 + 0x0c,
+        0xc0..=0xef => (byte - 0xc0) / 2 + 0x18,
+        _ => byte - 0xf0 + 0x30,
+    }
+}
+
+fn decode_position(bits: &mut BitReader) -> usize {
+    let byte = bits.read_bits_or_zero(8);
+    let upper = position_upper_bits(byte);
+    let mut lower = byte;
+
+    for _ in 0..position_code_len(byte) - 2 {
+        lower = (lower << 1) | bits.read_bit_or_zero();
+    }
+
+    ((upper << 6) | (lower & 0x3f)) as usize
+}
+
+pub(super) fn decompress(compressed: &[u8], len: usize) -> Result<Vec<u8>, StuffItError> {
+    let mut bits = BitReader::msb_first(compressed);
+    let mut tree = AdaptiveHuffman::new();
+
+    let mut window = [b' '; WINDOW_SIZE];
+    let mut window_position = WINDOW_SIZE - MAX_MATCH;
+
+    let mut output = Vec::with_capacity(output_capacity(len, compressed.len()));
+
+    while output.len() < len {
+        // LZHUF reads zeroes past the end of its input, so running out of data is only detectable
+        // as a failure to produce enough output.
+        if bits.remaining() == 0 {
+            return Err(StuffItError::CorruptData);
+        }
+
+        let symbol = tree.decode(&mut bits);
+
+        if symbol < 256 {
+            let byte = symbol as u8;
+
+            output.push(byte);
+            window[window_position] = byte;
+            window_position = (window_position + 1) % WINDOW_SIZE;
+        } else {
+            let distance = decode_position(&mut bits);
+            let start = (window_position + WINDOW_SIZE - distance - 1) % WINDOW_SIZE;
+            let length = symbol - 256 + THRESHOLD + 1;
+
+            for k in 0..length {
+                let byte = window[(start + k) % WINDOW_SIZE];
+
+                output.push(byte);
+                window[window_position] = byte;
+                window_position = (window_position + 1) % WINDOW_SIZE;
+            }
+        }
+    }
+
+    output.truncate(len);
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    enum Token {
+        Literal(u8),
+        Match(usize, usize),
+    }
+
+    /// Encodes the given tokens in the same way as LZHUF's encoder.
+    fn compress(tokens: &[Token]) -> Vec<u8> {
+        let mut tree = AdaptiveHuffman::new();
+        let mut bits: Vec<u8> = vec![];
+
+        for token in tokens {
+            let symbol = match token {
+                Token::Literal(byte) => *byte as usize,
+                Token::Match(_, length) => length - THRESHOLD - 1 + 256,
+            };
+
+            bits.extend(tree.code(symbol));
+            tree.update(symbol);
+
+            if let Token::Match(distance, _) = token {
+                // Find the shortest code whose upper bits match the position's upper bits.
+                let upper = (*distance >> 6) as u32;
+                let byte = (0..=255)
+                    .find(|byte| position_upper_bits(*byte) == upper)
+                    .unwrap();
+
+                let code_len = position_code_len(byte);
+
+                for i in 0..code_len {
+                    bits.push(((byte >> (7 - i)) & 1) as u8);
+                }
+
+                for i in 0..6 {
+                    bits.push(((distance >> (5 - i)) & 1) as u8);
+                }
+            }
+        }
+
+        bits.chunks(8)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .fold(0, |byte, (i, bit)| byte | (bit << (7 - i)))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn position_tables() {
+        let mut counts = [0; 64];
+
+        for byte in 0..=255 {
+            counts[position_upper_bits(byte) as usize] += 1;
+        }
+
+        // Every byte that starts a given position code must share that code's length
+        for byte in 0..=255 {
+            assert_eq!(
+                1 << (8 - position_code_len(byte)),
+                counts[position_upper_bits(byte) as usize]
+            );
+        }
+
+        assert_eq!(32, counts[0]);
+        assert_eq!(1, counts[0x3f]);
+    }
+
+    #[test]
+    fn decompress_lzah() -> Result<(), StuffItError> {
+        let mut tokens = vec![];
+        let mut expected = vec![];
+
+        for byte in b"Hello, world! " {
+            tokens.push(Token::Literal(*byte));
+            expected.push(*byte);
+        }
+
+        // Repeat the whole phrase several times, then refer back into the space-filled window
+        tokens.push(Token::Match(13, 42));
+        for i in 0..42 {
+            expected.push(expected[i]);
+        }
+
+        tokens.push(Token::Match(WINDOW_SIZE - 1, 5));
+        expected.extend_from_slice(b"     ");
+
+        // Enough literals to force the tree to be rebuilt at least once
+        let mut state = 7u32;
+        for _ in 0..40_000 {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            let byte = b"etaoin shrdlu"[(state >> 16) as usize % 13];
+
+            tokens.push(Token::Literal(byte));
+            expected.push(byte);
+        }
+
+        tokens.push(Token::Match(1000, MAX_MATCH));
+        let start = expected.len() - 1001;
+        for i in 0..MAX_MATCH {
+            expected.push(expected[start + i]);
+        }
+
+        let compressed = compress(&tokens);
+
+        assert_eq!(expected, decompress(&compressed, expected.len())?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,289 @@
+//! Decompression for StuffIt's LZW method, which is identical to the scheme used by the Unix
+//! `compress` utility in "block mode" with a maximum code width of 14 bits.
+
+use super::archive::StuffItError;
+use super::bits::BitReader;
+use crate::util::output_capacity;
+
+const MAX_BITS: u32 = 14;
+const INITIAL_BITS: u32 = 9;
+const CLEAR_CODE: usize = 256;
+const FIRST_CODE: usize = 257;
+
+pub(super) fn decompress(compressed: &[u8], len: usize) -> Result<Vec<u8>, StuffItError> {
+    let mut output = Vec::with_capacity(output_capacity(len, compressed.len()));
+    let mut bits = BitReader::lsb_first(compressed);
+
+    let mut prefixes = vec![0u16; 1 << MAX_BITS];
+    let mut suffixes = vec![0u8; 1 << MAX_BITS];
+
+    for (i, suffix) in suffixes.iter_mut().enumerate().take(256) {
+        *suffix = i as u8;
+    }
+
+    let mut code_width = INITIAL_BITS;
+    let mut next_code = FIRST_CODE;
+    let mut previous: Option<usize> = None;
+    let mut first_byte = 0u8;
+    let mut stack = Vec::new();
+
+    // Like `compress`, codes are written in groups of eight; when the code width changes (or the
+    // table is cleared), the remainder of the current group is skipped.
+    let mut group_start = 0;
+
+    while output.len() < len {
+        if code_width < MAX_BITS && next_code > (1 << code_width) - 1 {
+            skip_to_group_end(&mut bits, &mut group_start, code_width);
+            code_width += 1;
+        }
+
+        if bits.remaining() < code_width as usize {
+            break;
+        }
+
+        let code = bits.read_bits(code_width)? as usize;
+
+        if code == CLEAR_CODE {
+            skip_to_group_end(&mut bits, &mut group_start, code_width);
+            code_width = INITIAL_BITS;
+
+            // The first code after a clear adds a (never-used) entry in the clear code's slot.
+            next_code = CLEAR_CODE;
+            continue;
+        }
+
+        let previous_code = match previous {
+            Some(previous_code) => previous_code,
+            None => {
+                if code >= 256 {
+                    return Err(StuffItError::CorruptData);
+                }
+
+                first_byte = code as u8;
+                output.push(first_byte);
+                previous = Some(code);
+
+                continue;
+            }
+        };
+
+        stack.clear();
+
+        let mut current = code;
+
+        if code >= next_code {
+            // The encoder emitted a code for a string it had only just added to its table; that
+            // string is always the previous string followed by its own first byte.
+            if code > next_code {
+                return Err(StuffItError::CorruptData);
+            }
+
+            stack.push(first_byte);
+            current = previous_code;
+        }
+
+        while current >= 256 {
+            stack.push(suffixes[current]);
+            current = prefixes[current] as usize;
+        }
+
+        first_byte = current as u8;
+        stack.push(first_byte);
+
+        output.extend(stack.iter().rev());
+
+        if next_code < 1 << MAX_BITS {
+            prefixes[next_code] = previous_code as u16;
+            suffixes[next_code] = first_byte;
+            next_code += 1;
+        }
+
+        previous = Some(code);
+    }
+
+    output.truncate(len);
+
+    Ok(output)
+}
+
+fn skip_to_group_end(bits: &mut BitReader, group_start: &mut usize, code_width: u32) {
+    let group_bits = code_width as usize * 8;
+    let consumed = bits.position() - *group_start;
+    let position = *group_start + (consumed + group_bits - 1) / group_bits * group_bits;
+
+    bits.set_position(position);
+    *group_start = position;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    struct BitWriter {
+        bytes: Vec<u8>,
+        position: usize,
+    }
+
+    impl BitWriter {
+        fn write(&mut self, value: usize, width: u32) {
+            for i in 0..width {
+                if self.position % 8 == 0 {
+                    self.bytes.push(0);
+                }
+
+                if value & (1 << i) != 0 {
+                    *self.bytes.last_mut().unwrap() |= 1 << (self.position % 8);
+                }
+
+                self.position += 1;
+            }
+        }
+
+        fn skip_to_group_end(&mut self, group_start: &mut usize, code_width: u32) {
+            let group_bits = code_width as usize * 8;
+
+            while (self.position - *group_start) % group_bits != 0 {
+                self.write(0, 1);
+            }
+
+            *group_start = self.position;
+        }
+    }
+
+    /// Compresses data in the same way as `compress -b 14`, optionally clearing the table whenever
+    /// it fills up.
+    fn compress(data: &[u8], clear_when_full: bool) -> Vec<u8> {
+        let mut writer = BitWriter {
+            bytes: vec![],
+            position: 0,
+        };
+
+        let mut table: HashMap<Vec<u8>, usize> = HashMap::new();
+        let mut next_code = FIRST_CODE;
+        let mut code_width = INITIAL_BITS;
+        let mut group_start = 0;
+        let mut codes_since_clear = 0;
+
+        let mut emit = |writer: &mut BitWriter, code: usize, codes_since_clear: &mut usize| {
+            // Mirror the decoder, whose table lags one code behind the encoder's.
+            let decoder_next_code = FIRST_CODE + codes_since_clear.saturating_sub(1);
+
+            if code_width < MAX_BITS && decoder_next_code > (1 << code_width) - 1 {
+                writer.skip_to_group_end(&mut group_start, code_width);
+                code_width += 1;
+            }
+
+            writer.write(code, code_width);
+            *codes_since_clear += 1;
+
+            if code == CLEAR_CODE {
+                writer.skip_to_group_end(&mut group_start, code_width);
+                code_width = INITIAL_BITS;
+                *codes_since_clear = 0;
+            }
+        };
+
+        let lookup = |table: &HashMap<Vec<u8>, usize>, string: &[u8]| match string.len() {
+            1 => Some(string[0] as usize),
+            _ => table.get(string).copied(),
+        };
+
+        let mut current: Vec<u8> = vec![];
+
+        for &byte in data {
+            let mut extended = current.clone();
+            extended.push(byte);
+
+            if lookup(&table, &extended).is_some() {
+                current = extended;
+                continue;
+            }
+
+            emit(
+                &mut writer,
+                lookup(&table, &current).unwrap(),
+                &mut codes_since_clear,
+            );
+
+            if next_code < 1 << MAX_BITS {
+                table.insert(extended, next_code);
+                next_code += 1;
+            } else if clear_when_full {
+                emit(&mut writer, CLEAR_CODE, &mut codes_since_clear);
+                table.clear();
+                next_code = FIRST_CODE;
+            }
+
+            current = vec![byte];
+        }
+
+        if !current.is_empty() {
+            emit(
+                &mut writer,
+                lookup(&table, &current).unwrap(),
+                &mut codes_since_clear,
+            );
+        }
+
+        writer.bytes
+    }
+
+    fn sample_data() -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut state = 1u32;
+
+        while data.len() < 200_000 {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            let word =
+                ["alpha ", "beta ", "gamma ", "delta\r", "epsilon "][(state >> 16) as usize % 5];
+
+            data.extend_from_slice(word.as_bytes());
+            data.push((state >> 24) as u8);
+        }
+
+        data
+    }
+
+    #[test]
+    fn decompress_small() -> Result<(), StuffItError> {
+        let original = b"TOBEORNOTTOBEORTOBEORNOT#aaaaaaaaaaaaaaa";
+
+        assert_eq!(
+            original.to_vec(),
+            decompress(&compress(original, false), original.len())?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn decompress_growing_code_width() -> Result<(), StuffItError> {
+        let original = sample_data();
+
+        assert_eq!(
+            original,
+            decompress(&compress(&original, false), original.len())?
+        );
+        assert_eq!(
+            original,
+            decompress(&compress(&original, true), original.len())?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn decompress_corrupt() {
+        // The second code refers to an entry that can't exist yet.
+        let mut writer = BitWriter {
+            bytes: vec![],
+            position: 0,
+        };
+
+        writer.write(b'A' as usize, 9);
+        writer.write(300, 9);
+
+        assert_eq!(Err(StuffItError::CorruptData), decompress(&writer.bytes, 4));
+    }
+}
@@ -0,0 +1,192 @@
+use std::io::Read;
+
+use super::archive::StuffItError;
+use super::{arsenic, huffman, lzah, lzw, method13};
+use crate::binhex::BinHexExpander;
+use crate::util::output_capacity;
+
+/// The method used to compress one fork of an entry in a StuffIt archive.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum CompressionMethod {
+    /// The fork is stored without compression.
+    None,
+
+    /// The fork is compressed with the same run-length encoding scheme used by BinHex.
+    Rle90,
+
+    /// The fork is compressed with the LZW scheme used by the Unix `compress` utility.
+    Lzw,
+
+    /// The fork is compressed with a static Huffman code stored at the start of the fork.
+    Huffman,
+
+    /// The fork is compressed with LZ77 and an adaptive Huffman code ("LZAH").
+    Lzah,
+
+    /// The fork is compressed with a fixed Huffman code.
+    FixedHuffman,
+
+    /// The fork is compressed with the "MW" scheme.
+    Mw,
+
+    /// The fork is compressed with LZ77 and static Huffman codes (StuffIt 3's default method).
+    Method13,
+
+    /// The fork is compressed with the scheme used by StuffIt InstallerMaker.
+    Installer,
+
+    /// The fork is compressed with block sorting and arithmetic coding ("Arsenic," StuffIt 4's
+    /// default method).
+    Arsenic,
+
+    /// The fork is compressed with some other method.
+    ///
+    /// The method number is included.
+    Other(u8),
+}
+
+impl CompressionMethod {
+    /// Returns `true` if this library can decompress every fork compressed with this method.
+    ///
+    /// Method 13 forks can be decompressed if they store their own codes, but not if they use one
+    /// of StuffIt's predefined code sets, and so this method returns `false` for method 13.
+    pub fn is_supported(&self) -> bool {
+        matches!(
+            self,
+            CompressionMethod::None
+                | CompressionMethod::Rle90
+                | CompressionMethod::Lzw
+                | CompressionMethod::Huffman
+                | CompressionMethod::Lzah
+                | CompressionMethod::Arsenic
+        )
+    }
+
+    /// Returns `true` if forks compressed with this method carry a CRC of their uncompressed
+    /// contents in their entry's header.
+    ///
+    /// Arsenic streams carry their own checksum, and the header's CRC field is unused.
+    pub(super) fn has_header_crc(&self) -> bool {
+        *self != CompressionMethod::Arsenic
+    }
+
+    /// Decompresses the given compressed fork, which should expand to exactly `len` bytes.
+    pub(super) fn decompress(
+        &self,
+        compressed: &[u8],
+        len: usize,
+    ) -> Result<Vec<u8>, StuffItError> {
+        let decompressed = match self {
+            CompressionMethod::None => compressed.to_vec(),
+            CompressionMethod::Rle90 => {
+                let mut expanded = Vec::with_capacity(output_capacity(len, compressed.len()));
+
+                BinHexExpander::new(compressed)
+                    .take(len as u64)
+                    .read_to_end(&mut expanded)
+                    .map_err(|_| StuffItError::CorruptData)?;
+
+                expanded
+            }
+            CompressionMethod::Lzw => lzw::decompress(compressed, len)?,
+            CompressionMethod::Huffman => huffman::decompress(compressed, len)?,
+            CompressionMethod::Lzah => lzah::decompress(compressed, len)?,
+            CompressionMethod::Method13 => method13::decompress(compressed, len)?,
+            CompressionMethod::Arsenic => arsenic::decompress(compressed, len)?,
+            method => return Err(StuffItError::UnsupportedMethod(*method)),
+        };
+
+        if decompressed.len() == len {
+            Ok(decompressed)
+        } else {
+            Err(StuffItError::CorruptData)
+        }
+    }
+}
+
+impl From<u8> for CompressionMethod {
+    fn from(method: u8) -> Self {
+        match method {
+            0 => CompressionMethod::None,
+            1 => CompressionMethod::Rle90,
+            2 => CompressionMethod::Lzw,
+            3 => CompressionMethod::Huffman,
+            5 => CompressionMethod::Lzah,
+            6 => CompressionMethod::FixedHuffman,
+            8 => CompressionMethod::Mw,
+            13 => CompressionMethod::Method13,
+            14 => CompressionMethod::Installer,
+            15 => CompressionMethod::Arsenic,
+            _ => CompressionMethod::Other(method),
+        }
+    }
+}
+
+impl From<CompressionMethod> for u8 {
+    fn from(method: CompressionMethod) -> Self {
+        match method {
+            CompressionMethod::None => 0,
+            CompressionMethod::Rle90 => 1,
+            CompressionMethod::Lzw => 2,
+            CompressionMethod::Huffman => 3,
+            CompressionMethod::Lzah => 5,
+            CompressionMethod::FixedHuffman => 6,
+            CompressionMethod::Mw => 8,
+            CompressionMethod::Method13 => 13,
+            CompressionMethod::Installer => 14,
+            CompressionMethod::Arsenic => 15,
+            CompressionMethod::Other(method) => method,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::binhex::BinHexCompressor;
+    use std::io::Write;
+
+    #[test]
+    fn method_numbers() {
+        for method in 0..16 {
+            assert_eq!(method, u8::from(CompressionMethod::from(method)));
+        }
+
+        assert_eq!(CompressionMethod::Other(4), CompressionMethod::from(4));
+        assert!(CompressionMethod::Arsenic.is_supported());
+        assert!(!CompressionMethod::Method13.is_supported());
+        assert!(!CompressionMethod::Installer.is_supported());
+    }
+
+    #[test]
+    fn rle90() -> Result<(), StuffItError> {
+        let original = b"Hello\x90\x90, wooooooooorld!!!!!!".to_vec();
+
+        let mut compressor = BinHexCompressor::new(vec![]);
+        compressor.write_all(&original)?;
+        let compressed = compressor.finish()?;
+
+        assert!(compressed.len() < original.len());
+        assert_eq!(
+            original,
+            CompressionMethod::Rle90.decompress(&compressed, original.len())?
+        );
+
+        assert_eq!(
+            Err(StuffItError::CorruptData),
+            CompressionMethod::Rle90.decompress(&compressed, original.len() + 1)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported() {
+        assert_eq!(
+            Err(StuffItError::UnsupportedMethod(
+                CompressionMethod::Installer
+            )),
+            CompressionMethod::Installer.decompress(&[0; 4], 4)
+        );
+    }
+}
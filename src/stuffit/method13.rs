@@ -0,0 +1,430 @@
+//! Decompression for StuffIt's method 13, which combines LZ77 with a 64 KiB window and static
+//! Huffman codes. StuffIt 3 used this as its default method.
+//!
+//! Compressed forks start with a byte that selects either a set of codes stored in the fork itself
+//! (encoded with a fixed "meta-code") or one of five predefined sets of codes. The predefined
+//! code-length tables aren't included in this crate, so only forks with stored codes can be
+//! decompressed.
+
+use super::archive::StuffItError;
+use super::bits::BitReader;
+use super::method::CompressionMethod;
+use super::prefix::PrefixCode;
+use crate::util::output_capacity;
+
+/// The number of symbols in the literal/length codes: 256 literals, 62 short match lengths, two
+/// escapes for longer match lengths, and an end-of-stream marker.
+const LITERAL_CODE_SIZE: usize = 321;
+
+const END_OF_STREAM: usize = 0x140;
+
+/// The fixed code used to encode the lengths of stored codes. Codes are listed least significant
+/// bit first.
+const META_CODES: [(u32, u32); 37] = [
+    (0x5d8, 11),
+    (0x058, 8),
+    (0x040, 8),
+    (0x0c0, 8),
+    (0x000, 8),
+    (0x078, 7),
+    (0x02b, 6),
+    (0x014, 5),
+    (0x00c, 5),
+    (0x01c, 5),
+    (0x01b, 5),
+    (0x00b, 6),
+    (0x010, 5),
+    (0x020, 6),
+    (0x038, 7),
+    (0x018, 7),
+    (0x0d8, 9),
+    (0xbd8, 12),
+    (0x180, 10),
+    (0x680, 11),
+    (0x380, 11),
+    (0xf80, 12),
+    (0x780, 12),
+    (0x480, 11),
+    (0x080, 11),
+    (0x280, 11),
+    (0x3d8, 12),
+    (0xfd8, 12),
+    (0x7d8, 12),
+    (0x9d8, 12),
+    (0x1d8, 12),
+    (0x004, 5),
+    (0x001, 2),
+    (0x002, 2),
+    (0x007, 3),
+    (0x003, 4),
+    (0x008, 5),
+];
+
+pub(super) fn decompress(compressed: &[u8], len: usize) -> Result<Vec<u8>, StuffItError> {
+    let (&selector, compressed) = compressed.split_first().ok_or(StuffItError::CorruptData)?;
+
+    if selector >> 4 != 0 {
+        // The five predefined code sets aren't included in this crate
+        return Err(StuffItError::UnsupportedMethod(CompressionMethod::Method13));
+    }
+
+    let mut bits = BitReader::lsb_first(compressed);
+
+    let mut meta_code = PrefixCode::new();
+
+    for (symbol, (code, code_len)) in META_CODES.iter().enumerate() {
+        meta_code.add(symbol, *code, *code_len, false)?;
+    }
+
+    let first_code = read_code(&mut bits, &meta_code, LITERAL_CODE_SIZE)?;
+    let second_code = match selector & 0x08 {
+        0 => Some(read_code(&mut bits, &meta_code, LITERAL_CODE_SIZE)?),
+        _ => None,
+    };
+    let offset_code = read_code(&mut bits, &meta_code, (selector & 0x07) as usize + 10)?;
+
+    let mut output = Vec::with_capacity(output_capacity(len, compressed.len()));
+    let mut code = &first_code;
+
+    while output.len() < len {
+        let symbol = code.decode(&mut bits)?;
+
+        if symbol < 0x100 {
+            output.push(symbol as u8);
+            code = &first_code;
+
+            continue;
+        }
+
+        code = second_code.as_ref().unwrap_or(&first_code);
+
+        let length = match symbol {
+            0x100..=0x13d => symbol - 0x100 + 3,
+            0x13e => bits.read_bits(10)? as usize + 65,
+            0x13f => bits.read_bits(15)? as usize + 65,
+            END_OF_STREAM => break,
+            _ => return Err(StuffItError::CorruptData),
+        };
+
+        let distance = match offset_code.decode(&mut bits)? {
+            0 => 1,
+            1 => 2,
+            offset_bits => {
+                (1 << (offset_bits - 1)) + bits.read_bits(offset_bits as u32 - 1)? as usize + 1
+            }
+        };
+
+        if distance > output.len() {
+            return Err(StuffItError::CorruptData);
+        }
+
+        let start = output.len() - distance;
+
+        for i in 0..length {
+            output.push(output[start + i]);
+        }
+    }
+
+    output.truncate(len);
+
+    Ok(output)
+}
+
+/// Reads the lengths of a stored canonical code with the given number of symbols, then builds the
+/// code.
+fn read_code(
+    bits: &mut BitReader,
+    meta_code: &PrefixCode,
+    size: usize,
+) -> Result<PrefixCode, StuffItError> {
+    let mut lengths = vec![0; size];
+    let mut length: i32 = 0;
+    let mut i = 0;
+
+    while i < size {
+        // Every meta-code symbol ends by assigning the current length to the current symbol;
+        // some symbols also repeat the current length beforehand.
+        let repeat = match meta_code.decode(bits)? {
+            31 => {
+                length = -1;
+                0
+            }
+            32 => {
+                length += 1;
+                0
+            }
+            33 => {
+                length -= 1;
+                0
+            }
+            34 => bits.read_bit()? as usize,
+            35 => bits.read_bits(3)? as usize + 2,
+            36 => bits.read_bits(6)? as usize + 10,
+            value => {
+                length = value as i32 + 1;
+                0
+            }
+        };
+
+        if i + repeat >= size {
+            return Err(StuffItError::CorruptData);
+        }
+
+        for length_slot in lengths.iter_mut().skip(i).take(repeat + 1) {
+            *length_slot = length;
+        }
+
+        i += repeat + 1;
+    }
+
+    PrefixCode::from_lengths(&lengths)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct BitWriter {
+        bytes: Vec<u8>,
+        position: usize,
+    }
+
+    impl BitWriter {
+        fn new() -> Self {
+            BitWriter {
+                bytes: vec![],
+                position: 0,
+            }
+        }
+
+        fn write_bit(&mut self, bit: u32) {
+            if self.position % 8 == 0 {
+                self.bytes.push(0);
+            }
+
+            *self.bytes.last_mut().unwrap() |= (bit as u8 & 1) << (self.position % 8);
+            self.position += 1;
+        }
+
+        fn write_bits(&mut self, value: u32, count: u32) {
+            for i in 0..count {
+                self.write_bit(value >> i);
+            }
+        }
+
+        fn write_meta_symbol(&mut self, symbol: usize) {
+            let (code, len) = META_CODES[symbol];
+            self.write_bits(code, len);
+        }
+
+        fn write_code_lengths(&mut self, lengths: &[i32]) {
+            let mut current = 0;
+            let mut i = 0;
+
+            while i < lengths.len() {
+                let target = lengths[i];
+                let mut run = lengths[i..].iter().take_while(|l| **l == target).count();
+
+                if target != current {
+                    match target - current {
+                        1 => self.write_meta_symbol(32),
+                        -1 => self.write_meta_symbol(33),
+                        _ if target == -1 => self.write_meta_symbol(31),
+                        _ => self.write_meta_symbol(target as usize - 1),
+                    }
+
+                    current = target;
+                    i += 1;
+                    run -= 1;
+                }
+
+                while run >= 3 {
+                    if run >= 11 {
+                        let count = run.min(74);
+                        self.write_meta_symbol(36);
+                        self.write_bits(count as u32 - 11, 6);
+                        i += count;
+                        run -= count;
+                    } else {
+                        let count = run.min(10);
+                        self.write_meta_symbol(35);
+                        self.write_bits(count as u32 - 3, 3);
+                        i += count;
+                        run -= count;
+                    }
+                }
+
+                if run > 0 {
+                    self.write_meta_symbol(34);
+                    self.write_bit(run as u32 - 1);
+                    i += run;
+                }
+            }
+        }
+
+        fn write_symbol(&mut self, codes: &[(u32, u32)], symbol: usize) {
+            let (code, len) = codes[symbol];
+
+            for i in (0..len).rev() {
+                self.write_bit(code >> i);
+            }
+        }
+    }
+
+    fn canonical_codes(lengths: &[i32]) -> Vec<(u32, u32)> {
+        let mut codes = vec![(0, 0); lengths.len()];
+        let mut code = 0;
+
+        for len in 1..=32 {
+            for symbol in 0..lengths.len() {
+                if lengths[symbol] == len {
+                    codes[symbol] = (code, len as u32);
+                    code += 1;
+                }
+            }
+
+            code <<= 1;
+        }
+
+        codes
+    }
+
+    enum Token {
+        Literal(u8),
+        Match(usize, usize),
+    }
+
+    fn compress(tokens: &[Token]) -> Vec<u8> {
+        let first_lengths: Vec<i32> = (0..LITERAL_CODE_SIZE)
+            .map(|symbol| match symbol {
+                0x01 => -1,
+                0x61..=0x7a => 6,
+                0x00..=0xff => 10,
+                _ => 9,
+            })
+            .collect();
+
+        let second_lengths: Vec<i32> = (0..LITERAL_CODE_SIZE)
+            .map(|symbol| if symbol < 0x100 { 9 } else { 8 })
+            .collect();
+
+        let mut offset_lengths = vec![4; 15];
+        offset_lengths.extend_from_slice(&[5, 5]);
+
+        let mut writer = BitWriter::new();
+        writer.write_code_lengths(&first_lengths);
+        writer.write_code_lengths(&second_lengths);
+        writer.write_code_lengths(&offset_lengths);
+
+        let first_codes = canonical_codes(&first_lengths);
+        let second_codes = canonical_codes(&second_lengths);
+        let offset_codes = canonical_codes(&offset_lengths);
+
+        let mut codes = &first_codes;
+
+        for token in tokens {
+            match token {
+                Token::Literal(byte) => {
+                    writer.write_symbol(codes, *byte as usize);
+                    codes = &first_codes;
+                }
+                Token::Match(distance, length) => {
+                    match length {
+                        3..=64 => writer.write_symbol(codes, length - 3 + 0x100),
+                        65..=1088 => {
+                            writer.write_symbol(codes, 0x13e);
+                            writer.write_bits(*length as u32 - 65, 10);
+                        }
+                        _ => {
+                            writer.write_symbol(codes, 0x13f);
+                            writer.write_bits(*length as u32 - 65, 15);
+                        }
+                    }
+
+                    match distance {
+                        1 => writer.write_symbol(&offset_codes, 0),
+                        2 => writer.write_symbol(&offset_codes, 1),
+                        _ => {
+                            let offset_bits = 32 - (*distance as u32 - 1).leading_zeros();
+                            writer.write_symbol(&offset_codes, offset_bits as usize);
+                            writer.write_bits(
+                                (*distance as u32 - 1) - (1 << (offset_bits - 1)),
+                                offset_bits - 1,
+                            );
+                        }
+                    }
+
+                    codes = &second_codes;
+                }
+            }
+        }
+
+        writer.write_symbol(codes, END_OF_STREAM);
+
+        let mut compressed = vec![0x07];
+        compressed.extend(writer.bytes);
+        compressed
+    }
+
+    #[test]
+    fn meta_code_is_complete() {
+        let kraft_sum: f64 = META_CODES
+            .iter()
+            .map(|(_, len)| 1.0 / (1u64 << len) as f64)
+            .sum();
+
+        assert_eq!(1.0, kraft_sum);
+
+        let mut meta_code = PrefixCode::new();
+
+        for (symbol, (code, code_len)) in META_CODES.iter().enumerate() {
+            assert!(meta_code.add(symbol, *code, *code_len, false).is_ok());
+        }
+    }
+
+    #[test]
+    fn decompress_method13() -> Result<(), StuffItError> {
+        let mut tokens = vec![];
+        let mut expected: Vec<u8> = vec![];
+
+        for byte in b"the quick brown fox jumps over the lazy dog\r\x00\xff" {
+            tokens.push(Token::Literal(*byte));
+            expected.push(*byte);
+        }
+
+        for (distance, length) in [(1, 3), (2, 64), (46, 100), (3, 2000), (40_000, 5)].iter() {
+            if *distance > expected.len() {
+                while expected.len() < *distance {
+                    let byte = b"abcdefghijklmnopqrstuvwxyz"[expected.len() * 7 % 26];
+
+                    tokens.push(Token::Literal(byte));
+                    expected.push(byte);
+                }
+            }
+
+            tokens.push(Token::Match(*distance, *length));
+
+            let start = expected.len() - distance;
+
+            for i in 0..*length {
+                expected.push(expected[start + i]);
+            }
+        }
+
+        let compressed = compress(&tokens);
+
+        assert_eq!(expected, decompress(&compressed, expected.len())?);
+        // Decompression stops at the end-of-stream marker
+        assert_eq!(expected, decompress(&compressed, expected.len() + 1)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn predefined_codes() {
+        assert_eq!(
+            Err(StuffItError::UnsupportedMethod(CompressionMethod::Method13)),
+            decompress(&[0x10, 0x00], 1)
+        );
+    }
+}
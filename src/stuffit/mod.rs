@@ -0,0 +1,35 @@
+//! Tools for reading classic StuffIt and StuffIt 5 archives.
+//!
+//! StuffIt was the dominant archive format on the "classic" Mac OS. Archives written by StuffIt 1.5
+//! through 4.5 share a simple structure: a short header followed by a sequence of entries, each of
+//! which describes a file (with its Finder metadata and separately-compressed data and resource
+//! forks) or marks the start or end of a folder. Those archives can be read with
+//! [`StuffItArchive`].
+//!
+//! StuffIt 5 introduced a new container (identified by a `StuffIt (c)1997-` preamble) that arranges
+//! its entries in a linked tree and can encrypt whole archives or individual entries. Those archives
+//! can be read with [`StuffIt5Archive`]; their entries are described in the same way as entries in
+//! classic archives.
+//!
+//! Forks compressed with no compression, RLE90, LZW, Huffman, LZAH, method 13 (with stored codes),
+//! or Arsenic can be decompressed, and are checked against the CRC in their entry's header (or, for
+//! Arsenic, at the end of the compressed data). Encrypted forks can't be read.
+
+mod archive;
+mod arsenic;
+pub(crate) mod bits;
+mod entry;
+mod huffman;
+mod lzah;
+mod lzw;
+mod method;
+mod method13;
+pub(crate) mod prefix;
+mod stuffit5;
+
+pub use archive::{StuffItArchive, StuffItError};
+pub(crate) use archive::{SECOND_SIGNATURE, SIGNATURES};
+pub use entry::StuffItEntry;
+pub use method::CompressionMethod;
+pub use stuffit5::StuffIt5Archive;
+pub(crate) use stuffit5::SIGNATURE as STUFFIT5_SIGNATURE;
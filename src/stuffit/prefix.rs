@@ -0,0 +1,140 @@
+use super::archive::StuffItError;
+use super::bits::BitReader;
+
+/// The longest code a prefix code may contain.
+const MAX_CODE_LEN: i32 = 32;
+
+#[derive(Copy, Clone)]
+enum Slot {
+    Empty,
+    Branch(usize),
+    Leaf(usize),
+}
+
+/// A prefix (Huffman) code, stored as a binary tree that's walked one bit at a time.
+pub(crate) struct PrefixCode {
+    nodes: Vec<[Slot; 2]>,
+}
+
+impl PrefixCode {
+    /// Creates an empty code to which codes can be added with [`PrefixCode::add`].
+    pub fn new() -> Self {
+        PrefixCode {
+            nodes: vec![[Slot::Empty; 2]],
+        }
+    }
+
+    /// Creates a canonical code from the given code lengths, in which the first (shortest) code is
+    /// all zeroes and codes of the same length are assigned in order of increasing symbol.
+    ///
+    /// Symbols with a length of zero or less are omitted from the code.
+    pub fn from_lengths(lengths: &[i32]) -> Result<Self, StuffItError> {
+        let mut prefix_code = PrefixCode::new();
+        let mut code: u64 = 0;
+
+        for len in 1..=MAX_CODE_LEN {
+            for (symbol, _) in lengths.iter().enumerate().filter(|(_, l)| **l == len) {
+                if code >= 1 << len {
+                    return Err(StuffItError::CorruptData);
+                }
+
+                prefix_code.add(symbol, code as u32, len as u32, true)?;
+                code += 1;
+            }
+
+            code <<= 1;
+        }
+
+        if lengths.iter().any(|len| *len > MAX_CODE_LEN) {
+            return Err(StuffItError::CorruptData);
+        }
+
+        Ok(prefix_code)
+    }
+
+    /// Adds a code for the given symbol. If `high_bit_first` is `true`, the code's most significant
+    /// bit is the first bit read from the stream; otherwise, its least significant bit is first.
+    pub fn add(
+        &mut self,
+        symbol: usize,
+        code: u32,
+        len: u32,
+        high_bit_first: bool,
+    ) -> Result<(), StuffItError> {
+        let mut node = 0;
+
+        for i in 0..len {
+            let bit = match high_bit_first {
+                true => (code >> (len - 1 - i)) & 1,
+                false => (code >> i) & 1,
+            } as usize;
+
+            if i == len - 1 {
+                return match self.nodes[node][bit] {
+                    Slot::Empty => {
+                        self.nodes[node][bit] = Slot::Leaf(symbol);
+                        Ok(())
+                    }
+                    _ => Err(StuffItError::CorruptData),
+                };
+            }
+
+            node = match self.nodes[node][bit] {
+                Slot::Branch(next) => next,
+                Slot::Empty => {
+                    self.nodes.push([Slot::Empty; 2]);
+                    self.nodes[node][bit] = Slot::Branch(self.nodes.len() - 1);
+
+                    self.nodes.len() - 1
+                }
+                Slot::Leaf(_) => return Err(StuffItError::CorruptData),
+            };
+        }
+
+        // Zero-length codes can't be represented
+        Err(StuffItError::CorruptData)
+    }
+
+    /// Reads the next symbol from the given bit reader.
+    pub fn decode(&self, bits: &mut BitReader) -> Result<usize, StuffItError> {
+        let mut node = 0;
+
+        loop {
+            match self.nodes[node][bits.read_bit()? as usize] {
+                Slot::Branch(next) => node = next,
+                Slot::Leaf(symbol) => return Ok(symbol),
+                Slot::Empty => return Err(StuffItError::CorruptData),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn canonical_code() -> Result<(), StuffItError> {
+        // Lengths 2, 1, 3, 3 yield codes 10, 0, 110, 111
+        let code = PrefixCode::from_lengths(&[2, 1, 3, 3, 0, -1])?;
+        let mut bits = BitReader::msb_first(&[0b1001_1011, 0b1000_0000]);
+
+        assert_eq!(0, code.decode(&mut bits)?);
+        assert_eq!(1, code.decode(&mut bits)?);
+        assert_eq!(2, code.decode(&mut bits)?);
+        assert_eq!(3, code.decode(&mut bits)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_code() {
+        assert!(PrefixCode::from_lengths(&[1, 1, 1]).is_err());
+        assert!(PrefixCode::from_lengths(&[33]).is_err());
+
+        let code = PrefixCode::from_lengths(&[1, 2]).unwrap();
+        let mut bits = BitReader::msb_first(&[0b1100_0000]);
+
+        assert_eq!(Err(StuffItError::CorruptData), code.decode(&mut bits));
+    }
+}
@@ -0,0 +1,548 @@
+use std::cmp;
+use std::convert::TryInto;
+use std::io::{Read, Seek, SeekFrom};
+
+use crc16::{State, ARC};
+
+use super::archive::{self, StuffItError};
+use super::entry::{ForkInfo, StuffItEntry};
+use super::method::CompressionMethod;
+use crate::finder::FinderFlags;
+use crate::util::{u16_at, u32_at};
+
+/// The text at the start of every StuffIt 5 archive; the full text continues with the copyright
+/// year and Aladdin's name and URL.
+pub(crate) const SIGNATURE: &[u8; 16] = b"StuffIt (c)1997-";
+
+/// The length, in bytes, of a StuffIt 5 archive's header, including its 80-byte text preamble.
+const ARCHIVE_HEADER_LENGTH: usize = 100;
+
+/// The magic number at the start of every entry header.
+const ENTRY_MAGIC: u32 = 0xa5a5_a5a5;
+
+/// The length, in bytes, of the fixed portion of each entry header.
+const ENTRY_HEADER_FIXED_LENGTH: usize = 48;
+
+/// The length, in bytes, of the fixed portion of the "Finder" block that follows each entry header.
+const FINDER_BLOCK_LENGTH: usize = 14;
+
+/// The length, in bytes, of the resource fork descriptor that follows the Finder block of entries
+/// with resource forks.
+const RESOURCE_FORK_DESCRIPTOR_LENGTH: usize = 14;
+
+const ARCHIVE_ENCRYPTED_FLAG: u8 = 0x80;
+const ENTRY_FOLDER_FLAG: u8 = 0x40;
+const ENTRY_ENCRYPTED_FLAG: u8 = 0x20;
+const HAS_RESOURCE_FORK_FLAG: u16 = 0x0001;
+
+/// A StuffIt archive written by StuffIt 5 or later.
+///
+/// StuffIt 5 archives begin with a human-readable preamble, followed by a tree of file and folder
+/// entries. Each folder's entries immediately follow the folder's own entry, and each file's
+/// compressed resource and data forks immediately follow the file's entry. Forks are usually
+/// compressed with [`CompressionMethod::Arsenic`], but may use any of the methods supported by
+/// classic StuffIt archives.
+///
+/// Archives (and individual entries) may be encrypted; encrypted archives' directories can be
+/// listed, but their forks can't be read.
+pub struct StuffIt5Archive<R: Read + Seek> {
+    source: R,
+    base_offset: u64,
+    encrypted: bool,
+    entries: Vec<StuffItEntry>,
+}
+
+impl<R: Read + Seek> StuffIt5Archive<R> {
+    /// Creates a new StuffIt 5 archive that will extract data from the given source, starting at
+    /// the source's current position, and reads the archive's directory tree.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the archive's header or any of its entries' headers
+    /// could not be read, or if any entry's header doesn't match its checksum.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use clarus::stuffit::{StuffIt5Archive, StuffItError};
+    ///
+    /// fn main() -> Result<(), StuffItError> {
+    ///     let mut archive = StuffIt5Archive::new(File::open("example.sit")?)?;
+    ///
+    ///     for entry in archive.entries().to_vec() {
+    ///         if !entry.is_folder() {
+    ///             let data_fork = archive.data_fork(&entry)?;
+    ///             println!("{}: {} bytes", entry.path().join("/"), data_fork.len());
+    ///         }
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn new(mut source: R) -> Result<Self, StuffItError> {
+        let base_offset = source.stream_position()?;
+        let source_len = source.seek(SeekFrom::End(0))? - base_offset;
+        source.seek(SeekFrom::Start(base_offset))?;
+
+        let mut header = [0; ARCHIVE_HEADER_LENGTH];
+        source.read_exact(&mut header)?;
+
+        if header[0..SIGNATURE.len()] != SIGNATURE[..] {
+            return Err(StuffItError::InvalidHeader);
+        }
+
+        let encrypted = header[83] & ARCHIVE_ENCRYPTED_FLAG != 0;
+        let end = cmp::min(u32_at(&header, 84) as u64, source_len);
+        let root_entry_count = u16_at(&header, 92) as usize;
+        let mut offset = u32_at(&header, 94) as u64;
+
+        let mut entries = Vec::new();
+        let mut folders: Vec<String> = Vec::new();
+
+        // The number of entries remaining in each folder that's currently being read, starting
+        // with the root folder
+        let mut remaining = vec![root_entry_count];
+
+        while let Some(count) = remaining.last_mut() {
+            if *count == 0 {
+                remaining.pop();
+                folders.pop();
+
+                continue;
+            }
+
+            let entry = read_entry(&mut source, base_offset, offset, end)?;
+            offset = entry.next_offset;
+
+            if entry.is_placeholder {
+                continue;
+            }
+
+            *count -= 1;
+
+            let mut path = folders.clone();
+            path.push(entry.name.clone());
+
+            if let Some(child_count) = entry.child_count {
+                remaining.push(child_count);
+                folders.push(entry.name);
+            }
+
+            let (mut data_fork, mut resource_fork) = (entry.data_fork, entry.resource_fork);
+            data_fork.encrypted |= encrypted;
+            resource_fork.encrypted |= encrypted;
+
+            entries.push(StuffItEntry::new(
+                path,
+                entry.child_count.is_some(),
+                entry.file_type,
+                entry.creator,
+                entry.flags,
+                entry.created,
+                entry.modified,
+                data_fork,
+                resource_fork,
+            ));
+        }
+
+        Ok(StuffIt5Archive {
+            source,
+            base_offset,
+            encrypted,
+            entries,
+        })
+    }
+
+    /// Returns `true` if this archive is encrypted.
+    pub fn is_encrypted(&self) -> bool {
+        self.encrypted
+    }
+
+    /// Returns all of the files and folders in this archive, in the order in which they appear in
+    /// the archive. Folders precede their contents.
+    pub fn entries(&self) -> &[StuffItEntry] {
+        &self.entries
+    }
+
+    /// Reads and decompresses the given entry's data fork.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if the fork is encrypted or compressed with an unsupported
+    /// method, if its compressed data is malformed, or if its decompressed content doesn't match
+    /// its checksum.
+    pub fn data_fork(&mut self, entry: &StuffItEntry) -> Result<Vec<u8>, StuffItError> {
+        archive::read_fork(&mut self.source, self.base_offset, entry.data_fork)
+    }
+
+    /// Reads and decompresses the given entry's resource fork.
+    ///
+    /// The returned fork may be opened with [`crate::rsrc::ResourceFork`].
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if the fork is encrypted or compressed with an unsupported
+    /// method, if its compressed data is malformed, or if its decompressed content doesn't match
+    /// its checksum.
+    pub fn resource_fork(&mut self, entry: &StuffItEntry) -> Result<Vec<u8>, StuffItError> {
+        archive::read_fork(&mut self.source, self.base_offset, entry.resource_fork)
+    }
+}
+
+/// An entry as it appears in a StuffIt 5 archive, before its path has been resolved.
+struct RawEntry {
+    name: String,
+    child_count: Option<usize>,
+    is_placeholder: bool,
+    file_type: [u8; 4],
+    creator: [u8; 4],
+    flags: FinderFlags,
+    created: u32,
+    modified: u32,
+    data_fork: ForkInfo,
+    resource_fork: ForkInfo,
+    next_offset: u64,
+}
+
+fn read_entry<R: Read + Seek>(
+    source: &mut R,
+    base_offset: u64,
+    offset: u64,
+    end: u64,
+) -> Result<RawEntry, StuffItError> {
+    if offset + ENTRY_HEADER_FIXED_LENGTH as u64 > end {
+        return Err(StuffItError::InvalidHeader);
+    }
+
+    source.seek(SeekFrom::Start(base_offset + offset))?;
+
+    let mut header = vec![0; ENTRY_HEADER_FIXED_LENGTH];
+    source.read_exact(&mut header)?;
+
+    if u32_at(&header, 0) != ENTRY_MAGIC {
+        return Err(StuffItError::InvalidHeader);
+    }
+
+    let version = header[4];
+    let header_len = u16_at(&header, 6) as usize;
+
+    if header_len < ENTRY_HEADER_FIXED_LENGTH {
+        return Err(StuffItError::InvalidHeader);
+    }
+
+    header.resize(header_len, 0);
+    source.read_exact(&mut header[ENTRY_HEADER_FIXED_LENGTH..])?;
+
+    // The header's checksum is calculated with the checksum field itself set to zero.
+    let provided_checksum = u16_at(&header, 32);
+    header[32..34].copy_from_slice(&[0, 0]);
+    let calculated_checksum = State::<ARC>::calculate(&header);
+
+    if provided_checksum != calculated_checksum {
+        return Err(StuffItError::InvalidChecksum(
+            provided_checksum as u32,
+            calculated_checksum as u32,
+        ));
+    }
+
+    let entry_flags = header[9];
+    let is_folder = entry_flags & ENTRY_FOLDER_FLAG != 0;
+    let encrypted = entry_flags & ENTRY_ENCRYPTED_FLAG != 0;
+    let name_len = u16_at(&header, 30) as usize;
+    let data_len = u32_at(&header, 34);
+
+    let (child_count, data_method, name_start) = if is_folder {
+        (Some(u16_at(&header, 46) as usize), 0, 48)
+    } else {
+        // Files may include some key-checking data for encrypted forks before their names.
+        (None, header[46], 48 + header[47] as usize)
+    };
+
+    let name_bytes = header
+        .get(name_start..name_start + name_len)
+        .ok_or(StuffItError::InvalidHeader)?;
+    let (name, _, _) = encoding_rs::MACINTOSH.decode(name_bytes);
+
+    let mut finder_block = [0; FINDER_BLOCK_LENGTH];
+    source.read_exact(&mut finder_block)?;
+
+    // Version 1 entries have a slightly longer (and otherwise unused) Finder block.
+    let finder_block_padding = if version == 1 { 22 } else { 18 };
+    source.seek(SeekFrom::Current(finder_block_padding))?;
+
+    let mut resource_fork = ForkInfo::empty();
+
+    if u16_at(&finder_block, 0) & HAS_RESOURCE_FORK_FLAG != 0 {
+        let mut descriptor = [0; RESOURCE_FORK_DESCRIPTOR_LENGTH];
+        source.read_exact(&mut descriptor)?;
+        source.seek(SeekFrom::Current(descriptor[13] as i64))?;
+
+        resource_fork = ForkInfo {
+            method: CompressionMethod::from(descriptor[12]),
+            encrypted,
+            offset: 0,
+            len: u32_at(&descriptor, 0),
+            compressed_len: u32_at(&descriptor, 4),
+            crc: u16_at(&descriptor, 8),
+        };
+    }
+
+    let forks_offset = source.stream_position()? - base_offset;
+
+    let mut data_fork = ForkInfo::empty();
+
+    let next_offset = if is_folder {
+        forks_offset
+    } else {
+        resource_fork.offset = forks_offset;
+
+        data_fork = ForkInfo {
+            method: CompressionMethod::from(data_method),
+            encrypted,
+            offset: forks_offset + resource_fork.compressed_len as u64,
+            len: data_len,
+            compressed_len: u32_at(&header, 38),
+            crc: u16_at(&header, 42),
+        };
+
+        data_fork.offset + data_fork.compressed_len as u64
+    };
+
+    if next_offset > end {
+        return Err(StuffItError::InvalidHeader);
+    }
+
+    Ok(RawEntry {
+        name: name.to_string(),
+        child_count,
+        // Some folders have "placeholder" entries that don't appear in their parents' counts.
+        is_placeholder: is_folder && data_len == 0xffff_ffff,
+        file_type: finder_block[4..8].try_into().unwrap(),
+        creator: finder_block[8..12].try_into().unwrap(),
+        flags: FinderFlags::from(u16_at(&finder_block, 12)),
+        created: u32_at(&header, 10),
+        modified: u32_at(&header, 14),
+        data_fork,
+        resource_fork,
+        next_offset,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rsrc::ResourceFork;
+    use std::io::Cursor;
+
+    const RESOURCE_FORK: &[u8] = include_bytes!("../rsrc/string-table.rsrc");
+
+    enum Node<'a> {
+        File(&'a str, &'a [u8], &'a [u8]),
+        Folder(&'a str, Vec<Node<'a>>),
+    }
+
+    fn entry_bytes(node: &Node, version: u8, entry_flags: u8, offset: usize) -> Vec<u8> {
+        let (name, is_folder) = match node {
+            Node::File(name, _, _) => (name, false),
+            Node::Folder(name, _) => (name, true),
+        };
+
+        let mut header = vec![0; ENTRY_HEADER_FIXED_LENGTH];
+        header[0..4].copy_from_slice(&ENTRY_MAGIC.to_be_bytes());
+        header[4] = version;
+        header[6..8].copy_from_slice(&((48 + name.len()) as u16).to_be_bytes());
+        header[9] = entry_flags | if is_folder { ENTRY_FOLDER_FLAG } else { 0 };
+        header[10..14].copy_from_slice(&0xb000_0000u32.to_be_bytes());
+        header[14..18].copy_from_slice(&0xb000_0001u32.to_be_bytes());
+        header[30..32].copy_from_slice(&(name.len() as u16).to_be_bytes());
+        header.extend_from_slice(name.as_bytes());
+
+        let mut finder_block = vec![0; FINDER_BLOCK_LENGTH + if version == 1 { 22 } else { 18 }];
+        let mut forks = vec![];
+
+        match node {
+            Node::File(_, data_fork, resource_fork) => {
+                header[34..38].copy_from_slice(&(data_fork.len() as u32).to_be_bytes());
+                header[38..42].copy_from_slice(&(data_fork.len() as u32).to_be_bytes());
+                header[42..44].copy_from_slice(&State::<ARC>::calculate(data_fork).to_be_bytes());
+
+                finder_block[4..8].copy_from_slice(b"TEXT");
+                finder_block[8..12].copy_from_slice(b"ttxt");
+                finder_block[12..14].copy_from_slice(&0x4000u16.to_be_bytes());
+
+                if !resource_fork.is_empty() {
+                    finder_block[0..2].copy_from_slice(&HAS_RESOURCE_FORK_FLAG.to_be_bytes());
+
+                    let len = (resource_fork.len() as u32).to_be_bytes();
+                    finder_block.extend_from_slice(&len);
+                    finder_block.extend_from_slice(&len);
+                    finder_block
+                        .extend_from_slice(&State::<ARC>::calculate(resource_fork).to_be_bytes());
+                    finder_block.extend_from_slice(&[0, 0, 0, 0]);
+                }
+
+                forks.extend_from_slice(resource_fork);
+                forks.extend_from_slice(data_fork);
+            }
+            Node::Folder(_, children) => {
+                header[46..48].copy_from_slice(&(children.len() as u16).to_be_bytes());
+            }
+        }
+
+        let checksum = State::<ARC>::calculate(&header);
+        header[32..34].copy_from_slice(&checksum.to_be_bytes());
+
+        header.extend(finder_block);
+        header.extend(forks);
+
+        if let Node::Folder(_, children) = node {
+            for child in children {
+                let child_offset = offset + header.len();
+                header.extend(entry_bytes(child, version, entry_flags, child_offset));
+            }
+        }
+
+        header
+    }
+
+    fn archive_bytes(nodes: &[Node], version: u8, archive_flags: u8, entry_flags: u8) -> Vec<u8> {
+        let mut archive =
+            b"StuffIt (c)1997-2001 Aladdin Systems, Inc., http://www.aladdinsys.com/StuffIt/\r\n"
+                .to_vec();
+        archive.resize(ARCHIVE_HEADER_LENGTH, 0);
+        archive[82] = 5;
+        archive[83] = archive_flags;
+        archive[92..94].copy_from_slice(&(nodes.len() as u16).to_be_bytes());
+        archive[94..98].copy_from_slice(&(ARCHIVE_HEADER_LENGTH as u32).to_be_bytes());
+
+        for node in nodes {
+            let offset = archive.len();
+            archive.extend(entry_bytes(node, version, entry_flags, offset));
+        }
+
+        let len = archive.len() as u32;
+        archive[84..88].copy_from_slice(&len.to_be_bytes());
+
+        archive
+    }
+
+    fn sample_nodes<'a>() -> Vec<Node<'a>> {
+        vec![
+            Node::Folder(
+                "Folder",
+                vec![
+                    Node::File("Inner", b"Hello from the data fork!", RESOURCE_FORK),
+                    Node::Folder("Empty", vec![]),
+                ],
+            ),
+            Node::File("Outer", b"Outer data fork", &[]),
+        ]
+    }
+
+    #[test]
+    fn read_archive() -> Result<(), StuffItError> {
+        for version in [1, 3].iter() {
+            let mut archive =
+                StuffIt5Archive::new(Cursor::new(archive_bytes(&sample_nodes(), *version, 0, 0)))?;
+
+            assert!(!archive.is_encrypted());
+
+            let paths: Vec<String> = archive
+                .entries()
+                .iter()
+                .map(|entry| entry.path().join("/"))
+                .collect();
+
+            assert_eq!(
+                vec!["Folder", "Folder/Inner", "Folder/Empty", "Outer"],
+                paths
+            );
+
+            let inner = archive.entries()[1].clone();
+
+            assert!(!inner.is_folder());
+            assert_eq!(*b"TEXT", inner.file_type());
+            assert_eq!(*b"ttxt", inner.creator());
+            assert!(inner.flags().is_invisible());
+            assert_eq!(0xb000_0000, inner.created());
+            assert_eq!(0xb000_0001, inner.modified());
+
+            assert_eq!(
+                b"Hello from the data fork!".to_vec(),
+                archive.data_fork(&inner)?
+            );
+
+            let resource_fork = archive.resource_fork(&inner)?;
+            assert_eq!(RESOURCE_FORK.to_vec(), resource_fork);
+            assert!(ResourceFork::new(Cursor::new(resource_fork)).is_ok());
+
+            let outer = archive.entries()[3].clone();
+            assert_eq!(b"Outer data fork".to_vec(), archive.data_fork(&outer)?);
+            assert!(archive.resource_fork(&outer)?.is_empty());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn encrypted_archive() -> Result<(), StuffItError> {
+        let mut archive = StuffIt5Archive::new(Cursor::new(archive_bytes(
+            &sample_nodes(),
+            3,
+            ARCHIVE_ENCRYPTED_FLAG,
+            0,
+        )))?;
+
+        assert!(archive.is_encrypted());
+
+        let inner = archive.entries()[1].clone();
+        assert!(inner.is_encrypted());
+        assert_eq!(Err(StuffItError::Encrypted), archive.data_fork(&inner));
+
+        let archive = StuffIt5Archive::new(Cursor::new(archive_bytes(
+            &sample_nodes(),
+            3,
+            0,
+            ENTRY_ENCRYPTED_FLAG,
+        )))?;
+        assert!(!archive.is_encrypted());
+        assert!(archive.entries()[1].is_encrypted());
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_entries() {
+        let bytes = archive_bytes(&sample_nodes(), 3, 0, 0);
+
+        let mut bad_checksum = bytes.clone();
+        bad_checksum[ARCHIVE_HEADER_LENGTH + 48] = b'f';
+
+        assert!(matches!(
+            StuffIt5Archive::new(Cursor::new(bad_checksum)),
+            Err(StuffItError::InvalidChecksum(_, _))
+        ));
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[ARCHIVE_HEADER_LENGTH] = 0;
+
+        assert!(matches!(
+            StuffIt5Archive::new(Cursor::new(bad_magic)),
+            Err(StuffItError::InvalidHeader)
+        ));
+
+        let mut truncated = bytes;
+        truncated.truncate(truncated.len() - 4);
+
+        assert!(matches!(
+            StuffIt5Archive::new(Cursor::new(truncated)),
+            Err(StuffItError::InvalidHeader)
+        ));
+
+        assert!(matches!(
+            StuffIt5Archive::new(Cursor::new(vec![0; ARCHIVE_HEADER_LENGTH])),
+            Err(StuffItError::InvalidHeader)
+        ));
+    }
+}
@@ -0,0 +1,81 @@
+use std::convert::TryInto;
+
+/// Returns the big-endian `u16` at the given offset.
+///
+/// # Panics
+///
+/// This function panics if the given bytes end before the value does.
+pub(crate) fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes(bytes[offset..offset + 2].try_into().unwrap())
+}
+
+/// Returns the big-endian `u32` at the given offset.
+///
+/// # Panics
+///
+/// This function panics if the given bytes end before the value does.
+pub(crate) fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+/// Returns the big-endian `i16` at the given offset.
+///
+/// # Panics
+///
+/// This function panics if the given bytes end before the value does.
+pub(crate) fn i16_at(bytes: &[u8], offset: usize) -> i16 {
+    i16::from_be_bytes(bytes[offset..offset + 2].try_into().unwrap())
+}
+
+/// Returns the big-endian `i32` at the given offset.
+///
+/// # Panics
+///
+/// This function panics if the given bytes end before the value does.
+pub(crate) fn i32_at(bytes: &[u8], offset: usize) -> i32 {
+    i32::from_be_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+/// Returns the big-endian `u16` at the given offset, or `None` if the given bytes end before the
+/// value does.
+pub(crate) fn get_u16_at(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        bytes
+            .get(offset..offset.checked_add(2)?)?
+            .try_into()
+            .unwrap(),
+    ))
+}
+
+/// Returns the big-endian `u32` at the given offset, or `None` if the given bytes end before the
+/// value does.
+pub(crate) fn get_u32_at(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        bytes
+            .get(offset..offset.checked_add(4)?)?
+            .try_into()
+            .unwrap(),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn read_values() {
+        let bytes = [0x12, 0x34, 0x56, 0x78, 0xff, 0xfe];
+
+        assert_eq!(0x1234, u16_at(&bytes, 0));
+        assert_eq!(0x5678_fffe, u32_at(&bytes, 2));
+        assert_eq!(-2, i16_at(&bytes, 4));
+        assert_eq!(0x0078_fffe, i32_at(&[0, 0x78, 0xff, 0xfe], 0));
+        assert_eq!(-1, i32_at(&[0xff; 4], 0));
+
+        assert_eq!(Some(0xfffe), get_u16_at(&bytes, 4));
+        assert_eq!(None, get_u16_at(&bytes, 5));
+        assert_eq!(Some(0x3456_78ff), get_u32_at(&bytes, 1));
+        assert_eq!(None, get_u32_at(&bytes, 3));
+        assert_eq!(None, get_u32_at(&bytes, usize::MAX));
+    }
+}
@@ -0,0 +1,35 @@
+use std::borrow::Cow;
+
+/// The longest filename, in bytes, that archive formats storing Macintosh filenames can record.
+pub(crate) const MAX_FILENAME_LENGTH: usize = 63;
+
+/// Encodes a filename in the Macintosh character encoding, returning `None` if the filename is
+/// empty, is longer than [`MAX_FILENAME_LENGTH`] bytes when encoded, or contains characters that
+/// can't be represented in the Macintosh character encoding.
+pub(crate) fn encode_filename(filename: &str) -> Option<Cow<'_, [u8]>> {
+    let (name_bytes, _, had_errors) = encoding_rs::MACINTOSH.encode(filename);
+
+    if had_errors || name_bytes.is_empty() || name_bytes.len() > MAX_FILENAME_LENGTH {
+        None
+    } else {
+        Some(name_bytes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encode_filename() {
+        assert_eq!(
+            Some(&b"SimpleText\xaa Document"[..]),
+            super::encode_filename("SimpleText™ Document").as_deref()
+        );
+
+        assert!(super::encode_filename("").is_none());
+        assert!(super::encode_filename("🙂").is_none());
+        assert!(super::encode_filename(&"x".repeat(MAX_FILENAME_LENGTH + 1)).is_none());
+        assert!(super::encode_filename(&"x".repeat(MAX_FILENAME_LENGTH)).is_some());
+    }
+}
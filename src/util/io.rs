@@ -0,0 +1,173 @@
+use std::cmp;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+/// Copies exactly `len` bytes from the given source to the given destination.
+///
+/// # Errors
+///
+/// This function returns an error of kind [`io::ErrorKind::UnexpectedEof`] if the source ends
+/// before `len` bytes have been copied, or any error returned while reading from the source or
+/// writing to the destination.
+pub(crate) fn copy_exact(
+    source: &mut impl Read,
+    dest: &mut impl Write,
+    len: u64,
+) -> io::Result<()> {
+    let bytes_copied = io::copy(&mut source.take(len), dest)?;
+
+    if bytes_copied < len {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+    }
+
+    Ok(())
+}
+
+/// The largest multiple of a compressed input's length that's reserved up front for its output.
+const MAX_RESERVED_EXPANSION: usize = 32;
+
+/// Returns the capacity to reserve for `len` bytes of output decompressed from `compressed_len`
+/// bytes of input.
+///
+/// Expected output lengths come from archive or image metadata and can't be trusted, so the
+/// reservation is limited to a generous multiple of the input's length; any output beyond that is
+/// allocated as it's actually produced.
+pub(crate) fn output_capacity(len: usize, compressed_len: usize) -> usize {
+    len.min(compressed_len.saturating_mul(MAX_RESERVED_EXPANSION))
+}
+
+/// Resolves the given seek target against a stream's current position and length, returning an
+/// error of kind [`io::ErrorKind::InvalidInput`] if the target would be negative or overflow.
+pub(crate) fn seek_position(pos: SeekFrom, position: u64, len: u64) -> io::Result<u64> {
+    let (base, offset) = match pos {
+        SeekFrom::Start(offset) => return Ok(offset),
+        SeekFrom::End(offset) => (len, offset),
+        SeekFrom::Current(offset) => (position, offset),
+    };
+
+    if offset >= 0 {
+        base.checked_add(offset as u64)
+    } else {
+        base.checked_sub(offset.unsigned_abs())
+    }
+    .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))
+}
+
+/// A reader for a fixed-length region of a larger, seekable source.
+///
+/// Bounded readers read directly from their source. Seeking is relative to the start of the
+/// region, and reads never extend beyond the end of the region. The source is only repositioned
+/// on the first read after the reader is created or seeked, so a reader assumes it has exclusive
+/// use of its source for as long as it exists.
+pub(crate) struct BoundedReader<'a, R: Read + Seek> {
+    source: &'a mut R,
+    start: u64,
+    len: u64,
+    position: u64,
+    source_positioned: bool,
+}
+
+impl<'a, R: Read + Seek> BoundedReader<'a, R> {
+    pub(crate) fn new(source: &'a mut R, start: u64, len: u64) -> Self {
+        BoundedReader {
+            source,
+            start,
+            len,
+            position: 0,
+            source_positioned: false,
+        }
+    }
+
+    pub(crate) fn len(&self) -> u64 {
+        self.len
+    }
+}
+
+impl<'a, R: Read + Seek> Read for BoundedReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position >= self.len || buf.is_empty() {
+            return Ok(0);
+        }
+
+        if !self.source_positioned {
+            let offset = self
+                .start
+                .checked_add(self.position)
+                .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))?;
+
+            self.source.seek(SeekFrom::Start(offset))?;
+            self.source_positioned = true;
+        }
+
+        let max_len = cmp::min(buf.len() as u64, self.len - self.position) as usize;
+
+        let bytes_read = self.source.read(&mut buf[..max_len])?;
+        self.position += bytes_read as u64;
+
+        Ok(bytes_read)
+    }
+}
+
+impl<'a, R: Read + Seek> Seek for BoundedReader<'a, R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.position = seek_position(pos, self.position, self.len)?;
+
+        self.source_positioned = false;
+
+        Ok(self.position)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn copy_exact() {
+        let mut dest = vec![];
+
+        assert!(super::copy_exact(&mut &b"Hello!"[..], &mut dest, 5).is_ok());
+        assert_eq!(b"Hello", dest.as_slice());
+
+        assert_eq!(
+            io::ErrorKind::UnexpectedEof,
+            super::copy_exact(&mut &b"Hi"[..], &mut vec![], 5)
+                .unwrap_err()
+                .kind()
+        );
+    }
+
+    #[test]
+    fn output_capacity() {
+        assert_eq!(100, super::output_capacity(100, 10));
+        assert_eq!(320, super::output_capacity(usize::MAX, 10));
+        assert_eq!(usize::MAX, super::output_capacity(usize::MAX, usize::MAX));
+    }
+
+    #[test]
+    fn bounded_reader() -> io::Result<()> {
+        let mut source = Cursor::new((0..16).collect::<Vec<u8>>());
+        let mut reader = BoundedReader::new(&mut source, 4, 8);
+        assert_eq!(8, reader.len());
+
+        let mut content = vec![];
+        reader.read_to_end(&mut content)?;
+        assert_eq!(vec![4, 5, 6, 7, 8, 9, 10, 11], content);
+
+        let mut bytes = [0; 4];
+        assert_eq!(6, reader.seek(SeekFrom::End(-2))?);
+        assert_eq!(2, reader.read(&mut bytes)?);
+        assert_eq!([10, 11], bytes[..2]);
+
+        // Reads past the end of the region (but not the source) produce nothing
+        assert_eq!(10, reader.seek(SeekFrom::Current(2))?);
+        assert_eq!(0, reader.read(&mut bytes)?);
+
+        assert_eq!(
+            io::ErrorKind::InvalidInput,
+            reader.seek(SeekFrom::Current(-11)).unwrap_err().kind()
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,9 @@
+//! Helpers shared by the modules that read and write individual formats.
+
+mod bytes;
+mod filename;
+mod io;
+
+pub(crate) use bytes::{get_u16_at, get_u32_at, i16_at, i32_at, u16_at, u32_at};
+pub(crate) use filename::{encode_filename, MAX_FILENAME_LENGTH};
+pub(crate) use io::{copy_exact, output_capacity, seek_position, BoundedReader};